@@ -19,6 +19,8 @@ fn main() {
 		"pin.svg", 
 		"pinned.svg",
 		"use.svg",
+		"file.svg",
+		"folder.svg",
 	];
 	
 	for icon in &icon_files {
@@ -1,12 +1,36 @@
-use crate::clipboard::{ClipboardItem, ClipboardManager};
-use crate::config::{get_default_config_path, Config, Theme};
-use crate::error::ClipboardResult;
-use crate::storage::{create_storage, Storage};
-use crate::ui::Message;
-use iced::{Element, Subscription, Task, Theme as IcedTheme};
-use log::{debug, error, info};
+use crate::clipboard::{
+	paste, CaptureRules, CaptureRulesHandle, ClipboardBackend, ClipboardItem, ClipboardManager, PauseHandle,
+	PollingHandle, PollingSettings, RecordingState, SecretFilter, SecretFilterHandle, Selection,
+};
+use crate::config::{get_default_config_path, Config, DedupPolicy, Theme};
+use crate::daemon::RecorderLock;
+use crate::error::{ClipboardError, ClipboardResult};
+use crate::instance::WindowInstance;
+use crate::ipc::{DbusServer, IpcClient, Request, Response};
+use crate::storage::{
+	open_storage, prune, record_copy, ItemPage, MemoryStorage, RetentionPolicy, SqliteStorage, Storage, StorageChange,
+	VaultHandle,
+};
+use chrono::{DateTime, Utc};
+use crate::ui::{Message, StartupFailure};
+use iced::{window, Element, Subscription, Task, Theme as IcedTheme};
+use log::{debug, error, info, warn};
 use std::sync::Arc;
 use tokio::sync::Mutex;
+use uuid::Uuid;
+
+/// Nombre d'éléments chargés à chaque défilement en bas de la liste
+const PAGE_SIZE: usize = 50;
+
+/// Position de défilement relative à partir de laquelle la page suivante est chargée
+const LOAD_MORE_THRESHOLD: f32 = 0.9;
+
+/// Options de démarrage données en ligne de commande
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StartupOptions {
+	/// Démarrer avec l'enregistrement suspendu (`--paused`)
+	pub paused: bool,
+}
 
 /// État de l'application
 #[derive(Clone)]
@@ -20,19 +44,83 @@ pub struct ClipboardManagerApp {
 	/// Stockage des éléments
 	storage: Arc<Mutex<Box<dyn Storage>>>,
 	
-	/// Gestionnaire du presse-papiers
-	clipboard_manager: Arc<Mutex<ClipboardManager>>,
+	/// Coffre de la clé de l'historique, s'il est chiffré
+	vault: Option<VaultHandle>,
+	
+	/// Phrase secrète saisie pour déverrouiller l'historique, et sa confirmation
+	passphrase: String,
+	passphrase_confirmation: String,
+	
+	/// Erreur du dernier déverrouillage
+	unlock_error: Option<String>,
+	
+	/// Gestionnaire du presse-papiers, absent si le presse-papiers est inaccessible
+	clipboard_manager: Option<Arc<Mutex<ClipboardManager>>>,
+	
+	/// Étapes du démarrage qui ont échoué, affichées à la place de l'historique
+	startup_failures: Vec<StartupFailure>,
+	
+	/// Raison pour laquelle l'historique est gardé en mémoire, faute d'avoir pu ouvrir la base
+	storage_fallback: Option<String>,
+	
+	/// L'historique est une base en mémoire provisoire, à remplacer par la vraie
+	memory_storage: bool,
+	
+	/// Incrémenté à chaque réouverture de l'historique
+	storage_generation: u64,
+	
+	/// Réglages de scrutation partagés avec la surveillance du presse-papiers
+	polling: PollingHandle,
+	
+	/// Filtre des secrets partagé avec la surveillance du presse-papiers
+	secret_filter: SecretFilterHandle,
+	
+	/// Règles de capture par application partagées avec la surveillance du presse-papiers
+	capture_rules: CaptureRulesHandle,
+	
+	/// Enregistrement des copies, partagé avec la surveillance du presse-papiers
+	recording: RecordingState,
+	recording_handle: PauseHandle,
+	
+	/// Verrou d'enregistrement, tenu quand l'application enregistre elle-même les copies
+	_recorder_lock: Option<Arc<RecorderLock>>,
+	
+	/// Un démon enregistre les copies : l'application se contente d'afficher l'historique
+	daemon_running: bool,
+	
+	/// Le démon a répondu : sa pause se commande depuis l'application
+	daemon_reachable: bool,
 	
-	/// Éléments du presse-papiers
+	/// Service D-Bus publié par l'application, qui signale les copies enregistrées
+	dbus: Option<DbusServer>,
+	
+	/// La fenêtre est affichée, et non masquée depuis le bus ou par une relance
+	window_visible: bool,
+	
+	/// Instance unique de l'interface, que les relances de l'application sollicitent
+	window_instance: Option<Arc<WindowInstance>>,
+	
+	/// Éléments du presse-papiers chargés, les plus récents en premier
 	items: Vec<ClipboardItem>,
 	
+	/// Copies secrètes gardées en mémoire seulement, aussi présentes dans `items`
+	memory_items: Vec<ClipboardItem>,
+	
+	/// Nombre total d'éléments dans l'historique
+	total_items: usize,
+	
+	/// Une page supplémentaire est en cours de chargement
+	loading_more: bool,
+	
 	/// Terme de recherche
 	search_query: String,
 }
 
 impl ClipboardManagerApp {
 	/// Crée une nouvelle instance de l'application
-	pub fn new() -> (Self, Task<Message>) {
+	///
+	/// `instance` est l'instance unique de l'interface, si elle a pu être prise.
+	pub fn new(options: StartupOptions, instance: Option<WindowInstance>) -> (Self, Task<Message>) {
 		let config_path = get_default_config_path();
 		
 		// Charger la configuration
@@ -53,117 +141,394 @@ impl ClipboardManagerApp {
 			Err(e) => error!("Erreur création dossier données: {}", e),
 		}
 		
-		// Initialiser le stockage
-		let storage = match create_storage(&config.data_dir) {
-			Ok(storage) => {
-				info!("Stockage initialisé dans {}", config.data_dir.display());
-				storage
+		// Laisser l'enregistrement au démon s'il tourne déjà
+		let (recorder_lock, daemon_running) = match RecorderLock::try_acquire(&config.data_dir) {
+			Ok(Some(lock)) => (Some(Arc::new(lock)), false),
+			Ok(None) => {
+				info!("Un démon enregistre les copies, l'application affiche son historique");
+				(None, true)
 			}
 			Err(e) => {
-				error!("Erreur initialisation stockage: {}. Arrêt de l'application.", e);
-				panic!("Impossible d'initialiser le stockage");
+				warn!("Verrou d'enregistrement indisponible: {}", e);
+				(None, false)
 			}
 		};
 		
+		// Initialiser le stockage, en mémoire à défaut
+		let mut startup_failures = Vec::new();
+		let (storage, vault, storage_error) = Self::open_history(&config);
+		let memory_storage = storage_error.is_some();
+		let storage_fallback = storage_error.and_then(|e| Self::storage_failure(e, &mut startup_failures));
+		
 		// Initialiser le gestionnaire de presse-papiers
-		let clipboard_manager = match ClipboardManager::new() {
-			Ok(manager) => {
-				info!("Gestionnaire de presse-papiers initialisé");
-				manager
-			}
-			Err(e) => {
-				error!("Erreur initialisation gestionnaire de presse-papiers: {}. Arrêt de l'application.", e);
-				panic!("Impossible d'initialiser le gestionnaire de presse-papiers");
-			}
+		let clipboard_manager = Self::open_clipboard(&config, &mut startup_failures);
+		
+		if options.paused && daemon_running {
+			warn!("--paused sans effet : c'est le démon qui enregistre les copies");
+		}
+		let recording = if options.paused && !daemon_running {
+			info!("Démarrage avec l'enregistrement en pause");
+			RecordingState::Paused { until: None }
+		} else {
+			RecordingState::Recording
 		};
 		
 		let app = Self {
+			vault,
+			startup_failures,
+			storage_fallback,
+			memory_storage,
+			recording,
+			recording_handle: Arc::new(Mutex::new(recording)),
+			_recorder_lock: recorder_lock,
+			daemon_running,
+			window_instance: instance.map(Arc::new),
+			..Self::assemble(config, storage, clipboard_manager)
+		};
+		
+		// Charger les éléments au démarrage, après un premier nettoyage
+		let task = if app.is_locked() {
+			info!("Historique chiffré verrouillé, en attente de la phrase secrète");
+			Task::none()
+		} else {
+			app.load_history()
+		};
+		let task = if app.daemon_running {
+			Task::batch([task, Self::control_daemon(None)])
+		} else {
+			task
+		};
+		(app, task)
+	}
+
+	/// Crée l'application sur un historique et un presse-papiers déjà ouverts
+	///
+	/// Sans démon, instance unique ni chiffrement : l'application enregistre
+	/// elle-même les copies qu'elle reçoit. Permet de piloter l'application de
+	/// bout en bout avec `MemoryStorage` et `MemoryClipboard`.
+	pub fn with_history<B: ClipboardBackend>(
+		config: Config,
+		storage: Box<dyn Storage>,
+		clipboard: ClipboardManager<B>,
+	) -> (Self, Task<Message>) {
+		let app = Self::assemble(config, storage, Some(Arc::new(Mutex::new(clipboard.boxed()))));
+		let task = app.load_history();
+		(app, task)
+	}
+
+	/// Crée l'application sur l'historique qu'enregistre un démon déjà lancé
+	///
+	/// L'application n'écrit pas l'historique : ses modifications passent par
+	/// le socket du démon.
+	pub fn with_daemon(config: Config, storage: Box<dyn Storage>) -> (Self, Task<Message>) {
+		let app = Self { daemon_running: true, ..Self::assemble(config, storage, None) };
+		let task = Task::batch([app.load_history(), Self::control_daemon(None)]);
+		(app, task)
+	}
+
+	/// État initial de l'application, avant démarrage des services
+	fn assemble(
+		config: Config,
+		storage: Box<dyn Storage>,
+		clipboard_manager: Option<Arc<Mutex<ClipboardManager>>>,
+	) -> Self {
+		let polling = Arc::new(Mutex::new(PollingSettings::from(&config)));
+		let secret_filter = Arc::new(Mutex::new(SecretFilter::from(&config)));
+		let capture_rules = Arc::new(Mutex::new(CaptureRules::from(&config)));
+		Self {
 			config,
 			ui_state: crate::ui::State::default(),
 			storage: Arc::new(Mutex::new(storage)),
-			clipboard_manager: Arc::new(Mutex::new(clipboard_manager)),
+			vault: None,
+			passphrase: String::new(),
+			passphrase_confirmation: String::new(),
+			unlock_error: None,
+			clipboard_manager,
+			startup_failures: Vec::new(),
+			storage_fallback: None,
+			memory_storage: false,
+			storage_generation: 0,
+			polling,
+			secret_filter,
+			capture_rules,
+			recording: RecordingState::Recording,
+			recording_handle: Arc::new(Mutex::new(RecordingState::Recording)),
+			_recorder_lock: None,
+			daemon_running: false,
+			daemon_reachable: false,
+			dbus: None,
+			window_visible: true,
+			window_instance: None,
 			items: Vec::new(),
+			memory_items: Vec::new(),
+			total_items: 0,
+			loading_more: false,
 			search_query: String::new(),
-		};
-		
-		let storage_clone = app.storage.clone();
-		
-		// Charger les éléments au démarrage
-		(app, Task::perform(Self::load_items(storage_clone), Message::ItemsLoaded))
+		}
+	}
+
+	/// Éléments de l'historique chargés, les plus récents en premier
+	pub fn items(&self) -> &[ClipboardItem] {
+		&self.items
 	}
 
 	/// Met à jour l'état de l'application en fonction du message reçu
 	pub fn update(&mut self, message: Message) -> Task<Message> {
 		match message {
-			Message::ItemsLoaded(items) => {
-				self.items = items;
+			Message::ItemsLoaded(query, page) => {
+				// Ignorer les résultats d'une recherche remplacée depuis
+				if query != self.search_query {
+					return Task::none();
+				}
+				self.items = page.items;
+				for item in self.memory_items.iter().filter(|item| item.matches_search(&query)) {
+					let position = self.items.partition_point(|loaded| loaded.timestamp > item.timestamp);
+					self.items.insert(position, item.clone());
+				}
+				self.total_items = page.total;
+				self.loading_more = false;
+				self.ui_state.selected_index = self.ui_state.selected_index.min(self.items.len().saturating_sub(1));
+				// Les éléments voués à expirer, enregistrés avant un redémarrage, doivent encore
+				// être nettoyés, même s'ils ne figurent pas dans la page chargée
+				self.schedule_retention()
+			}
+			Message::MoreItemsLoaded(query, page) => {
+				if query != self.search_query {
+					return Task::none();
+				}
+				self.loading_more = false;
+				// Ignorer une page devenue obsolète après un rechargement
+				if page.offset == self.stored_len() {
+					let items = page.items
+						.into_iter()
+						.filter(|item| !self.items.iter().any(|loaded| loaded.id == item.id))
+						.collect::<Vec<_>>();
+					self.items.extend(items);
+					// Les copies gardées en mémoire peuvent précéder la nouvelle page
+					self.items.sort_by_key(|item| std::cmp::Reverse(item.timestamp));
+					self.total_items = page.total;
+				}
 				Task::none()
 			}
+			Message::StorageChanged(change) => self.apply_change(change),
+			Message::LoadMoreItems => {
+				let loaded = self.stored_len();
+				if self.loading_more || loaded >= self.total_items {
+					return Task::none();
+				}
+				self.loading_more = true;
+				self.load_items(loaded, PAGE_SIZE, Message::MoreItemsLoaded)
+			}
+			Message::ListScrolled(position) => {
+				if position >= LOAD_MORE_THRESHOLD {
+					self.update(Message::LoadMoreItems)
+				} else {
+					Task::none()
+				}
+			}
 			Message::NewClipboardItem(item) => {
+				if self.is_locked() {
+					debug!("Historique verrouillé, copie ignorée");
+					return Task::none();
+				}
+				if item.memory_only {
+					return self.keep_in_memory(item);
+				}
+				let expiry = item.expires_at;
+				let storage = self.storage.clone();
+				let dedup_policy = self.config.dedup_policy;
+				let retention = RetentionPolicy::from(&self.config);
+				let clipboard_manager = self.clipboard_manager.clone();
+				let dbus = self.dbus.clone();
+				let record = Task::perform(
+					async move {
+						let (change, report) = record_copy(storage.lock().await.as_ref(), item, dedup_policy, &retention)?;
+						if let Some(manager) = clipboard_manager.filter(|_| !report.lapsed.is_empty()) {
+							manager.lock().await.clear_lapsed(&report.lapsed);
+						}
+						if let Some(dbus) = dbus {
+							for item in &change.upserted {
+								if let Err(e) = dbus.item_added(item).await {
+									warn!("Signal D-Bus non émis: {}", e);
+								}
+							}
+						}
+						Ok(change)
+					},
+					Self::change_message("Erreur sauvegarde élément"),
+				);
+				match expiry {
+					Some(expires_at) => Task::batch([record, Self::retention_at(expires_at)]),
+					None => record,
+				}
+			}
+			Message::UseItem(id) => {
 				let storage = self.storage.clone();
+				let clipboard_manager = self.clipboard_manager.clone();
+				let in_memory = self.memory_item(id);
+				
 				Task::perform(
 					async move {
-						let storage = storage.lock().await;
-						storage.add_item(item.clone())?;
-						storage.flush()?;
+						let item = match in_memory {
+							Some(item) => item,
+							None => Self::load_full_item(storage, id).await?,
+						};
+						let mut manager = clipboard_manager.ok_or_else(clipboard_unavailable)?.lock_owned().await;
+						// Coller directement le contenu
+						manager.paste_to_active_window(&item).await?;
 						Ok(())
 					},
 					|result: ClipboardResult<()>| {
 						if let Err(e) = result {
-							error!("Erreur sauvegarde élément: {}", e);
+							error!("Erreur lors du collage: {}", e);
 						}
-						Message::ReloadItems
+						Message::None
 					},
 				)
 			}
-			Message::UseItem(id) => {
-				let item = self.items.iter().find(|item| item.id == id).cloned();
-				if let Some(item) = item {
-					let clipboard_manager = self.clipboard_manager.clone();
-					
-					Task::perform(
-						async move {
-							let mut manager = clipboard_manager.lock().await;
-							// Coller directement le contenu
-							manager.paste_to_active_window(&item).await?;
-							Ok(())
-						},
-						|result: ClipboardResult<()>| {
-							if let Err(e) = result {
-								error!("Erreur lors du collage: {}", e);
-							}
-							Message::None
-						},
-					)
-				} else {
-					Task::none()
-				}
+			Message::RestoreItem(id, selection) => {
+				let storage = self.storage.clone();
+				let clipboard_manager = self.clipboard_manager.clone();
+				let in_memory = self.memory_item(id);
+
+				Task::perform(
+					async move {
+						let item = match in_memory {
+							Some(item) => item,
+							None => Self::load_full_item(storage, id).await?,
+						};
+						let mut manager = clipboard_manager.ok_or_else(clipboard_unavailable)?.lock_owned().await;
+						manager.set_selection_content(&item, selection)?;
+						Ok(())
+					},
+					|result: ClipboardResult<()>| {
+						if let Err(e) = result {
+							error!("Erreur lors de la restauration: {}", e);
+						}
+						Message::None
+					},
+				)
 			}
 			Message::PinItem(id) => {
+				// Une copie gardée en mémoire le reste, même épinglée
+				if let Some(item) = self.memory_items.iter_mut().find(|item| item.id == id) {
+					item.pinned = !item.pinned;
+					let pinned = item.pinned;
+					if let Some(item) = self.items.iter_mut().find(|item| item.id == id) {
+						item.pinned = pinned;
+					}
+					return Task::none();
+				}
 				let item = self.items.iter().find(|item| item.id == id).cloned();
 				if let Some(mut item) = item {
 					item.pinned = !item.pinned;
+					if self.daemon_running {
+						return Self::ask_daemon("Erreur épinglage élément", Request::Pin { id, pinned: item.pinned });
+					}
 					let storage = self.storage.clone();
 					
 					Task::perform(
 						async move {
 							let storage = storage.lock().await;
-							storage.update_item(item)?;
+							let stored = storage.update_item(item)?;
 							storage.flush()?;
-							Ok(())
-						},
-						|result: ClipboardResult<()>| {
-							if let Err(e) = result {
-								error!("Erreur épinglage élément: {}", e);
-							}
-							Message::ReloadItems
+							Ok(StorageChange {
+								upserted: vec![stored],
+								total: storage.count_items()?,
+								..StorageChange::default()
+							})
 						},
+						Self::change_message("Erreur épinglage élément"),
 					)
 				} else {
 					Task::none()
 				}
 			}
+			Message::SetExpiry(id, expiry) => {
+				let expires_at = expiry.expires_at();
+				if let Some(item) = self.memory_items.iter_mut().find(|item| item.id == id) {
+					item.expires_at = expires_at;
+					if let Some(item) = self.items.iter_mut().find(|item| item.id == id) {
+						item.expires_at = expires_at;
+					}
+					return expires_at.map(Self::retention_at).unwrap_or_else(Task::none);
+				}
+				let Some(mut item) = self.items.iter().find(|item| item.id == id).cloned() else {
+					return Task::none();
+				};
+				item.expires_at = expires_at;
+				// Le démon nettoie lui-même l'historique à l'échéance
+				if self.daemon_running {
+					return Self::ask_daemon("Erreur expiration élément", Request::Expire { id, expires_at });
+				}
+				let storage = self.storage.clone();
+				
+				let update = Task::perform(
+					async move {
+						let storage = storage.lock().await;
+						let stored = storage.update_item(item)?;
+						storage.flush()?;
+						Ok(StorageChange {
+							upserted: vec![stored],
+							total: storage.count_items()?,
+							..StorageChange::default()
+						})
+					},
+					Self::change_message("Erreur expiration élément"),
+				);
+				match expires_at {
+					Some(expires_at) => Task::batch([update, Self::retention_at(expires_at)]),
+					None => update,
+				}
+			}
+			Message::TogglePause => {
+				if self.recording.is_paused() {
+					self.set_recording(RecordingState::Recording)
+				} else {
+					self.set_recording(RecordingState::Paused { until: None })
+				}
+			}
+			Message::PauseFor(delay) => {
+				self.set_recording(RecordingState::Paused { until: Some(Utc::now() + delay) })
+			}
+			Message::PauseElapsed(until) => {
+				// Ignorer la fin d'une pause remplacée entre-temps
+				if self.recording == (RecordingState::Paused { until: Some(until) }) {
+					self.set_recording(RecordingState::Recording)
+				} else {
+					Task::none()
+				}
+			}
+			Message::RecordingChanged(recording) => {
+				// Pause ou reprise demandée par un client, ou état confirmé par le démon
+				self.recording = recording;
+				self.daemon_reachable = self.daemon_running;
+				Self::pause_timer(recording)
+			}
+			Message::RetryStartup => self.retry_startup(),
+			Message::DbusReady(dbus) => {
+				self.dbus = Some(dbus);
+				Task::none()
+			}
+			Message::ToggleWindow => {
+				self.window_visible = !self.window_visible;
+				let visible = self.window_visible;
+				window::get_latest().and_then(move |id| {
+					if visible {
+						window::change_mode::<Message>(id, window::Mode::Windowed).chain(window::gain_focus(id))
+					} else {
+						window::change_mode(id, window::Mode::Hidden)
+					}
+				})
+			}
 			Message::RemoveItem(id) => {
+				if self.memory_items.iter().any(|item| item.id == id) {
+					self.forget_memory_items(|item| item.id == id);
+					return Task::none();
+				}
+				if self.daemon_running {
+					return Self::ask_daemon("Erreur suppression élément", Request::Delete { id });
+				}
 				let storage = self.storage.clone();
 				
 				Task::perform(
@@ -171,32 +536,34 @@ impl ClipboardManagerApp {
 						let storage = storage.lock().await;
 						storage.remove_item(id)?;
 						storage.flush()?;
-						Ok(())
-					},
-					|result: ClipboardResult<()>| {
-						if let Err(e) = result {
-							error!("Erreur suppression élément: {}", e);
-						}
-						Message::ReloadItems
+						Ok(StorageChange {
+							removed: vec![id],
+							total: storage.count_items()?,
+							..StorageChange::default()
+						})
 					},
+					Self::change_message("Erreur suppression élément"),
 				)
 			}
 			Message::ClearItems => {
+				self.forget_memory_items(|item| !item.pinned);
+				if self.daemon_running {
+					return Self::ask_daemon("Erreur suppression éléments", Request::Clear);
+				}
 				let storage = self.storage.clone();
 				
 				Task::perform(
 					async move {
 						let storage = storage.lock().await;
-						storage.clear_non_pinned()?;
+						let removed = storage.clear_non_pinned()?;
 						storage.flush()?;
-						Ok(())
-					},
-					|result: ClipboardResult<()>| {
-						if let Err(e) = result {
-							error!("Erreur suppression éléments: {}", e);
-						}
-						Message::ReloadItems
+						Ok(StorageChange {
+							removed,
+							total: storage.count_items()?,
+							..StorageChange::default()
+						})
 					},
+					Self::change_message("Erreur suppression éléments"),
 				)
 			}
 			Message::SetTheme(theme) => {
@@ -218,12 +585,137 @@ impl ClipboardManagerApp {
 				)
 			}
 			Message::SearchChanged(query) => {
+				// La recherche est faite par le stockage, page par page
 				self.search_query = query;
+				self.loading_more = false;
+				self.load_items(0, PAGE_SIZE, Message::ItemsLoaded)
+			}
+			Message::ConfigChanged(config) => {
+				info!("Configuration modifiée, application des nouveaux réglages");
+				let paste_changed = config.paste_backend != self.config.paste_backend;
+				self.config = *config;
+				
+				// La surveillance relit ces réglages avant chaque vérification
+				let settings = PollingSettings::from(&self.config);
+				let polling = self.polling.clone();
+				let secrets = SecretFilter::from(&self.config);
+				let secret_filter = self.secret_filter.clone();
+				let rules = CaptureRules::from(&self.config);
+				let capture_rules = self.capture_rules.clone();
+				let paste_backend = self.config.paste_backend;
+				let clipboard_manager = self.clipboard_manager.clone().filter(|_| paste_changed);
+				Task::perform(
+					async move {
+						*polling.lock().await = settings;
+						*secret_filter.lock().await = secrets;
+						*capture_rules.lock().await = rules;
+						if let Some(manager) = clipboard_manager {
+							let backend = paste::resolve_in_background(paste_backend).await;
+							manager.lock().await.set_paste_backend(backend);
+						}
+					},
+					// Les limites de conservation ont pu être abaissées
+					|_| Message::ApplyRetention,
+				)
+			}
+			Message::ApplyRetention => {
+				if self.is_locked() {
+					return Task::none();
+				}
+				// Les copies gardées en mémoire expirent comme celles de l'historique
+				let now = Utc::now();
+				let mut lapsed: Vec<(Selection, String)> = self.memory_items
+					.iter()
+					.filter(|item| !item.pinned && item.expires_at.is_some_and(|expires_at| expires_at <= now))
+					.map(|item| (item.selection, item.hash()))
+					.collect();
+				self.forget_memory_items(|item| lapsed.iter().any(|(_, hash)| *hash == item.hash()));
+				
+				let storage = self.storage.clone();
+				let retention = RetentionPolicy::from(&self.config);
+				let clipboard_manager = self.clipboard_manager.clone();
+				let pruned = Task::perform(
+					async move {
+						let storage = storage.lock().await;
+						let (change, report) = prune(storage.as_ref(), &retention)?;
+						lapsed.extend(report.lapsed);
+						if let Some(manager) = clipboard_manager.filter(|_| !lapsed.is_empty()) {
+							manager.lock().await.clear_lapsed(&lapsed);
+						}
+						Ok(change)
+					},
+					|result: ClipboardResult<StorageChange>| match result {
+						Ok(change) if change.removed.is_empty() => Message::None,
+						Ok(change) => Message::StorageChanged(change),
+						Err(e) => {
+							error!("Erreur nettoyage historique: {}", e);
+							Message::None
+						}
+					},
+				);
+				// Chaque nettoyage programme le suivant, à la prochaine expiration
+				pruned.chain(self.schedule_retention())
+			}
+			Message::NextExpiry(stored) => {
+				let in_memory = self.memory_items
+					.iter()
+					.filter(|item| !item.pinned)
+					.filter_map(|item| item.expires_at)
+					.min();
+				match stored.into_iter().chain(in_memory).min() {
+					Some(expires_at) => Self::retention_at(expires_at),
+					None => Task::none(),
+				}
+			}
+			Message::PassphraseChanged(passphrase) => {
+				self.passphrase = passphrase;
 				Task::none()
 			}
-			Message::ReloadItems => {
+			Message::PassphraseConfirmationChanged(confirmation) => {
+				self.passphrase_confirmation = confirmation;
+				Task::none()
+			}
+			Message::Unlock => {
+				let Some(vault) = self.vault.clone() else {
+					return Task::none();
+				};
+				if !vault.is_initialized() && self.passphrase != self.passphrase_confirmation {
+					self.unlock_error = Some("Les deux phrases secrètes sont différentes".to_string());
+					return Task::none();
+				}
+				let passphrase = std::mem::take(&mut self.passphrase);
+				self.passphrase_confirmation.clear();
 				let storage = self.storage.clone();
-				Task::perform(Self::load_items(storage), Message::ItemsLoaded)
+				
+				Task::perform(
+					async move {
+						vault.unlock(Some(&passphrase))?;
+						// Chiffrer les éléments enregistrés avant l'activation du chiffrement
+						storage.lock().await.init()
+					},
+					|result: ClipboardResult<()>| Message::Unlocked(result.map_err(|e| e.to_string())),
+				)
+			}
+			Message::Unlocked(result) => match result {
+				Ok(()) => {
+					info!("Historique déverrouillé");
+					self.unlock_error = None;
+					self.load_history()
+				}
+				Err(e) => {
+					error!("Erreur déverrouillage historique: {}", e);
+					self.unlock_error = Some(e);
+					Task::none()
+				}
+			},
+			Message::SelectionFilterChanged(filter) => {
+				self.ui_state.selection_filter = filter;
+				Task::none()
+			}
+			Message::ReloadItems => {
+				// Recharger autant d'éléments que déjà affichés pour conserver le défilement
+				let limit = self.stored_len().max(PAGE_SIZE);
+				self.load_items(0, limit, Message::ItemsLoaded)
 			}
 			Message::NavigateUp => {
 				if !self.items.is_empty() {
@@ -260,30 +752,96 @@ impl ClipboardManagerApp {
 	}
 
 	/// Affiche l'interface utilisateur
-	pub fn view(&self) -> Element<Message> {
-		// Filtrer les éléments selon la recherche
-		let filtered_items = if self.search_query.is_empty() {
-			self.items.clone()
-		} else {
-			self.items
-				.iter()
-				.filter(|item| item.matches_search(&self.search_query))
-				.cloned()
-				.collect()
-		};
+	pub fn view(&self) -> Element<'_, Message> {
+		if !self.startup_failures.is_empty() {
+			return crate::ui::startup_error_view(&self.startup_failures);
+		}
+		
+		if let Some(vault) = self.vault.as_ref().filter(|vault| vault.is_locked()) {
+			return crate::ui::unlock_view(
+				&self.passphrase,
+				&self.passphrase_confirmation,
+				!vault.is_initialized(),
+				self.unlock_error.as_deref(),
+			);
+		}
+		
+		// Filtrer selon la sélection d'origine les éléments, déjà retenus par la recherche
+		let filter = self.ui_state.selection_filter;
+		let filtered_items = self.items
+			.iter()
+			.filter(|item| filter.matches(item))
+			.cloned()
+			.collect();
 		
 		// Utiliser une vue avec le theme léger pour éviter les problèmes de lifetime
-		crate::ui::view(self.ui_state.clone(), filtered_items, self.search_query.clone(), self.config.theme, self.theme())
+		crate::ui::view(
+			self.ui_state.clone(),
+			filtered_items,
+			self.search_query.clone(),
+			self.config.theme,
+			self.config.track_primary_selection,
+			self.recording_controls(),
+			self.storage_fallback.as_deref(),
+		)
 	}
 
 	/// Abonnements aux événements externes
-	pub fn subscription(_app: &Self) -> Subscription<Message> {
+	pub fn subscription(app: &Self) -> Subscription<Message> {
+		// Les copies enregistrées par le démon arrivent par le stockage, et c'est lui qui sert le socket et le bus
+		let recorder = match &app.clipboard_manager {
+			Some(clipboard_manager) if !app.daemon_running => Subscription::batch([
+				crate::ui::clipboard_subscription(
+					app.config.watcher_backend,
+					app.config.track_primary_selection,
+					app.polling.clone(),
+					app.secret_filter.clone(),
+					app.capture_rules.clone(),
+					app.recording_handle.clone(),
+				),
+				crate::ui::ipc_subscription(
+					app.storage.clone(),
+					clipboard_manager.clone(),
+					app.recording_handle.clone(),
+				),
+				crate::ui::dbus_subscription(
+					app.storage.clone(),
+					clipboard_manager.clone(),
+					app.recording_handle.clone(),
+				),
+			]),
+			// Sans presse-papiers, rien à enregistrer
+			_ => Subscription::none(),
+		};
+		// Sans démon, c'est l'interface qui nettoie l'historique à intervalles réguliers
+		let retention = if app.daemon_running {
+			Subscription::none()
+		} else {
+			crate::ui::retention_subscription(app.config.retention_check_minutes)
+		};
+		let relaunches = match &app.window_instance {
+			Some(instance) => crate::ui::window_subscription(instance.clone()),
+			None => Subscription::none(),
+		};
 		Subscription::batch([
-			crate::ui::clipboard_subscription(),
+			recorder,
+			retention,
+			relaunches,
+			crate::ui::config_subscription(get_default_config_path()),
+			crate::ui::storage_subscription(app.storage.clone(), app.storage_generation),
 			crate::ui::keyboard_subscription(),
 		])
 	}
 
+	/// Titre de la fenêtre, qui signale aussi la pause de l'enregistrement
+	pub fn title(&self) -> String {
+		if self.recording_controls().is_some_and(|recording| recording.is_paused()) {
+			"Gestionnaire de presse-papiers (en pause)".to_string()
+		} else {
+			"Gestionnaire de presse-papiers".to_string()
+		}
+	}
+
 	/// Thème de l'application
 	pub fn theme(&self) -> IcedTheme {
 		match self.config.theme {
@@ -303,14 +861,321 @@ impl ClipboardManagerApp {
 		}
 	}
 
-	/// Charge les éléments depuis le stockage
-	async fn load_items(storage: Arc<Mutex<Box<dyn Storage>>>) -> Vec<ClipboardItem> {
+	/// Ouvre l'historique, ou à défaut un historique en mémoire accompagné de l'erreur rencontrée
+	fn open_history(config: &Config) -> (Box<dyn Storage>, Option<VaultHandle>, Option<ClipboardError>) {
+		match open_storage(config) {
+			Ok((storage, vault)) => {
+				info!("Stockage initialisé dans {}", config.data_dir.display());
+				(storage, vault, None)
+			}
+			Err(e) => {
+				let storage: Box<dyn Storage> = match SqliteStorage::in_memory().and_then(|storage| {
+					storage.init()?;
+					Ok(storage)
+				}) {
+					Ok(storage) => Box::new(storage),
+					// Dernier recours, qui ne peut pas échouer
+					Err(memory_error) => {
+						error!("Erreur création base en mémoire: {}. Historique gardé dans une simple liste.", memory_error);
+						Box::new(MemoryStorage::new())
+					}
+				};
+				(storage, None, Some(e))
+			}
+		}
+	}
+	
+	/// Prend en compte l'échec de l'ouverture de l'historique
+	///
+	/// Une base verrouillée par un autre processus n'empêche pas de démarrer :
+	/// l'historique est gardé en mémoire et la raison retournée. Toute autre
+	/// erreur est un échec du démarrage.
+	fn storage_failure(error: ClipboardError, failures: &mut Vec<StartupFailure>) -> Option<String> {
+		match error {
+			ClipboardError::StorageLocked(_) => {
+				warn!("{}. Historique gardé en mémoire.", error);
+				Some(error.summary().to_string())
+			}
+			error => {
+				error!("Erreur initialisation stockage: {}", error);
+				failures.push(StartupFailure::new("Ouverture de l'historique", &error));
+				None
+			}
+		}
+	}
+	
+	/// Crée le gestionnaire de presse-papiers, ou note l'échec du démarrage
+	fn open_clipboard(config: &Config, failures: &mut Vec<StartupFailure>) -> Option<Arc<Mutex<ClipboardManager>>> {
+		match ClipboardManager::new() {
+			Ok(mut manager) => {
+				info!("Gestionnaire de presse-papiers initialisé");
+				// `Auto` est résolu au premier collage, pour ne pas retarder l'ouverture
+				manager.set_paste_backend(config.paste_backend);
+				Some(Arc::new(Mutex::new(manager)))
+			}
+			Err(e) => {
+				error!("Erreur initialisation gestionnaire de presse-papiers: {}", e);
+				failures.push(StartupFailure::new("Accès au presse-papiers", &e));
+				None
+			}
+		}
+	}
+	
+	/// Retente les étapes du démarrage qui ont échoué
+	///
+	/// Les copies gardées dans l'historique en mémoire sont reportées dans la
+	/// base rouverte.
+	fn retry_startup(&mut self) -> Task<Message> {
+		let mut failures = Vec::new();
+		if self.clipboard_manager.is_none() {
+			self.clipboard_manager = Self::open_clipboard(&self.config, &mut failures);
+		}
+		
+		let mut task = Task::none();
+		if self.memory_storage {
+			let (storage, vault, storage_error) = Self::open_history(&self.config);
+			match storage_error {
+				Some(e) => self.storage_fallback = Self::storage_failure(e, &mut failures),
+				None => match self.storage.try_lock() {
+					Ok(mut current) => {
+						let memory = std::mem::replace(&mut *current, storage);
+						if let Err(e) = carry_over(memory.as_ref(), current.as_ref(), self.config.dedup_policy) {
+							warn!("Copies gardées en mémoire non reportées dans l'historique: {}", e);
+						}
+						info!("Historique rouvert");
+						self.vault = vault;
+						self.memory_storage = false;
+						self.storage_fallback = None;
+						self.storage_generation += 1;
+						task = if self.is_locked() { Task::none() } else { self.load_history() };
+					}
+					// Historique occupé par une opération en cours : l'utilisateur peut réessayer,
+					// l'historique reste en mémoire et le bandeau de secours reste affiché
+					Err(_) => {
+						let error = ClipboardError::StorageLocked("réouverture reportée, historique occupé".to_string());
+						self.storage_fallback = Self::storage_failure(error, &mut failures);
+					}
+				},
+			}
+		}
+		
+		self.startup_failures = failures;
+		task
+	}
+	
+	/// Indique si l'historique est chiffré et pas encore déverrouillé
+	fn is_locked(&self) -> bool {
+		self.vault.as_ref().is_some_and(|vault| vault.is_locked())
+	}
+
+	/// Charge la première page de l'historique, après un premier nettoyage
+	fn load_history(&self) -> Task<Message> {
+		Task::batch([
+			self.load_items(0, PAGE_SIZE, Message::ItemsLoaded),
+			Task::done(Message::ApplyRetention),
+		])
+	}
+
+	/// Applique une modification du stockage aux éléments chargés
+	///
+	/// Un élément modifié qui tombe après la dernière page chargée est laissé
+	/// de côté : il sera lu avec sa page. Pendant une recherche, les résultats
+	/// sont relus : seul le stockage sait lesquels restent retenus.
+	fn apply_change(&mut self, change: StorageChange) -> Task<Message> {
+		if change.reload || !self.search_query.is_empty() {
+			return self.update(Message::ReloadItems);
+		}
+
+		let fully_loaded = self.stored_len() >= self.total_items;
+		self.items.retain(|item| !change.removed.contains(&item.id));
+		for item in change.upserted {
+			self.items.retain(|loaded| loaded.id != item.id);
+			let position = self.items.partition_point(|loaded| loaded.timestamp > item.timestamp);
+			if position < self.items.len() || fully_loaded {
+				self.items.insert(position, item);
+			}
+		}
+
+		self.total_items = change.total;
+		self.ui_state.selected_index = self.ui_state.selected_index.min(self.items.len().saturating_sub(1));
+		Task::none()
+	}
+
+	/// Nombre d'éléments chargés depuis le stockage, hors copies gardées en mémoire
+	fn stored_len(&self) -> usize {
+		self.items.len() - self.memory_items.len()
+	}
+
+	/// Copie complète gardée en mémoire pour `id`
+	fn memory_item(&self, id: Uuid) -> Option<ClipboardItem> {
+		self.memory_items.iter().find(|item| item.id == id).cloned()
+	}
+
+	/// Garde une copie secrète en tête de la liste sans jamais l'enregistrer
+	fn keep_in_memory(&mut self, item: ClipboardItem) -> Task<Message> {
+		let hash = item.content_hash.clone();
+		self.forget_memory_items(|kept| kept.content_hash == hash);
+		if item.matches_search(&self.search_query) {
+			let position = self.items.partition_point(|loaded| loaded.timestamp > item.timestamp);
+			self.items.insert(position, item.clone());
+		}
+		self.memory_items.insert(0, item);
+		Task::none()
+	}
+
+	/// Oublie les copies gardées en mémoire qui vérifient `forget`
+	fn forget_memory_items(&mut self, forget: impl Fn(&ClipboardItem) -> bool) {
+		let forgotten: Vec<Uuid> = self.memory_items.iter().filter(|item| forget(item)).map(|item| item.id).collect();
+		self.memory_items.retain(|item| !forgotten.contains(&item.id));
+		self.items.retain(|item| !forgotten.contains(&item.id));
+		self.ui_state.selected_index = self.ui_state.selected_index.min(self.items.len().saturating_sub(1));
+	}
+
+	/// État de l'enregistrement des copies, s'il peut être commandé depuis l'application
+	///
+	/// Quand un démon enregistre les copies, sa pause n'est commandée qu'une fois son état connu.
+	fn recording_controls(&self) -> Option<RecordingState> {
+		(!self.daemon_running || self.daemon_reachable).then_some(self.recording)
+	}
+
+	/// Suspend ou reprend l'enregistrement des copies
+	///
+	/// Une pause limitée dans le temps prend fin d'elle-même à son échéance.
+	fn set_recording(&mut self, recording: RecordingState) -> Task<Message> {
+		match recording {
+			RecordingState::Recording => info!("Enregistrement des copies repris"),
+			RecordingState::Paused { until: None } => info!("Enregistrement des copies suspendu"),
+			RecordingState::Paused { until: Some(until) } => info!("Enregistrement des copies suspendu jusqu'à {}", until),
+		}
+		self.recording = recording;
+		let update = if self.daemon_running {
+			Self::control_daemon(Some(recording))
+		} else {
+			let handle = self.recording_handle.clone();
+			Task::perform(
+				async move {
+					*handle.lock().await = recording;
+				},
+				|_| Message::None,
+			)
+		};
+		Task::batch([update, Self::pause_timer(recording)])
+	}
+
+	/// Signale la fin d'une pause limitée dans le temps
+	fn pause_timer(recording: RecordingState) -> Task<Message> {
+		match recording {
+			RecordingState::Paused { until: Some(until) } => {
+				let delay = (until - Utc::now()).to_std().unwrap_or_default();
+				Task::perform(tokio::time::sleep(delay), move |_| Message::PauseElapsed(until))
+			}
+			_ => Task::none(),
+		}
+	}
+
+	/// Transmet au démon une pause ou une reprise, ou lui demande son état (`None`)
+	fn control_daemon(recording: Option<RecordingState>) -> Task<Message> {
+		Task::perform(
+			async move {
+				let mut client = IpcClient::connect_default().await?;
+				match recording {
+					None => client.status().await,
+					Some(RecordingState::Recording) => client.resume().await,
+					Some(RecordingState::Paused { until }) => {
+						// Arrondi à la minute supérieure : la requête part un instant après le choix du délai
+						let minutes = until.map(|until| ((until - Utc::now()).num_seconds().max(0) as u64).div_ceil(60));
+						client.pause(minutes).await
+					}
+				}
+			},
+			|result: ClipboardResult<RecordingState>| match result {
+				Ok(recording) => Message::RecordingChanged(recording),
+				Err(e) => {
+					error!("Erreur lors de la commande du démon: {}", e);
+					Message::None
+				}
+			},
+		)
+	}
+
+	/// Demande au démon, seul à écrire l'historique, de le modifier
+	///
+	/// La modification revient ensuite par le stockage, comme les copies enregistrées par le démon.
+	fn ask_daemon(context: &'static str, request: Request) -> Task<Message> {
+		Task::perform(
+			async move {
+				let mut client = IpcClient::connect_default().await?;
+				client.call(&request).await
+			},
+			move |result: ClipboardResult<Response>| match result {
+				Ok(_) => Message::None,
+				Err(e) => {
+					error!("{}: {}", context, e);
+					Message::ReloadItems
+				}
+			},
+		)
+	}
+
+	/// Lit dans l'historique la prochaine expiration, pour y programmer le nettoyage
+	fn schedule_retention(&self) -> Task<Message> {
+		let storage = self.storage.clone();
+		Task::perform(
+			async move { storage.lock().await.next_expiry() },
+			|result: ClipboardResult<Option<DateTime<Utc>>>| match result {
+				Ok(expires_at) => Message::NextExpiry(expires_at),
+				Err(e) => {
+					error!("Erreur lecture des expirations: {}", e);
+					Message::None
+				}
+			},
+		)
+	}
+
+	/// Relance le nettoyage de l'historique à l'expiration d'un élément
+	fn retention_at(expires_at: DateTime<Utc>) -> Task<Message> {
+		let delay = (expires_at - Utc::now()).to_std().unwrap_or_default();
+		Task::perform(tokio::time::sleep(delay), |_| Message::ApplyRetention)
+	}
+
+	/// Message produit à l'issue d'une opération sur le stockage
+	///
+	/// En cas d'erreur, l'état du stockage est incertain : l'historique est relu.
+	fn change_message(context: &'static str) -> impl Fn(ClipboardResult<StorageChange>) -> Message {
+		move |result| match result {
+			Ok(change) => Message::StorageChanged(change),
+			Err(e) => {
+				error!("{}: {}", context, e);
+				Message::ReloadItems
+			}
+		}
+	}
+
+	/// Charge un élément complet : la liste affichée ne contient que des éléments allégés
+	async fn load_full_item(storage: Arc<Mutex<Box<dyn Storage>>>, id: Uuid) -> ClipboardResult<ClipboardItem> {
+		storage
+			.lock()
+			.await
+			.get_item(id)?
+			.ok_or_else(|| ClipboardError::Storage(format!("Élément introuvable: {}", id)))
+	}
+
+	/// Charge une page des éléments retenus par la recherche en cours, puis la
+	/// transmet à `loaded` avec cette recherche
+	fn load_items(&self, offset: usize, limit: usize, loaded: fn(String, ItemPage) -> Message) -> Task<Message> {
+		let storage = self.storage.clone();
+		let query = self.search_query.clone();
+		Task::perform(Self::load_page(storage, query.clone(), offset, limit), move |page| loaded(query.clone(), page))
+	}
+
+	/// Charge une page d'éléments depuis le stockage
+	async fn load_page(storage: Arc<Mutex<Box<dyn Storage>>>, query: String, offset: usize, limit: usize) -> ItemPage {
 		let storage = storage.lock().await;
-		match storage.get_all_items() {
-			Ok(items) => items,
+		match storage.search(&query, offset, limit) {
+			Ok(page) => page,
 			Err(e) => {
 				error!("Erreur chargement éléments: {}", e);
-				Vec::new()
+				ItemPage { offset, ..ItemPage::default() }
 			}
 		}
 	}
@@ -333,3 +1198,19 @@ fn is_macos_dark_mode() -> bool {
 fn is_macos_dark_mode() -> bool {
 	false
 }
+
+/// Erreur d'une opération qui nécessite le presse-papiers, resté inaccessible
+fn clipboard_unavailable() -> ClipboardError {
+	ClipboardError::Clipboard("Presse-papiers inaccessible".to_string())
+}
+
+/// Reporte les éléments d'un historique dans un autre, en appliquant la politique de doublons
+fn carry_over(from: &dyn Storage, to: &dyn Storage, policy: DedupPolicy) -> ClipboardResult<()> {
+	// Du plus ancien au plus récent, pour conserver l'ordre de l'historique
+	for item in from.get_all_items()?.into_iter().rev() {
+		if let Some(item) = from.get_item(item.id)? {
+			to.record_item(item, policy)?;
+		}
+	}
+	to.flush()
+}
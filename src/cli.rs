@@ -0,0 +1,510 @@
+use crate::clipboard::{ClipboardItem, RecordingState, Selection};
+use crate::config::{get_default_config_path, Config, KeySource, StorageBackend};
+use crate::error::{ClipboardError, ClipboardResult};
+use crate::ipc::{HistoryCounts, IpcClient, IpcContext, ItemSummary, Request, Response};
+use crate::daemon::RecorderLock;
+use crate::storage::{create_storage, export_history, import_history, open_storage, EncryptedStorage, Storage, Vault};
+use crate::utils::format_size;
+use chrono::{DateTime, Local, Utc};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, IsTerminal, Read, Write};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+/// Nombre d'éléments affichés par défaut par `list` et `search`
+const DEFAULT_LIMIT: usize = 20;
+
+/// Aide affichée par `help`
+const USAGE: &str = "\
+Utilisation : clipboard-manager [commande]
+
+Sans commande, ouvre l'interface (--paused : enregistrement suspendu).
+
+Commandes :
+  daemon [--paused]            enregistre l'historique en arrière-plan
+  list [--limit N] [--json]    affiche les derniers éléments, numérotés à partir de 1
+  search <texte> [--limit N] [--json]
+                               cherche dans le texte et les chemins copiés
+  get <id|n> [--json]          affiche le contenu d'un élément
+  copy <id|n> [--primary]      remet un élément dans le presse-papiers
+  pin <id|n>, unpin <id|n>     épingle ou désépingle un élément
+  rm <id|n>                    supprime un élément
+  clear                        supprime les éléments non épinglés
+  export [fichier]             exporte l'historique en JSON (sortie standard par défaut)
+  import <fichier>             importe un export, sans dupliquer les éléments présents
+  stats                        résume l'historique
+  rekey [passphrase|keyfile|secret-service]
+                               remplace la clé de l'historique chiffré et le rechiffre";
+
+/// Élément désigné par son identifiant ou par sa position dans `list`, à partir de 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemRef {
+	Id(Uuid),
+	Index(usize),
+}
+
+impl ItemRef {
+	/// Interprète un identifiant ou une position
+	pub fn parse(value: &str) -> ClipboardResult<Self> {
+		if let Ok(id) = Uuid::parse_str(value) {
+			return Ok(ItemRef::Id(id));
+		}
+		match value.parse::<usize>() {
+			Ok(index) if index > 0 => Ok(ItemRef::Index(index)),
+			_ => Err(ClipboardError::Config(format!(
+				"Élément invalide: {} (identifiant ou position à partir de 1)",
+				value
+			))),
+		}
+	}
+}
+
+/// Sous-commande de gestion de l'historique
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+	List { limit: usize, json: bool },
+	Search { query: String, limit: usize, json: bool },
+	Get { item: ItemRef, json: bool },
+	Copy { item: ItemRef, selection: Selection },
+	Pin { item: ItemRef, pinned: bool },
+	Remove(ItemRef),
+	Clear,
+	Export(Option<PathBuf>),
+	Import(PathBuf),
+	Stats,
+	Rekey(Option<KeySource>),
+	Help,
+}
+
+impl Command {
+	/// Interprète les arguments ; `None` sans sous-commande, pour ouvrir l'interface
+	pub fn parse(args: &[String]) -> ClipboardResult<Option<Self>> {
+		let Some((name, rest)) = args.split_first() else {
+			return Ok(None);
+		};
+		// Lancement de l'interface ou du démon, qui n'acceptent que `--paused`
+		if name == "daemon" || (name.starts_with("--") && name != "--help") {
+			let launch_args = if name == "daemon" { rest } else { args };
+			if let Some(other) = launch_args.iter().find(|arg| *arg != "--paused") {
+				let message = if other.starts_with("--") {
+					format!("Option inconnue: {}", other)
+				} else {
+					format!("Argument en trop: {}", other)
+				};
+				return Err(usage_error(message));
+			}
+			return Ok(None);
+		}
+		let mut options = Options::parse(rest)?;
+
+		let command = match name.as_str() {
+			"list" => Command::List { limit: options.limit, json: options.json },
+			"search" => Command::Search { query: options.required("texte")?, limit: options.limit, json: options.json },
+			"get" => Command::Get { item: ItemRef::parse(&options.required("élément")?)?, json: options.json },
+			"copy" => Command::Copy {
+				item: ItemRef::parse(&options.required("élément")?)?,
+				selection: if options.primary { Selection::Primary } else { Selection::Clipboard },
+			},
+			"pin" | "unpin" => Command::Pin {
+				item: ItemRef::parse(&options.required("élément")?)?,
+				pinned: name == "pin",
+			},
+			"rm" => Command::Remove(ItemRef::parse(&options.required("élément")?)?),
+			"clear" => Command::Clear,
+			"export" => Command::Export(options.optional().map(PathBuf::from)),
+			"import" => Command::Import(PathBuf::from(options.required("fichier")?)),
+			"stats" => Command::Stats,
+			"rekey" => Command::Rekey(options.optional().map(|name| parse_key_source(&name)).transpose()?),
+			"help" | "--help" => Command::Help,
+			other => {
+				return Err(ClipboardError::Config(format!(
+					"Commande inconnue: {} (clipboard-manager help pour la liste)",
+					other
+				)))
+			}
+		};
+		if let Some(extra) = options.positional.first() {
+			return Err(usage_error(format!("Argument en trop pour {}: {}", name, extra)));
+		}
+		Ok(Some(command))
+	}
+}
+
+/// Options communes aux sous-commandes
+struct Options {
+	positional: Vec<String>,
+	limit: usize,
+	json: bool,
+	primary: bool,
+}
+
+impl Options {
+	fn parse(args: &[String]) -> ClipboardResult<Self> {
+		let mut options = Options { positional: Vec::new(), limit: DEFAULT_LIMIT, json: false, primary: false };
+		let mut args = args.iter();
+		while let Some(arg) = args.next() {
+			match arg.as_str() {
+				"--json" => options.json = true,
+				"--primary" => options.primary = true,
+				"--limit" => {
+					options.limit = args
+						.next()
+						.and_then(|value| value.parse().ok())
+						.ok_or_else(|| ClipboardError::Config("--limit attend un nombre".to_string()))?;
+				}
+				option if option.starts_with("--") => {
+					return Err(usage_error(format!("Option inconnue: {}", option)));
+				}
+				_ => options.positional.push(arg.clone()),
+			}
+		}
+		Ok(options)
+	}
+
+	/// Premier argument de la sous-commande, obligatoire
+	fn required(&mut self, name: &str) -> ClipboardResult<String> {
+		if self.positional.is_empty() {
+			return Err(ClipboardError::Config(format!("Argument manquant: <{}>", name)));
+		}
+		Ok(self.positional.remove(0))
+	}
+
+	/// Premier argument de la sous-commande, facultatif
+	fn optional(&mut self) -> Option<String> {
+		(!self.positional.is_empty()).then(|| self.positional.remove(0))
+	}
+}
+
+/// Erreur d'utilisation, suivie de l'aide
+fn usage_error(message: String) -> ClipboardError {
+	ClipboardError::Config(format!("{}\n\n{}", message, USAGE))
+}
+
+/// Exécute une sous-commande
+pub fn run(command: Command) -> ClipboardResult<()> {
+	match command {
+		Command::Help => {
+			println!("{}", USAGE);
+			Ok(())
+		}
+		Command::Rekey(target) => rekey(target),
+		command => tokio::runtime::Runtime::new()?.block_on(run_history_command(command)),
+	}
+}
+
+/// Exécute une sous-commande portant sur l'historique
+async fn run_history_command(command: Command) -> ClipboardResult<()> {
+	let config = Config::load(get_default_config_path())?;
+	let mut history = History::open(&config).await?;
+	match command {
+		Command::List { limit, json } => {
+			let (items, total) = history.call(Request::List { offset: 0, limit }).await?.into_items()?;
+			print_items(&items, total, json)?;
+		}
+		Command::Search { query, limit, json } => {
+			let (items, total) = history.call(Request::Search { query, limit }).await?.into_items()?;
+			print_items(&items, total, json)?;
+		}
+		Command::Get { item, json } => {
+			let id = history.resolve(item).await?;
+			let item = history.call(Request::Get { id }).await?.into_item()?;
+			if json {
+				println!("{}", serde_json::to_string_pretty(&item)?);
+			} else if let Some(text) = item.text {
+				print!("{}", text);
+				if std::io::stdout().is_terminal() && !text.ends_with('\n') {
+					println!();
+				}
+			} else {
+				println!("{} ({})", item.summary.preview, item.mime_types.join(", "));
+			}
+		}
+		Command::Copy { item, selection } => {
+			if matches!(history, History::Local(_)) {
+				return Err(ClipboardError::Clipboard(
+					"Aucun gestionnaire en cours d'exécution pour garder la copie : lancez clipboard-manager daemon"
+						.to_string(),
+				));
+			}
+			let id = history.resolve(item).await?;
+			history.call(Request::Restore { id, selection }).await?.into_done()?;
+		}
+		Command::Pin { item, pinned } => {
+			let id = history.resolve(item).await?;
+			history.call(Request::Pin { id, pinned }).await?.into_done()?;
+		}
+		Command::Remove(item) => {
+			let id = history.resolve(item).await?;
+			history.call(Request::Delete { id }).await?.into_done()?;
+		}
+		Command::Clear => {
+			let removed = history.call(Request::Clear).await?.into_cleared()?;
+			println!("{} éléments supprimés", removed);
+		}
+		Command::Export(path) => {
+			let items = history.call(Request::Export).await?.into_exported()?;
+			let count = match path {
+				Some(path) => write_export(&items, BufWriter::new(File::create(&path)?))?,
+				None => write_export(&items, std::io::stdout().lock())?,
+			};
+			eprintln!("{} éléments exportés", count);
+		}
+		Command::Import(path) => {
+			let items = serde_json::from_reader(BufReader::new(File::open(&path)?))?;
+			let (imported, skipped) = history.call(Request::Import { items }).await?.into_imported()?;
+			println!("{} éléments importés, {} déjà présents", imported, skipped);
+		}
+		Command::Stats => {
+			let counts = history.call(Request::Stats).await?.into_stats()?;
+			print!("{}", HistoryStats::from_counts(counts, &config));
+		}
+		Command::Rekey(_) | Command::Help => unreachable!("commande traitée sans l'historique"),
+	}
+	Ok(())
+}
+
+/// Accès à l'historique
+///
+/// Le processus qui enregistre l'historique, démon ou interface, est passé par
+/// son socket pour toutes les commandes, export, import et statistiques
+/// compris : les modifications lui parviennent, et le stockage Sled, qu'il
+/// verrouille, reste accessible. Sans lui, l'historique est ouvert directement.
+enum History {
+	Remote(IpcClient),
+	Local(IpcContext),
+}
+
+impl History {
+	async fn open(config: &Config) -> ClipboardResult<Self> {
+		if let Ok(client) = IpcClient::connect_default().await {
+			return Ok(History::Remote(client));
+		}
+		let storage = Arc::new(Mutex::new(open_local_storage(config)?));
+		Ok(History::Local(IpcContext::new(storage, Arc::new(Mutex::new(RecordingState::default())))))
+	}
+
+	async fn call(&mut self, request: Request) -> ClipboardResult<Response> {
+		match self {
+			History::Remote(client) => client.call(&request).await,
+			History::Local(context) => context.handle(request).await,
+		}
+	}
+
+	/// Identifiant de l'élément désigné
+	async fn resolve(&mut self, item: ItemRef) -> ClipboardResult<Uuid> {
+		match item {
+			ItemRef::Id(id) => Ok(id),
+			ItemRef::Index(index) => {
+				let (items, _) = self.call(Request::List { offset: index - 1, limit: 1 }).await?.into_items()?;
+				items
+					.first()
+					.map(|item| item.id)
+					.ok_or_else(|| ClipboardError::Storage(format!("Aucun élément en position {}", index)))
+			}
+		}
+	}
+}
+
+/// Ouvre l'historique de la configuration, en demandant la phrase secrète s'il le faut
+fn open_local_storage(config: &Config) -> ClipboardResult<Box<dyn Storage>> {
+	std::fs::create_dir_all(&config.data_dir)?;
+	let (storage, vault) = open_storage(config)?;
+	if let Some(vault) = vault.filter(|vault| vault.is_locked()) {
+		if !vault.is_initialized() {
+			return Err(ClipboardError::Storage(
+				"L'historique n'a pas encore de clé : ouvrez l'application pour la créer".to_string(),
+			));
+		}
+		vault.unlock(Some(&read_passphrase("Phrase secrète de l'historique: ")?))?;
+	}
+	Ok(storage)
+}
+
+/// Affiche des éléments, une ligne chacun, ou en JSON
+fn print_items(items: &[ItemSummary], total: usize, json: bool) -> ClipboardResult<()> {
+	if json {
+		println!("{}", serde_json::to_string_pretty(items)?);
+		return Ok(());
+	}
+	for (index, item) in items.iter().enumerate() {
+		println!(
+			"{:>3}  {}  {}  {}{}",
+			index + 1,
+			item.id,
+			item.timestamp.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+			if item.pinned { "* " } else { "" },
+			item.preview.replace(['\n', '\r', '\t'], " ")
+		);
+	}
+	if total > items.len() {
+		eprintln!("{} éléments sur {}", items.len(), total);
+	}
+	Ok(())
+}
+
+/// Écrit l'historique complet en JSON et retourne le nombre d'éléments exportés
+pub fn export_items(storage: &dyn Storage, writer: impl Write) -> ClipboardResult<usize> {
+	write_export(&export_history(storage)?, writer)
+}
+
+/// Importe un export JSON ; retourne le nombre d'éléments importés et ignorés
+///
+/// Les éléments déjà présents, par leur identifiant ou leur contenu, sont ignorés.
+pub fn import_items(storage: &dyn Storage, reader: impl Read) -> ClipboardResult<(usize, usize)> {
+	import_history(storage, serde_json::from_reader(reader)?)
+}
+
+/// Écrit des éléments en JSON et retourne leur nombre
+fn write_export(items: &[ClipboardItem], writer: impl Write) -> ClipboardResult<usize> {
+	serde_json::to_writer_pretty(writer, items)?;
+	Ok(items.len())
+}
+
+/// Résumé de l'historique
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HistoryStats {
+	pub items: usize,
+	pub texts: usize,
+	pub images: usize,
+	pub files: usize,
+	pub pinned: usize,
+	pub expiring: usize,
+	pub oldest: Option<DateTime<Utc>>,
+	pub newest: Option<DateTime<Utc>>,
+	/// Place occupée par le dossier de données, en octets
+	pub disk_usage: u64,
+	pub backend: StorageBackend,
+	pub encryption: KeySource,
+}
+
+impl HistoryStats {
+	/// Résume l'historique et le dossier de données de la configuration
+	pub fn collect(storage: &dyn Storage, config: &Config) -> ClipboardResult<Self> {
+		Ok(Self::from_counts(HistoryCounts::collect(storage)?, config))
+	}
+
+	/// Complète le décompte des éléments par le dossier de données de la configuration
+	pub fn from_counts(counts: HistoryCounts, config: &Config) -> Self {
+		HistoryStats {
+			items: counts.items,
+			texts: counts.texts,
+			images: counts.images,
+			files: counts.files,
+			pinned: counts.pinned,
+			expiring: counts.expiring,
+			oldest: counts.oldest,
+			newest: counts.newest,
+			disk_usage: dir_size(&config.data_dir),
+			backend: config.storage_backend,
+			encryption: config.encryption,
+		}
+	}
+}
+
+impl std::fmt::Display for HistoryStats {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let date = |date: Option<DateTime<Utc>>| {
+			date.map_or("-".to_string(), |date| date.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string())
+		};
+		writeln!(f, "Éléments : {} ({} épinglés, {} avec expiration)", self.items, self.pinned, self.expiring)?;
+		writeln!(f, "Textes : {}, images : {}, fichiers : {}", self.texts, self.images, self.files)?;
+		writeln!(f, "Plus ancien : {}, plus récent : {}", date(self.oldest), date(self.newest))?;
+		writeln!(
+			f,
+			"Stockage : {:?}, {} sur le disque, chiffrement : {}",
+			self.backend,
+			format_size(self.disk_usage),
+			self.encryption
+		)
+	}
+}
+
+/// Taille totale des fichiers d'un dossier et de ses sous-dossiers
+fn dir_size(path: &std::path::Path) -> u64 {
+	std::fs::read_dir(path)
+		.into_iter()
+		.flatten()
+		.flatten()
+		.map(|entry| match entry.metadata() {
+			Ok(metadata) if metadata.is_dir() => dir_size(&entry.path()),
+			Ok(metadata) => metadata.len(),
+			Err(_) => 0,
+		})
+		.sum()
+}
+
+/// Interprète le nom d'une source de clé donné en ligne de commande
+pub fn parse_key_source(name: &str) -> ClipboardResult<KeySource> {
+	match name {
+		"passphrase" => Ok(KeySource::Passphrase),
+		"keyfile" => Ok(KeySource::Keyfile),
+		"secret-service" => Ok(KeySource::SecretService),
+		other => Err(ClipboardError::Config(format!(
+			"Source de clé inconnue: {} (passphrase, keyfile ou secret-service)",
+			other
+		))),
+	}
+}
+
+/// Remplace la clé de l'historique chiffré et rechiffre tous ses éléments
+///
+/// `target` désigne la source qui protège la nouvelle clé, par défaut celle
+/// de la configuration. Les phrases secrètes sont demandées sur le terminal.
+/// Aucun autre processus ne doit enregistrer l'historique pendant ce temps.
+pub fn rekey(target: Option<KeySource>) -> ClipboardResult<()> {
+	let config_path = get_default_config_path();
+	let mut config = Config::load(&config_path)?;
+	if config.encryption == KeySource::None {
+		return Err(ClipboardError::Config("Le chiffrement de l'historique n'est pas activé".to_string()));
+	}
+	let _lock = RecorderLock::try_acquire(&config.data_dir)?.ok_or_else(|| {
+		ClipboardError::Unexpected(
+			"L'historique est en cours d'enregistrement : arrêtez le démon et l'interface avant de changer sa clé"
+				.to_string(),
+		)
+	})?;
+
+	config.move_legacy_keyfile()?;
+	let mut vault = Vault::new(&config.data_dir, config.encryption, config.keyfile());
+	if !vault.is_initialized() {
+		return Err(ClipboardError::Storage("L'historique n'a pas encore de clé".to_string()));
+	}
+
+	let current = match config.encryption {
+		KeySource::Passphrase => Some(read_passphrase("Phrase secrète actuelle: ")?),
+		_ => None,
+	};
+	vault.unlock(current.as_deref())?;
+
+	let target = target.unwrap_or(config.encryption);
+	let passphrase = match target {
+		KeySource::Passphrase => {
+			let passphrase = read_passphrase("Nouvelle phrase secrète: ")?;
+			if passphrase != read_passphrase("Confirmation: ")? {
+				return Err(ClipboardError::Config("Les deux phrases secrètes sont différentes".to_string()));
+			}
+			Some(passphrase)
+		}
+		_ => None,
+	};
+	vault.rekey(target, passphrase.as_deref())?;
+
+	if config.encryption != target {
+		config.encryption = target;
+		config.save(&config_path)?;
+	}
+	println!("Nouvelle clé de l'historique, protégée par {}", target);
+
+	// L'initialisation rechiffre les éléments, puis oublie l'ancienne clé
+	let storage = EncryptedStorage::new(create_storage(config.storage_backend, &config.data_dir)?, Arc::new(vault));
+	storage.init()?;
+	println!("Éléments de l'historique rechiffrés");
+	Ok(())
+}
+
+/// Lit une phrase secrète sur le terminal, sans l'afficher
+fn read_passphrase(prompt: &str) -> ClipboardResult<String> {
+	Ok(rpassword::prompt_password(prompt)?)
+}
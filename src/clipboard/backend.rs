@@ -0,0 +1,166 @@
+use crate::clipboard::{mime, ClipboardContent, FileEntry, ImageMetadata, MimeData, Selection};
+use crate::error::{ClipboardError, ClipboardResult};
+use arboard::{Clipboard, ImageData};
+
+/// Accès bas niveau aux sélections du système
+///
+/// `ClipboardManager` et `ClipboardWatcher` passent par ce trait : les tests
+/// leur donnent un presse-papiers en mémoire (`MemoryClipboard`).
+pub trait ClipboardBackend: Send + 'static {
+	/// Contenu principal de la sélection, `None` si elle est vide
+	fn read(&mut self, selection: Selection) -> ClipboardResult<Option<ClipboardContent>>;
+
+	/// Toutes les représentations MIME offertes par la sélection
+	fn read_formats(&mut self, selection: Selection) -> Vec<MimeData>;
+
+	/// Offre un contenu dans la sélection
+	///
+	/// Les représentations d'origine (`formats`), si elles sont fournies, sont
+	/// offertes de préférence au seul contenu principal.
+	fn write(&mut self, content: &ClipboardContent, formats: &[MimeData], selection: Selection) -> ClipboardResult<()>;
+
+	/// Vide la sélection
+	fn clear(&mut self, selection: Selection) -> ClipboardResult<()>;
+}
+
+impl ClipboardBackend for Box<dyn ClipboardBackend> {
+	fn read(&mut self, selection: Selection) -> ClipboardResult<Option<ClipboardContent>> {
+		(**self).read(selection)
+	}
+
+	fn read_formats(&mut self, selection: Selection) -> Vec<MimeData> {
+		(**self).read_formats(selection)
+	}
+
+	fn write(&mut self, content: &ClipboardContent, formats: &[MimeData], selection: Selection) -> ClipboardResult<()> {
+		(**self).write(content, formats, selection)
+	}
+
+	fn clear(&mut self, selection: Selection) -> ClipboardResult<()> {
+		(**self).clear(selection)
+	}
+}
+
+/// Presse-papiers du système, via arboard et, sous Wayland, wl-clipboard-rs
+pub struct ArboardBackend {
+	clipboard: Clipboard,
+}
+
+impl ArboardBackend {
+	/// Se connecte au presse-papiers de la session
+	pub fn new() -> ClipboardResult<Self> {
+		let clipboard = Clipboard::new().map_err(|e| ClipboardError::Clipboard(e.to_string()))?;
+		Ok(Self { clipboard })
+	}
+
+	/// Prépare une lecture ciblant la sélection indiquée
+	fn getter(&mut self, selection: Selection) -> arboard::Get<'_> {
+		#[cfg(target_os = "linux")]
+		{
+			use arboard::GetExtLinux;
+			self.clipboard.get().clipboard(selection.into())
+		}
+		#[cfg(not(target_os = "linux"))]
+		{
+			let _ = selection;
+			self.clipboard.get()
+		}
+	}
+
+	/// Prépare une écriture ciblant la sélection indiquée
+	fn setter(&mut self, selection: Selection) -> arboard::Set<'_> {
+		#[cfg(target_os = "linux")]
+		{
+			use arboard::SetExtLinux;
+			self.clipboard.set().clipboard(selection.into())
+		}
+		#[cfg(not(target_os = "linux"))]
+		{
+			let _ = selection;
+			self.clipboard.set()
+		}
+	}
+}
+
+impl ClipboardBackend for ArboardBackend {
+	fn read(&mut self, selection: Selection) -> ClipboardResult<Option<ClipboardContent>> {
+		// Une liste de fichiers est aussi offerte sous forme de texte : la tester en premier
+		if let Ok(paths) = self.getter(selection).file_list() {
+			if !paths.is_empty() {
+				let entries = paths.into_iter().map(FileEntry::from_path).collect();
+				return Ok(Some(ClipboardContent::Files(entries)));
+			}
+		}
+
+		// Ensuite, essayons de récupérer le texte
+		if let Ok(text) = self.getter(selection).text() {
+			if !text.is_empty() {
+				return Ok(Some(ClipboardContent::Text(text)));
+			}
+		}
+
+		// Si ce n'est pas du texte, essayons une image
+		match self.getter(selection).image() {
+			Ok(image) => {
+				let metadata = ImageMetadata::new(image.width, image.height);
+
+				// Conversion en Vec<u8> pour la sérialisation
+				Ok(Some(ClipboardContent::Image(image.bytes.to_vec(), metadata)))
+			}
+			Err(_) => Ok(None),
+		}
+	}
+
+	fn read_formats(&mut self, selection: Selection) -> Vec<MimeData> {
+		mime::read_formats(selection)
+	}
+
+	fn write(&mut self, content: &ClipboardContent, formats: &[MimeData], selection: Selection) -> ClipboardResult<()> {
+		// Restaurer toutes les représentations d'origine si possible
+		if !formats.is_empty() {
+			match mime::write_formats(formats, selection) {
+				Ok(()) => return Ok(()),
+				Err(e) => log::warn!("Restauration multi-formats impossible: {}. Repli sur le contenu principal.", e),
+			}
+		}
+
+		match content {
+			ClipboardContent::Text(text) => {
+				self.setter(selection)
+					.text(text.clone())
+					.map_err(|e| ClipboardError::Clipboard(e.to_string()))?;
+			}
+			ClipboardContent::Image(data, metadata) => {
+				let image = ImageData {
+					width: metadata.width,
+					height: metadata.height,
+					bytes: data.as_slice().into(),
+				};
+				self.setter(selection)
+					.image(image)
+					.map_err(|e| ClipboardError::Clipboard(e.to_string()))?;
+			}
+			ClipboardContent::Files(entries) => {
+				let paths: Vec<_> = entries.iter().map(|entry| &entry.path).collect();
+				self.setter(selection)
+					.file_list(&paths)
+					.map_err(|e| ClipboardError::Clipboard(e.to_string()))?;
+			}
+		}
+		Ok(())
+	}
+
+	fn clear(&mut self, selection: Selection) -> ClipboardResult<()> {
+		#[cfg(target_os = "linux")]
+		let result = {
+			use arboard::ClearExtLinux;
+			self.clipboard.clear_with().clipboard(selection.into())
+		};
+		#[cfg(not(target_os = "linux"))]
+		let result = {
+			let _ = selection;
+			self.clipboard.clear()
+		};
+		result.map_err(|e| ClipboardError::Clipboard(e.to_string()))
+	}
+}
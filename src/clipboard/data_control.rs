@@ -0,0 +1,104 @@
+use crate::clipboard::Selection;
+use crate::error::{ClipboardError, ClipboardResult};
+use tokio::sync::mpsc;
+
+/// Canal de notification des changements de sélection
+pub type SelectionNotifier = mpsc::UnboundedSender<Selection>;
+
+/// Écoute des changements de sélection via le protocole wlr-data-control
+///
+/// Le compositeur notifie chaque nouvelle copie : aucun réveil n'a lieu tant
+/// que le presse-papiers ne change pas.
+pub struct DataControlListener {
+	#[cfg(feature = "wayland")]
+	cancel: wl_clipboard_rs::watch::CancelHandle,
+}
+
+#[cfg(feature = "wayland")]
+impl DataControlListener {
+	/// Démarre l'écoute dans un thread dédié
+	///
+	/// Échoue immédiatement si aucun compositeur Wayland n'est joignable ou
+	/// s'il ne propose pas le protocole data-control. Si la sélection PRIMARY
+	/// est demandée mais non supportée, seul le presse-papiers est écouté.
+	pub fn spawn(notifier: SelectionNotifier, track_primary: bool) -> ClipboardResult<Self> {
+		use std::sync::mpsc as std_mpsc;
+		use wl_clipboard_rs::paste::{self, Seat};
+		use wl_clipboard_rs::watch::{ClipboardEvent, ClipboardType, Watcher};
+
+		let (init_tx, init_rx) = std_mpsc::channel();
+
+		std::thread::Builder::new()
+			.name("data-control".to_string())
+			.spawn(move || {
+				let watcher = if track_primary {
+					Watcher::new(ClipboardType::Both, Seat::Unspecified).or_else(|e| {
+						log::warn!("Sélection PRIMARY non suivie: {}", e);
+						Watcher::new(ClipboardType::Regular, Seat::Unspecified)
+					})
+				} else {
+					Watcher::new(ClipboardType::Regular, Seat::Unspecified)
+				};
+
+				let mut watcher = match watcher {
+					Ok(watcher) => {
+						let _ = init_tx.send(Ok(watcher.cancel_handle()));
+						watcher
+					}
+					Err(e) => {
+						let _ = init_tx.send(Err(e.to_string()));
+						return;
+					}
+				};
+
+				loop {
+					match watcher.next_event() {
+						Ok(Some(ClipboardEvent::Changed { clipboard, .. })) => {
+							let selection = match clipboard {
+								paste::ClipboardType::Regular => Selection::Clipboard,
+								paste::ClipboardType::Primary => Selection::Primary,
+							};
+							// Le récepteur a été fermé : plus personne n'écoute
+							if notifier.send(selection).is_err() {
+								break;
+							}
+						}
+						Ok(Some(ClipboardEvent::Cleared { .. })) => {}
+						Ok(None) => break,
+						Err(e) => {
+							log::error!("Erreur du protocole data-control: {}", e);
+							break;
+						}
+					}
+				}
+
+				log::debug!("Écoute data-control terminée");
+			})
+			.map_err(ClipboardError::Io)?;
+
+		let cancel = init_rx
+			.recv()
+			.map_err(|e| ClipboardError::Clipboard(format!("Thread data-control interrompu: {}", e)))?
+			.map_err(|e| ClipboardError::Clipboard(format!("Data-control indisponible: {}", e)))?;
+
+		Ok(Self { cancel })
+	}
+
+	/// Arrête l'écoute et libère le thread
+	pub fn cancel(&self) {
+		self.cancel.cancel();
+	}
+}
+
+#[cfg(not(feature = "wayland"))]
+impl DataControlListener {
+	/// Le support Wayland n'est pas compilé : l'écoute est indisponible
+	pub fn spawn(_notifier: SelectionNotifier, _track_primary: bool) -> ClipboardResult<Self> {
+		Err(ClipboardError::Clipboard(
+			"Support Wayland non compilé (fonctionnalité `wayland`)".to_string(),
+		))
+	}
+
+	/// Sans effet sans le support Wayland
+	pub fn cancel(&self) {}
+}
@@ -0,0 +1,128 @@
+use crate::clipboard::MimeData;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Type MIME standard des listes de fichiers
+pub const URI_LIST_MIME: &str = "text/uri-list";
+
+/// Type MIME utilisé par les gestionnaires de fichiers GNOME
+pub const GNOME_COPIED_FILES_MIME: &str = "x-special/gnome-copied-files";
+
+/// Un fichier ou dossier référencé par une copie
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileEntry {
+	pub path: PathBuf,
+	/// Taille au moment de la copie (absente pour les dossiers)
+	pub size: Option<u64>,
+	pub is_dir: bool,
+}
+
+impl FileEntry {
+	/// Crée une entrée à partir des métadonnées actuelles du chemin
+	pub fn from_path(path: PathBuf) -> Self {
+		let metadata = std::fs::metadata(&path).ok();
+		let is_dir = metadata.as_ref().map(|m| m.is_dir()).unwrap_or(false);
+		let size = metadata.filter(|m| m.is_file()).map(|m| m.len());
+
+		Self { path, size, is_dir }
+	}
+
+	/// Nom affiché du fichier
+	pub fn name(&self) -> String {
+		self.path
+			.file_name()
+			.map(|name| name.to_string_lossy().into_owned())
+			.unwrap_or_else(|| self.path.display().to_string())
+	}
+
+	/// Vérifie que le fichier référencé existe toujours
+	pub fn exists(&self) -> bool {
+		self.path.exists()
+	}
+}
+
+/// Extrait les chemins d'une liste `text/uri-list`
+pub fn parse_uri_list(text: &str) -> Vec<PathBuf> {
+	text.lines()
+		.map(str::trim)
+		.filter(|line| !line.is_empty() && !line.starts_with('#'))
+		.filter_map(uri_to_path)
+		.collect()
+}
+
+/// Extrait les chemins d'un contenu `x-special/gnome-copied-files`
+///
+/// La première ligne indique l'opération (`copy` ou `cut`), les suivantes les URI.
+pub fn parse_gnome_copied_files(text: &str) -> Vec<PathBuf> {
+	let mut lines = text.lines();
+	match lines.next().map(str::trim) {
+		Some("copy") | Some("cut") => parse_uri_list(&lines.collect::<Vec<_>>().join("\n")),
+		_ => Vec::new(),
+	}
+}
+
+/// Construit une liste de fichiers à partir des représentations MIME offertes
+pub fn files_from_formats(formats: &[MimeData]) -> Option<Vec<FileEntry>> {
+	let paths = formats.iter().find_map(|format| {
+		let text = std::str::from_utf8(&format.data).ok()?;
+		let paths = match format.mime_type.as_str() {
+			URI_LIST_MIME => parse_uri_list(text),
+			GNOME_COPIED_FILES_MIME => parse_gnome_copied_files(text),
+			_ => return None,
+		};
+		(!paths.is_empty()).then_some(paths)
+	})?;
+
+	Some(paths.into_iter().map(FileEntry::from_path).collect())
+}
+
+/// Sérialise des chemins au format `text/uri-list`
+pub fn to_uri_list<P: AsRef<Path>>(paths: &[P]) -> String {
+	paths
+		.iter()
+		.map(|path| path_to_uri(path.as_ref()))
+		.collect::<Vec<_>>()
+		.join("\r\n")
+}
+
+/// Convertit une URI `file://` en chemin local
+fn uri_to_path(uri: &str) -> Option<PathBuf> {
+	let rest = uri.strip_prefix("file://")?;
+	// Ignorer un éventuel nom d'hôte (file://hote/chemin)
+	let path = &rest[rest.find('/')?..];
+	percent_decode(path).map(PathBuf::from)
+}
+
+/// Convertit un chemin local en URI `file://`
+fn path_to_uri(path: &Path) -> String {
+	let mut uri = String::from("file://");
+	for byte in path.to_string_lossy().bytes() {
+		match byte {
+			b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => {
+				uri.push(byte as char)
+			}
+			_ => uri.push_str(&format!("%{:02X}", byte)),
+		}
+	}
+	uri
+}
+
+/// Décode les séquences `%XX` d'une URI
+fn percent_decode(input: &str) -> Option<String> {
+	let bytes = input.as_bytes();
+	let mut decoded = Vec::with_capacity(bytes.len());
+	let mut i = 0;
+
+	while i < bytes.len() {
+		if bytes[i] == b'%' {
+			let hex = input.get(i + 1..i + 3)?;
+			decoded.push(u8::from_str_radix(hex, 16).ok()?);
+			i += 3;
+		} else {
+			decoded.push(bytes[i]);
+			i += 1;
+		}
+	}
+
+	String::from_utf8(decoded).ok()
+}
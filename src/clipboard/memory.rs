@@ -0,0 +1,73 @@
+use crate::clipboard::{ClipboardBackend, ClipboardContent, MimeData, Selection};
+use crate::error::ClipboardResult;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Contenu d'une sélection du presse-papiers en mémoire
+#[derive(Debug, Clone)]
+struct Offer {
+	content: ClipboardContent,
+	formats: Vec<MimeData>,
+}
+
+/// Presse-papiers en mémoire, pour les tests
+///
+/// Les copies d'un `MemoryClipboard` partagent les mêmes sélections : le test
+/// garde une copie pour simuler les copies de l'utilisateur (`copy`) et
+/// vérifier ce que le gestionnaire a restauré (`content`).
+#[derive(Debug, Clone, Default)]
+pub struct MemoryClipboard {
+	selections: Arc<Mutex<HashMap<Selection, Offer>>>,
+}
+
+impl MemoryClipboard {
+	/// Crée un presse-papiers vide
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Simule une copie de l'utilisateur dans la sélection indiquée
+	pub fn copy(&self, selection: Selection, content: ClipboardContent) {
+		self.copy_with_formats(selection, content, Vec::new());
+	}
+
+	/// Simule une copie offrant plusieurs représentations
+	pub fn copy_with_formats(&self, selection: Selection, content: ClipboardContent, formats: Vec<MimeData>) {
+		self.lock().insert(selection, Offer { content, formats });
+	}
+
+	/// Contenu actuel de la sélection indiquée
+	pub fn content(&self, selection: Selection) -> Option<ClipboardContent> {
+		self.lock().get(&selection).map(|offer| offer.content.clone())
+	}
+
+	/// Représentations actuellement offertes par la sélection indiquée
+	pub fn formats(&self, selection: Selection) -> Vec<MimeData> {
+		self.lock().get(&selection).map(|offer| offer.formats.clone()).unwrap_or_default()
+	}
+
+	/// Accès aux sélections, même après la panique d'un autre thread de test
+	fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<Selection, Offer>> {
+		self.selections.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+	}
+}
+
+impl ClipboardBackend for MemoryClipboard {
+	fn read(&mut self, selection: Selection) -> ClipboardResult<Option<ClipboardContent>> {
+		Ok(self.content(selection))
+	}
+
+	fn read_formats(&mut self, selection: Selection) -> Vec<MimeData> {
+		self.formats(selection)
+	}
+
+	fn write(&mut self, content: &ClipboardContent, formats: &[MimeData], selection: Selection) -> ClipboardResult<()> {
+		self.copy_with_formats(selection, content.clone(), formats.to_vec());
+		Ok(())
+	}
+
+	fn clear(&mut self, selection: Selection) -> ClipboardResult<()> {
+		self.lock().remove(&selection);
+		Ok(())
+	}
+}
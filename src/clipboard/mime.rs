@@ -0,0 +1,115 @@
+use crate::clipboard::{MimeData, Selection};
+use crate::error::ClipboardResult;
+
+/// Taille maximale conservée pour une représentation MIME (16 Mo)
+pub const MAX_FORMAT_SIZE: usize = 16 * 1024 * 1024;
+
+/// Indique si un type MIME doit être conservé dans l'historique
+///
+/// Les cibles X11 (`TARGETS`, `UTF8_STRING`...) ne sont que des alias,
+/// régénérés automatiquement lors de la restauration.
+pub fn is_persisted_mime(mime_type: &str) -> bool {
+	mime_type.contains('/')
+}
+
+/// Lit toutes les représentations MIME offertes par le presse-papiers
+///
+/// La lecture est opportuniste : une représentation illisible ou trop
+/// volumineuse est ignorée, et une session sans data-control n'en fournit aucune.
+#[cfg(feature = "wayland")]
+pub fn read_formats(selection: Selection) -> Vec<MimeData> {
+	use std::io::Read;
+	use wl_clipboard_rs::paste::{get_contents, get_mime_types_ordered, ClipboardType, MimeType, Seat};
+
+	let clipboard = match selection {
+		Selection::Clipboard => ClipboardType::Regular,
+		Selection::Primary => ClipboardType::Primary,
+	};
+
+	let mime_types = match get_mime_types_ordered(clipboard, Seat::Unspecified) {
+		Ok(mime_types) => mime_types,
+		Err(e) => {
+			log::debug!("Types MIME indisponibles: {}", e);
+			return Vec::new();
+		}
+	};
+
+	let mut formats = Vec::new();
+	// L'indication d'une copie secrète est lue pour que le filtre des secrets la voie
+	let offered = mime_types
+		.into_iter()
+		.filter(|m| is_persisted_mime(m) || crate::clipboard::secrets::is_secret_hint_mime(m));
+	for mime_type in offered {
+		let pipe = match get_contents(clipboard, Seat::Unspecified, MimeType::Specific(&mime_type)) {
+			Ok((pipe, _)) => pipe,
+			Err(e) => {
+				log::debug!("Lecture {} impossible: {}", mime_type, e);
+				continue;
+			}
+		};
+
+		let mut data = Vec::new();
+		if let Err(e) = pipe.take(MAX_FORMAT_SIZE as u64 + 1).read_to_end(&mut data) {
+			log::debug!("Lecture {} interrompue: {}", mime_type, e);
+			continue;
+		}
+		if data.len() > MAX_FORMAT_SIZE {
+			log::debug!("Représentation {} ignorée (plus de {} octets)", mime_type, MAX_FORMAT_SIZE);
+			continue;
+		}
+
+		formats.push(MimeData::new(mime_type, data));
+	}
+
+	formats
+}
+
+/// Sans support Wayland, seules les représentations d'arboard sont disponibles
+#[cfg(not(feature = "wayland"))]
+pub fn read_formats(_selection: Selection) -> Vec<MimeData> {
+	Vec::new()
+}
+
+/// Offre toutes les représentations d'un élément dans le presse-papiers
+#[cfg(feature = "wayland")]
+pub fn write_formats(formats: &[MimeData], selection: Selection) -> ClipboardResult<()> {
+	use crate::error::ClipboardError;
+	use wl_clipboard_rs::copy::{ClipboardType, MimeSource, MimeType, Options, Source};
+
+	let sources = formats
+		.iter()
+		.map(|format| MimeSource {
+			source: Source::Bytes(format.data.clone().into_boxed_slice()),
+			mime_type: MimeType::Specific(format.mime_type.clone()),
+		})
+		.collect();
+
+	let mut options = Options::new();
+	options.clipboard(match selection {
+		Selection::Clipboard => ClipboardType::Regular,
+		Selection::Primary => ClipboardType::Primary,
+	});
+	options
+		.copy_multi(sources)
+		.map_err(|e| ClipboardError::Clipboard(e.to_string()))
+}
+
+/// Sans support Wayland, la restauration multi-formats est indisponible
+#[cfg(not(feature = "wayland"))]
+pub fn write_formats(_formats: &[MimeData], _selection: Selection) -> ClipboardResult<()> {
+	Err(crate::error::ClipboardError::Clipboard(
+		"Support Wayland non compilé (fonctionnalité `wayland`)".to_string(),
+	))
+}
+
+/// Libellé court d'un type MIME pour l'affichage
+pub fn mime_label(mime_type: &str) -> String {
+	match mime_type {
+		"text/html" => "HTML".to_string(),
+		"text/rtf" | "application/rtf" | "text/richtext" => "RTF".to_string(),
+		"text/uri-list" => "URI".to_string(),
+		_ if mime_type.starts_with("text/plain") => "Texte".to_string(),
+		_ if mime_type.starts_with("image/") => mime_type["image/".len()..].to_uppercase(),
+		_ => mime_type.to_string(),
+	}
+}
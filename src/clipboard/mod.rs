@@ -1,36 +1,220 @@
+mod backend;
+mod data_control;
+pub mod files;
+mod memory;
+pub mod mime;
+pub mod paste;
+pub mod rules;
+pub mod secrets;
+pub mod source;
+mod session;
+pub mod virtual_keyboard;
 mod watcher;
 
-pub use watcher::ClipboardWatcher;
+pub use backend::{ArboardBackend, ClipboardBackend};
+pub use data_control::SelectionNotifier;
+pub use files::FileEntry;
+pub use memory::MemoryClipboard;
+pub use rules::{CaptureRules, CaptureRulesHandle};
+pub use secrets::{SecretFilter, SecretFilterHandle};
+pub use paste::Keystroke;
+pub use source::SourceApplication;
+pub use watcher::{ClipboardEventReceiver, ClipboardWatcher, PauseHandle, PollingHandle, PollingSettings, RecordingState};
 
-use crate::error::{ClipboardError, ClipboardResult};
-use arboard::{Clipboard, ImageData};
+use crate::config::PasteBackend;
+use crate::error::ClipboardResult;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::hash_map::DefaultHasher;
 use std::fmt;
+use std::hash::{Hash, Hasher};
 use uuid::Uuid;
-use std::process::{Command, Stdio};
 
 /// Types d'éléments pouvant être stockés dans le presse-papiers
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ClipboardContent {
 	Text(String),
 	Image(Vec<u8>, ImageMetadata),
+	Files(Vec<FileEntry>),
 }
 
+impl ClipboardContent {
+	/// Empreinte SHA-256 du contenu, en hexadécimal
+	///
+	/// Deux contenus identiques ont la même empreinte quelle que soit leur origine,
+	/// ce qui permet de les comparer sans relire leurs données.
+	pub fn content_hash(&self) -> String {
+		let mut hasher = Sha256::new();
+		match self {
+			ClipboardContent::Text(text) => {
+				hasher.update(b"text\0");
+				hasher.update(text.as_bytes());
+			}
+			ClipboardContent::Image(data, metadata) => {
+				hasher.update(b"image\0");
+				hasher.update((metadata.width as u64).to_le_bytes());
+				hasher.update((metadata.height as u64).to_le_bytes());
+				hasher.update(data);
+			}
+			ClipboardContent::Files(entries) => {
+				hasher.update(b"files\0");
+				for entry in entries {
+					hasher.update(entry.path.to_string_lossy().as_bytes());
+					hasher.update(b"\0");
+				}
+			}
+		}
+
+		hasher
+			.finalize()
+			.iter()
+			.map(|byte| format!("{:02x}", byte))
+			.collect()
+	}
+
+	/// Clé de comparaison rapide, pour détecter un changement sans calculer l'empreinte
+	///
+	/// Une image est résumée par ses dimensions, sa taille et un échantillon
+	/// régulier de ses pixels : une modification aux mêmes dimensions qui
+	/// n'atteint aucun échantillon passe inaperçue.
+	pub fn quick_key(&self) -> u64 {
+		let mut hasher = DefaultHasher::new();
+		match self {
+			ClipboardContent::Text(text) => {
+				hasher.write_u8(0);
+				text.hash(&mut hasher);
+			}
+			ClipboardContent::Image(data, metadata) => {
+				hasher.write_u8(1);
+				metadata.width.hash(&mut hasher);
+				metadata.height.hash(&mut hasher);
+				data.len().hash(&mut hasher);
+				let stride = (data.len() / IMAGE_SAMPLES).max(IMAGE_SAMPLE_SIZE);
+				for start in (0..data.len()).step_by(stride) {
+					hasher.write(&data[start..data.len().min(start + IMAGE_SAMPLE_SIZE)]);
+				}
+				hasher.write(&data[data.len().saturating_sub(IMAGE_SAMPLE_SIZE)..]);
+			}
+			ClipboardContent::Files(entries) => {
+				hasher.write_u8(2);
+				for entry in entries {
+					entry.path.hash(&mut hasher);
+				}
+			}
+		}
+		hasher.finish()
+	}
+}
+
+/// Nombre d'échantillons d'une image dans sa clé de comparaison rapide
+const IMAGE_SAMPLES: usize = 4096;
+
+/// Taille d'un échantillon (en octets, quatre pixels RGBA)
+const IMAGE_SAMPLE_SIZE: usize = 16;
+
 /// Métadonnées pour les images
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ImageMetadata {
 	pub width: usize,
 	pub height: usize,
+	/// Aperçu réduit encodé en PNG, affiché dans l'historique
+	#[serde(default, with = "serde_bytes", skip_serializing_if = "Vec::is_empty")]
+	pub thumbnail: Vec<u8>,
+	/// Clé de l'image complète dans le stockage, si ses pixels n'ont pas été chargés
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub blob: Option<String>,
+}
+
+impl ImageMetadata {
+	/// Métadonnées d'une image dont les pixels sont en mémoire
+	pub fn new(width: usize, height: usize) -> Self {
+		Self {
+			width,
+			height,
+			thumbnail: Vec::new(),
+			blob: None,
+		}
+	}
+}
+
+/// Une représentation du contenu sous un type MIME donné
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MimeData {
+	pub mime_type: String,
+	#[serde(with = "serde_bytes")]
+	pub data: Vec<u8>,
+	/// Clé des données dans le stockage, si elles n'ont pas été chargées
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub blob: Option<String>,
+}
+
+impl MimeData {
+	/// Représentation dont les données sont en mémoire
+	pub fn new(mime_type: impl Into<String>, data: Vec<u8>) -> Self {
+		Self {
+			mime_type: mime_type.into(),
+			data,
+			blob: None,
+		}
+	}
+}
+
+/// Sélection dont provient ou vers laquelle est restauré un élément
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum Selection {
+	/// Presse-papiers standard (copier/coller explicite)
+	#[default]
+	Clipboard,
+	/// Sélection PRIMARY (texte sélectionné, collé au clic milieu)
+	Primary,
+}
+
+impl fmt::Display for Selection {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Selection::Clipboard => write!(f, "Presse-papiers"),
+			Selection::Primary => write!(f, "Sélection primaire"),
+		}
+	}
 }
 
 /// Un élément du presse-papiers avec ses métadonnées
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ClipboardItem {
 	pub id: Uuid,
 	pub content: ClipboardContent,
 	pub timestamp: DateTime<Utc>,
 	pub pinned: bool,
+	/// Toutes les représentations offertes lors de la copie, dans l'ordre de la source
+	#[serde(default)]
+	pub formats: Vec<MimeData>,
+	/// Type MIME privilégié par l'application source
+	#[serde(default)]
+	pub preferred_mime: Option<String>,
+	/// Sélection dont provient l'élément
+	#[serde(default)]
+	pub selection: Selection,
+	/// Empreinte du contenu, utilisée pour détecter les doublons
+	#[serde(default)]
+	pub content_hash: String,
+	/// Nombre de fois où ce contenu a été copié
+	#[serde(default = "default_copy_count")]
+	pub copy_count: u32,
+	/// Dernière copie ou utilisation de ce contenu
+	#[serde(default)]
+	pub last_used: Option<DateTime<Utc>>,
+	/// Date à laquelle l'élément est retiré de l'historique
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub expires_at: Option<DateTime<Utc>>,
+	/// Élément secret conservé en mémoire seulement, jamais enregistré
+	#[serde(skip)]
+	pub memory_only: bool,
+}
+
+/// Valeur par défaut du compteur de copies pour les éléments enregistrés sans lui
+fn default_copy_count() -> u32 {
+	1
 }
 
 impl ClipboardItem {
@@ -38,10 +222,64 @@ impl ClipboardItem {
 	pub fn new(content: ClipboardContent) -> Self {
 		Self {
 			id: Uuid::new_v4(),
+			content_hash: content.content_hash(),
 			content,
 			timestamp: Utc::now(),
 			pinned: false,
+			formats: Vec::new(),
+			preferred_mime: None,
+			selection: Selection::Clipboard,
+			copy_count: 1,
+			last_used: None,
+			expires_at: None,
+			memory_only: false,
+		}
+	}
+
+	/// Empreinte du contenu, recalculée si l'élément a été enregistré sans elle
+	pub fn hash(&self) -> String {
+		if self.content_hash.is_empty() {
+			self.content.content_hash()
+		} else {
+			self.content_hash.clone()
+		}
+	}
+
+	/// Fusionne une nouvelle copie du même contenu dans cet élément
+	///
+	/// Les représentations, la sélection d'origine et l'expiration sont celles de
+	/// la copie la plus récente ; `move_to_top` ramène l'élément en tête de l'historique.
+	pub fn merge_copy(&mut self, copy: ClipboardItem, move_to_top: bool) {
+		let now = copy.timestamp;
+		if move_to_top {
+			self.timestamp = now;
 		}
+		self.copy_count = self.copy_count.saturating_add(1);
+		self.last_used = Some(now);
+		self.formats = copy.formats;
+		self.preferred_mime = copy.preferred_mime;
+		self.selection = copy.selection;
+		self.expires_at = copy.expires_at.or(self.expires_at);
+	}
+
+	/// Indique la sélection dont provient l'élément
+	pub fn with_selection(mut self, selection: Selection) -> Self {
+		self.selection = selection;
+		self
+	}
+
+	/// Associe les représentations MIME offertes, la première étant la privilégiée
+	pub fn with_formats(mut self, formats: Vec<MimeData>) -> Self {
+		self.preferred_mime = formats.first().map(|format| format.mime_type.clone());
+		self.formats = formats;
+		self
+	}
+
+	/// Indique si l'élément porte des représentations au-delà du texte brut
+	pub fn has_rich_formats(&self) -> bool {
+		self.formats
+			.iter()
+			.any(|format| !format.mime_type.starts_with("text/plain"))
 	}
 
 	/// Vérifie si l'élément contient du texte correspondant à la recherche
@@ -49,10 +287,22 @@ impl ClipboardItem {
 		if query.is_empty() {
 			return true;
 		}
+		self.search_text().is_some_and(|text| text.contains(&query.to_lowercase()))
+	}
 
+	/// Texte sur lequel porte la recherche, en minuscules : le texte copié ou
+	/// les chemins, un par ligne. Une image n'en a pas.
+	pub fn search_text(&self) -> Option<String> {
 		match &self.content {
-			ClipboardContent::Text(text) => text.to_lowercase().contains(&query.to_lowercase()),
-			ClipboardContent::Image(_, _) => false,
+			ClipboardContent::Text(text) => Some(text.to_lowercase()),
+			ClipboardContent::Image(_, _) => None,
+			ClipboardContent::Files(entries) => Some(
+				entries
+					.iter()
+					.map(|entry| entry.path.to_string_lossy().to_lowercase())
+					.collect::<Vec<_>>()
+					.join("\n"),
+			),
 		}
 	}
 }
@@ -71,144 +321,177 @@ impl fmt::Display for ClipboardItem {
 			ClipboardContent::Image(_, metadata) => {
 				write!(f, "Image {}x{}", metadata.width, metadata.height)
 			}
+			ClipboardContent::Files(entries) => match entries.as_slice() {
+				[entry] => write!(f, "{}", entry.name()),
+				_ => write!(f, "{} fichiers", entries.len()),
+			},
+		}
+	}
+}
+
+#[cfg(target_os = "linux")]
+impl From<Selection> for arboard::LinuxClipboardKind {
+	fn from(selection: Selection) -> Self {
+		match selection {
+			Selection::Clipboard => arboard::LinuxClipboardKind::Clipboard,
+			Selection::Primary => arboard::LinuxClipboardKind::Primary,
 		}
 	}
 }
 
 /// Gestion des opérations de presse-papiers
-pub struct ClipboardManager {
-	clipboard: Clipboard,
+///
+/// Par défaut, le gestionnaire accède au presse-papiers du système ; les tests
+/// lui donnent un presse-papiers en mémoire (`with_backend`).
+pub struct ClipboardManager<B: ClipboardBackend = Box<dyn ClipboardBackend>> {
+	backend: B,
+	paste: PasteBackend,
 }
 
 impl ClipboardManager {
 	/// Crée une nouvelle instance du gestionnaire de presse-papiers
+	///
+	/// Le mécanisme de collage est détecté au premier collage, sauf s'il est
+	/// fixé auparavant (`set_paste_backend`).
 	pub fn new() -> ClipboardResult<Self> {
-		let clipboard = Clipboard::new().map_err(|e| ClipboardError::Clipboard(e.to_string()))?;
-		Ok(Self { clipboard })
+		Ok(Self { backend: Box::new(ArboardBackend::new()?), paste: PasteBackend::Auto })
+	}
+}
+
+impl<B: ClipboardBackend> ClipboardManager<B> {
+	/// Crée un gestionnaire passant par l'accès au presse-papiers indiqué
+	///
+	/// Aucune frappe n'est simulée : coller ne fait que copier l'élément.
+	pub fn with_backend(backend: B) -> Self {
+		Self { backend, paste: PasteBackend::CopyOnly }
+	}
+
+	/// Même gestionnaire, derrière un accès au presse-papiers quelconque
+	pub fn boxed(self) -> ClipboardManager {
+		ClipboardManager { backend: Box::new(self.backend), paste: self.paste }
 	}
 
 	/// Récupère le contenu actuel du presse-papiers
 	pub fn get_current_content(&mut self) -> ClipboardResult<Option<ClipboardContent>> {
-		// D'abord, essayons de récupérer le texte
-		if let Ok(text) = self.clipboard.get_text() {
-			if !text.is_empty() {
-				return Ok(Some(ClipboardContent::Text(text)));
-			}
-		}
+		self.get_selection_content(Selection::Clipboard)
+	}
 
-		// Si ce n'est pas du texte, essayons une image
-		match self.clipboard.get_image() {
-			Ok(image) => {
-				let metadata = ImageMetadata {
-					width: image.width,
-					height: image.height,
-				};
+	/// Récupère le contenu actuel de la sélection indiquée
+	pub fn get_selection_content(&mut self, selection: Selection) -> ClipboardResult<Option<ClipboardContent>> {
+		self.backend.read(selection)
+	}
 
-				// Conversion en Vec<u8> pour la sérialisation
-				Ok(Some(ClipboardContent::Image(image.bytes.to_vec(), metadata)))
-			}
-			Err(_) => Ok(None),
-		}
+	/// Récupère toutes les représentations MIME du contenu actuel
+	pub fn get_current_formats(&mut self) -> Vec<MimeData> {
+		self.get_selection_formats(Selection::Clipboard)
+	}
+
+	/// Récupère toutes les représentations MIME de la sélection indiquée
+	pub fn get_selection_formats(&mut self, selection: Selection) -> Vec<MimeData> {
+		self.backend.read_formats(selection)
 	}
 
 	/// Place un élément dans le presse-papiers
 	pub fn set_content(&mut self, item: &ClipboardItem) -> ClipboardResult<()> {
+		self.set_selection_content(item, Selection::Clipboard)
+	}
+
+	/// Place un élément dans la sélection indiquée, avec toutes ses représentations d'origine
+	pub fn set_selection_content(&mut self, item: &ClipboardItem, selection: Selection) -> ClipboardResult<()> {
 		match &item.content {
-			ClipboardContent::Text(text) => {
-				self.clipboard
-					.set_text(text.clone())
-					.map_err(|e| ClipboardError::Clipboard(e.to_string()))?;
+			// Toujours offrir la liste d'URI standard pour les fichiers
+			ClipboardContent::Files(entries)
+				if !item.formats.is_empty()
+					&& !item.formats.iter().any(|format| format.mime_type == files::URI_LIST_MIME) =>
+			{
+				let paths: Vec<_> = entries.iter().map(|entry| &entry.path).collect();
+				let mut formats = item.formats.clone();
+				formats.push(MimeData::new(files::URI_LIST_MIME, files::to_uri_list(&paths).into_bytes()));
+				self.backend.write(&item.content, &formats, selection)
 			}
-			ClipboardContent::Image(data, metadata) => {
-				let image = ImageData {
-					width: metadata.width,
-					height: metadata.height,
-					bytes: data.as_slice().into(),
-				};
-				self.clipboard
-					.set_image(image)
-					.map_err(|e| ClipboardError::Clipboard(e.to_string()))?;
+			_ => self.backend.write(&item.content, &item.formats, selection),
+		}
+	}
+
+	/// Vide la sélection indiquée si elle contient encore le contenu d'empreinte `hash`
+	///
+	/// Retourne `true` si la sélection a été vidée.
+	pub fn clear_selection_if(&mut self, selection: Selection, hash: &str) -> ClipboardResult<bool> {
+		let holds = self
+			.get_selection_content(selection)?
+			.is_some_and(|content| content.content_hash() == hash);
+		if holds {
+			self.backend.clear(selection)?;
+		}
+		Ok(holds)
+	}
+
+	/// Vide les sélections qui contiennent encore un élément expiré
+	///
+	/// Le presse-papiers est toujours vérifié : l'élément a pu y être restauré
+	/// depuis la sélection dont il provient.
+	pub fn clear_lapsed(&mut self, lapsed: &[(Selection, String)]) {
+		for (origin, hash) in lapsed {
+			let mut selections = vec![Selection::Clipboard];
+			if *origin != Selection::Clipboard {
+				selections.push(*origin);
+			}
+			for selection in selections {
+				match self.clear_selection_if(selection, hash) {
+					Ok(true) => log::info!("Élément expiré retiré du presse-papiers ({})", selection),
+					Ok(false) => {}
+					Err(e) => log::error!("Erreur lors du vidage du presse-papiers: {}", e),
+				}
 			}
 		}
-		Ok(())
 	}
 
-	/// Vérifie si ydotool est disponible
-	pub fn check_ydotool_available() -> bool {
-		Command::new("ydotool")
-			.arg("--version")
-			.output()
-			.map(|output| output.status.success())
-			.unwrap_or(false)
+	/// Mécanisme utilisé pour coller dans la fenêtre active
+	pub fn paste_backend(&self) -> PasteBackend {
+		self.paste
+	}
+
+	/// Change le mécanisme de collage ; `Auto` sera résolu au prochain collage
+	pub fn set_paste_backend(&mut self, backend: PasteBackend) {
+		self.paste = backend;
 	}
 
 	/// Colle directement le contenu dans la fenêtre active
+	///
+	/// Le texte brut est saisi au clavier, ce qui respecte la disposition de
+	/// l'utilisateur ; les images, les fichiers et les contenus riches sont
+	/// placés dans le presse-papiers puis collés par Ctrl+V. En copie seule,
+	/// ou si la saisie échoue, l'élément reste dans le presse-papiers pour
+	/// être collé à la main.
 	pub async fn paste_to_active_window(&mut self, item: &ClipboardItem) -> ClipboardResult<()> {
-		// D'abord, mettre le contenu dans le presse-papiers si c'est une image
-		match &item.content {
-			ClipboardContent::Image(_, _) => {
-				self.set_content(item)?;
+		if self.paste == PasteBackend::Auto {
+			self.paste = paste::resolve_in_background(PasteBackend::Auto).await;
+		}
+
+		let keystroke = match &item.content {
+			ClipboardContent::Text(text) if !item.has_rich_formats() && self.paste != PasteBackend::CopyOnly => {
+				Keystroke::Text(text.clone())
 			}
-			_ => {} // Pour le texte, on laisse ydotool type gérer
+			_ => Keystroke::Paste,
+		};
+		let copied = keystroke == Keystroke::Paste;
+		if copied {
+			self.set_content(item)?;
+		}
+		if self.paste == PasteBackend::CopyOnly {
+			log::info!("Élément copié, à coller manuellement");
+			return Ok(());
 		}
-		
+
 		// Petite pause pour s'assurer que l'environnement est prêt
 		tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
-		
-		// Pour AZERTY, utiliser la commande appropriée
-		if cfg!(target_os = "linux") {
-			match &item.content {
-				ClipboardContent::Text(text) => {
-					// Pour le texte, utiliser la commande type qui gère l'AZERTY automatiquement
-					let result = Command::new("ydotool")
-						.arg("type")
-						.arg("--")
-						.arg(text)
-						.stdin(Stdio::null())
-						.output();
-						
-					match result {
-						Ok(output) => {
-							if output.status.success() {
-								return Ok(());
-							} else {
-								log::error!("ydotool type failed: {}", String::from_utf8_lossy(&output.stderr));
-							}
-						}
-						Err(e) => {
-							log::error!("Error executing ydotool type: {}", e);
-						}
-					}
-				}
-				ClipboardContent::Image(_, _) => {
-					// Pour les images, utiliser Ctrl+V
-					let result = Command::new("ydotool")
-						.args(&["key", "29:1", "47:1", "47:0", "29:0"])  // Ctrl+V
-						.output();
-						
-					match result {
-						Ok(output) => {
-							if output.status.success() {
-								return Ok(());
-							} else {
-								log::error!("ydotool key failed: {}", String::from_utf8_lossy(&output.stderr));
-							}
-						}
-						Err(e) => {
-							log::error!("Error executing ydotool key: {}", e);
-						}
-					}
-				}
+
+		if let Err(e) = paste::inject(self.paste, keystroke).await {
+			if !copied {
+				self.set_content(item)?;
 			}
-			
-			// Si ydotool échoue, retourner une erreur appropriée
-			return Err(ClipboardError::Unexpected(
-				"Erreur lors du collage avec ydotool. Assurez-vous que ydotool est installé et que le service ydotool est en cours d'exécution."
-					.to_string()
-			));
-		}
-		
-		// Pour les autres OS, on ne fait rien (set_content a déjà mis dans le presse-papiers)
+			return Err(e);
+		}
 		Ok(())
 	}
 }
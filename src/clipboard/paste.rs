@@ -0,0 +1,226 @@
+use crate::clipboard::virtual_keyboard;
+use crate::config::PasteBackend;
+use crate::error::{ClipboardError, ClipboardResult};
+use log::{debug, info, warn};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// Délai maximal accordé à un outil pour saisir un texte
+const INJECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Mécanismes essayés par la détection automatique, par ordre de préférence
+///
+/// `wtype` passe par le même protocole que le clavier virtuel intégré : il ne
+/// le remplace que si le support Wayland n'est pas compilé.
+#[cfg(feature = "wayland")]
+const DETECTION_ORDER: [PasteBackend; 4] = [
+	PasteBackend::VirtualKeyboard,
+	PasteBackend::Xdotool,
+	PasteBackend::Dotool,
+	PasteBackend::Ydotool,
+];
+
+#[cfg(not(feature = "wayland"))]
+const DETECTION_ORDER: [PasteBackend; 4] = [
+	PasteBackend::Wtype,
+	PasteBackend::Xdotool,
+	PasteBackend::Dotool,
+	PasteBackend::Ydotool,
+];
+
+/// Frappes simulées dans la fenêtre active
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Keystroke {
+	/// Saisir un texte caractère par caractère
+	Text(String),
+	/// Raccourci Ctrl+V, qui colle le contenu du presse-papiers
+	Paste,
+}
+
+/// Premier mécanisme de collage disponible dans la session, `CopyOnly` à défaut
+pub fn detect() -> PasteBackend {
+	DETECTION_ORDER
+		.into_iter()
+		.find(|backend| is_available(*backend))
+		.unwrap_or(PasteBackend::CopyOnly)
+}
+
+/// Mécanisme de collage à utiliser selon la configuration
+///
+/// `Auto` est résolu par la détection. Un mécanisme imposé est gardé même s'il
+/// semble indisponible : l'échec du collage en donnera la raison.
+pub fn resolve(configured: PasteBackend) -> PasteBackend {
+	let backend = match configured {
+		PasteBackend::Auto => detect(),
+		backend => {
+			if !is_available(backend) {
+				warn!("Mécanisme de collage {} imposé mais indisponible", backend);
+			}
+			backend
+		}
+	};
+	info!("Collage automatique: {}", backend);
+	backend
+}
+
+/// `resolve` exécuté hors du runtime asynchrone
+///
+/// La détection se connecte au compositeur et parcourt le `PATH` : elle ne
+/// doit pas bloquer l'interface ni le démon.
+pub async fn resolve_in_background(configured: PasteBackend) -> PasteBackend {
+	tokio::task::spawn_blocking(move || resolve(configured))
+		.await
+		.unwrap_or_else(|e| {
+			warn!("Détection du mécanisme de collage interrompue: {}", e);
+			PasteBackend::CopyOnly
+		})
+}
+
+/// Indique si le mécanisme de collage peut fonctionner dans cette session
+pub fn is_available(backend: PasteBackend) -> bool {
+	match backend {
+		PasteBackend::Auto | PasteBackend::CopyOnly => true,
+		PasteBackend::VirtualKeyboard => virtual_keyboard::offered(),
+		PasteBackend::Wtype => in_path("wtype") && virtual_keyboard_offered(),
+		PasteBackend::Xdotool => in_path("xdotool") && x11_session(),
+		PasteBackend::Dotool => in_path("dotool") && uinput_writable(),
+		PasteBackend::Ydotool => in_path("ydotool") && ydotool_socket().exists(),
+	}
+}
+
+/// Simule les frappes dans la fenêtre active avec le mécanisme indiqué
+///
+/// `CopyOnly` ne simule rien.
+pub async fn inject(backend: PasteBackend, keystroke: Keystroke) -> ClipboardResult<()> {
+	let backend = match backend {
+		PasteBackend::Auto => resolve_in_background(backend).await,
+		backend => backend,
+	};
+	debug!("Collage par {}", backend);
+
+	match (backend, &keystroke) {
+		(PasteBackend::Auto | PasteBackend::CopyOnly, _) => Ok(()),
+		(PasteBackend::VirtualKeyboard, _) => tokio::task::spawn_blocking(move || virtual_keyboard::send(&keystroke))
+			.await
+			.map_err(|e| ClipboardError::Paste(format!("Clavier virtuel Wayland: {}", e)))?,
+		(PasteBackend::Wtype, Keystroke::Text(text)) => run("wtype", &["--", text], None).await,
+		(PasteBackend::Wtype, Keystroke::Paste) => run("wtype", &["-M", "ctrl", "v", "-m", "ctrl"], None).await,
+		(PasteBackend::Xdotool, Keystroke::Text(text)) => {
+			run("xdotool", &["type", "--clearmodifiers", "--", text], None).await
+		}
+		(PasteBackend::Xdotool, Keystroke::Paste) => run("xdotool", &["key", "--clearmodifiers", "ctrl+v"], None).await,
+		(PasteBackend::Dotool, keystroke) => run("dotool", &[], Some(dotool_script(keystroke))).await,
+		(PasteBackend::Ydotool, Keystroke::Text(text)) => run("ydotool", &["type", "--", text], None).await,
+		// Codes evdev de Ctrl (29) et de V (47), enfoncés puis relâchés
+		(PasteBackend::Ydotool, Keystroke::Paste) => run("ydotool", &["key", "29:1", "47:1", "47:0", "29:0"], None).await,
+	}
+}
+
+/// Commandes lues par `dotool` sur son entrée standard
+///
+/// `type` saisit le reste de la ligne : les retours à la ligne du texte
+/// deviennent des appuis sur Entrée.
+pub fn dotool_script(keystroke: &Keystroke) -> String {
+	match keystroke {
+		Keystroke::Text(text) => text
+			.split('\n')
+			.map(|line| format!("type {}\n", line))
+			.collect::<Vec<_>>()
+			.join("key enter\n"),
+		Keystroke::Paste => "key ctrl+v\n".to_string(),
+	}
+}
+
+/// Keysym XKB produisant un caractère, `None` pour un caractère de contrôle
+///
+/// Les caractères Latin-1 ont leur propre keysym ; les autres sont désignés
+/// par leur point de code Unicode (`0x01000000 + code`).
+pub fn keysym(c: char) -> Option<u32> {
+	match c {
+		'\n' => Some(0xff0d),
+		'\t' => Some(0xff09),
+		c if c.is_control() => None,
+		' '..='~' | '\u{a0}'..='\u{ff}' => Some(c as u32),
+		c => Some(0x0100_0000 + c as u32),
+	}
+}
+
+/// Exécute un outil d'injection et attend la fin de la saisie
+async fn run(program: &str, args: &[&str], script: Option<String>) -> ClipboardResult<()> {
+	let mut child = Command::new(program)
+		.args(args)
+		.stdin(if script.is_some() { Stdio::piped() } else { Stdio::null() })
+		.stdout(Stdio::null())
+		.stderr(Stdio::piped())
+		.kill_on_drop(true)
+		.spawn()
+		.map_err(|e| ClipboardError::Paste(format!("{} indisponible: {}", program, e)))?;
+
+	if let (Some(script), Some(mut stdin)) = (script, child.stdin.take()) {
+		stdin
+			.write_all(script.as_bytes())
+			.await
+			.map_err(|e| ClipboardError::Paste(format!("Erreur écriture vers {}: {}", program, e)))?;
+	}
+
+	match tokio::time::timeout(INJECT_TIMEOUT, child.wait_with_output()).await {
+		Ok(Ok(output)) if output.status.success() => Ok(()),
+		Ok(Ok(output)) => Err(ClipboardError::Paste(format!(
+			"{} a échoué: {}",
+			program,
+			String::from_utf8_lossy(&output.stderr).trim()
+		))),
+		Ok(Err(e)) => Err(ClipboardError::Paste(format!("Erreur exécution {}: {}", program, e))),
+		Err(_) => Err(ClipboardError::Paste(format!("{} n'a pas terminé à temps", program))),
+	}
+}
+
+/// Le compositeur propose le protocole virtual-keyboard, dont dépend aussi `wtype`
+///
+/// Sans le support Wayland compilé, le protocole ne peut être vérifié : une
+/// session Wayland est supposée le proposer.
+fn virtual_keyboard_offered() -> bool {
+	if cfg!(feature = "wayland") {
+		virtual_keyboard::offered()
+	} else {
+		std::env::var_os("WAYLAND_DISPLAY").is_some()
+	}
+}
+
+/// Session X11, où `xdotool` atteint toutes les fenêtres
+fn x11_session() -> bool {
+	std::env::var_os("DISPLAY").is_some() && std::env::var_os("WAYLAND_DISPLAY").is_none()
+}
+
+/// `dotool` écrit dans `/dev/uinput`, souvent réservé au groupe `input`
+fn uinput_writable() -> bool {
+	std::fs::OpenOptions::new().write(true).open("/dev/uinput").is_ok()
+}
+
+/// Socket du service `ydotoold`, sans lequel `ydotool` ne peut rien saisir
+fn ydotool_socket() -> PathBuf {
+	if let Some(socket) = std::env::var_os("YDOTOOL_SOCKET") {
+		return PathBuf::from(socket);
+	}
+	let runtime = dirs::runtime_dir().map(|dir| dir.join(".ydotool_socket"));
+	runtime
+		.filter(|socket| socket.exists())
+		.unwrap_or_else(|| PathBuf::from("/tmp/.ydotool_socket"))
+}
+
+/// Indique si un exécutable de ce nom se trouve dans le `PATH`
+fn in_path(program: &str) -> bool {
+	use std::os::unix::fs::PermissionsExt;
+
+	let executable = |path: &Path| {
+		path.metadata()
+			.map(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+			.unwrap_or(false)
+	};
+	std::env::var_os("PATH")
+		.map(|paths| std::env::split_paths(&paths).any(|dir| executable(&dir.join(program))))
+		.unwrap_or(false)
+}
@@ -0,0 +1,70 @@
+use crate::clipboard::source::SourceApplication;
+use crate::clipboard::{ClipboardContent, ClipboardItem};
+use crate::config::{CaptureAction, CaptureRule, Config};
+use chrono::Duration;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Règles de capture partagées, modifiables pendant la surveillance
+pub type CaptureRulesHandle = Arc<Mutex<CaptureRules>>;
+
+/// Règles de capture par application
+///
+/// La première règle qui désigne l'application d'origine s'applique ; sans
+/// règle correspondante, ou si l'origine est inconnue, l'action par défaut s'applique.
+#[derive(Debug, Clone, Default)]
+pub struct CaptureRules {
+	rules: Vec<CaptureRule>,
+	default: CaptureAction,
+}
+
+impl CaptureRules {
+	/// Crée les règles de capture
+	pub fn new(rules: Vec<CaptureRule>, default: CaptureAction) -> Self {
+		Self { rules, default }
+	}
+
+	/// Indique s'il faut chercher l'application d'origine des copies
+	///
+	/// Sans règle, toutes les copies sont traitées de la même manière.
+	pub fn needs_source(&self) -> bool {
+		!self.rules.is_empty()
+	}
+
+	/// Action à appliquer aux copies de l'application indiquée
+	pub fn action_for(&self, source: Option<&SourceApplication>) -> CaptureAction {
+		source
+			.and_then(|source| self.rules.iter().find(|rule| source.matches(&rule.app)))
+			.map_or(self.default, |rule| rule.action)
+	}
+
+	/// Applique une action à une copie ; `None` si elle ne doit pas être conservée
+	pub fn apply(action: CaptureAction, mut item: ClipboardItem) -> Option<ClipboardItem> {
+		match action {
+			CaptureAction::Store => Some(item),
+			CaptureAction::Ignore => None,
+			CaptureAction::TextOnly => {
+				if !matches!(item.content, ClipboardContent::Text(_)) {
+					return None;
+				}
+				let formats = std::mem::take(&mut item.formats)
+					.into_iter()
+					.filter(|format| format.mime_type.starts_with("text/plain"))
+					.collect();
+				Some(item.with_formats(formats))
+			}
+			CaptureAction::Expire { minutes } => {
+				let expires_at = item.timestamp + Duration::minutes(minutes.min(u32::MAX.into()) as i64);
+				// Une expiration plus proche, décidée par un détecteur de secrets, est conservée
+				item.expires_at = Some(item.expires_at.map_or(expires_at, |current| current.min(expires_at)));
+				Some(item)
+			}
+		}
+	}
+}
+
+impl From<&Config> for CaptureRules {
+	fn from(config: &Config) -> Self {
+		Self::new(config.capture_rules.clone(), config.default_capture)
+	}
+}
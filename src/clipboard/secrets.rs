@@ -0,0 +1,191 @@
+use crate::clipboard::{ClipboardContent, ClipboardItem, MimeData};
+use crate::config::{Config, DetectorAction, SecretDetector, SecretHintAction};
+use chrono::Duration;
+use log::{debug, warn};
+use regex::Regex;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Filtre des secrets partagé, modifiable pendant la surveillance
+pub type SecretFilterHandle = Arc<Mutex<SecretFilter>>;
+
+/// Types MIME par lesquels les gestionnaires de mots de passe signalent une copie secrète
+///
+/// `x-kde-passwordManagerHint` vaut `secret` ; les types `nspasteboard` suffisent
+/// par leur seule présence.
+pub const SECRET_HINT_MIME_TYPES: [&str; 3] = [
+	"x-kde-passwordManagerHint",
+	"org.nspasteboard.ConcealedType",
+	"application/x-nspasteboard-concealed-type",
+];
+
+/// Caractère remplaçant les secrets masqués
+const MASK_CHAR: char = '•';
+
+/// Nombre de caractères laissés visibles à la fin d'un secret masqué
+const MASK_VISIBLE_SUFFIX: usize = 4;
+
+/// Indique si un type MIME peut porter l'indication d'une copie secrète
+pub fn is_secret_hint_mime(mime_type: &str) -> bool {
+	SECRET_HINT_MIME_TYPES.contains(&mime_type)
+}
+
+/// Indique si les représentations offertes signalent une copie secrète
+pub fn has_secret_hint(formats: &[MimeData]) -> bool {
+	formats.iter().any(|format| match format.mime_type.as_str() {
+		"x-kde-passwordManagerHint" => String::from_utf8_lossy(&format.data).trim() == "secret",
+		mime_type => is_secret_hint_mime(mime_type),
+	})
+}
+
+/// Détecteur dont l'expression régulière est compilée
+struct CompiledDetector {
+	name: String,
+	regex: Regex,
+	action: DetectorAction,
+	luhn: bool,
+}
+
+impl CompiledDetector {
+	/// Passages du texte reconnus par le détecteur (positions en octets)
+	fn find(&self, text: &str) -> Vec<(usize, usize)> {
+		self.regex
+			.find_iter(text)
+			.filter(|found| !self.luhn || luhn_valid(found.as_str()))
+			.map(|found| (found.start(), found.end()))
+			.collect()
+	}
+}
+
+/// Filtre appliqué aux copies avant qu'elles n'atteignent l'historique
+///
+/// Les copies signalées par un gestionnaire de mots de passe sont ignorées ou
+/// gardées en mémoire ; le texte reconnu par un détecteur est ignoré, masqué
+/// ou voué à expirer.
+pub struct SecretFilter {
+	hint_action: SecretHintAction,
+	detectors: Vec<CompiledDetector>,
+	expiry: Duration,
+}
+
+impl SecretFilter {
+	/// Crée un filtre ; un détecteur dont l'expression est invalide est ignoré
+	pub fn new(hint_action: SecretHintAction, detectors: &[SecretDetector], expiry: Duration) -> Self {
+		let detectors = detectors
+			.iter()
+			.filter_map(|detector| match Regex::new(&detector.pattern) {
+				Ok(regex) => Some(CompiledDetector {
+					name: detector.name.clone(),
+					regex,
+					action: detector.action,
+					luhn: detector.luhn,
+				}),
+				Err(e) => {
+					warn!("Détecteur de secrets « {} » ignoré: {}", detector.name, e);
+					None
+				}
+			})
+			.collect();
+
+		Self { hint_action, detectors, expiry }
+	}
+
+	/// Applique le filtre à une copie ; `None` si elle ne doit pas être conservée
+	pub fn apply(&self, mut item: ClipboardItem) -> Option<ClipboardItem> {
+		if has_secret_hint(&item.formats) {
+			return match self.hint_action {
+				SecretHintAction::Skip => {
+					debug!("Copie signalée comme secrète ignorée");
+					None
+				}
+				SecretHintAction::MemoryOnly => {
+					debug!("Copie signalée comme secrète gardée en mémoire");
+					item.memory_only = true;
+					Some(item)
+				}
+			};
+		}
+		// L'indication n'a servi qu'au filtre : elle n'est ni enregistrée ni restaurée
+		item.formats.retain(|format| !is_secret_hint_mime(&format.mime_type));
+
+		let ClipboardContent::Text(text) = &item.content else {
+			return Some(item);
+		};
+
+		let mut masked = Vec::new();
+		let mut expire = false;
+		for detector in &self.detectors {
+			let found = detector.find(text);
+			if found.is_empty() {
+				continue;
+			}
+			debug!("Secret détecté: {}", detector.name);
+			match detector.action {
+				DetectorAction::Drop => return None,
+				DetectorAction::Mask => masked.extend(found),
+				DetectorAction::Expire => expire = true,
+			}
+		}
+
+		if !masked.is_empty() {
+			let text = mask(text, masked);
+			// Les autres représentations contiennent le secret en clair
+			item.formats.clear();
+			item.preferred_mime = None;
+			item.content = ClipboardContent::Text(text);
+			item.content_hash = item.content.content_hash();
+		}
+		if expire {
+			item.expires_at = Some(item.timestamp + self.expiry);
+		}
+		Some(item)
+	}
+}
+
+impl From<&Config> for SecretFilter {
+	fn from(config: &Config) -> Self {
+		let expiry = Duration::seconds(config.secret_expiry_seconds.min(u32::MAX.into()) as i64);
+		Self::new(config.secret_hint_action, &config.secret_detectors, expiry)
+	}
+}
+
+/// Masque les passages indiqués, en laissant visibles leurs derniers caractères
+fn mask(text: &str, mut ranges: Vec<(usize, usize)>) -> String {
+	ranges.sort();
+	let mut masked = String::with_capacity(text.len());
+	let mut position = 0;
+	for (start, end) in ranges {
+		let start = start.max(position);
+		if start >= end {
+			continue;
+		}
+		masked.push_str(&text[position..start]);
+
+		let secret: Vec<char> = text[start..end].chars().collect();
+		let visible = if secret.len() > 2 * MASK_VISIBLE_SUFFIX { MASK_VISIBLE_SUFFIX } else { 0 };
+		masked.extend(std::iter::repeat_n(MASK_CHAR, secret.len() - visible));
+		masked.extend(&secret[secret.len() - visible..]);
+		position = end;
+	}
+	masked.push_str(&text[position..]);
+	masked
+}
+
+/// Vérifie la clé de Luhn des chiffres d'un texte
+fn luhn_valid(text: &str) -> bool {
+	let digits: Vec<u32> = text.chars().filter_map(|c| c.to_digit(10)).collect();
+	if digits.len() < 2 {
+		return false;
+	}
+	let sum: u32 = digits
+		.iter()
+		.rev()
+		.enumerate()
+		.map(|(index, &digit)| match (index % 2 == 1, digit * 2) {
+			(true, doubled) if doubled > 9 => doubled - 9,
+			(true, doubled) => doubled,
+			(false, _) => digit,
+		})
+		.sum();
+	sum.is_multiple_of(10)
+}
@@ -0,0 +1,51 @@
+use log::debug;
+use zbus::{Connection, Proxy};
+
+/// Suivi du verrouillage de la session via logind
+///
+/// Le proxy garde en cache la propriété `LockedHint` et la met à jour sur
+/// notification : l'interroger à chaque scrutation ne coûte pas d'aller-retour.
+#[derive(Default)]
+pub struct SessionMonitor {
+	proxy: Option<Proxy<'static>>,
+	unavailable: bool,
+}
+
+impl SessionMonitor {
+	/// Crée un moniteur, connecté paresseusement au bus système
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Indique si la session courante est verrouillée
+	///
+	/// Sans logind (ou sans bus système), la session est considérée déverrouillée.
+	pub async fn is_locked(&mut self) -> bool {
+		if self.proxy.is_none() && !self.unavailable {
+			match Self::connect().await {
+				Ok(proxy) => self.proxy = Some(proxy),
+				Err(e) => {
+					debug!("État de la session indisponible: {}", e);
+					self.unavailable = true;
+				}
+			}
+		}
+
+		match &self.proxy {
+			Some(proxy) => proxy.get_property::<bool>("LockedHint").await.unwrap_or(false),
+			None => false,
+		}
+	}
+
+	/// Ouvre un proxy sur la session logind de l'application
+	async fn connect() -> zbus::Result<Proxy<'static>> {
+		let connection = Connection::system().await?;
+		Proxy::new(
+			&connection,
+			"org.freedesktop.login1",
+			"/org/freedesktop/login1/session/auto",
+			"org.freedesktop.login1.Session",
+		)
+		.await
+	}
+}
@@ -0,0 +1,135 @@
+use log::debug;
+use serde_json::Value;
+use std::time::Duration;
+use tokio::process::Command;
+
+/// Délai maximal accordé au compositeur pour désigner la fenêtre active
+const QUERY_TIMEOUT: Duration = Duration::from_millis(300);
+
+/// Application dont provient une copie
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceApplication {
+	/// Identifiant d'application Wayland (`app_id`)
+	pub app_id: Option<String>,
+	/// Classe de fenêtre X11 (`WM_CLASS`), aussi pour les clients XWayland
+	pub window_class: Option<String>,
+}
+
+impl SourceApplication {
+	/// Indique si l'application porte l'identifiant ou la classe indiqués, sans tenir compte de la casse
+	pub fn matches(&self, app: &str) -> bool {
+		[&self.app_id, &self.window_class]
+			.into_iter()
+			.flatten()
+			.any(|name| name.eq_ignore_ascii_case(app))
+	}
+}
+
+/// Application de la fenêtre active, qui est en pratique celle qui vient de copier
+///
+/// Les données du presse-papiers ne désignent pas leur auteur : seuls Hyprland,
+/// Sway et les sessions X11 permettent de retrouver la fenêtre active.
+/// Retourne `None` ailleurs, ou si le compositeur ne répond pas à temps.
+pub async fn focused_application() -> Option<SourceApplication> {
+	let env = |name: &str| std::env::var_os(name).is_some();
+
+	let source = if env("HYPRLAND_INSTANCE_SIGNATURE") {
+		query("hyprctl", &["activewindow", "-j"]).await.and_then(|output| parse_hyprland_window(&output))
+	} else if env("SWAYSOCK") {
+		query("swaymsg", &["-t", "get_tree", "-r"]).await.and_then(|output| parse_sway_tree(&output))
+	} else if env("DISPLAY") && !env("WAYLAND_DISPLAY") {
+		let active = query("xprop", &["-root", "_NET_ACTIVE_WINDOW"]).await?;
+		let window = parse_xprop_active_window(&active)?;
+		let class = query("xprop", &["-id", &window, "WM_CLASS"]).await?;
+		parse_xprop_class(&class).map(|class| SourceApplication { app_id: None, window_class: Some(class) })
+	} else {
+		None
+	};
+
+	if source.is_none() {
+		debug!("Application à l'origine de la copie inconnue");
+	}
+	source
+}
+
+/// Exécute une commande d'interrogation du compositeur et retourne sa sortie
+async fn query(program: &str, args: &[&str]) -> Option<String> {
+	let output = Command::new(program).args(args).kill_on_drop(true).output();
+	match tokio::time::timeout(QUERY_TIMEOUT, output).await {
+		Ok(Ok(output)) if output.status.success() => Some(String::from_utf8_lossy(&output.stdout).into_owned()),
+		Ok(Ok(output)) => {
+			debug!("{} a échoué: {}", program, String::from_utf8_lossy(&output.stderr).trim());
+			None
+		}
+		Ok(Err(e)) => {
+			debug!("{} indisponible: {}", program, e);
+			None
+		}
+		Err(_) => {
+			debug!("{} n'a pas répondu à temps", program);
+			None
+		}
+	}
+}
+
+/// Extrait l'application de la sortie de `hyprctl activewindow -j`
+///
+/// Hyprland range l'`app_id` des clients Wayland dans `class`, comme la classe des clients XWayland.
+pub fn parse_hyprland_window(output: &str) -> Option<SourceApplication> {
+	let window: Value = serde_json::from_str(output).ok()?;
+	let class = non_empty(window.get("class"))?;
+	let xwayland = window.get("xwayland").and_then(Value::as_bool).unwrap_or(false);
+
+	Some(if xwayland {
+		SourceApplication { app_id: None, window_class: Some(class) }
+	} else {
+		SourceApplication { app_id: Some(class), window_class: None }
+	})
+}
+
+/// Extrait l'application de la fenêtre active de la sortie de `swaymsg -t get_tree -r`
+pub fn parse_sway_tree(output: &str) -> Option<SourceApplication> {
+	let tree: Value = serde_json::from_str(output).ok()?;
+	let focused = find_focused(&tree)?;
+
+	let source = SourceApplication {
+		app_id: non_empty(focused.get("app_id")),
+		window_class: non_empty(focused.pointer("/window_properties/class")),
+	};
+	(source.app_id.is_some() || source.window_class.is_some()).then_some(source)
+}
+
+/// Cherche le nœud actif dans l'arbre des conteneurs de Sway
+fn find_focused(node: &Value) -> Option<&Value> {
+	if node.get("focused").and_then(Value::as_bool) == Some(true) {
+		return Some(node);
+	}
+	["nodes", "floating_nodes"]
+		.into_iter()
+		.filter_map(|key| node.get(key).and_then(Value::as_array))
+		.flatten()
+		.find_map(find_focused)
+}
+
+/// Extrait l'identifiant de la fenêtre active de `xprop -root _NET_ACTIVE_WINDOW`
+pub fn parse_xprop_active_window(output: &str) -> Option<String> {
+	let window = output.rsplit('#').next()?.trim();
+	(window.starts_with("0x") && window != "0x0").then(|| window.to_string())
+}
+
+/// Extrait la classe de `xprop -id <fenêtre> WM_CLASS`
+///
+/// `WM_CLASS` contient l'instance puis la classe : `WM_CLASS(STRING) = "navigator", "firefox"`.
+pub fn parse_xprop_class(output: &str) -> Option<String> {
+	let (_, values) = output.split_once('=')?;
+	let class = values.split(',').next_back()?.trim().trim_matches('"');
+	(!class.is_empty()).then(|| class.to_string())
+}
+
+/// Chaîne non vide d'un champ JSON
+fn non_empty(value: Option<&Value>) -> Option<String> {
+	value
+		.and_then(Value::as_str)
+		.filter(|value| !value.is_empty())
+		.map(str::to_string)
+}
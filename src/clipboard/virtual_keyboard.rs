@@ -0,0 +1,206 @@
+use crate::clipboard::paste::Keystroke;
+use crate::error::{ClipboardError, ClipboardResult};
+
+/// Interface du protocole annoncée par le compositeur
+#[cfg(feature = "wayland")]
+const MANAGER_INTERFACE: &str = "zwp_virtual_keyboard_manager_v1";
+
+/// Keysym de la touche Contrôle gauche, toujours première touche du keymap
+const CONTROL_L: u32 = 0xffe3;
+
+/// Keysym de la lettre v
+const LATIN_V: u32 = 0x0076;
+
+/// Keysyms d'un keymap : codes XKB 10 à 255, le code 9 étant Contrôle
+pub const MAX_KEYSYMS: usize = 246;
+
+/// Masque du modificateur Contrôle (troisième modificateur réel de XKB)
+#[cfg(feature = "wayland")]
+const CONTROL_MASK: u32 = 1 << 2;
+
+/// Format XKB v1 du keymap (`wl_keyboard.keymap_format.xkb_v1`)
+#[cfg(feature = "wayland")]
+const KEYMAP_FORMAT_XKB_V1: u32 = 1;
+
+/// Keymap XKB dont chaque touche produit un seul keysym
+///
+/// La touche 1 (code XKB 9) est Contrôle gauche, liée au modificateur
+/// Contrôle ; les suivantes produisent `keysyms` dans l'ordre. Le keymap ne
+/// dépend pas de la disposition du clavier de l'utilisateur.
+pub fn keymap(keysyms: &[u32]) -> String {
+	let mut keycodes = String::new();
+	let mut symbols = String::new();
+	for (index, keysym) in std::iter::once(&CONTROL_L).chain(keysyms).enumerate() {
+		keycodes.push_str(&format!("\t\t<K{}> = {};\n", index, index + 9));
+		symbols.push_str(&format!("\t\tkey <K{}> {{ [ 0x{:x} ] }};\n", index, keysym));
+	}
+
+	format!(
+		"xkb_keymap {{\n\
+		\txkb_keycodes \"(unnamed)\" {{\n\t\tminimum = 8;\n\t\tmaximum = {};\n{}\t}};\n\
+		\txkb_types \"(unnamed)\" {{ include \"complete\" }};\n\
+		\txkb_compatibility \"(unnamed)\" {{ include \"complete\" }};\n\
+		\txkb_symbols \"(unnamed)\" {{\n{}\t\tmodifier_map Control {{ <K0> }};\n\t}};\n\
+		}};\n",
+		keysyms.len() + 9,
+		keycodes,
+		symbols
+	)
+}
+
+/// Keymaps successifs et touches à frapper (codes evdev) pour produire les frappes
+///
+/// Chaque keymap produit au plus `MAX_KEYSYMS` keysyms : un texte plus varié
+/// est découpé en morceaux, chacun saisi avec son propre keymap. Les
+/// caractères sans keysym sont ignorés ; un keysym répété réutilise sa touche.
+pub fn layout(keystroke: &Keystroke) -> Vec<(Vec<u32>, Vec<u32>)> {
+	let text = match keystroke {
+		Keystroke::Text(text) => text,
+		Keystroke::Paste => return vec![(vec![LATIN_V], vec![2])],
+	};
+
+	let mut chunks = Vec::new();
+	let mut keysyms = Vec::new();
+	let mut keys = Vec::new();
+	for keysym in text.chars().filter_map(crate::clipboard::paste::keysym) {
+		let index = match keysyms.iter().position(|known| *known == keysym) {
+			Some(index) => index,
+			None => {
+				if keysyms.len() == MAX_KEYSYMS {
+					chunks.push((std::mem::take(&mut keysyms), std::mem::take(&mut keys)));
+				}
+				keysyms.push(keysym);
+				keysyms.len() - 1
+			}
+		};
+		// Touche 1 : Contrôle ; code evdev = code XKB - 8
+		keys.push(index as u32 + 2);
+	}
+	chunks.push((keysyms, keys));
+	chunks
+}
+
+/// Erreur de collage pour une erreur du protocole
+fn failed(e: impl std::fmt::Display) -> ClipboardError {
+	ClipboardError::Paste(format!("Clavier virtuel Wayland: {}", e))
+}
+
+#[cfg(feature = "wayland")]
+mod session {
+	use wayland_client::globals::GlobalListContents;
+	use wayland_client::protocol::{wl_registry::WlRegistry, wl_seat::WlSeat};
+	use wayland_client::{delegate_noop, Connection, Dispatch, QueueHandle};
+	use wayland_protocols_misc::zwp_virtual_keyboard_v1::client::{
+		zwp_virtual_keyboard_manager_v1::ZwpVirtualKeyboardManagerV1, zwp_virtual_keyboard_v1::ZwpVirtualKeyboardV1,
+	};
+
+	/// État de la connexion : aucun événement utile n'est attendu
+	pub struct Session;
+
+	impl Dispatch<WlRegistry, GlobalListContents> for Session {
+		fn event(
+			_: &mut Self,
+			_: &WlRegistry,
+			_: <WlRegistry as wayland_client::Proxy>::Event,
+			_: &GlobalListContents,
+			_: &Connection,
+			_: &QueueHandle<Self>,
+		) {
+		}
+	}
+
+	delegate_noop!(Session: ignore WlSeat);
+	delegate_noop!(Session: ZwpVirtualKeyboardManagerV1);
+	delegate_noop!(Session: ZwpVirtualKeyboardV1);
+}
+
+/// Indique si le compositeur propose le protocole virtual-keyboard
+#[cfg(feature = "wayland")]
+pub fn offered() -> bool {
+	use wayland_client::globals::registry_queue_init;
+	use wayland_client::Connection;
+
+	let Ok(connection) = Connection::connect_to_env() else {
+		return false;
+	};
+	match registry_queue_init::<session::Session>(&connection) {
+		Ok((globals, _)) => globals
+			.contents()
+			.with_list(|list| list.iter().any(|global| global.interface == MANAGER_INTERFACE)),
+		Err(_) => false,
+	}
+}
+
+/// Simule les frappes au moyen d'un clavier virtuel créé pour l'occasion
+///
+/// Bloquant : à appeler hors du runtime asynchrone.
+#[cfg(feature = "wayland")]
+pub fn send(keystroke: &Keystroke) -> ClipboardResult<()> {
+	use std::io::Write;
+	use std::os::fd::AsFd;
+	use std::os::unix::fs::OpenOptionsExt;
+	use std::time::Instant;
+	use wayland_client::globals::registry_queue_init;
+	use wayland_client::protocol::wl_seat::WlSeat;
+	use wayland_client::Connection;
+	use wayland_protocols_misc::zwp_virtual_keyboard_v1::client::zwp_virtual_keyboard_manager_v1::ZwpVirtualKeyboardManagerV1;
+
+	let connection = Connection::connect_to_env().map_err(failed)?;
+	let (globals, mut queue) = registry_queue_init::<session::Session>(&connection).map_err(failed)?;
+	let handle = queue.handle();
+	let seat: WlSeat = globals.bind(&handle, 1..=1, ()).map_err(failed)?;
+	let manager: ZwpVirtualKeyboardManagerV1 = globals.bind(&handle, 1..=1, ()).map_err(failed)?;
+	let keyboard = manager.create_virtual_keyboard(&seat, &handle, ());
+
+	let start = Instant::now();
+	for (keysyms, keys) in layout(keystroke) {
+		// Le compositeur lit le keymap dans un fichier, terminé par un octet nul
+		let mut keymap = keymap(&keysyms).into_bytes();
+		keymap.push(0);
+		let path = crate::utils::runtime_dir()?.join(format!("clipboard-manager-keymap-{}", std::process::id()));
+		let mut file = std::fs::OpenOptions::new()
+			.read(true)
+			.write(true)
+			.create(true)
+			.truncate(true)
+			.mode(0o600)
+			.open(&path)?;
+		let _ = std::fs::remove_file(&path);
+		file.write_all(&keymap)?;
+		keyboard.keymap(KEYMAP_FORMAT_XKB_V1, file.as_fd(), keymap.len() as u32);
+		queue.roundtrip(&mut session::Session).map_err(failed)?;
+
+		let mut press = |key: u32, pressed: bool| {
+			keyboard.key(start.elapsed().as_millis() as u32, key, pressed as u32);
+			queue.roundtrip(&mut session::Session).map(|_| ()).map_err(failed)
+		};
+		if *keystroke == Keystroke::Paste {
+			press(1, true)?;
+			keyboard.modifiers(CONTROL_MASK, 0, 0, 0);
+		}
+		for key in keys {
+			press(key, true)?;
+			press(key, false)?;
+		}
+		if *keystroke == Keystroke::Paste {
+			press(1, false)?;
+			keyboard.modifiers(0, 0, 0, 0);
+		}
+	}
+
+	keyboard.destroy();
+	connection.roundtrip().map_err(failed)?;
+	Ok(())
+}
+
+/// Sans le support Wayland compilé, le protocole n'est jamais utilisé
+#[cfg(not(feature = "wayland"))]
+pub fn offered() -> bool {
+	false
+}
+
+#[cfg(not(feature = "wayland"))]
+pub fn send(keystroke: &Keystroke) -> ClipboardResult<()> {
+	let _ = keystroke;
+	Err(failed("support Wayland non compilé"))
+}
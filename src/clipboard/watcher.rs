@@ -1,6 +1,15 @@
-use crate::clipboard::{ClipboardContent, ClipboardItem, ClipboardManager};
+use crate::clipboard::data_control::{DataControlListener, SelectionNotifier};
+use crate::clipboard::rules::{CaptureRules, CaptureRulesHandle};
+use crate::clipboard::secrets::{SecretFilter, SecretFilterHandle};
+use crate::clipboard::session::SessionMonitor;
+use crate::clipboard::{
+	files, source, ArboardBackend, ClipboardBackend, ClipboardContent, ClipboardItem, ClipboardManager, Selection,
+};
+use crate::config::{CaptureAction, Config, WatcherBackend};
 use crate::error::ClipboardResult;
-use log::{debug, error, info};
+use chrono::{DateTime, Utc};
+use log::{debug, error, info, warn};
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::{mpsc, Mutex};
@@ -10,25 +19,219 @@ use tokio::time;
 pub type ClipboardEventSender = mpsc::Sender<ClipboardItem>;
 pub type ClipboardEventReceiver = mpsc::Receiver<ClipboardItem>;
 
+/// Réglages de scrutation partagés, modifiables pendant la surveillance
+pub type PollingHandle = Arc<Mutex<PollingSettings>>;
+
+/// État de l'enregistrement partagé, modifiable pendant la surveillance
+pub type PauseHandle = Arc<Mutex<RecordingState>>;
+
+/// Ouvre l'accès au presse-papiers, au démarrage puis après chaque erreur de lecture
+type Connector<B> = Arc<dyn Fn() -> ClipboardResult<B> + Send + Sync>;
+
+/// Démarre une source de changements de sélection remplaçant le compositeur
+type Listen = Arc<dyn Fn(SelectionNotifier, bool) -> ClipboardResult<()> + Send + Sync>;
+
+/// Enregistrement des copies dans l'historique
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RecordingState {
+	#[default]
+	Recording,
+	/// Enregistrement suspendu, jusqu'à la date indiquée ou jusqu'à la reprise
+	Paused { until: Option<DateTime<Utc>> },
+}
+
+impl RecordingState {
+	/// Indique si l'enregistrement est suspendu à la date indiquée
+	pub fn is_paused_at(&self, now: DateTime<Utc>) -> bool {
+		match self {
+			RecordingState::Recording => false,
+			RecordingState::Paused { until } => until.is_none_or(|until| now < until),
+		}
+	}
+
+	/// Indique si l'enregistrement est actuellement suspendu
+	pub fn is_paused(&self) -> bool {
+		self.is_paused_at(Utc::now())
+	}
+}
+
+/// Nombre de vérifications sans changement avant chaque doublement de l'intervalle
+const IDLE_CHECKS_PER_BACKOFF: u32 = 20;
+
+/// Réglages de la scrutation périodique
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PollingSettings {
+	/// Intervalle de base entre deux vérifications
+	pub interval: Duration,
+	/// Intervalle maximal atteint en cas d'inactivité
+	pub max_interval: Duration,
+	/// Ralentir quand le presse-papiers reste inchangé ou que la session est verrouillée
+	pub adaptive: bool,
+}
+
+impl PollingSettings {
+	/// Délai avant la prochaine vérification
+	///
+	/// L'intervalle double toutes les `IDLE_CHECKS_PER_BACKOFF` vérifications
+	/// sans changement, et passe directement au maximum si la session est verrouillée.
+	pub fn next_delay(&self, idle_checks: u32, locked: bool) -> Duration {
+		if !self.adaptive {
+			return self.interval;
+		}
+		let max_interval = self.max_interval.max(self.interval);
+		if locked {
+			return max_interval;
+		}
+
+		let doublings = (idle_checks / IDLE_CHECKS_PER_BACKOFF).min(16);
+		self.interval.saturating_mul(1 << doublings).min(max_interval)
+	}
+}
+
+impl From<&Config> for PollingSettings {
+	fn from(config: &Config) -> Self {
+		Self {
+			interval: Duration::from_millis(config.check_interval_ms.max(1)),
+			max_interval: Duration::from_millis(config.max_check_interval_ms),
+			adaptive: config.adaptive_polling,
+		}
+	}
+}
+
 /// Surveillance du presse-papiers, exécuté dans un thread tokio séparé
-pub struct ClipboardWatcher {
+///
+/// Par défaut, le presse-papiers du système est surveillé ; `with_clipboard`
+/// en substitue un autre, un `MemoryClipboard` par exemple.
+pub struct ClipboardWatcher<B: ClipboardBackend = Box<dyn ClipboardBackend>> {
 	sender: ClipboardEventSender,
 	receiver: Option<ClipboardEventReceiver>,
-	last_content: Arc<Mutex<Option<ClipboardContent>>>,
+	/// Clé rapide du dernier contenu vu pour chaque sélection
+	last_content: Arc<Mutex<HashMap<Selection, u64>>>,
 	running: Arc<Mutex<bool>>,
+	backend: WatcherBackend,
+	track_primary: bool,
+	polling: PollingHandle,
+	secrets: SecretFilterHandle,
+	capture: CaptureRulesHandle,
+	recording: PauseHandle,
+	listener: Mutex<Option<DataControlListener>>,
+	/// Source des changements de sélection, à la place du compositeur
+	listen: Option<Listen>,
+	connect: Connector<B>,
 }
 
 impl ClipboardWatcher {
 	/// Crée une nouvelle instance du surveillant de presse-papiers
 	pub fn new() -> Self {
+		Self::from_config(&Config::default())
+	}
+
+	/// Crée un surveillant selon la configuration
+	pub fn from_config(config: &Config) -> Self {
+		let polling = Arc::new(Mutex::new(PollingSettings::from(config)));
+		Self::with_polling(config.watcher_backend, config.track_primary_selection, polling)
+			.with_secret_filter(Arc::new(Mutex::new(SecretFilter::from(config))))
+			.with_capture_rules(Arc::new(Mutex::new(CaptureRules::from(config))))
+	}
+
+	/// Crée un surveillant utilisant le mécanisme de détection indiqué,
+	/// suivant éventuellement la sélection PRIMARY en plus du presse-papiers
+	pub fn with_backend(backend: WatcherBackend, track_primary: bool) -> Self {
+		let polling = Arc::new(Mutex::new(PollingSettings::from(&Config::default())));
+		Self::with_polling(backend, track_primary, polling)
+	}
+
+	/// Crée un surveillant dont les réglages de scrutation sont partagés :
+	/// les modifier via `polling` prend effet dès la vérification suivante
+	pub fn with_polling(backend: WatcherBackend, track_primary: bool, polling: PollingHandle) -> Self {
 		let (sender, receiver) = mpsc::channel(100); // Buffer de 100 événements
 		Self {
 			sender,
 			receiver: Some(receiver),
-			last_content: Arc::new(Mutex::new(None)),
+			last_content: Arc::new(Mutex::new(HashMap::new())),
 			running: Arc::new(Mutex::new(false)),
+			backend,
+			track_primary,
+			polling,
+			secrets: Arc::new(Mutex::new(SecretFilter::from(&Config::default()))),
+			capture: Arc::new(Mutex::new(CaptureRules::default())),
+			recording: Arc::new(Mutex::new(RecordingState::Recording)),
+			listener: Mutex::new(None),
+			listen: None,
+			connect: Arc::new(|| Ok(Box::new(ArboardBackend::new()?) as Box<dyn ClipboardBackend>)),
 		}
 	}
+}
+
+impl<B: ClipboardBackend> ClipboardWatcher<B> {
+	/// Surveille le presse-papiers ouvert par `connect` plutôt que celui du système
+	///
+	/// `connect` est rappelée après chaque erreur de lecture. Les événements
+	/// data-control ne concernent que le presse-papiers du système : un autre
+	/// presse-papiers est surveillé par scrutation, à moins que
+	/// `with_selection_events` ne lui fournisse ses propres événements.
+	pub fn with_clipboard<C: ClipboardBackend>(
+		self,
+		connect: impl Fn() -> ClipboardResult<C> + Send + Sync + 'static,
+	) -> ClipboardWatcher<C> {
+		ClipboardWatcher {
+			sender: self.sender,
+			receiver: self.receiver,
+			last_content: self.last_content,
+			running: self.running,
+			backend: WatcherBackend::Polling,
+			track_primary: self.track_primary,
+			polling: self.polling,
+			secrets: self.secrets,
+			capture: self.capture,
+			recording: self.recording,
+			listener: self.listener,
+			listen: self.listen,
+			connect: Arc::new(connect),
+		}
+	}
+
+	/// Reçoit les changements de sélection de `listen` plutôt que du compositeur
+	///
+	/// `listen` est appelée au démarrage avec le canal à notifier ; en cas
+	/// d'échec, `backend` décide du repli sur la scrutation. Permet d'éprouver
+	/// la surveillance par événements avec un autre presse-papiers.
+	pub fn with_selection_events(
+		mut self,
+		backend: WatcherBackend,
+		listen: impl Fn(SelectionNotifier, bool) -> ClipboardResult<()> + Send + Sync + 'static,
+	) -> Self {
+		self.backend = backend;
+		self.listen = Some(Arc::new(listen));
+		self
+	}
+
+	/// Filtre les copies avec un filtre des secrets partagé : le modifier via
+	/// `secrets` prend effet dès la copie suivante
+	pub fn with_secret_filter(mut self, secrets: SecretFilterHandle) -> Self {
+		self.secrets = secrets;
+		self
+	}
+
+	/// Applique des règles de capture par application partagées : les modifier
+	/// via `capture` prend effet dès la copie suivante
+	pub fn with_capture_rules(mut self, capture: CaptureRulesHandle) -> Self {
+		self.capture = capture;
+		self
+	}
+
+	/// Suspend l'enregistrement selon un état partagé : les copies faites
+	/// pendant la pause ne sont pas enregistrées, seule leur empreinte est
+	/// mémorisée pour les reconnaître à la reprise
+	pub fn with_recording(mut self, recording: PauseHandle) -> Self {
+		self.recording = recording;
+		self
+	}
+
+	/// Réglages de scrutation partagés de ce surveillant
+	pub fn polling_handle(&self) -> PollingHandle {
+		self.polling.clone()
+	}
 
 	/// Récupère le récepteur du canal d'événements
 	pub fn take_receiver(&mut self) -> Option<ClipboardEventReceiver> {
@@ -44,84 +247,225 @@ impl ClipboardWatcher {
 		*running = true;
 		drop(running);
 
+		match self.backend {
+			WatcherBackend::Polling => self.start_polling(),
+			WatcherBackend::DataControl => {
+				if let Err(e) = self.start_data_control().await {
+					*self.running.lock().await = false;
+					return Err(e);
+				}
+			}
+			WatcherBackend::Auto => {
+				if let Err(e) = self.start_data_control().await {
+					warn!("{}. Repli sur la scrutation périodique.", e);
+					self.start_polling();
+				}
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Surveille le presse-papiers par scrutation périodique
+	fn start_polling(&self) {
 		let sender = self.sender.clone();
 		let last_content = self.last_content.clone();
 		let running = self.running.clone();
+		let polling = self.polling.clone();
+		let secrets = self.secrets.clone();
+		let capture = self.capture.clone();
+		let recording = self.recording.clone();
+		let connect = self.connect.clone();
+		let selections = if self.track_primary {
+			vec![Selection::Clipboard, Selection::Primary]
+		} else {
+			vec![Selection::Clipboard]
+		};
+
+		info!("Surveillance du presse-papiers par scrutation");
 
 		tokio::spawn(async move {
-			let mut interval = time::interval(Duration::from_millis(500));
-			
+			let mut manager = None;
+			let mut session = SessionMonitor::new();
+			let mut idle_checks: u32 = 0;
+
 			while *running.lock().await {
-				interval.tick().await;
-				
-				// Vérifier le contenu du presse-papiers
-				match ClipboardManager::new() {
-					Ok(mut manager) => {
-						match manager.get_current_content() {
-							Ok(Some(current_content)) => {
-								let mut last = last_content.lock().await;
-
-								// Vérifier si le contenu a changé
-								let content_changed = match &*last {
-									Some(last_content) => {
-										!Self::contents_equal(last_content, &current_content)
-									}
-									None => true, // Pas de contenu précédent
-								};
-
-								if content_changed {
-									debug!("Nouveau contenu détecté dans le presse-papiers");
-									
-									// Mettre à jour le dernier contenu connu
-									*last = Some(current_content.clone());
-									
-									// Notifier les auditeurs
-									let item = ClipboardItem::new(current_content);
-									if let Err(e) = sender.send(item).await {
-										error!("Erreur lors de l'envoi de l'événement: {}", e);
-									}
-								}
-							}
-							Ok(None) => {
-								debug!("Presse-papiers vide");
-							}
-							Err(e) => {
-								error!("Erreur lors de la lecture du presse-papiers: {}", e);
-							}
-						}
-					}
-					Err(e) => {
-						error!("Erreur lors de la création du gestionnaire de presse-papiers: {}", e);
-					}
+				// Relire les réglages à chaque tour pour suivre les changements de configuration
+				let settings = *polling.lock().await;
+				let locked = settings.adaptive && session.is_locked().await;
+				time::sleep(settings.next_delay(idle_checks, locked)).await;
+
+				// Inutile de lire le presse-papiers d'une session verrouillée
+				if locked {
+					continue;
+				}
+
+				// Pendant une pause, le contenu copié est seulement mémorisé : à la
+				// reprise, seul un contenu différent est enregistré
+				let record = !recording.lock().await.is_paused();
+
+				let mut changed = false;
+				for &selection in &selections {
+					changed |= Self::check_clipboard(&mut manager, &connect, selection, &last_content, &secrets, &capture, &sender, record).await;
+				}
+				idle_checks = if changed { 0 } else { idle_checks.saturating_add(1) };
+			}
+
+			info!("Surveillance du presse-papiers arrêtée");
+		});
+	}
+
+	/// Surveille le presse-papiers à partir des événements du compositeur
+	async fn start_data_control(&self) -> ClipboardResult<()> {
+		let (notifier, mut notifications) = mpsc::unbounded_channel();
+		*self.listener.lock().await = match &self.listen {
+			Some(listen) => {
+				listen(notifier, self.track_primary)?;
+				None
+			}
+			None => Some(DataControlListener::spawn(notifier, self.track_primary)?),
+		};
+
+		let sender = self.sender.clone();
+		let last_content = self.last_content.clone();
+		let running = self.running.clone();
+		let secrets = self.secrets.clone();
+		let capture = self.capture.clone();
+		let recording = self.recording.clone();
+		let connect = self.connect.clone();
+
+		info!("Surveillance du presse-papiers par événements data-control");
+
+		tokio::spawn(async move {
+			let mut manager = None;
+
+			while let Some(selection) = notifications.recv().await {
+				if !*running.lock().await {
+					break;
+				}
+				// Une copie faite pendant une pause est seulement mémorisée
+				let record = !recording.lock().await.is_paused();
+				if !record {
+					debug!("Enregistrement suspendu, copie ignorée");
 				}
+				Self::check_clipboard(&mut manager, &connect, selection, &last_content, &secrets, &capture, &sender, record).await;
 			}
-			
+
 			info!("Surveillance du presse-papiers arrêtée");
 		});
 
 		Ok(())
 	}
 
+	/// Lit une sélection et notifie les auditeurs si son contenu a changé
+	///
+	/// Sans `record`, le nouveau contenu est seulement mémorisé. Retourne `true`
+	/// si un nouveau contenu a été détecté.
+	#[allow(clippy::too_many_arguments)]
+	async fn check_clipboard(
+		manager: &mut Option<ClipboardManager<B>>,
+		connect: &Connector<B>,
+		selection: Selection,
+		last_content: &Mutex<HashMap<Selection, u64>>,
+		secrets: &Mutex<SecretFilter>,
+		capture: &Mutex<CaptureRules>,
+		sender: &ClipboardEventSender,
+		record: bool,
+	) -> bool {
+		// Réutiliser le gestionnaire d'un appel à l'autre, le recréer après une erreur
+		if manager.is_none() {
+			match connect() {
+				Ok(backend) => *manager = Some(ClipboardManager::with_backend(backend)),
+				Err(e) => {
+					error!("Erreur lors de la création du gestionnaire de presse-papiers: {}", e);
+					return false;
+				}
+			}
+		}
+		let Some(current) = manager.as_mut() else {
+			return false;
+		};
+
+		match current.get_selection_content(selection) {
+			Ok(Some(current_content)) => {
+				let mut last = last_content.lock().await;
+
+				// Comparer une clé rapide : l'empreinte complète d'une image inchangée
+				// n'est pas recalculée à chaque vérification
+				let key = current_content.quick_key();
+				let content_changed = last.get(&selection) != Some(&key);
+
+				if content_changed {
+					debug!("Nouveau contenu détecté dans le presse-papiers");
+
+					// Mettre à jour la dernière clé connue
+					last.insert(selection, key);
+					if !record {
+						return true;
+					}
+					drop(last);
+
+					// L'application active au moment de la copie en est l'auteur ; en
+					// scrutation, elle n'est relevée qu'à la détection et peut avoir changé
+					// depuis la copie
+					let rules = capture.lock().await.clone();
+					let source = if rules.needs_source() {
+						source::focused_application().await
+					} else {
+						None
+					};
+					let action = rules.action_for(source.as_ref());
+					if action == CaptureAction::Ignore {
+						debug!("Copie ignorée selon les règles de capture ({:?})", source);
+						return true;
+					}
+
+					// Notifier les auditeurs avec toutes les représentations offertes
+					let formats = current.get_selection_formats(selection);
+					let content = match current_content {
+						ClipboardContent::Files(_) => current_content,
+						other => files::files_from_formats(&formats)
+							.map(ClipboardContent::Files)
+							.unwrap_or(other),
+					};
+					let item = ClipboardItem::new(content)
+						.with_formats(formats)
+						.with_selection(selection);
+					// Le contenu d'une copie secrète n'est jamais journalisé
+					let Some(item) = secrets.lock().await.apply(item) else {
+						debug!("Copie secrète écartée de l'historique");
+						return true;
+					};
+					let Some(item) = CaptureRules::apply(action, item) else {
+						debug!("Copie écartée selon les règles de capture ({:?})", source);
+						return true;
+					};
+					if let Err(e) = sender.send(item).await {
+						error!("Erreur lors de l'envoi de l'événement: {}", e);
+					}
+				}
+
+				content_changed
+			}
+			Ok(None) => {
+				debug!("Presse-papiers vide");
+				false
+			}
+			Err(e) => {
+				error!("Erreur lors de la lecture du presse-papiers: {}", e);
+				*manager = None;
+				false
+			}
+		}
+	}
+
 	/// Arrête la surveillance du presse-papiers
 	pub async fn stop(&self) {
 		let mut running = self.running.lock().await;
 		*running = false;
-	}
 
-	/// Compare deux contenus de presse-papiers pour déterminer s'ils sont égaux
-	fn contents_equal(a: &ClipboardContent, b: &ClipboardContent) -> bool {
-		match (a, b) {
-			(ClipboardContent::Text(a_text), ClipboardContent::Text(b_text)) => a_text == b_text,
-			(
-				ClipboardContent::Image(a_data, a_meta),
-				ClipboardContent::Image(b_data, b_meta),
-			) => {
-				a_meta.width == b_meta.width
-					&& a_meta.height == b_meta.height
-					&& a_data == b_data
-			}
-			// Types différents
-			_ => false,
+		if let Some(listener) = self.listener.lock().await.take() {
+			listener.cancel();
 		}
 	}
 }
@@ -130,4 +474,4 @@ impl Default for ClipboardWatcher {
 	fn default() -> Self {
 		Self::new()
 	}
-}
\ No newline at end of file
+}
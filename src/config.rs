@@ -1,11 +1,13 @@
 use crate::error::{ClipboardError, ClipboardResult};
 use log::info;
 use serde::{Deserialize, Serialize};
+use std::fmt;
 use std::fs;
 use std::path::{Path, PathBuf};
 
 /// Configuration de l'application
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
 	/// Taille maximale de l'historique du presse-papiers
 	pub max_history_size: usize,
@@ -13,9 +15,57 @@ pub struct Config {
 	/// Durée de conservation des éléments (en jours)
 	pub retention_days: u32,
 	
+	/// Volume maximal des images de l'historique, décompressées (en Mo, 0 pour illimité)
+	pub max_image_storage_mb: u64,
+	
+	/// Intervalle entre deux nettoyages périodiques de l'historique (en minutes)
+	pub retention_check_minutes: u64,
+	
+	/// Traitement d'un contenu déjà présent dans l'historique
+	pub dedup_policy: DedupPolicy,
+	
 	/// Intervalle de vérification du presse-papiers (en millisecondes)
 	pub check_interval_ms: u64,
 	
+	/// Ralentir la scrutation quand le presse-papiers reste inchangé ou que la session est verrouillée
+	pub adaptive_polling: bool,
+	
+	/// Intervalle maximal atteint par la scrutation adaptative (en millisecondes)
+	pub max_check_interval_ms: u64,
+	
+	/// Moteur de stockage de l'historique
+	pub storage_backend: StorageBackend,
+	
+	/// Chiffrement de l'historique : origine de la clé, ou `None` pour le désactiver
+	pub encryption: KeySource,
+	
+	/// Fichier contenant la clé de l'historique (par défaut `history.key` à côté de la configuration)
+	pub keyfile_path: Option<PathBuf>,
+	
+	/// Mécanisme utilisé pour détecter les changements du presse-papiers
+	pub watcher_backend: WatcherBackend,
+	
+	/// Traitement des copies signalées comme secrètes par un gestionnaire de mots de passe
+	pub secret_hint_action: SecretHintAction,
+	
+	/// Détecteurs de secrets appliqués au texte copié
+	pub secret_detectors: Vec<SecretDetector>,
+	
+	/// Durée de vie des éléments expirant automatiquement (en secondes)
+	pub secret_expiry_seconds: u64,
+	
+	/// Règles de capture par application ; la première qui correspond s'applique
+	pub capture_rules: Vec<CaptureRule>,
+	
+	/// Traitement des copies sans règle correspondante ou d'origine inconnue
+	pub default_capture: CaptureAction,
+	
+	/// Enregistrer aussi la sélection PRIMARY (texte sélectionné, clic milieu)
+	pub track_primary_selection: bool,
+	
+	/// Mécanisme simulant la saisie pour coller un élément dans la fenêtre active
+	pub paste_backend: PasteBackend,
+	
 	/// Thème de l'interface (clair ou sombre)
 	pub theme: Theme,
 	
@@ -34,12 +84,214 @@ pub enum Theme {
 	System,
 }
 
+/// Moteurs de stockage de l'historique
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum StorageBackend {
+	/// Base SQLite (une base Sled existante y est importée au premier lancement)
+	#[default]
+	Sqlite,
+	/// Base Sled
+	Sled,
+}
+
+/// Origines possibles de la clé de chiffrement de l'historique
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum KeySource {
+	/// Historique non chiffré
+	#[default]
+	None,
+	/// Clé dérivée d'une phrase secrète, demandée au démarrage
+	Passphrase,
+	/// Clé conservée dans un fichier local
+	Keyfile,
+	/// Clé conservée dans le trousseau du bureau (Secret Service)
+	SecretService,
+}
+
+impl fmt::Display for KeySource {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			KeySource::None => write!(f, "aucune"),
+			KeySource::Passphrase => write!(f, "phrase secrète"),
+			KeySource::Keyfile => write!(f, "fichier de clé"),
+			KeySource::SecretService => write!(f, "trousseau du bureau"),
+		}
+	}
+}
+
+/// Mécanismes de détection des changements du presse-papiers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum WatcherBackend {
+	/// Événements data-control si le compositeur les propose, sinon scrutation
+	#[default]
+	Auto,
+	/// Événements du protocole wlr-data-control (Wayland uniquement)
+	DataControl,
+	/// Scrutation périodique du presse-papiers
+	Polling,
+}
+
+/// Mécanismes de collage dans la fenêtre active
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum PasteBackend {
+	/// Premier mécanisme disponible dans la session, sinon `CopyOnly`
+	#[default]
+	Auto,
+	/// Protocole Wayland virtual-keyboard, sans outil externe
+	VirtualKeyboard,
+	/// Outil `wtype` (Wayland, protocole virtual-keyboard)
+	Wtype,
+	/// Outil `xdotool` (sessions X11)
+	Xdotool,
+	/// Outil `dotool` (accès à `/dev/uinput`)
+	Dotool,
+	/// Outil `ydotool` (service `ydotoold` démarré)
+	Ydotool,
+	/// Copier l'élément sans le coller : l'utilisateur colle lui-même
+	CopyOnly,
+}
+
+impl fmt::Display for PasteBackend {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			PasteBackend::Auto => write!(f, "détection automatique"),
+			PasteBackend::VirtualKeyboard => write!(f, "clavier virtuel Wayland"),
+			PasteBackend::Wtype => write!(f, "wtype"),
+			PasteBackend::Xdotool => write!(f, "xdotool"),
+			PasteBackend::Dotool => write!(f, "dotool"),
+			PasteBackend::Ydotool => write!(f, "ydotool"),
+			PasteBackend::CopyOnly => write!(f, "copie seule"),
+		}
+	}
+}
+
+/// Traitement des copies signalées comme secrètes (`x-kde-passwordManagerHint: secret`...)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum SecretHintAction {
+	/// Ignorer la copie
+	#[default]
+	Skip,
+	/// Afficher la copie dans l'historique sans jamais l'enregistrer
+	MemoryOnly,
+}
+
+/// Action appliquée à un texte reconnu par un détecteur de secrets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DetectorAction {
+	/// Ignorer la copie
+	Drop,
+	/// Enregistrer la copie en masquant le secret
+	Mask,
+	/// Enregistrer la copie et la retirer après `secret_expiry_seconds`
+	Expire,
+}
+
+/// Détecteur de secrets par expression régulière
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SecretDetector {
+	/// Nom affiché dans les journaux
+	pub name: String,
+	/// Expression régulière recherchée dans le texte copié
+	pub pattern: String,
+	/// Action appliquée quand l'expression est trouvée
+	pub action: DetectorAction,
+	/// Ne retenir que les nombres dont la clé de Luhn est valide (numéros de carte)
+	#[serde(default)]
+	pub luhn: bool,
+}
+
+impl SecretDetector {
+	fn new(name: &str, pattern: &str, action: DetectorAction) -> Self {
+		Self {
+			name: name.to_string(),
+			pattern: pattern.to_string(),
+			action,
+			luhn: false,
+		}
+	}
+}
+
+/// Détecteurs proposés par défaut
+pub fn default_secret_detectors() -> Vec<SecretDetector> {
+	vec![
+		SecretDetector::new(
+			"Clé privée",
+			r"-----BEGIN (?:[A-Z0-9]+ )*PRIVATE KEY(?: BLOCK)?-----",
+			DetectorAction::Drop,
+		),
+		SecretDetector::new("Clé d'accès AWS", r"\b(?:AKIA|ASIA)[0-9A-Z]{16}\b", DetectorAction::Expire),
+		SecretDetector::new("Jeton GitHub", r"\bgh[pousr]_[A-Za-z0-9]{36,}\b", DetectorAction::Expire),
+		SecretDetector::new("Jeton Slack", r"\bxox[abprs]-[A-Za-z0-9-]{10,}", DetectorAction::Expire),
+		SecretDetector::new("Clé d'API", r"\b(?:sk|rk)[-_](?:live_|test_|proj-)?[A-Za-z0-9_-]{20,}", DetectorAction::Expire),
+		SecretDetector::new(
+			"Jeton JWT",
+			r"\beyJ[A-Za-z0-9_-]{5,}\.eyJ[A-Za-z0-9_-]{5,}\.[A-Za-z0-9_-]+",
+			DetectorAction::Expire,
+		),
+		SecretDetector {
+			luhn: true,
+			..SecretDetector::new("Numéro de carte", r"\b\d(?:[ -]?\d){12,18}\b", DetectorAction::Mask)
+		},
+	]
+}
+
+/// Traitement d'une copie selon l'application dont elle provient
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CaptureAction {
+	/// Enregistrer la copie
+	#[default]
+	Store,
+	/// Ne pas enregistrer la copie
+	Ignore,
+	/// N'enregistrer que le texte brut ; les autres contenus sont ignorés
+	TextOnly,
+	/// Enregistrer la copie et la retirer après le délai indiqué
+	Expire { minutes: u64 },
+}
+
+/// Règle de capture associée à une application
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CaptureRule {
+	/// Identifiant d'application Wayland (`app_id`) ou classe de fenêtre X11, sans tenir compte de la casse
+	pub app: String,
+	/// Traitement des copies de cette application
+	pub action: CaptureAction,
+}
+
+/// Traitement des contenus copiés plusieurs fois
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DedupPolicy {
+	/// Remonter l'élément existant en tête de l'historique
+	#[default]
+	MoveToTop,
+	/// Mettre à jour le compteur et la date d'utilisation sans déplacer l'élément
+	UpdateInPlace,
+	/// Conserver chaque copie comme un élément distinct
+	KeepDuplicates,
+}
+
 impl Default for Config {
 	fn default() -> Self {
 		Self {
 			max_history_size: 100,
 			retention_days: 30,
+			max_image_storage_mb: 256,
+			retention_check_minutes: 60,
+			dedup_policy: DedupPolicy::MoveToTop,
 			check_interval_ms: 500,
+			adaptive_polling: true,
+			max_check_interval_ms: 5000,
+			storage_backend: StorageBackend::Sqlite,
+			encryption: KeySource::None,
+			keyfile_path: None,
+			watcher_backend: WatcherBackend::Auto,
+			secret_hint_action: SecretHintAction::Skip,
+			secret_detectors: default_secret_detectors(),
+			secret_expiry_seconds: 300,
+			capture_rules: Vec::new(),
+			default_capture: CaptureAction::Store,
+			track_primary_selection: false,
+			paste_backend: PasteBackend::Auto,
 			theme: Theme::System,
 			always_on_top: true,
 			data_dir: get_default_data_dir(),
@@ -48,6 +300,35 @@ impl Default for Config {
 }
 
 impl Config {
+	/// Fichier contenant la clé de l'historique
+	pub fn keyfile(&self) -> PathBuf {
+		self.keyfile_path
+			.clone()
+			.unwrap_or_else(|| get_default_config_dir().join(DEFAULT_KEYFILE_NAME))
+	}
+	
+	/// Déplace le fichier de clé que les versions précédentes rangeaient avec l'historique
+	///
+	/// Une copie de l'historique ne doit pas emporter la clé qui le déchiffre.
+	pub fn move_legacy_keyfile(&self) -> ClipboardResult<()> {
+		let legacy = self.data_dir.join(DEFAULT_KEYFILE_NAME);
+		let keyfile = self.keyfile();
+		if self.keyfile_path.is_some() || !legacy.is_file() || keyfile.exists() {
+			return Ok(());
+		}
+		
+		if let Some(parent) = keyfile.parent() {
+			fs::create_dir_all(parent)
+				.map_err(|e| ClipboardError::Config(format!("Erreur création dossier de la clé: {}", e)))?;
+		}
+		// Le dossier de données peut se trouver sur un autre système de fichiers
+		fs::rename(&legacy, &keyfile)
+			.or_else(|_| fs::copy(&legacy, &keyfile).and_then(|_| fs::remove_file(&legacy)))
+			.map_err(|e| ClipboardError::Config(format!("Erreur déplacement fichier de clé: {}", e)))?;
+		info!("Fichier de clé déplacé de {} vers {}", legacy.display(), keyfile.display());
+		Ok(())
+	}
+	
 	/// Charge la configuration depuis un fichier
 	pub fn load<P: AsRef<Path>>(path: P) -> ClipboardResult<Self> {
 		let path = path.as_ref();
@@ -91,6 +372,10 @@ impl Config {
 	}
 }
 
+/// Nom du fichier de clé par défaut, dans le dossier de configuration
+const DEFAULT_KEYFILE_NAME: &str = "history.key";
+
+
 /// Détermine le chemin par défaut pour le dossier de données
 fn get_default_data_dir() -> PathBuf {
 	let mut path = dirs::data_local_dir()
@@ -100,12 +385,16 @@ fn get_default_data_dir() -> PathBuf {
 	path
 }
 
-/// Détermine le chemin par défaut pour le fichier de configuration
-pub fn get_default_config_path() -> PathBuf {
+/// Détermine le dossier par défaut de la configuration, qui accueille aussi le fichier de clé
+fn get_default_config_dir() -> PathBuf {
 	let mut path = dirs::config_dir()
 		.unwrap_or_else(|| PathBuf::from("."));
 		
 	path.push("clipboard-manager");
-	path.push("config.json");
 	path
+}
+
+/// Détermine le chemin par défaut pour le fichier de configuration
+pub fn get_default_config_path() -> PathBuf {
+	get_default_config_dir().join("config.json")
 }
\ No newline at end of file
@@ -0,0 +1,403 @@
+use crate::clipboard::{
+	CaptureRules, CaptureRulesHandle, ClipboardBackend, ClipboardEventReceiver, ClipboardItem, ClipboardManager,
+	ClipboardWatcher, PauseHandle, PollingHandle, PollingSettings, RecordingState, SecretFilter, SecretFilterHandle,
+};
+use crate::config::{get_default_config_path, Config, StorageBackend};
+use crate::error::{ClipboardError, ClipboardResult};
+use crate::ipc::{socket_path, DbusInterface, DbusServer, IpcContext, IpcServer};
+use crate::storage::{open_storage, prune, record_copy, RetentionPolicy, Storage};
+use chrono::{DateTime, Utc};
+use log::{debug, error, info, warn};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::fs::{File, OpenOptions, TryLockError};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::{broadcast, mpsc, Mutex};
+use tokio::time::{self, Instant};
+
+/// Fichier verrouillé par le processus qui enregistre l'historique
+const LOCK_FILE: &str = "recorder.lock";
+
+/// Verrou garantissant qu'un seul processus enregistre les copies d'un dossier de données
+///
+/// Le démon le prend au démarrage ; l'interface le prend si aucun démon ne
+/// tourne, et se contente sinon d'afficher l'historique qu'il enregistre. Le
+/// système libère le verrou à la fin du processus, même brutale.
+#[derive(Debug)]
+pub struct RecorderLock {
+	_file: File,
+}
+
+impl RecorderLock {
+	/// Prend le verrou ; `None` s'il est déjà tenu par un autre processus
+	pub fn try_acquire(data_dir: &Path) -> ClipboardResult<Option<Self>> {
+		std::fs::create_dir_all(data_dir)?;
+		let file = OpenOptions::new()
+			.create(true)
+			.truncate(false)
+			.write(true)
+			.open(data_dir.join(LOCK_FILE))?;
+
+		match file.try_lock() {
+			Ok(()) => Ok(Some(Self { _file: file })),
+			Err(TryLockError::WouldBlock) => Ok(None),
+			Err(TryLockError::Error(e)) => Err(e.into()),
+		}
+	}
+}
+
+/// Enregistre l'historique en arrière-plan, sans interface
+///
+/// Le démon possède la surveillance du presse-papiers et le stockage ;
+/// l'interface, lancée à la demande, affiche l'historique qu'il enregistre.
+/// Il s'arrête sur SIGINT ou SIGTERM.
+pub fn run(paused: bool) -> ClipboardResult<()> {
+	let runtime = tokio::runtime::Runtime::new()?;
+	runtime.block_on(async {
+		let config_path = get_default_config_path();
+		let daemon = Daemon::open(&config_path, paused)?;
+		daemon.run(&config_path).await
+	})
+}
+
+/// Ouvre le presse-papiers surveillé, à nouveau après chaque erreur de lecture
+type Connector = Arc<dyn Fn() -> ClipboardResult<Box<dyn ClipboardBackend>> + Send + Sync>;
+
+/// État du démon d'enregistrement
+pub struct Daemon {
+	config: Config,
+	storage: Arc<Mutex<Box<dyn Storage>>>,
+	clipboard_manager: Arc<Mutex<ClipboardManager>>,
+	watcher: Option<ClipboardWatcher>,
+	polling: PollingHandle,
+	secrets: SecretFilterHandle,
+	capture: CaptureRulesHandle,
+	recording: PauseHandle,
+	dbus: Option<DbusServer>,
+	/// Presse-papiers surveillé à la place de celui du système
+	connect: Option<Connector>,
+	_lock: Option<RecorderLock>,
+}
+
+impl Daemon {
+	/// Prend le verrou d'enregistrement et ouvre l'historique
+	fn open(config_path: &Path, paused: bool) -> ClipboardResult<Self> {
+		let config = Config::load(config_path)?;
+		let lock = RecorderLock::try_acquire(&config.data_dir)?.ok_or_else(|| {
+			ClipboardError::Unexpected(format!(
+				"Un autre processus enregistre déjà l'historique de {}",
+				config.data_dir.display()
+			))
+		})?;
+
+		// Sled verrouille sa base : l'interface ne pourrait plus l'ouvrir
+		if config.storage_backend == StorageBackend::Sled {
+			return Err(ClipboardError::Config(
+				"Le démon nécessite le stockage SQLite, partageable avec l'interface".to_string(),
+			));
+		}
+
+		let (storage, vault) = open_storage(&config)?;
+		if vault.is_some_and(|vault| vault.is_locked()) {
+			return Err(ClipboardError::Storage(format!(
+				"Historique chiffré verrouillé : définissez {} ou utilisez un fichier de clé",
+				crate::storage::vault::PASSPHRASE_ENV
+			)));
+		}
+		info!("Historique ouvert dans {}", config.data_dir.display());
+		let clipboard_manager = ClipboardManager::new()?;
+
+		let recording = if paused {
+			info!("Démarrage avec l'enregistrement en pause");
+			RecordingState::Paused { until: None }
+		} else {
+			RecordingState::Recording
+		};
+
+		Ok(Self {
+			recording: Arc::new(Mutex::new(recording)),
+			_lock: Some(lock),
+			..Self::assemble(config, storage, clipboard_manager)
+		})
+	}
+
+	/// Démon enregistrant les copies du presse-papiers indiqué dans l'historique indiqué
+	///
+	/// Ni verrou d'enregistrement ni fichier de configuration : sert aux tests
+	/// de bout en bout, avec un `MemoryClipboard` et un `MemoryStorage`.
+	pub fn with_history<B: ClipboardBackend + Clone + Sync>(
+		config: Config,
+		storage: Box<dyn Storage>,
+		clipboard: B,
+	) -> Self {
+		let clipboard_manager = ClipboardManager::with_backend(clipboard.clone()).boxed();
+		Self {
+			connect: Some(Arc::new(move || Ok(Box::new(clipboard.clone()) as Box<dyn ClipboardBackend>))),
+			..Self::assemble(config, storage, clipboard_manager)
+		}
+	}
+
+	/// État initial du démon, avant la surveillance
+	fn assemble(config: Config, storage: Box<dyn Storage>, clipboard_manager: ClipboardManager) -> Self {
+		Self {
+			polling: Arc::new(Mutex::new(PollingSettings::from(&config))),
+			secrets: Arc::new(Mutex::new(SecretFilter::from(&config))),
+			capture: Arc::new(Mutex::new(CaptureRules::from(&config))),
+			recording: Arc::new(Mutex::new(RecordingState::Recording)),
+			config,
+			storage: Arc::new(Mutex::new(storage)),
+			clipboard_manager: Arc::new(Mutex::new(clipboard_manager)),
+			watcher: None,
+			dbus: None,
+			connect: None,
+			_lock: None,
+		}
+	}
+
+	/// Historique enregistré par le démon
+	pub fn history(&self) -> Arc<Mutex<Box<dyn Storage>>> {
+		self.storage.clone()
+	}
+
+	/// Enregistre les copies jusqu'à la réception de SIGINT ou SIGTERM
+	async fn run(mut self, config_path: &Path) -> ClipboardResult<()> {
+		let (_config_watcher, configs) = watch_config(config_path)?;
+		let mut terminate = signal(SignalKind::terminate())?;
+
+		// Le socket de contrôle est supprimé à l'abandon de la tâche
+		let context = IpcContext::new(self.storage.clone(), self.recording.clone())
+			.with_clipboard(self.clipboard_manager.clone());
+		let server = match socket_path().and_then(|path| IpcServer::bind(&path, context.clone())) {
+			Ok(server) => Some(tokio::spawn(server.serve())),
+			Err(e) => {
+				error!("Contrôle par socket indisponible: {}", e);
+				None
+			}
+		};
+
+		// Sans fenêtre à afficher, `ToggleWindow` lance l'interface
+		self.dbus = match DbusServer::start(DbusInterface::new(context)).await {
+			Ok(dbus) => Some(dbus),
+			Err(e) => {
+				error!("Service D-Bus indisponible: {}", e);
+				None
+			}
+		};
+
+		let shutdown = async move {
+			tokio::select! {
+				_ = tokio::signal::ctrl_c() => {}
+				_ = terminate.recv() => {}
+			}
+		};
+		let recorded = self.record_while(configs, shutdown).await;
+
+		if let Some(server) = server {
+			server.abort();
+			let _ = server.await;
+		}
+		recorded
+	}
+
+	/// Enregistre les copies et nettoie l'historique jusqu'à l'achèvement de `shutdown`
+	pub async fn record_until(self, shutdown: impl Future<Output = ()>) -> ClipboardResult<()> {
+		// Sans fichier surveillé, la configuration reste celle du démarrage
+		let (_configs, no_configs) = mpsc::unbounded_channel();
+		self.record_while(no_configs, shutdown).await
+	}
+
+	/// Boucle d'enregistrement, qui applique chaque configuration reçue par `configs`
+	async fn record_while(
+		mut self,
+		mut configs: mpsc::UnboundedReceiver<Config>,
+		shutdown: impl Future<Output = ()>,
+	) -> ClipboardResult<()> {
+		let mut receiver = self.start_watcher().await?;
+		let mut changes = self.storage.lock().await.subscribe();
+		tokio::pin!(shutdown);
+
+		let mut expiry = self.storage.lock().await.next_expiry()?;
+		let mut next_retention = Instant::now() + self.retention_interval();
+		self.prune().await;
+		info!("Démon d'enregistrement démarré");
+
+		loop {
+			let wake = expiry.map_or(next_retention, |expiry| next_retention.min(instant_at(expiry)));
+
+			tokio::select! {
+				item = receiver.recv() => match item {
+					Some(item) => self.record(item).await,
+					None => {
+						error!("Surveillance du presse-papiers interrompue");
+						break;
+					}
+				},
+				_ = time::sleep_until(wake) => {
+					self.prune().await;
+					if wake >= next_retention {
+						next_retention = Instant::now() + self.retention_interval();
+					}
+				}
+				change = changes.recv() => {
+					// Une expiration a pu être ajoutée, ici ou par l'interface
+					if !matches!(change, Err(broadcast::error::RecvError::Closed)) {
+						expiry = self.storage.lock().await.next_expiry().unwrap_or_else(|e| {
+							error!("Erreur lecture des expirations: {}", e);
+							expiry
+						});
+					}
+				}
+				Some(config) = configs.recv() => {
+					receiver = match self.apply_config(config, receiver).await {
+						Ok(receiver) => receiver,
+						Err(e) => {
+							error!("Erreur redémarrage surveillance presse-papiers: {}", e);
+							break;
+						}
+					};
+					next_retention = Instant::now() + self.retention_interval();
+				}
+				_ = &mut shutdown => break,
+			}
+		}
+
+		info!("Arrêt du démon d'enregistrement");
+		if let Some(watcher) = self.watcher.take() {
+			watcher.stop().await;
+		}
+		self.storage.lock().await.flush()
+	}
+
+	/// Crée et démarre la surveillance du presse-papiers selon la configuration
+	async fn start_watcher(&mut self) -> ClipboardResult<ClipboardEventReceiver> {
+		let mut watcher = ClipboardWatcher::with_polling(
+			self.config.watcher_backend,
+			self.config.track_primary_selection,
+			self.polling.clone(),
+		)
+		.with_secret_filter(self.secrets.clone())
+		.with_capture_rules(self.capture.clone())
+		.with_recording(self.recording.clone());
+		if let Some(connect) = self.connect.clone() {
+			watcher = watcher.with_clipboard(move || connect());
+		}
+
+		let receiver = watcher
+			.take_receiver()
+			.ok_or_else(|| ClipboardError::Unexpected("Récepteur de la surveillance indisponible".to_string()))?;
+		watcher.start().await?;
+		self.watcher = Some(watcher);
+		Ok(receiver)
+	}
+
+	/// Applique une configuration modifiée
+	///
+	/// Changer de mécanisme de surveillance ou de sélections suivies relance la surveillance.
+	async fn apply_config(
+		&mut self,
+		config: Config,
+		receiver: ClipboardEventReceiver,
+	) -> ClipboardResult<ClipboardEventReceiver> {
+		info!("Configuration modifiée, application des nouveaux réglages");
+		*self.polling.lock().await = PollingSettings::from(&config);
+		*self.secrets.lock().await = SecretFilter::from(&config);
+		*self.capture.lock().await = CaptureRules::from(&config);
+
+		let restart = config.watcher_backend != self.config.watcher_backend
+			|| config.track_primary_selection != self.config.track_primary_selection;
+		self.config = config;
+		// Les limites de conservation ont pu être abaissées
+		self.prune().await;
+
+		if !restart {
+			return Ok(receiver);
+		}
+		if let Some(watcher) = self.watcher.take() {
+			watcher.stop().await;
+		}
+		self.start_watcher().await
+	}
+
+	/// Enregistre une copie
+	async fn record(&self, item: ClipboardItem) {
+		// Sans interface ouverte, une copie gardée en mémoire ne serait visible nulle part
+		if item.memory_only {
+			debug!("Copie signalée comme secrète non conservée");
+			return;
+		}
+
+		let retention = RetentionPolicy::from(&self.config);
+		let recorded = record_copy(self.storage.lock().await.as_ref(), item, self.config.dedup_policy, &retention);
+		match recorded {
+			Ok((change, report)) => {
+				self.clipboard_manager.lock().await.clear_lapsed(&report.lapsed);
+				if let Some(dbus) = &self.dbus {
+					for item in &change.upserted {
+						if let Err(e) = dbus.item_added(item).await {
+							warn!("Signal D-Bus non émis: {}", e);
+						}
+					}
+				}
+			}
+			Err(e) => error!("Erreur sauvegarde élément: {}", e),
+		}
+	}
+
+	/// Applique les limites de conservation et retire les éléments expirés
+	async fn prune(&self) {
+		let pruned = prune(self.storage.lock().await.as_ref(), &RetentionPolicy::from(&self.config));
+		match pruned {
+			Ok((_, report)) => {
+				if report.total() > 0 {
+					info!("Nettoyage de l'historique: {}", report);
+				}
+				self.clipboard_manager.lock().await.clear_lapsed(&report.lapsed);
+			}
+			Err(e) => error!("Erreur nettoyage historique: {}", e),
+		}
+	}
+
+	/// Intervalle entre deux nettoyages périodiques
+	fn retention_interval(&self) -> Duration {
+		Duration::from_secs(self.config.retention_check_minutes.max(1) * 60)
+	}
+}
+
+/// Instant de l'horloge tokio correspondant à une date
+fn instant_at(date: DateTime<Utc>) -> Instant {
+	Instant::now() + (date - Utc::now()).to_std().unwrap_or_default()
+}
+
+/// Surveille le fichier de configuration et transmet chaque version lisible
+fn watch_config(config_path: &Path) -> ClipboardResult<(RecommendedWatcher, mpsc::UnboundedReceiver<Config>)> {
+	let (sender, receiver) = mpsc::unbounded_channel();
+	let path = config_path.to_path_buf();
+	let mut watcher = notify::recommended_watcher(move |result: notify::Result<Event>| {
+		let Ok(event) = result else {
+			return;
+		};
+		let relevant = matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_))
+			&& event.paths.iter().any(|changed| changed.file_name() == path.file_name());
+		if !relevant {
+			return;
+		}
+		match Config::load(&path) {
+			Ok(config) => {
+				let _ = sender.send(config);
+			}
+			// Fichier en cours d'écriture : attendre l'événement suivant
+			Err(e) => debug!("Configuration illisible pour l'instant: {}", e),
+		}
+	})
+	.map_err(|e| ClipboardError::Config(format!("Erreur surveillance configuration: {}", e)))?;
+
+	// Surveiller le dossier : les éditeurs remplacent souvent le fichier
+	let watch_dir = config_path.parent().map(PathBuf::from).unwrap_or_default();
+	if let Err(e) = watcher.watch(&watch_dir, RecursiveMode::NonRecursive) {
+		warn!("Configuration non surveillée, les modifications demanderont un redémarrage: {}", e);
+	}
+	Ok((watcher, receiver))
+}
@@ -15,15 +15,69 @@ pub enum ClipboardError {
 	#[error("Erreur de stockage: {0}")]
 	Storage(String),
 
+	#[error("Historique verrouillé par un autre processus: {0}")]
+	StorageLocked(String),
+
 	#[error("Erreur UI: {0}")]
 	Ui(String),
 
 	#[error("Erreur de configuration: {0}")]
 	Config(String),
 
+	#[error("Erreur IPC: {0}")]
+	Ipc(String),
+
+	#[error("Erreur D-Bus: {0}")]
+	Dbus(String),
+
+	#[error("Erreur de collage: {0}")]
+	Paste(String),
+
 	#[error("Erreur inattendue: {0}")]
 	Unexpected(String),
 }
 
+impl ClipboardError {
+	/// Ce qui a échoué, en quelques mots compréhensibles par l'utilisateur
+	pub fn summary(&self) -> &'static str {
+		match self {
+			ClipboardError::Io(_) => "Accès aux fichiers impossible",
+			ClipboardError::Serialization(_) => "Données illisibles",
+			ClipboardError::Clipboard(_) => "Presse-papiers inaccessible",
+			ClipboardError::Storage(_) => "Historique inaccessible",
+			ClipboardError::StorageLocked(_) => "Historique utilisé par un autre processus",
+			ClipboardError::Ui(_) => "Erreur d'affichage",
+			ClipboardError::Config(_) => "Configuration invalide",
+			ClipboardError::Ipc(_) => "Communication avec le gestionnaire impossible",
+			ClipboardError::Dbus(_) => "Bus de session inaccessible",
+			ClipboardError::Paste(_) => "Collage automatique impossible",
+			ClipboardError::Unexpected(_) => "Erreur inattendue",
+		}
+	}
+
+	/// Piste de résolution à proposer à l'utilisateur, s'il en existe une
+	pub fn hint(&self) -> Option<&'static str> {
+		match self {
+			ClipboardError::Io(_) => Some("Vérifiez les droits d'accès au dossier de données et l'espace disque disponible."),
+			ClipboardError::Clipboard(_) => {
+				Some("Vérifiez qu'une session Wayland ou X11 est ouverte (variables WAYLAND_DISPLAY ou DISPLAY).")
+			}
+			ClipboardError::Storage(_) => {
+				Some("La base de l'historique est peut-être endommagée : déplacez-la hors du dossier de données pour repartir d'un historique vide.")
+			}
+			ClipboardError::StorageLocked(_) => {
+				Some("Fermez le processus qui utilise l'historique ; contrairement à Sled, le stockage SQLite peut être partagé.")
+			}
+			ClipboardError::Config(_) => Some("Corrigez le fichier de configuration ou supprimez-le pour revenir aux réglages par défaut."),
+			ClipboardError::Ipc(_) => Some("Vérifiez que le démon ou l'application est lancé."),
+			ClipboardError::Dbus(_) => Some("Vérifiez que le bus de session D-Bus est disponible (DBUS_SESSION_BUS_ADDRESS)."),
+			ClipboardError::Paste(_) => Some(
+				"L'élément est resté dans le presse-papiers. Installez wtype, xdotool (X11), dotool ou ydotool, ou choisissez \"paste_backend\": \"CopyOnly\".",
+			),
+			ClipboardError::Serialization(_) | ClipboardError::Ui(_) | ClipboardError::Unexpected(_) => None,
+		}
+	}
+}
+
 /// Alias de résultat pour les opérations du gestionnaire de presse-papiers
 pub type ClipboardResult<T> = Result<T, ClipboardError>;
\ No newline at end of file
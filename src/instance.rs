@@ -0,0 +1,103 @@
+use crate::error::{ClipboardError, ClipboardResult};
+use log::{debug, info};
+use std::fs::{File, OpenOptions, TryLockError};
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Fichier verrouillé par l'instance dont la fenêtre est ouverte
+const LOCK_FILE: &str = "clipboard-manager-window.lock";
+
+/// Socket sur lequel l'instance ouverte attend les demandes d'affichage
+const SOCKET_FILE: &str = "clipboard-manager-window.sock";
+
+/// Tentatives de connexion à une instance encore en cours de démarrage
+const CONNECT_ATTEMPTS: u32 = 20;
+
+/// Délai entre deux tentatives de connexion
+const CONNECT_RETRY: Duration = Duration::from_millis(100);
+
+/// Instance de l'interface graphique, unique par utilisateur
+///
+/// La première instance prend le verrou et écoute sur son socket ; une
+/// instance lancée ensuite s'y connecte pour afficher ou masquer la fenêtre
+/// déjà ouverte, puis se termine. Le système libère le verrou à la fin du
+/// processus, même brutale, et le socket laissé est alors remplacé.
+#[derive(Debug)]
+pub struct WindowInstance {
+	_lock: File,
+	listener: UnixListener,
+	socket_path: PathBuf,
+}
+
+impl WindowInstance {
+	/// Devient l'instance ouverte ; `None` si une autre instance l'est déjà
+	pub fn acquire(dir: &Path) -> ClipboardResult<Option<Self>> {
+		std::fs::create_dir_all(dir)?;
+		let lock = OpenOptions::new()
+			.create(true)
+			.truncate(false)
+			.write(true)
+			.mode(0o600)
+			.open(dir.join(LOCK_FILE))?;
+
+		match lock.try_lock() {
+			Ok(()) => {}
+			Err(TryLockError::WouldBlock) => return Ok(None),
+			Err(TryLockError::Error(e)) => return Err(e.into()),
+		}
+
+		// Le verrou garantit qu'un socket présent a été laissé par une instance terminée
+		let socket_path = dir.join(SOCKET_FILE);
+		if socket_path.exists() {
+			std::fs::remove_file(&socket_path)?;
+		}
+		let listener = crate::utils::with_private_umask(|| UnixListener::bind(&socket_path))?;
+		std::fs::set_permissions(&socket_path, std::fs::Permissions::from_mode(0o600))?;
+		debug!("Instance de l'interface sur {}", socket_path.display());
+		Ok(Some(Self { _lock: lock, listener, socket_path }))
+	}
+
+	/// Demande à l'instance ouverte d'afficher ou de masquer sa fenêtre
+	///
+	/// Une instance qui vient de prendre le verrou a quelques instants pour ouvrir son socket.
+	pub fn toggle_running(dir: &Path) -> ClipboardResult<()> {
+		let socket_path = dir.join(SOCKET_FILE);
+		let mut attempts = 0;
+		loop {
+			match UnixStream::connect(&socket_path) {
+				Ok(_) => {
+					info!("Fenêtre de l'instance déjà ouverte basculée");
+					return Ok(());
+				}
+				Err(e) if attempts + 1 >= CONNECT_ATTEMPTS => {
+					return Err(ClipboardError::Ipc(format!(
+						"Instance ouverte injoignable sur {}: {}",
+						socket_path.display(),
+						e
+					)));
+				}
+				Err(_) => {
+					attempts += 1;
+					std::thread::sleep(CONNECT_RETRY);
+				}
+			}
+		}
+	}
+
+	/// Écoute des demandes d'affichage, à appeler depuis un runtime tokio
+	///
+	/// Chaque connexion reçue est une demande d'affichage ou de masquage de la fenêtre.
+	pub fn listener(&self) -> ClipboardResult<tokio::net::UnixListener> {
+		let listener = self.listener.try_clone()?;
+		listener.set_nonblocking(true)?;
+		Ok(tokio::net::UnixListener::from_std(listener)?)
+	}
+}
+
+impl Drop for WindowInstance {
+	fn drop(&mut self) {
+		let _ = std::fs::remove_file(&self.socket_path);
+	}
+}
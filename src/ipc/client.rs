@@ -0,0 +1,108 @@
+use crate::clipboard::{RecordingState, Selection};
+use crate::error::{ClipboardError, ClipboardResult};
+use crate::ipc::{socket_path, ItemDetails, ItemSummary, Request, Response};
+use chrono::{DateTime, Utc};
+use std::path::Path;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::net::unix::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::UnixStream;
+use uuid::Uuid;
+
+/// Client du socket de contrôle du gestionnaire
+pub struct IpcClient {
+	lines: Lines<BufReader<OwnedReadHalf>>,
+	writer: OwnedWriteHalf,
+}
+
+impl IpcClient {
+	/// Se connecte au socket indiqué
+	pub async fn connect(path: &Path) -> ClipboardResult<Self> {
+		let stream = UnixStream::connect(path)
+			.await
+			.map_err(|e| ClipboardError::Ipc(format!("Connexion à {} impossible: {}", path.display(), e)))?;
+		let (reader, writer) = stream.into_split();
+		Ok(Self { lines: BufReader::new(reader).lines(), writer })
+	}
+
+	/// Se connecte au gestionnaire de la session
+	pub async fn connect_default() -> ClipboardResult<Self> {
+		Self::connect(&socket_path()?).await
+	}
+
+	/// Envoie une requête et retourne la réponse, y compris une réponse d'erreur
+	pub async fn send(&mut self, request: &Request) -> ClipboardResult<Response> {
+		let mut line = serde_json::to_string(request)?;
+		line.push('\n');
+		self.writer.write_all(line.as_bytes()).await?;
+
+		let reply = self
+			.lines
+			.next_line()
+			.await?
+			.ok_or_else(|| ClipboardError::Ipc("Connexion fermée par le gestionnaire".to_string()))?;
+		Ok(serde_json::from_str(&reply)?)
+	}
+
+	/// Envoie une requête ; une réponse d'erreur devient une erreur
+	pub async fn call(&mut self, request: &Request) -> ClipboardResult<Response> {
+		match self.send(request).await? {
+			Response::Error { message } => Err(ClipboardError::Ipc(message)),
+			response => Ok(response),
+		}
+	}
+
+	/// Éléments de l'historique à partir de la position `offset`, et nombre total d'éléments
+	pub async fn list(&mut self, offset: usize, limit: usize) -> ClipboardResult<(Vec<ItemSummary>, usize)> {
+		self.call(&Request::List { offset, limit }).await?.into_items()
+	}
+
+	/// Éléments correspondant à la recherche, et nombre total de correspondances
+	pub async fn search(&mut self, query: &str, limit: usize) -> ClipboardResult<(Vec<ItemSummary>, usize)> {
+		self.call(&Request::Search { query: query.to_string(), limit }).await?.into_items()
+	}
+
+	/// Contenu complet d'un élément
+	pub async fn get(&mut self, id: Uuid) -> ClipboardResult<ItemDetails> {
+		self.call(&Request::Get { id }).await?.into_item()
+	}
+
+	/// Remet un élément dans la sélection indiquée
+	pub async fn restore(&mut self, id: Uuid, selection: Selection) -> ClipboardResult<()> {
+		self.call(&Request::Restore { id, selection }).await?.into_done()
+	}
+
+	/// Épingle ou désépingle un élément
+	pub async fn pin(&mut self, id: Uuid, pinned: bool) -> ClipboardResult<()> {
+		self.call(&Request::Pin { id, pinned }).await?.into_done()
+	}
+
+	/// Fixe ou retire l'échéance d'un élément
+	pub async fn expire(&mut self, id: Uuid, expires_at: Option<DateTime<Utc>>) -> ClipboardResult<()> {
+		self.call(&Request::Expire { id, expires_at }).await?.into_done()
+	}
+
+	/// Supprime un élément
+	pub async fn delete(&mut self, id: Uuid) -> ClipboardResult<()> {
+		self.call(&Request::Delete { id }).await?.into_done()
+	}
+
+	/// Supprime les éléments non épinglés et retourne leur nombre
+	pub async fn clear(&mut self) -> ClipboardResult<usize> {
+		self.call(&Request::Clear).await?.into_cleared()
+	}
+
+	/// Suspend l'enregistrement, indéfiniment ou pendant `minutes`
+	pub async fn pause(&mut self, minutes: Option<u64>) -> ClipboardResult<RecordingState> {
+		self.call(&Request::Pause { minutes }).await?.into_recording()
+	}
+
+	/// Reprend l'enregistrement
+	pub async fn resume(&mut self) -> ClipboardResult<RecordingState> {
+		self.call(&Request::Resume).await?.into_recording()
+	}
+
+	/// État de l'enregistrement
+	pub async fn status(&mut self) -> ClipboardResult<RecordingState> {
+		self.call(&Request::Status).await?.into_recording()
+	}
+}
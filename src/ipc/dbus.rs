@@ -0,0 +1,218 @@
+use crate::clipboard::{ClipboardItem, RecordingState, Selection};
+use crate::error::{ClipboardError, ClipboardResult};
+use crate::ipc::{IpcContext, ItemKind, ItemSummary, Request, Response};
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use uuid::Uuid;
+use zbus::zvariant::Type;
+use zbus::{connection, fdo, interface, Connection, SignalContext};
+
+/// Nom du service sur le bus de session
+pub const BUS_NAME: &str = "org.clipboardmanager.ClipboardManager";
+
+/// Chemin de l'objet exposé par le service
+pub const OBJECT_PATH: &str = "/org/clipboardmanager/ClipboardManager";
+
+/// Nom de l'interface D-Bus, versionné comme le veut l'usage freedesktop
+pub const INTERFACE_NAME: &str = "org.clipboardmanager.ClipboardManager1";
+
+/// Élément de l'historique tel que transmis sur le bus, de signature `(sssxb)`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Type)]
+pub struct BusItem {
+	pub id: String,
+	/// `text`, `image` ou `files`
+	pub kind: String,
+	/// Aperçu d'une ligne, tel qu'affiché dans l'historique
+	pub preview: String,
+	/// Date de la copie, en secondes depuis l'époque Unix
+	pub timestamp: i64,
+	pub pinned: bool,
+}
+
+impl From<ItemSummary> for BusItem {
+	fn from(summary: ItemSummary) -> Self {
+		Self {
+			id: summary.id.to_string(),
+			kind: match summary.kind {
+				ItemKind::Text => "text",
+				ItemKind::Image => "image",
+				ItemKind::Files => "files",
+			}
+			.to_string(),
+			preview: summary.preview,
+			timestamp: summary.timestamp.timestamp(),
+			pinned: summary.pinned,
+		}
+	}
+}
+
+impl From<&ClipboardItem> for BusItem {
+	fn from(item: &ClipboardItem) -> Self {
+		Self::from(ItemSummary::from(item))
+	}
+}
+
+/// Interface `org.clipboardmanager.ClipboardManager1`
+///
+/// Les requêtes passent par le même contexte que le socket de contrôle.
+pub struct DbusInterface {
+	context: IpcContext,
+	window_toggles: Option<mpsc::UnboundedSender<()>>,
+}
+
+impl DbusInterface {
+	/// Crée l'interface sur le contexte des requêtes
+	///
+	/// Sans destinataire (`with_window_toggles`), `ToggleWindow` lance l'interface graphique.
+	pub fn new(context: IpcContext) -> Self {
+		Self { context, window_toggles: None }
+	}
+
+	/// Signale sur `sender` chaque demande d'affichage ou de masquage de la fenêtre
+	pub fn with_window_toggles(mut self, sender: mpsc::UnboundedSender<()>) -> Self {
+		self.window_toggles = Some(sender);
+		self
+	}
+
+	/// Exécute une requête, une réponse d'erreur devenant une erreur D-Bus
+	async fn call(&self, request: Request) -> fdo::Result<Response> {
+		debug!("Requête D-Bus: {:?}", request);
+		match self.context.handle(request).await {
+			Ok(Response::Error { message }) => Err(fdo::Error::Failed(message)),
+			Ok(response) => Ok(response),
+			Err(e) => Err(fdo::Error::Failed(e.to_string())),
+		}
+	}
+
+	/// Éléments et nombre total d'une réponse `items`
+	async fn items(&self, request: Request) -> fdo::Result<(Vec<BusItem>, u32)> {
+		let (items, total) = self.call(request).await?.into_items().map_err(failed)?;
+		Ok((items.into_iter().map(BusItem::from).collect(), total.try_into().unwrap_or(u32::MAX)))
+	}
+
+	/// État de l'enregistrement d'une réponse `recording` : en pause, et échéance (0 sans échéance)
+	async fn recording(&self, request: Request) -> fdo::Result<(bool, i64)> {
+		let recording = self.call(request).await?.into_recording().map_err(failed)?;
+		Ok(match recording {
+			RecordingState::Paused { until } => (true, until.map_or(0, |until| until.timestamp())),
+			RecordingState::Recording => (false, 0),
+		})
+	}
+}
+
+#[interface(name = "org.clipboardmanager.ClipboardManager1")]
+impl DbusInterface {
+	/// Éléments de l'historique à partir de la position `offset`, du plus récent au plus ancien
+	#[zbus(out_args("items", "total"))]
+	async fn list(&self, offset: u32, limit: u32) -> fdo::Result<(Vec<BusItem>, u32)> {
+		self.items(Request::List { offset: offset as usize, limit: limit as usize }).await
+	}
+
+	/// Éléments dont le texte ou les chemins contiennent `query`, sans tenir compte de la casse
+	#[zbus(out_args("items", "total"))]
+	async fn search(&self, query: String, limit: u32) -> fdo::Result<(Vec<BusItem>, u32)> {
+		self.items(Request::Search { query, limit: limit as usize }).await
+	}
+
+	/// Remet un élément dans le presse-papiers, ou dans la sélection primaire si `primary`
+	async fn restore(&self, id: &str, primary: bool) -> fdo::Result<()> {
+		let id = Uuid::parse_str(id).map_err(|e| fdo::Error::InvalidArgs(format!("Identifiant invalide: {}", e)))?;
+		let selection = if primary { Selection::Primary } else { Selection::Clipboard };
+		self.call(Request::Restore { id, selection }).await?.into_done().map_err(failed)
+	}
+
+	/// Affiche la fenêtre de l'historique, ou la masque si elle est affichée
+	async fn toggle_window(&self) -> fdo::Result<()> {
+		match &self.window_toggles {
+			Some(sender) => sender
+				.send(())
+				.map_err(|_| fdo::Error::Failed("Fenêtre indisponible".to_string())),
+			None => launch_window().map_err(failed),
+		}
+	}
+
+	/// Suspend l'enregistrement pendant `minutes`, ou indéfiniment avec 0
+	#[zbus(out_args("paused", "until"))]
+	async fn pause(&self, minutes: u32) -> fdo::Result<(bool, i64)> {
+		let minutes = (minutes > 0).then_some(minutes.into());
+		self.recording(Request::Pause { minutes }).await
+	}
+
+	/// Reprend l'enregistrement
+	#[zbus(out_args("paused", "until"))]
+	async fn resume(&self) -> fdo::Result<(bool, i64)> {
+		self.recording(Request::Resume).await
+	}
+
+	/// État de l'enregistrement : en pause, et échéance de la pause (0 sans échéance)
+	#[zbus(out_args("paused", "until"))]
+	async fn status(&self) -> fdo::Result<(bool, i64)> {
+		self.recording(Request::Status).await
+	}
+
+	/// Émis à chaque copie enregistrée dans l'historique
+	#[zbus(signal)]
+	async fn item_added(ctxt: &SignalContext<'_>, item: BusItem) -> zbus::Result<()>;
+}
+
+/// Service D-Bus du gestionnaire
+///
+/// Le service reste publié tant qu'une copie de la connexion est conservée.
+#[derive(Debug, Clone)]
+pub struct DbusServer {
+	connection: Connection,
+}
+
+impl DbusServer {
+	/// Publie le service sur le bus de session
+	pub async fn start(interface: DbusInterface) -> ClipboardResult<Self> {
+		Self::serve(connection::Builder::session().map_err(dbus_error)?, interface).await
+	}
+
+	/// Publie le service sur le bus à l'adresse indiquée, un bus privé par exemple
+	pub async fn start_at(address: &str, interface: DbusInterface) -> ClipboardResult<Self> {
+		Self::serve(connection::Builder::address(address).map_err(dbus_error)?, interface).await
+	}
+
+	/// Sert l'interface et réserve le nom du service
+	///
+	/// Le nom déjà pris par un autre processus est une erreur.
+	async fn serve(builder: connection::Builder<'_>, interface: DbusInterface) -> ClipboardResult<Self> {
+		let connection = builder
+			.serve_at(OBJECT_PATH, interface)
+			.and_then(|builder| builder.name(BUS_NAME))
+			.map_err(dbus_error)?
+			.build()
+			.await
+			.map_err(dbus_error)?;
+		info!("Service D-Bus {} publié", BUS_NAME);
+		Ok(Self { connection })
+	}
+
+	/// Émet le signal `ItemAdded` pour une copie enregistrée
+	pub async fn item_added(&self, item: &ClipboardItem) -> ClipboardResult<()> {
+		let context = SignalContext::new(&self.connection, OBJECT_PATH).map_err(dbus_error)?;
+		DbusInterface::item_added(&context, BusItem::from(item)).await.map_err(dbus_error)
+	}
+}
+
+/// Lance l'interface graphique dans un nouveau processus
+fn launch_window() -> ClipboardResult<()> {
+	let program = std::env::current_exe()?;
+	let mut child = std::process::Command::new(program).spawn()?;
+	// Attendre la fin du processus pour qu'il ne reste pas à l'état zombie
+	std::thread::spawn(move || child.wait());
+	info!("Interface graphique lancée à la demande du bus");
+	Ok(())
+}
+
+/// Erreur D-Bus générique
+fn failed(e: ClipboardError) -> fdo::Error {
+	fdo::Error::Failed(e.to_string())
+}
+
+/// Erreur du gestionnaire pour une erreur de zbus
+fn dbus_error(e: zbus::Error) -> ClipboardError {
+	ClipboardError::Dbus(e.to_string())
+}
@@ -0,0 +1,321 @@
+pub mod client;
+pub mod dbus;
+pub mod server;
+
+pub use client::IpcClient;
+pub use dbus::{DbusInterface, DbusServer};
+pub use server::{IpcContext, IpcServer};
+
+use crate::clipboard::{ClipboardContent, ClipboardItem, RecordingState, Selection};
+use crate::error::{ClipboardError, ClipboardResult};
+use crate::storage::Storage;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use uuid::Uuid;
+
+/// Nom du socket dans le dossier d'exécution de l'utilisateur
+const SOCKET_NAME: &str = "clipboard-manager.sock";
+
+/// Nombre d'éléments retournés par défaut par `list` et `search`
+const DEFAULT_LIMIT: usize = 20;
+
+/// Chemin du socket de contrôle : `$XDG_RUNTIME_DIR/clipboard-manager.sock`
+///
+/// Jamais dans un dossier partagé entre utilisateurs (voir `utils::runtime_dir`).
+pub fn socket_path() -> ClipboardResult<PathBuf> {
+	Ok(crate::utils::runtime_dir()?.join(SOCKET_NAME))
+}
+
+/// Requête adressée au gestionnaire, une par ligne JSON
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum Request {
+	/// Éléments de l'historique, du plus récent au plus ancien
+	List {
+		#[serde(default)]
+		offset: usize,
+		#[serde(default = "default_limit")]
+		limit: usize,
+	},
+	/// Éléments dont le texte ou les chemins contiennent `query`, sans tenir compte de la casse
+	Search {
+		query: String,
+		#[serde(default = "default_limit")]
+		limit: usize,
+	},
+	/// Contenu complet d'un élément
+	Get { id: Uuid },
+	/// Remet un élément dans le presse-papiers ou la sélection primaire
+	Restore {
+		id: Uuid,
+		#[serde(default)]
+		selection: Selection,
+	},
+	/// Épingle (ou désépingle avec `"pinned": false`) un élément
+	Pin {
+		id: Uuid,
+		#[serde(default = "default_pinned")]
+		pinned: bool,
+	},
+	/// Fixe l'échéance d'un élément, ou la retire sans `expires_at`
+	Expire {
+		id: Uuid,
+		#[serde(default)]
+		expires_at: Option<DateTime<Utc>>,
+	},
+	/// Supprime un élément
+	Delete { id: Uuid },
+	/// Supprime tous les éléments non épinglés
+	Clear,
+	/// Suspend l'enregistrement, indéfiniment ou pendant `minutes`
+	Pause {
+		#[serde(default)]
+		minutes: Option<u64>,
+	},
+	/// Reprend l'enregistrement
+	Resume,
+	/// État de l'enregistrement
+	Status,
+	/// Tous les éléments complets, pour un export
+	Export,
+	/// Ajoute des éléments exportés, sans dupliquer ceux déjà présents
+	Import { items: Vec<ClipboardItem> },
+	/// Décompte des éléments de l'historique
+	Stats,
+}
+
+fn default_limit() -> usize {
+	DEFAULT_LIMIT
+}
+
+fn default_pinned() -> bool {
+	true
+}
+
+/// Réponse du gestionnaire, une par requête
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Response {
+	/// Éléments demandés, et nombre total d'éléments correspondants
+	Items { items: Vec<ItemSummary>, total: usize },
+	/// Élément complet
+	Item { item: ItemDetails },
+	/// Opération effectuée
+	Done,
+	/// Nombre d'éléments supprimés par `clear`
+	Cleared { removed: usize },
+	/// État de l'enregistrement après la requête
+	Recording {
+		paused: bool,
+		#[serde(default, skip_serializing_if = "Option::is_none")]
+		until: Option<DateTime<Utc>>,
+	},
+	/// Éléments complets de l'historique
+	Exported { items: Vec<ClipboardItem> },
+	/// Nombre d'éléments importés et ignorés par `import`
+	Imported { imported: usize, skipped: usize },
+	/// Décompte des éléments de l'historique
+	Stats { stats: HistoryCounts },
+	/// Requête refusée ou échouée
+	Error { message: String },
+}
+
+impl Response {
+	/// Réponse décrivant l'état de l'enregistrement
+	pub fn recording(state: RecordingState) -> Self {
+		match state {
+			RecordingState::Paused { until } if state.is_paused() => Response::Recording { paused: true, until },
+			_ => Response::Recording { paused: false, until: None },
+		}
+	}
+
+	/// Éléments et nombre total d'éléments d'une réponse `items`
+	pub fn into_items(self) -> ClipboardResult<(Vec<ItemSummary>, usize)> {
+		match self {
+			Response::Items { items, total } => Ok((items, total)),
+			response => Err(response.unexpected()),
+		}
+	}
+
+	/// Élément complet d'une réponse `item`
+	pub fn into_item(self) -> ClipboardResult<ItemDetails> {
+		match self {
+			Response::Item { item } => Ok(item),
+			response => Err(response.unexpected()),
+		}
+	}
+
+	/// Vérifie qu'il s'agit d'une réponse `done`
+	pub fn into_done(self) -> ClipboardResult<()> {
+		match self {
+			Response::Done => Ok(()),
+			response => Err(response.unexpected()),
+		}
+	}
+
+	/// Nombre d'éléments supprimés d'une réponse `cleared`
+	pub fn into_cleared(self) -> ClipboardResult<usize> {
+		match self {
+			Response::Cleared { removed } => Ok(removed),
+			response => Err(response.unexpected()),
+		}
+	}
+
+	/// État de l'enregistrement d'une réponse `recording`
+	pub fn into_recording(self) -> ClipboardResult<RecordingState> {
+		match self {
+			Response::Recording { paused: true, until } => Ok(RecordingState::Paused { until }),
+			Response::Recording { paused: false, .. } => Ok(RecordingState::Recording),
+			response => Err(response.unexpected()),
+		}
+	}
+
+	/// Éléments complets d'une réponse `exported`
+	pub fn into_exported(self) -> ClipboardResult<Vec<ClipboardItem>> {
+		match self {
+			Response::Exported { items } => Ok(items),
+			response => Err(response.unexpected()),
+		}
+	}
+
+	/// Nombre d'éléments importés et ignorés d'une réponse `imported`
+	pub fn into_imported(self) -> ClipboardResult<(usize, usize)> {
+		match self {
+			Response::Imported { imported, skipped } => Ok((imported, skipped)),
+			response => Err(response.unexpected()),
+		}
+	}
+
+	/// Décompte d'une réponse `stats`
+	pub fn into_stats(self) -> ClipboardResult<HistoryCounts> {
+		match self {
+			Response::Stats { stats } => Ok(stats),
+			response => Err(response.unexpected()),
+		}
+	}
+
+	/// Erreur signalant une réponse qui ne correspond pas à la requête
+	fn unexpected(self) -> ClipboardError {
+		match self {
+			Response::Error { message } => ClipboardError::Ipc(message),
+			response => ClipboardError::Ipc(format!("Réponse inattendue: {:?}", response)),
+		}
+	}
+}
+
+/// Nature du contenu d'un élément
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemKind {
+	Text,
+	Image,
+	Files,
+}
+
+/// Élément de l'historique tel que listé
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ItemSummary {
+	pub id: Uuid,
+	pub kind: ItemKind,
+	/// Aperçu d'une ligne, tel qu'affiché dans l'historique
+	pub preview: String,
+	pub timestamp: DateTime<Utc>,
+	pub pinned: bool,
+	pub selection: Selection,
+	pub copy_count: u32,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl From<&ClipboardItem> for ItemSummary {
+	fn from(item: &ClipboardItem) -> Self {
+		Self {
+			id: item.id,
+			kind: match item.content {
+				ClipboardContent::Text(_) => ItemKind::Text,
+				ClipboardContent::Image(_, _) => ItemKind::Image,
+				ClipboardContent::Files(_) => ItemKind::Files,
+			},
+			preview: item.to_string(),
+			timestamp: item.timestamp,
+			pinned: item.pinned,
+			selection: item.selection,
+			copy_count: item.copy_count,
+			expires_at: item.expires_at,
+		}
+	}
+}
+
+/// Élément complet
+///
+/// Les pixels des images ne sont pas transmis : `restore` les remet dans le presse-papiers.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ItemDetails {
+	#[serde(flatten)]
+	pub summary: ItemSummary,
+	/// Texte copié, ou chemins des fichiers copiés à raison d'un par ligne
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub text: Option<String>,
+	/// Dimensions de l'image copiée
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub size: Option<(usize, usize)>,
+	/// Types MIME des représentations offertes lors de la copie
+	#[serde(default)]
+	pub mime_types: Vec<String>,
+}
+
+impl From<&ClipboardItem> for ItemDetails {
+	fn from(item: &ClipboardItem) -> Self {
+		let (text, size) = match &item.content {
+			ClipboardContent::Text(text) => (Some(text.clone()), None),
+			ClipboardContent::Image(_, metadata) => (None, Some((metadata.width, metadata.height))),
+			ClipboardContent::Files(entries) => {
+				let paths: Vec<_> = entries.iter().map(|entry| entry.path.to_string_lossy()).collect();
+				(Some(paths.join("\n")), None)
+			}
+		};
+
+		Self {
+			summary: ItemSummary::from(item),
+			text,
+			size,
+			mime_types: item.formats.iter().map(|format| format.mime_type.clone()).collect(),
+		}
+	}
+}
+
+/// Décompte des éléments de l'historique
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryCounts {
+	pub items: usize,
+	pub texts: usize,
+	pub images: usize,
+	pub files: usize,
+	pub pinned: usize,
+	pub expiring: usize,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub oldest: Option<DateTime<Utc>>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub newest: Option<DateTime<Utc>>,
+}
+
+impl HistoryCounts {
+	/// Compte les éléments de l'historique par nature et par état
+	pub fn collect(storage: &dyn Storage) -> ClipboardResult<Self> {
+		let mut counts = HistoryCounts::default();
+		for item in storage.get_all_items()? {
+			counts.items += 1;
+			match item.content {
+				ClipboardContent::Text(_) => counts.texts += 1,
+				ClipboardContent::Image(_, _) => counts.images += 1,
+				ClipboardContent::Files(_) => counts.files += 1,
+			}
+			counts.pinned += usize::from(item.pinned);
+			counts.expiring += usize::from(item.expires_at.is_some());
+			counts.oldest = Some(counts.oldest.map_or(item.timestamp, |oldest| oldest.min(item.timestamp)));
+			counts.newest = Some(counts.newest.map_or(item.timestamp, |newest| newest.max(item.timestamp)));
+		}
+		Ok(counts)
+	}
+}
@@ -0,0 +1,221 @@
+use crate::clipboard::{ClipboardItem, ClipboardManager, PauseHandle, RecordingState};
+use crate::error::{ClipboardError, ClipboardResult};
+use crate::ipc::{HistoryCounts, ItemDetails, ItemSummary, Request, Response};
+use crate::storage::{export_history, import_history, Storage};
+use chrono::{Duration, Utc};
+use log::{debug, error, info};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::{mpsc, Mutex};
+use uuid::Uuid;
+
+/// Ce que les requêtes manipulent : l'historique, le presse-papiers et l'enregistrement
+#[derive(Clone)]
+pub struct IpcContext {
+	storage: Arc<Mutex<Box<dyn Storage>>>,
+	clipboard: Option<Arc<Mutex<ClipboardManager>>>,
+	recording: PauseHandle,
+	recording_changes: Option<mpsc::UnboundedSender<RecordingState>>,
+}
+
+impl IpcContext {
+	/// Crée le contexte des requêtes
+	///
+	/// Sans gestionnaire de presse-papiers (`with_clipboard`), `restore` est refusée.
+	pub fn new(storage: Arc<Mutex<Box<dyn Storage>>>, recording: PauseHandle) -> Self {
+		Self { storage, clipboard: None, recording, recording_changes: None }
+	}
+
+	/// Utilise ce gestionnaire pour remettre les éléments dans le presse-papiers
+	pub fn with_clipboard(mut self, clipboard: Arc<Mutex<ClipboardManager>>) -> Self {
+		self.clipboard = Some(clipboard);
+		self
+	}
+
+	/// Signale sur `sender` chaque pause ou reprise demandée par un client
+	pub fn with_recording_changes(mut self, sender: mpsc::UnboundedSender<RecordingState>) -> Self {
+		self.recording_changes = Some(sender);
+		self
+	}
+
+	/// Exécute une requête
+	pub async fn handle(&self, request: Request) -> ClipboardResult<Response> {
+		match request {
+			Request::List { offset, limit } => {
+				let page = self.storage.lock().await.get_items(offset, limit)?;
+				Ok(Response::Items {
+					items: page.items.iter().map(ItemSummary::from).collect(),
+					total: page.total,
+				})
+			}
+			Request::Search { query, limit } => {
+				let page = self.storage.lock().await.search(&query, 0, limit)?;
+				Ok(Response::Items {
+					items: page.items.iter().map(ItemSummary::from).collect(),
+					total: page.total,
+				})
+			}
+			Request::Get { id } => {
+				let item = self.load_item(id).await?;
+				Ok(Response::Item { item: ItemDetails::from(&item) })
+			}
+			Request::Restore { id, selection } => {
+				let clipboard = self
+					.clipboard
+					.as_ref()
+					.ok_or_else(|| ClipboardError::Clipboard("Presse-papiers indisponible".to_string()))?;
+				let item = self.load_item(id).await?;
+				clipboard.lock().await.set_selection_content(&item, selection)?;
+				Ok(Response::Done)
+			}
+			Request::Pin { id, pinned } => {
+				let mut item = self.load_item(id).await?;
+				item.pinned = pinned;
+				let storage = self.storage.lock().await;
+				storage.update_item(item)?;
+				storage.flush()?;
+				Ok(Response::Done)
+			}
+			Request::Expire { id, expires_at } => {
+				let mut item = self.load_item(id).await?;
+				item.expires_at = expires_at;
+				let storage = self.storage.lock().await;
+				storage.update_item(item)?;
+				storage.flush()?;
+				Ok(Response::Done)
+			}
+			Request::Delete { id } => {
+				self.load_item(id).await?;
+				let storage = self.storage.lock().await;
+				storage.remove_item(id)?;
+				storage.flush()?;
+				Ok(Response::Done)
+			}
+			Request::Clear => {
+				let storage = self.storage.lock().await;
+				let removed = storage.clear_non_pinned()?;
+				storage.flush()?;
+				Ok(Response::Cleared { removed: removed.len() })
+			}
+			Request::Pause { minutes } => {
+				let until = minutes.map(|minutes| Utc::now() + Duration::minutes(minutes.min(u32::MAX.into()) as i64));
+				self.set_recording(RecordingState::Paused { until }).await
+			}
+			Request::Resume => self.set_recording(RecordingState::Recording).await,
+			Request::Status => Ok(Response::recording(*self.recording.lock().await)),
+			Request::Export => Ok(Response::Exported { items: export_history(self.storage.lock().await.as_ref())? }),
+			Request::Import { items } => {
+				let (imported, skipped) = import_history(self.storage.lock().await.as_ref(), items)?;
+				Ok(Response::Imported { imported, skipped })
+			}
+			Request::Stats => Ok(Response::Stats { stats: HistoryCounts::collect(self.storage.lock().await.as_ref())? }),
+		}
+	}
+
+	/// Charge un élément complet de l'historique
+	async fn load_item(&self, id: Uuid) -> ClipboardResult<ClipboardItem> {
+		self.storage
+			.lock()
+			.await
+			.get_item(id)?
+			.ok_or_else(|| ClipboardError::Storage(format!("Élément introuvable: {}", id)))
+	}
+
+	/// Suspend ou reprend l'enregistrement
+	async fn set_recording(&self, recording: RecordingState) -> ClipboardResult<Response> {
+		*self.recording.lock().await = recording;
+		info!("Enregistrement des copies modifié par un client: {:?}", recording);
+		if let Some(sender) = &self.recording_changes {
+			let _ = sender.send(recording);
+		}
+		Ok(Response::recording(recording))
+	}
+}
+
+/// Serveur de contrôle sur socket Unix
+///
+/// Chaque ligne reçue est une requête JSON, à laquelle répond une ligne JSON.
+/// Le socket est supprimé quand le serveur est abandonné.
+pub struct IpcServer {
+	listener: UnixListener,
+	path: PathBuf,
+	context: IpcContext,
+}
+
+impl IpcServer {
+	/// Ouvre le socket de contrôle
+	///
+	/// Un socket laissé par un processus terminé est remplacé ; un socket encore servi est une erreur.
+	pub fn bind(path: &Path, context: IpcContext) -> ClipboardResult<Self> {
+		if path.exists() {
+			if std::os::unix::net::UnixStream::connect(path).is_ok() {
+				return Err(ClipboardError::Ipc(format!("Socket déjà servi par un autre processus: {}", path.display())));
+			}
+			std::fs::remove_file(path)?;
+		}
+		if let Some(parent) = path.parent() {
+			std::fs::create_dir_all(parent)?;
+		}
+
+		let listener = crate::utils::with_private_umask(|| UnixListener::bind(path))?;
+		std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+		info!("Contrôle par socket sur {}", path.display());
+		Ok(Self { listener, path: path.to_path_buf(), context })
+	}
+
+	/// Répond aux clients jusqu'à l'abandon du serveur
+	pub async fn serve(self) {
+		loop {
+			match self.listener.accept().await {
+				Ok((stream, _)) => {
+					let context = self.context.clone();
+					tokio::spawn(async move {
+						if let Err(e) = serve_connection(stream, context).await {
+							debug!("Connexion IPC interrompue: {}", e);
+						}
+					});
+				}
+				Err(e) => {
+					error!("Erreur connexion IPC: {}", e);
+					tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+				}
+			}
+		}
+	}
+}
+
+impl Drop for IpcServer {
+	fn drop(&mut self) {
+		let _ = std::fs::remove_file(&self.path);
+	}
+}
+
+/// Répond aux requêtes d'un client jusqu'à sa déconnexion
+async fn serve_connection(stream: UnixStream, context: IpcContext) -> ClipboardResult<()> {
+	let (reader, mut writer) = stream.into_split();
+	let mut lines = BufReader::new(reader).lines();
+
+	while let Some(line) = lines.next_line().await? {
+		if line.trim().is_empty() {
+			continue;
+		}
+		let response = match serde_json::from_str::<Request>(&line) {
+			Ok(request) => {
+				debug!("Requête IPC: {:?}", request);
+				context
+					.handle(request)
+					.await
+					.unwrap_or_else(|e| Response::Error { message: e.to_string() })
+			}
+			Err(e) => Response::Error { message: format!("Requête invalide: {}", e) },
+		};
+
+		let mut reply = serde_json::to_string(&response)?;
+		reply.push('\n');
+		writer.write_all(reply.as_bytes()).await?;
+	}
+	Ok(())
+}
@@ -1,22 +1,27 @@
 pub mod app;
+pub mod cli;
 pub mod clipboard;
 pub mod config;
+pub mod daemon;
 pub mod error;
+pub mod instance;
+pub mod ipc;
 pub mod storage;
 pub mod ui;
 pub mod utils;
 
-use app::ClipboardManagerApp;
+use app::{ClipboardManagerApp, StartupOptions};
 use iced::{window, Result};
+use instance::WindowInstance;
 
 /// Fonction pour exécuter l'application en évitant les problèmes de durée de vie
-pub fn run(window_settings: window::Settings) -> Result {
+pub fn run(window_settings: window::Settings, options: StartupOptions, instance: Option<WindowInstance>) -> Result {
 	// Utiliser la nouvelle API d'Iced 0.13
-	iced::application("Gestionnaire de presse-papiers", 
+	iced::application(ClipboardManagerApp::title, 
 		ClipboardManagerApp::update, 
 		ClipboardManagerApp::view)
 		.subscription(ClipboardManagerApp::subscription)
 		.theme(ClipboardManagerApp::theme)
 		.window(window_settings)
-		.run_with(ClipboardManagerApp::new)
+		.run_with(move || ClipboardManagerApp::new(options, instance))
 }
\ No newline at end of file
@@ -1,13 +1,51 @@
 use anyhow::Result;
+use clipboard_manager::error::ClipboardError;
+use clipboard_manager::instance::WindowInstance;
 use env_logger::Env;
 use iced::{window, Size};
-use log::{error, info};
+use log::{error, info, warn};
 
 fn main() -> Result<()> {
-	// Initialisation du logger
-	env_logger::Builder::from_env(Env::default().default_filter_or("info")).init();
+	let args: Vec<String> = std::env::args().skip(1).collect();
+	let command = clipboard_manager::cli::Command::parse(&args)?;
+
+	// Initialisation du logger, discret pour les commandes de l'historique
+	let default_filter = if command.is_some() { "warn" } else { "info" };
+	env_logger::Builder::from_env(Env::default().default_filter_or(default_filter)).init();
+
+	// Opérations sur l'historique depuis le terminal : `list`, `search`, `get`, `copy`...
+	if let Some(command) = command {
+		clipboard_manager::cli::run(command).unwrap_or_else(|e| exit_with(e));
+		return Ok(());
+	}
+
+	// Enregistrement de l'historique en arrière-plan, sans interface : `daemon [--paused]`
+	if args.first().map(String::as_str) == Some("daemon") {
+		info!("Démarrage du démon ClipboardManager");
+		clipboard_manager::daemon::run(args.iter().any(|arg| arg == "--paused")).unwrap_or_else(|e| exit_with(e));
+		return Ok(());
+	}
+
+	// Une seule fenêtre : une relance affiche ou masque celle déjà ouverte
+	let runtime_dir = clipboard_manager::utils::runtime_dir()?;
+	let instance = match WindowInstance::acquire(&runtime_dir) {
+		Ok(Some(instance)) => Some(instance),
+		Ok(None) => {
+			WindowInstance::toggle_running(&runtime_dir)?;
+			return Ok(());
+		}
+		Err(e) => {
+			warn!("Détection d'une instance déjà ouverte impossible: {}", e);
+			None
+		}
+	};
+
 	info!("Démarrage de ClipboardManager");
 
+	let options = clipboard_manager::app::StartupOptions {
+		paused: args.iter().any(|arg| arg == "--paused"),
+	};
+
 	// Vérifier la disponibilité des ressources
 	match clipboard_manager::utils::ensure_resources_available() {
 		Ok(_) => info!("Ressources (icônes) vérifiées avec succès"),
@@ -30,7 +68,7 @@ fn main() -> Result<()> {
 	};
 
 	// Version simplifiée pour éviter les problèmes de durée de vie
-	let run_result = clipboard_manager::run(window_settings);
+	let run_result = clipboard_manager::run(window_settings, options, instance);
 
 	// Gérer le résultat
 	match run_result {
@@ -43,4 +81,13 @@ fn main() -> Result<()> {
 			Err(anyhow::anyhow!("Erreur d'exécution: {}", e))
 		}
 	}
-}
\ No newline at end of file
+}
+
+/// Explique une erreur à l'utilisateur, avec une piste de résolution, et termine le processus
+fn exit_with(error: ClipboardError) -> ! {
+	eprintln!("{} : {}", error.summary(), error);
+	if let Some(hint) = error.hint() {
+		eprintln!("{}", hint);
+	}
+	std::process::exit(1);
+}
@@ -0,0 +1,152 @@
+use crate::clipboard::{ClipboardContent, ClipboardItem, ImageMetadata};
+use crate::error::{ClipboardError, ClipboardResult};
+use image::codecs::png::PngEncoder;
+use image::{ExtendedColorType, ImageBuffer, ImageEncoder, ImageFormat, Rgba};
+use sha2::{Digest, Sha256};
+
+/// Type MIME de la représentation chiffrée des métadonnées d'un élément
+pub const SEALED_RECORD_MIME: &str = "application/x-clipboard-manager-sealed";
+
+/// Taille au-delà de laquelle une représentation MIME est rangée à part
+pub const INLINE_FORMAT_MAX: usize = 4 * 1024;
+
+/// Représentations toujours conservées dans l'enregistrement, quelle que soit leur taille
+///
+/// L'enveloppe d'un élément chiffré doit rester lisible sans charger de blob.
+const ALWAYS_INLINE: [&str; 1] = [SEALED_RECORD_MIME];
+
+/// Dimension maximale des aperçus d'images (en pixels)
+pub const THUMBNAIL_SIZE: u32 = 200;
+
+/// Élément prêt à être enregistré
+///
+/// Les métadonnées et les données volumineuses sont séparées : les blobs sont
+/// adressés par l'empreinte de leur contenu et peuvent être partagés.
+pub struct EncodedItem {
+	/// Métadonnées de l'élément, sans les données rangées dans les blobs
+	pub record: Vec<u8>,
+	/// Blobs produits par l'encodage (clé, données)
+	pub blobs: Vec<(String, Vec<u8>)>,
+	/// Clés de tous les blobs référencés, y compris ceux déjà enregistrés
+	pub references: Vec<String>,
+	/// Élément allégé, tel que décrit par `record`
+	pub light: ClipboardItem,
+}
+
+/// Clé d'un blob : empreinte SHA-256 de son contenu, en hexadécimal
+pub fn blob_key(data: &[u8]) -> String {
+	Sha256::digest(data)
+		.iter()
+		.map(|byte| format!("{:02x}", byte))
+		.collect()
+}
+
+/// Sépare un élément en métadonnées compactes et en blobs
+///
+/// Les pixels des images sont compressés en PNG et un aperçu est généré. Les
+/// données d'un élément allégé, déjà rangées dans les blobs, ne sont pas réécrites.
+pub fn encode_item(item: &ClipboardItem) -> ClipboardResult<EncodedItem> {
+	let mut light = item.clone();
+	let mut blobs = Vec::new();
+
+	if let ClipboardContent::Image(data, metadata) = &mut light.content {
+		if !data.is_empty() {
+			if metadata.thumbnail.is_empty() {
+				metadata.thumbnail = encode_thumbnail(data, metadata)?;
+			}
+			let png = encode_png(data, metadata.width as u32, metadata.height as u32)?;
+			let key = blob_key(&png);
+			metadata.blob = Some(key.clone());
+			blobs.push((key, png));
+			*data = Vec::new();
+		}
+	}
+
+	for format in &mut light.formats {
+		if format.data.len() > INLINE_FORMAT_MAX && !ALWAYS_INLINE.contains(&format.mime_type.as_str()) {
+			let data = std::mem::take(&mut format.data);
+			let key = blob_key(&data);
+			format.blob = Some(key.clone());
+			blobs.push((key, data));
+		}
+	}
+
+	let record = rmp_serde::to_vec_named(&light)
+		.map_err(|e| ClipboardError::Storage(format!("Erreur encodage élément: {}", e)))?;
+
+	let references = blob_keys(&light);
+
+	Ok(EncodedItem { record, blobs, references, light })
+}
+
+/// Décode les métadonnées d'un élément, sans charger ses blobs
+pub fn decode_record(record: &[u8]) -> ClipboardResult<ClipboardItem> {
+	rmp_serde::from_slice(record)
+		.map_err(|e| ClipboardError::Storage(format!("Erreur décodage élément: {}", e)))
+}
+
+/// Clés des blobs référencés par un élément
+pub fn blob_keys(item: &ClipboardItem) -> Vec<String> {
+	let mut keys = Vec::new();
+	if let ClipboardContent::Image(_, metadata) = &item.content {
+		keys.extend(metadata.blob.clone());
+	}
+	keys.extend(item.formats.iter().filter_map(|format| format.blob.clone()));
+	keys.sort();
+	keys.dedup();
+	keys
+}
+
+/// Charge les données rangées dans les blobs d'un élément allégé
+pub fn hydrate<F>(item: &mut ClipboardItem, mut load: F) -> ClipboardResult<()>
+where
+	F: FnMut(&str) -> ClipboardResult<Option<Vec<u8>>>,
+{
+	let mut load_blob = |key: &str| {
+		load(key)?.ok_or_else(|| ClipboardError::Storage(format!("Blob {} introuvable", key)))
+	};
+
+	if let ClipboardContent::Image(data, metadata) = &mut item.content {
+		if let (true, Some(key)) = (data.is_empty(), &metadata.blob) {
+			*data = decode_png(&load_blob(key)?)?;
+		}
+	}
+
+	for format in &mut item.formats {
+		if let (true, Some(key)) = (format.data.is_empty(), &format.blob) {
+			format.data = load_blob(key)?;
+		}
+	}
+
+	Ok(())
+}
+
+/// Compresse des pixels RGBA en PNG
+fn encode_png(rgba: &[u8], width: u32, height: u32) -> ClipboardResult<Vec<u8>> {
+	let mut png = Vec::new();
+	PngEncoder::new(&mut png)
+		.write_image(rgba, width, height, ExtendedColorType::Rgba8)
+		.map_err(|e| ClipboardError::Storage(format!("Erreur compression image: {}", e)))?;
+	Ok(png)
+}
+
+/// Décompresse une image PNG en pixels RGBA
+fn decode_png(png: &[u8]) -> ClipboardResult<Vec<u8>> {
+	let image = image::load_from_memory_with_format(png, ImageFormat::Png)
+		.map_err(|e| ClipboardError::Storage(format!("Erreur décompression image: {}", e)))?;
+	Ok(image.into_rgba8().into_raw())
+}
+
+/// Génère l'aperçu PNG d'une image, réduite à `THUMBNAIL_SIZE` en conservant ses proportions
+fn encode_thumbnail(rgba: &[u8], metadata: &ImageMetadata) -> ClipboardResult<Vec<u8>> {
+	let (width, height) = (metadata.width as u32, metadata.height as u32);
+	let image = ImageBuffer::<Rgba<u8>, &[u8]>::from_raw(width, height, rgba)
+		.ok_or_else(|| ClipboardError::Storage("Taille des pixels incohérente avec l'image".to_string()))?;
+
+	let ratio = (THUMBNAIL_SIZE as f32 / width.max(height).max(1) as f32).min(1.0);
+	let thumb_width = ((width as f32 * ratio) as u32).max(1);
+	let thumb_height = ((height as f32 * ratio) as u32).max(1);
+	let thumbnail = image::imageops::thumbnail(&image, thumb_width, thumb_height);
+
+	encode_png(thumbnail.as_raw(), thumb_width, thumb_height)
+}
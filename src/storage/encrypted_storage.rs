@@ -0,0 +1,373 @@
+use crate::clipboard::{ClipboardContent, ClipboardItem, ImageMetadata, MimeData};
+use crate::error::{ClipboardError, ClipboardResult};
+use crate::storage::codec::{self, SEALED_RECORD_MIME};
+use crate::storage::vault::{Vault, VaultHandle};
+use crate::storage::{ItemPage, Storage, StorageChange, StorageEventReceiver};
+use chrono::{DateTime, Utc};
+use log::{info, warn};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+/// Type MIME d'une donnée volumineuse chiffrée (pixels, représentation MIME)
+const SEALED_BLOB_MIME: &str = "application/x-clipboard-manager-sealed-blob";
+
+/// Nombre d'éléments déchiffrés à la fois pendant une recherche
+const SEARCH_PAGE_SIZE: usize = 256;
+
+/// Stockage chiffrant les éléments avant de les confier à un autre moteur
+///
+/// Le moteur sous-jacent ne reçoit que des enveloppes : la date, l'épinglage, la
+/// sélection, le compteur de copies et les dimensions des images restent
+/// lisibles pour le tri et le nettoyage, tout le contenu (texte, aperçus,
+/// représentations) est chiffré avec XChaCha20-Poly1305 et l'empreinte de
+/// contenu remplacée par un HMAC.
+pub struct EncryptedStorage {
+	inner: Box<dyn Storage>,
+	vault: VaultHandle,
+	events: broadcast::Sender<StorageChange>,
+	forwarding: AtomicBool,
+}
+
+impl EncryptedStorage {
+	/// Chiffre les éléments confiés à `inner` avec la clé du coffre
+	pub fn new(inner: Box<dyn Storage>, vault: VaultHandle) -> Self {
+		Self {
+			inner,
+			vault,
+			events: StorageChange::channel(),
+			forwarding: AtomicBool::new(false),
+		}
+	}
+
+	/// Construit l'enveloppe d'un élément complet
+	///
+	/// Retourne l'enveloppe et l'élément allégé qu'elle contient.
+	fn seal(&self, item: &ClipboardItem) -> ClipboardResult<(ClipboardItem, ClipboardItem)> {
+		let encoded = codec::encode_item(item)?;
+		let aad = item.id.as_bytes();
+
+		let mut formats = vec![MimeData::new(SEALED_RECORD_MIME, self.vault.seal(&encoded.record, aad)?)];
+		for (key, data) in &encoded.blobs {
+			let blob = rmp_serde::to_vec(&(key, serde_bytes::Bytes::new(data)))
+				.map_err(|e| ClipboardError::Storage(format!("Erreur encodage blob: {}", e)))?;
+			formats.push(MimeData::new(SEALED_BLOB_MIME, self.vault.seal(&blob, aad)?));
+		}
+
+		// Seules les dimensions d'une image restent lisibles, pour limiter le volume des images
+		let content = match &item.content {
+			ClipboardContent::Image(_, metadata) => {
+				ClipboardContent::Image(Vec::new(), ImageMetadata::new(metadata.width, metadata.height))
+			}
+			_ => ClipboardContent::Text(String::new()),
+		};
+		let envelope = ClipboardItem {
+			id: item.id,
+			content,
+			timestamp: item.timestamp,
+			pinned: item.pinned,
+			formats,
+			preferred_mime: None,
+			selection: item.selection,
+			content_hash: self.vault.keyed_hash(&item.content_hash)?,
+			copy_count: item.copy_count,
+			last_used: item.last_used,
+			expires_at: item.expires_at,
+			memory_only: false,
+		};
+		Ok((envelope, encoded.light))
+	}
+
+	/// Données volumineuses de l'enveloppe enregistrée pour `id`, par clé de blob
+	fn stored_blobs(&self, id: Uuid) -> ClipboardResult<HashMap<String, Vec<u8>>> {
+		match self.inner.get_item(id)? {
+			Some(envelope) => open_blobs(&self.vault, &envelope),
+			None => Ok(HashMap::new()),
+		}
+	}
+
+	/// Recharge les données d'un élément allégé depuis sa version enregistrée
+	fn complete(&self, mut item: ClipboardItem) -> ClipboardResult<ClipboardItem> {
+		let id = item.id;
+		let mut stored: Option<HashMap<String, Vec<u8>>> = None;
+		codec::hydrate(&mut item, |key| {
+			if stored.is_none() {
+				stored = Some(self.stored_blobs(id)?);
+			}
+			Ok(stored.as_ref().and_then(|blobs| blobs.get(key).cloned()))
+		})?;
+		Ok(item)
+	}
+
+	/// Chiffre les éléments enregistrés avant l'activation du chiffrement, puis
+	/// réécrit la base du moteur pour en effacer les versions en clair
+	fn seal_plaintext(&self) -> ClipboardResult<usize> {
+		let mut sealed = 0;
+		for item in self.inner.get_all_items()? {
+			if is_sealed(&item) {
+				continue;
+			}
+			if let Some(item) = self.inner.get_item(item.id)? {
+				let (envelope, _) = self.seal(&item)?;
+				self.inner.update_item(envelope)?;
+				sealed += 1;
+			}
+		}
+
+		// Les versions en clair ne doivent pas subsister dans les fichiers du moteur
+		if sealed > 0 {
+			self.inner.flush()?;
+			self.inner.compact()?;
+			info!("{} éléments de l'historique chiffrés", sealed);
+		}
+		Ok(sealed)
+	}
+
+	/// Rechiffre avec la clé actuelle les éléments enregistrés avant un changement de clé,
+	/// puis fait oublier l'ancienne au coffre
+	///
+	/// Interrompu, le rechiffrement reprend à la prochaine initialisation.
+	fn reseal_retired(&self) -> ClipboardResult<usize> {
+		if !self.vault.is_rotating() {
+			return Ok(0);
+		}
+
+		let mut resealed = 0;
+		for item in self.inner.get_all_items()? {
+			if !is_sealed(&item) {
+				continue;
+			}
+			if let Some(envelope) = self.inner.get_item(item.id)? {
+				let blobs = open_blobs(&self.vault, &envelope)?;
+				let mut item = open_record(&self.vault, envelope)?;
+				codec::hydrate(&mut item, |key| Ok(blobs.get(key).cloned()))?;
+				let (envelope, _) = self.seal(&item)?;
+				self.inner.update_item(envelope)?;
+				resealed += 1;
+			}
+		}
+
+		self.inner.flush()?;
+		self.inner.compact()?;
+		self.vault.retire()?;
+		info!("{} éléments de l'historique rechiffrés avec la nouvelle clé", resealed);
+		Ok(resealed)
+	}
+
+	/// Relaie les modifications du moteur sous-jacent, déchiffrées
+	///
+	/// Une modification illisible (coffre verrouillé) devient une demande de rechargement.
+	fn forward_events(&self) {
+		let mut receiver = self.inner.subscribe();
+		let vault = self.vault.clone();
+		let events = self.events.clone();
+
+		let spawned = std::thread::Builder::new()
+			.name("encrypted-changes".to_string())
+			.spawn(move || loop {
+				let change = match receiver.blocking_recv() {
+					Ok(change) => open_change(&vault, change),
+					Err(broadcast::error::RecvError::Lagged(_)) => StorageChange { reload: true, ..StorageChange::default() },
+					Err(broadcast::error::RecvError::Closed) => break,
+				};
+				let _ = events.send(change);
+			});
+
+		if let Err(e) = spawned {
+			warn!("Relais des modifications de l'historique indisponible: {}", e);
+		}
+	}
+}
+
+/// Indique si un élément enregistré est une enveloppe chiffrée
+fn is_sealed(item: &ClipboardItem) -> bool {
+	item.formats.iter().any(|format| format.mime_type == SEALED_RECORD_MIME)
+}
+
+/// Déchiffre l'élément allégé contenu dans une enveloppe
+///
+/// Un élément enregistré avant l'activation du chiffrement est retourné tel quel.
+fn open_record(vault: &Vault, envelope: ClipboardItem) -> ClipboardResult<ClipboardItem> {
+	let Some(sealed) = envelope.formats.iter().find(|format| format.mime_type == SEALED_RECORD_MIME) else {
+		return Ok(envelope);
+	};
+	let item = codec::decode_record(&vault.open(&sealed.data, envelope.id.as_bytes())?)?;
+	if item.id != envelope.id {
+		return Err(ClipboardError::Storage(format!("Enveloppe de l'élément {} altérée", envelope.id)));
+	}
+	Ok(item)
+}
+
+/// Déchiffre les données volumineuses d'une enveloppe complète
+fn open_blobs(vault: &Vault, envelope: &ClipboardItem) -> ClipboardResult<HashMap<String, Vec<u8>>> {
+	envelope
+		.formats
+		.iter()
+		.filter(|format| format.mime_type == SEALED_BLOB_MIME)
+		.map(|format| {
+			let blob = vault.open(&format.data, envelope.id.as_bytes())?;
+			let (key, data): (String, serde_bytes::ByteBuf) = rmp_serde::from_slice(&blob)
+				.map_err(|e| ClipboardError::Storage(format!("Erreur décodage blob: {}", e)))?;
+			Ok((key, data.into_vec()))
+		})
+		.collect()
+}
+
+/// Déchiffre les éléments d'une modification du moteur sous-jacent
+fn open_change(vault: &Vault, change: StorageChange) -> StorageChange {
+	let upserted = change
+		.upserted
+		.into_iter()
+		.map(|envelope| open_record(vault, envelope))
+		.collect::<ClipboardResult<Vec<_>>>();
+
+	match upserted {
+		Ok(upserted) => StorageChange { upserted, ..change },
+		Err(_) => StorageChange { total: change.total, reload: true, ..StorageChange::default() },
+	}
+}
+
+impl Storage for EncryptedStorage {
+	fn init(&self) -> ClipboardResult<()> {
+		self.inner.init()?;
+		// Verrouillé, l'historique sera chiffré au prochain appel, après déverrouillage
+		if !self.vault.is_locked() {
+			self.seal_plaintext()?;
+			self.reseal_retired()?;
+		}
+		Ok(())
+	}
+
+	fn get_all_items(&self) -> ClipboardResult<Vec<ClipboardItem>> {
+		self.inner
+			.get_all_items()?
+			.into_iter()
+			.map(|envelope| open_record(&self.vault, envelope))
+			.collect()
+	}
+
+	fn get_items(&self, offset: usize, limit: usize) -> ClipboardResult<ItemPage> {
+		let page = self.inner.get_items(offset, limit)?;
+		let items = page
+			.items
+			.into_iter()
+			.map(|envelope| open_record(&self.vault, envelope))
+			.collect::<ClipboardResult<Vec<_>>>()?;
+		Ok(ItemPage { items, ..page })
+	}
+
+	fn search(&self, query: &str, offset: usize, limit: usize) -> ClipboardResult<ItemPage> {
+		if query.is_empty() {
+			return self.get_items(offset, limit);
+		}
+
+		// Le texte n'est lisible qu'une fois déchiffré : l'historique est parcouru page par page
+		let mut items = Vec::new();
+		let mut total = 0;
+		let mut position = 0;
+		loop {
+			let page = self.inner.get_items(position, SEARCH_PAGE_SIZE)?;
+			let read = page.items.len();
+			for envelope in page.items {
+				let item = open_record(&self.vault, envelope)?;
+				if !item.matches_search(query) {
+					continue;
+				}
+				if total >= offset && items.len() < limit {
+					items.push(item);
+				}
+				total += 1;
+			}
+			position += read;
+			if read < SEARCH_PAGE_SIZE {
+				break;
+			}
+		}
+
+		Ok(ItemPage { items, offset, total })
+	}
+
+	fn count_items(&self) -> ClipboardResult<usize> {
+		self.inner.count_items()
+	}
+
+	fn get_item(&self, id: Uuid) -> ClipboardResult<Option<ClipboardItem>> {
+		let Some(envelope) = self.inner.get_item(id)? else {
+			return Ok(None);
+		};
+		let blobs = open_blobs(&self.vault, &envelope)?;
+		let mut item = open_record(&self.vault, envelope)?;
+		codec::hydrate(&mut item, |key| Ok(blobs.get(key).cloned()))?;
+		Ok(Some(item))
+	}
+
+	fn find_by_hash(&self, hash: &str) -> ClipboardResult<Option<ClipboardItem>> {
+		self.inner
+			.find_by_hash(&self.vault.keyed_hash(hash)?)?
+			.map(|envelope| open_record(&self.vault, envelope))
+			.transpose()
+	}
+
+	fn add_item(&self, item: ClipboardItem) -> ClipboardResult<ClipboardItem> {
+		let (envelope, light) = self.seal(&item)?;
+		self.inner.add_item(envelope)?;
+		Ok(light)
+	}
+
+	fn update_item(&self, item: ClipboardItem) -> ClipboardResult<ClipboardItem> {
+		let item = self.complete(item)?;
+		let (envelope, light) = self.seal(&item)?;
+		self.inner.update_item(envelope)?;
+		Ok(light)
+	}
+
+	fn remove_item(&self, id: Uuid) -> ClipboardResult<()> {
+		self.inner.remove_item(id)
+	}
+
+	fn clear_non_pinned(&self) -> ClipboardResult<Vec<Uuid>> {
+		self.inner.clear_non_pinned()
+	}
+
+	fn next_expiry(&self) -> ClipboardResult<Option<DateTime<Utc>>> {
+		// L'enveloppe garde l'expiration et l'épinglage en clair
+		self.inner.next_expiry()
+	}
+
+	fn lapsed_items(&self, now: DateTime<Utc>) -> ClipboardResult<Vec<ClipboardItem>> {
+		self.inner
+			.lapsed_items(now)?
+			.into_iter()
+			.map(|envelope| open_record(&self.vault, envelope))
+			.collect()
+	}
+
+	fn oldest_unpinned(&self, offset: usize, limit: usize, images_only: bool) -> ClipboardResult<Vec<ClipboardItem>> {
+		self.inner
+			.oldest_unpinned(offset, limit, images_only)?
+			.into_iter()
+			.map(|envelope| open_record(&self.vault, envelope))
+			.collect()
+	}
+
+	fn image_bytes(&self) -> ClipboardResult<u64> {
+		// L'enveloppe d'une image en garde les dimensions en clair
+		self.inner.image_bytes()
+	}
+
+	fn flush(&self) -> ClipboardResult<()> {
+		self.inner.flush()
+	}
+
+	fn compact(&self) -> ClipboardResult<()> {
+		self.inner.compact()
+	}
+
+	fn subscribe(&self) -> StorageEventReceiver {
+		if !self.forwarding.swap(true, Ordering::SeqCst) {
+			self.forward_events();
+		}
+		self.events.subscribe()
+	}
+}
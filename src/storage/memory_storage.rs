@@ -0,0 +1,199 @@
+use crate::clipboard::ClipboardItem;
+use crate::error::{ClipboardError, ClipboardResult};
+use crate::storage::codec;
+use crate::storage::retention::image_size;
+use crate::storage::{ItemPage, Storage, StorageChange, StorageEventReceiver};
+use chrono::{DateTime, Utc};
+use log::{debug, info};
+use std::sync::{Mutex, MutexGuard};
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+/// Élément gardé en mémoire
+struct Entry {
+	/// Élément complet, retourné par `get_item`
+	item: ClipboardItem,
+	/// Élément allégé, retourné par les listes comme le font les autres moteurs
+	light: ClipboardItem,
+}
+
+/// Historique gardé en mémoire, perdu à l'abandon du stockage
+///
+/// Historique jetable, sans base ni fichier, pour les tests de bout en bout.
+/// Sert aussi de dernier recours si même une base SQLite en mémoire ne peut être créée.
+pub struct MemoryStorage {
+	/// Éléments du plus récent au plus ancien
+	entries: Mutex<Vec<Entry>>,
+	/// Diffusion des modifications (aucun autre processus ne voit cet historique)
+	events: broadcast::Sender<StorageChange>,
+}
+
+impl MemoryStorage {
+	/// Crée un historique vide
+	pub fn new() -> Self {
+		Self { entries: Mutex::new(Vec::new()), events: StorageChange::channel() }
+	}
+
+	/// Accès aux éléments
+	fn entries(&self) -> ClipboardResult<MutexGuard<'_, Vec<Entry>>> {
+		self.entries
+			.lock()
+			.map_err(|e| ClipboardError::Storage(format!("Erreur verrouillage historique: {}", e)))
+	}
+
+	/// Enregistre un élément à sa place chronologique, en remplaçant sa version précédente
+	///
+	/// Retourne l'élément allégé tel qu'enregistré.
+	fn upsert(entries: &mut Vec<Entry>, item: ClipboardItem) -> ClipboardResult<ClipboardItem> {
+		let light = codec::encode_item(&item)?.light;
+		entries.retain(|entry| entry.item.id != item.id);
+		// Après les éléments plus récents : à date égale, le dernier enregistré passe devant
+		let position = entries.partition_point(|entry| entry.item.timestamp > item.timestamp);
+		entries.insert(position, Entry { item, light: light.clone() });
+		Ok(light)
+	}
+
+	/// Diffuse une modification aux abonnés
+	fn notify(&self, total: usize, upserted: Vec<ClipboardItem>, removed: Vec<Uuid>) {
+		if self.events.receiver_count() == 0 {
+			return;
+		}
+		let _ = self.events.send(StorageChange { upserted, removed, total, reload: false });
+	}
+}
+
+impl Default for MemoryStorage {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Storage for MemoryStorage {
+	fn init(&self) -> ClipboardResult<()> {
+		debug!("Initialisation de l'historique en mémoire");
+		Ok(())
+	}
+
+	fn get_all_items(&self) -> ClipboardResult<Vec<ClipboardItem>> {
+		Ok(self.entries()?.iter().map(|entry| entry.light.clone()).collect())
+	}
+
+	fn get_items(&self, offset: usize, limit: usize) -> ClipboardResult<ItemPage> {
+		let entries = self.entries()?;
+		let items = entries.iter().skip(offset).take(limit).map(|entry| entry.light.clone()).collect();
+
+		Ok(ItemPage { items, offset, total: entries.len() })
+	}
+
+	fn search(&self, query: &str, offset: usize, limit: usize) -> ClipboardResult<ItemPage> {
+		let entries = self.entries()?;
+		let matches: Vec<_> = entries.iter().filter(|entry| entry.light.matches_search(query)).collect();
+		let items = matches.iter().skip(offset).take(limit).map(|entry| entry.light.clone()).collect();
+
+		Ok(ItemPage { items, offset, total: matches.len() })
+	}
+
+	fn count_items(&self) -> ClipboardResult<usize> {
+		Ok(self.entries()?.len())
+	}
+
+	fn get_item(&self, id: Uuid) -> ClipboardResult<Option<ClipboardItem>> {
+		Ok(self.entries()?.iter().find(|entry| entry.item.id == id).map(|entry| entry.item.clone()))
+	}
+
+	fn find_by_hash(&self, hash: &str) -> ClipboardResult<Option<ClipboardItem>> {
+		Ok(self.entries()?.iter().find(|entry| entry.item.hash() == hash).map(|entry| entry.light.clone()))
+	}
+
+	fn add_item(&self, item: ClipboardItem) -> ClipboardResult<ClipboardItem> {
+		let mut entries = self.entries()?;
+		let id = item.id;
+		let stored = Self::upsert(&mut entries, item)?;
+
+		debug!("Élément ajouté: {}", id);
+		self.notify(entries.len(), vec![stored.clone()], Vec::new());
+		Ok(stored)
+	}
+
+	fn update_item(&self, item: ClipboardItem) -> ClipboardResult<ClipboardItem> {
+		let mut entries = self.entries()?;
+		let id = item.id;
+		let stored = Self::upsert(&mut entries, item)?;
+
+		debug!("Élément mis à jour: {}", id);
+		self.notify(entries.len(), vec![stored.clone()], Vec::new());
+		Ok(stored)
+	}
+
+	fn remove_item(&self, id: Uuid) -> ClipboardResult<()> {
+		let mut entries = self.entries()?;
+		let before = entries.len();
+		entries.retain(|entry| entry.item.id != id);
+
+		debug!("Élément supprimé: {}", id);
+		if entries.len() < before {
+			self.notify(entries.len(), Vec::new(), vec![id]);
+		}
+		Ok(())
+	}
+
+	fn clear_non_pinned(&self) -> ClipboardResult<Vec<Uuid>> {
+		let mut entries = self.entries()?;
+		let removed: Vec<Uuid> = entries.iter().filter(|entry| !entry.item.pinned).map(|entry| entry.item.id).collect();
+		entries.retain(|entry| entry.item.pinned);
+
+		info!("{} éléments non épinglés supprimés", removed.len());
+		if !removed.is_empty() {
+			self.notify(entries.len(), Vec::new(), removed.clone());
+		}
+		Ok(removed)
+	}
+
+	fn next_expiry(&self) -> ClipboardResult<Option<DateTime<Utc>>> {
+		Ok(self
+			.entries()?
+			.iter()
+			.filter(|entry| !entry.item.pinned)
+			.filter_map(|entry| entry.item.expires_at)
+			.min())
+	}
+
+	fn lapsed_items(&self, now: DateTime<Utc>) -> ClipboardResult<Vec<ClipboardItem>> {
+		Ok(self
+			.entries()?
+			.iter()
+			.filter(|entry| !entry.item.pinned && entry.item.expires_at.is_some_and(|expires_at| expires_at <= now))
+			.map(|entry| entry.light.clone())
+			.collect())
+	}
+
+	fn oldest_unpinned(&self, offset: usize, limit: usize, images_only: bool) -> ClipboardResult<Vec<ClipboardItem>> {
+		Ok(self
+			.entries()?
+			.iter()
+			.rev()
+			.filter(|entry| !entry.item.pinned && (!images_only || image_size(&entry.item) > 0))
+			.skip(offset)
+			.take(limit)
+			.map(|entry| entry.light.clone())
+			.collect())
+	}
+
+	fn image_bytes(&self) -> ClipboardResult<u64> {
+		Ok(self.entries()?.iter().map(|entry| image_size(&entry.item)).sum())
+	}
+
+	fn subscribe(&self) -> StorageEventReceiver {
+		self.events.subscribe()
+	}
+
+	fn flush(&self) -> ClipboardResult<()> {
+		// Rien à synchroniser : l'historique ne quitte pas la mémoire
+		Ok(())
+	}
+
+	fn compact(&self) -> ClipboardResult<()> {
+		// Rien n'est écrit sur le disque
+		Ok(())
+	}
+}
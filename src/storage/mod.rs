@@ -1,42 +1,305 @@
+pub mod codec;
+mod encrypted_storage;
+mod memory_storage;
+pub mod retention;
+pub mod schema;
 mod sled_storage;
+mod sqlite_storage;
+pub mod vault;
 
+pub use encrypted_storage::EncryptedStorage;
+pub use memory_storage::MemoryStorage;
+pub use retention::{apply_retention, PruneReport, RetentionPolicy};
 pub use sled_storage::SledStorage;
+pub use sqlite_storage::SqliteStorage;
+pub use vault::{Vault, VaultHandle};
 
 use crate::clipboard::ClipboardItem;
+use crate::config::{Config, DedupPolicy, KeySource, StorageBackend};
 use crate::error::ClipboardResult;
+use chrono::{DateTime, Utc};
+use log::{info, warn};
 use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::broadcast;
 use uuid::Uuid;
 
+/// Nombre de modifications conservées pour un abonné en retard
+const EVENT_CAPACITY: usize = 64;
+
+/// Récepteur des modifications du stockage
+pub type StorageEventReceiver = broadcast::Receiver<StorageChange>;
+
+/// Modification de l'historique
+///
+/// Retournée par les opérations de l'application et diffusée aux abonnés du
+/// stockage. L'appliquer plusieurs fois a le même effet que l'appliquer une fois.
+#[derive(Debug, Clone, Default)]
+pub struct StorageChange {
+	/// Éléments (allégés) ajoutés ou modifiés
+	pub upserted: Vec<ClipboardItem>,
+	/// Identifiants des éléments supprimés
+	pub removed: Vec<Uuid>,
+	/// Nombre total d'éléments après la modification
+	pub total: usize,
+	/// Modification dont le détail est inconnu (autre processus) : tout relire
+	pub reload: bool,
+}
+
+impl StorageChange {
+	/// Crée un canal de diffusion des modifications
+	pub fn channel() -> broadcast::Sender<StorageChange> {
+		broadcast::channel(EVENT_CAPACITY).0
+	}
+}
+
+/// Une page de l'historique, du plus récent au plus ancien
+#[derive(Debug, Clone, Default)]
+pub struct ItemPage {
+	/// Éléments (allégés) de la page
+	pub items: Vec<ClipboardItem>,
+	/// Position du premier élément de la page dans l'historique
+	pub offset: usize,
+	/// Nombre total d'éléments dans l'historique
+	pub total: usize,
+}
+
+impl ItemPage {
+	/// Indique si d'autres éléments suivent cette page
+	pub fn has_more(&self) -> bool {
+		self.offset + self.items.len() < self.total
+	}
+}
+
 /// Trait définissant les opérations de stockage
 pub trait Storage: Send + Sync {
 	/// Initialise le stockage
 	fn init(&self) -> ClipboardResult<()>;
 
-	/// Récupère tous les éléments du presse-papiers
+	/// Récupère tous les éléments du presse-papiers, du plus récent au plus ancien
+	///
+	/// Les éléments sont allégés : les pixels des images et les représentations
+	/// volumineuses ne sont pas chargés. `get_item` retourne l'élément complet.
 	fn get_all_items(&self) -> ClipboardResult<Vec<ClipboardItem>>;
 
-	/// Récupère un élément spécifique par son ID
+	/// Récupère au plus `limit` éléments (allégés) à partir de la position `offset`,
+	/// du plus récent au plus ancien, sans parcourir le reste de l'historique
+	fn get_items(&self, offset: usize, limit: usize) -> ClipboardResult<ItemPage>;
+
+	/// Récupère au plus `limit` éléments (allégés) dont le texte ou les chemins
+	/// contiennent `query`, sans tenir compte de la casse, du plus récent au plus
+	/// ancien à partir de la position `offset` parmi les résultats
+	///
+	/// Le total de la page est le nombre de résultats. Une requête vide
+	/// retourne la page de l'historique.
+	fn search(&self, query: &str, offset: usize, limit: usize) -> ClipboardResult<ItemPage>;
+
+	/// Nombre total d'éléments dans l'historique
+	fn count_items(&self) -> ClipboardResult<usize>;
+
+	/// Récupère un élément complet par son ID
 	fn get_item(&self, id: Uuid) -> ClipboardResult<Option<ClipboardItem>>;
 
-	/// Ajoute un nouvel élément
-	fn add_item(&self, item: ClipboardItem) -> ClipboardResult<()>;
+	/// Récupère l'élément (allégé) le plus récent ayant cette empreinte de contenu
+	fn find_by_hash(&self, hash: &str) -> ClipboardResult<Option<ClipboardItem>>;
+
+	/// Ajoute un nouvel élément et le retourne tel qu'enregistré (allégé)
+	fn add_item(&self, item: ClipboardItem) -> ClipboardResult<ClipboardItem>;
 
-	/// Met à jour un élément existant
-	fn update_item(&self, item: ClipboardItem) -> ClipboardResult<()>;
+	/// Met à jour un élément existant et le retourne tel qu'enregistré (allégé)
+	fn update_item(&self, item: ClipboardItem) -> ClipboardResult<ClipboardItem>;
 
 	/// Supprime un élément par son ID
 	fn remove_item(&self, id: Uuid) -> ClipboardResult<()>;
 
-	/// Supprime tous les éléments sauf ceux épinglés
-	fn clear_non_pinned(&self) -> ClipboardResult<()>;
+	/// Supprime tous les éléments sauf ceux épinglés, et retourne leurs identifiants
+	fn clear_non_pinned(&self) -> ClipboardResult<Vec<Uuid>>;
+
+	/// Date d'expiration la plus proche parmi les éléments non épinglés, lue dans un index
+	fn next_expiry(&self) -> ClipboardResult<Option<DateTime<Utc>>>;
+
+	/// Éléments (allégés) non épinglés dont la date d'expiration est atteinte à `now`,
+	/// lus dans un index
+	fn lapsed_items(&self, now: DateTime<Utc>) -> ClipboardResult<Vec<ClipboardItem>>;
+
+	/// Récupère au plus `limit` éléments (allégés) non épinglés à partir de la position
+	/// `offset`, du plus ancien au plus récent, sans parcourir le reste de l'historique
+	///
+	/// Avec `images_only`, seules les images sont parcourues.
+	fn oldest_unpinned(&self, offset: usize, limit: usize, images_only: bool) -> ClipboardResult<Vec<ClipboardItem>>;
+
+	/// Volume des images de l'historique, épinglées comprises (pixels RGBA, en octets), lu dans un index
+	fn image_bytes(&self) -> ClipboardResult<u64>;
 
 	/// Sauvegarde les données si nécessaire
 	fn flush(&self) -> ClipboardResult<()>;
+
+	/// Réécrit la base pour que les versions supprimées ou remplacées des
+	/// éléments ne subsistent plus sur le disque
+	fn compact(&self) -> ClipboardResult<()>;
+
+	/// S'abonne aux modifications du stockage
+	///
+	/// Les modifications faites par ce processus sont diffusées en détail ;
+	/// celles d'autres processus, quand le moteur permet de les détecter,
+	/// sous la forme d'une demande de rechargement.
+	fn subscribe(&self) -> StorageEventReceiver;
+
+	/// Enregistre une nouvelle copie en appliquant la politique de doublons
+	///
+	/// Retourne l'élément tel qu'il est désormais stocké (allégé).
+	fn record_item(&self, item: ClipboardItem, policy: DedupPolicy) -> ClipboardResult<ClipboardItem> {
+		let existing = match policy {
+			DedupPolicy::KeepDuplicates => None,
+			DedupPolicy::MoveToTop | DedupPolicy::UpdateInPlace => self.find_by_hash(&item.hash())?,
+		};
+
+		match existing {
+			Some(mut existing) => {
+				existing.merge_copy(item, policy == DedupPolicy::MoveToTop);
+				self.update_item(existing)
+			}
+			None => self.add_item(item),
+		}
+	}
+}
+
+/// Enregistre une copie, puis applique les limites de conservation
+///
+/// Retourne la modification de l'historique et le bilan du nettoyage, dont
+/// les éléments expirés à retirer du presse-papiers.
+pub fn record_copy(
+	storage: &dyn Storage,
+	item: ClipboardItem,
+	policy: DedupPolicy,
+	retention: &RetentionPolicy,
+) -> ClipboardResult<(StorageChange, PruneReport)> {
+	let stored = storage.record_item(item, policy)?;
+	let (change, report) = prune(storage, retention)?;
+	Ok((StorageChange { upserted: vec![stored], ..change }, report))
+}
+
+/// Applique les limites de conservation et synchronise l'historique sur le disque
+pub fn prune(storage: &dyn Storage, retention: &RetentionPolicy) -> ClipboardResult<(StorageChange, PruneReport)> {
+	let report = apply_retention(storage, retention)?;
+	storage.flush()?;
+	let change = StorageChange {
+		removed: report.removed.clone(),
+		total: storage.count_items()?,
+		..StorageChange::default()
+	};
+	Ok((change, report))
 }
 
+/// Tous les éléments complets, du plus récent au plus ancien, pour un export
+pub fn export_history(storage: &dyn Storage) -> ClipboardResult<Vec<ClipboardItem>> {
+	storage
+		.get_all_items()?
+		.into_iter()
+		.filter_map(|item| storage.get_item(item.id).transpose())
+		.collect()
+}
+
+/// Ajoute des éléments exportés ; retourne le nombre d'éléments importés et ignorés
+///
+/// Les éléments déjà présents, par leur identifiant ou leur contenu, sont ignorés.
+pub fn import_history(storage: &dyn Storage, items: Vec<ClipboardItem>) -> ClipboardResult<(usize, usize)> {
+	let total = items.len();
+	let mut imported = 0;
+	for item in items {
+		if storage.get_item(item.id)?.is_some() || storage.find_by_hash(&item.hash())?.is_some() {
+			continue;
+		}
+		storage.add_item(item)?;
+		imported += 1;
+	}
+	storage.flush()?;
+	Ok((imported, total - imported))
+}
+
+/// Métadonnée SQLite marquant l'import de la base Sled
+const SLED_IMPORT_MARKER: &str = "sled_import";
+
 /// Crée une instance du moteur de stockage configuré
-pub fn create_storage<P: AsRef<Path>>(data_dir: P) -> ClipboardResult<Box<dyn Storage>> {
-	let storage = SledStorage::new(data_dir)?;
+pub fn create_storage<P: AsRef<Path>>(backend: StorageBackend, data_dir: P) -> ClipboardResult<Box<dyn Storage>> {
+	let data_dir = data_dir.as_ref();
+	match backend {
+		StorageBackend::Sled => {
+			let storage = SledStorage::new(data_dir)?;
+			storage.init()?;
+			Ok(Box::new(storage))
+		}
+		StorageBackend::Sqlite => {
+			let storage = SqliteStorage::new(data_dir)?;
+			storage.init()?;
+			import_sled_once(&storage, data_dir);
+			Ok(Box::new(storage))
+		}
+	}
+}
+
+/// Ouvre l'historique décrit par la configuration
+///
+/// Si le chiffrement est activé, l'historique est retourné avec son coffre. Le
+/// coffre est déjà déverrouillé si la clé est disponible sans intervention :
+/// fichier de clé, trousseau du bureau ou phrase secrète dans `PASSPHRASE_ENV`.
+pub fn open_storage(config: &Config) -> ClipboardResult<(Box<dyn Storage>, Option<VaultHandle>)> {
+	let storage = create_storage(config.storage_backend, &config.data_dir)?;
+	if config.encryption == KeySource::None {
+		return Ok((storage, None));
+	}
+
+	if config.storage_backend == StorageBackend::Sqlite && config.data_dir.join("db").is_file() {
+		warn!(
+			"L'ancienne base Sled, non chiffrée, est toujours présente dans {}",
+			config.data_dir.display()
+		);
+	}
+
+	config.move_legacy_keyfile()?;
+	let vault = Arc::new(Vault::new(&config.data_dir, config.encryption, config.keyfile()));
+	match config.encryption {
+		KeySource::Passphrase => {
+			// Une phrase secrète erronée laisse l'historique verrouillé
+			if let Ok(passphrase) = std::env::var(vault::PASSPHRASE_ENV) {
+				if let Err(e) = vault.unlock(Some(&passphrase)) {
+					warn!("Phrase secrète de {} refusée: {}", vault::PASSPHRASE_ENV, e);
+				}
+			}
+		}
+		_ => vault.unlock(None)?,
+	}
+
+	let storage = EncryptedStorage::new(storage, vault.clone());
 	storage.init()?;
-	Ok(Box::new(storage))
-}
\ No newline at end of file
+	Ok((Box::new(storage), Some(vault)))
+}
+
+/// Importe une fois pour toutes la base Sled du dossier de données dans SQLite
+///
+/// La base Sled est lue sans être initialisée : ni migrée ni réindexée, elle
+/// reste utilisable par une version antérieure. Un échec est signalé sans
+/// empêcher le démarrage : l'import sera retenté au lancement suivant.
+fn import_sled_once(storage: &SqliteStorage, data_dir: &Path) {
+	// Sled conserve ses données dans un fichier `db` à la racine du dossier
+	if !data_dir.join("db").is_file() {
+		return;
+	}
+	match storage.meta(SLED_IMPORT_MARKER) {
+		Ok(None) => {}
+		Ok(Some(_)) => return,
+		Err(e) => {
+			warn!("Import de la base Sled ignoré: {}", e);
+			return;
+		}
+	}
+
+	let result = SledStorage::new(data_dir)
+		.and_then(|sled| sled.export_items())
+		.and_then(|items| storage.import_items(&items, SLED_IMPORT_MARKER));
+
+	match result {
+		Ok(count) => info!("{} éléments importés depuis la base Sled", count),
+		Err(e) => warn!("Import de la base Sled impossible: {}", e),
+	}
+}
@@ -0,0 +1,220 @@
+use crate::clipboard::{ClipboardContent, ClipboardItem, Selection};
+use crate::config::Config;
+use crate::error::ClipboardResult;
+use crate::storage::Storage;
+use chrono::{Duration, Utc};
+use log::{debug, info};
+use std::collections::HashSet;
+use std::fmt;
+use uuid::Uuid;
+
+/// Limites de conservation de l'historique
+///
+/// Une limite à zéro dans la configuration est désactivée. Les éléments
+/// épinglés ne sont jamais supprimés, mais comptent dans la taille de l'historique.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetentionPolicy {
+	/// Nombre maximal d'éléments conservés
+	pub max_items: Option<usize>,
+	/// Âge maximal d'un élément
+	pub max_age: Option<Duration>,
+	/// Volume maximal occupé par les images (en octets)
+	pub max_image_bytes: Option<u64>,
+}
+
+impl From<&Config> for RetentionPolicy {
+	fn from(config: &Config) -> Self {
+		Self {
+			max_items: (config.max_history_size > 0).then_some(config.max_history_size),
+			max_age: (config.retention_days > 0).then(|| Duration::days(config.retention_days.into())),
+			max_image_bytes: (config.max_image_storage_mb > 0)
+				.then(|| config.max_image_storage_mb.saturating_mul(1024 * 1024)),
+		}
+	}
+}
+
+/// Bilan d'une passe de nettoyage
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PruneReport {
+	/// Éléments supprimés car trop anciens
+	pub expired: usize,
+	/// Éléments supprimés pour respecter la taille de l'historique
+	pub over_count: usize,
+	/// Images supprimées pour respecter le volume maximal
+	pub over_image_size: usize,
+	/// Volume total libéré (en octets)
+	pub freed_bytes: u64,
+	/// Identifiants des éléments supprimés
+	pub removed: Vec<Uuid>,
+	/// Sélection d'origine et empreinte des éléments arrivés à leur date d'expiration
+	///
+	/// Le presse-papiers qui contient encore l'un de ces contenus doit être vidé.
+	pub lapsed: Vec<(Selection, String)>,
+}
+
+impl PruneReport {
+	/// Nombre total d'éléments supprimés
+	pub fn total(&self) -> usize {
+		self.expired + self.over_count + self.over_image_size
+	}
+}
+
+impl fmt::Display for PruneReport {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"{} éléments supprimés ({} expirés, {} au-delà de la taille maximale, {} images au-delà du volume maximal), {} octets libérés",
+			self.total(),
+			self.expired,
+			self.over_count,
+			self.over_image_size,
+			self.freed_bytes
+		)
+	}
+}
+
+/// Taille approximative du contenu d'un élément (en octets)
+///
+/// Les représentations rangées dans les blobs ne sont pas chargées et ne comptent pas.
+fn item_size(item: &ClipboardItem) -> u64 {
+	let content_size = match &item.content {
+		ClipboardContent::Text(text) => text.len() as u64,
+		ClipboardContent::Image(..) => image_size(item),
+		ClipboardContent::Files(_) => 0,
+	};
+	let formats_size: usize = item.formats.iter().map(|format| format.data.len()).sum();
+	content_size + formats_size as u64
+}
+
+/// Taille décompressée (RGBA) d'une image, nulle pour les autres contenus
+///
+/// Calculée à partir des dimensions : les pixels des éléments allégés ne sont pas chargés.
+pub fn image_size(item: &ClipboardItem) -> u64 {
+	match &item.content {
+		ClipboardContent::Image(_, metadata) => metadata.width as u64 * metadata.height as u64 * 4,
+		_ => 0,
+	}
+}
+
+/// Nombre d'éléments lus à la fois dans les index du stockage pendant un nettoyage
+const PRUNE_PAGE_SIZE: usize = 64;
+
+/// Parcourt les éléments non épinglés du plus ancien au plus récent, page par page
+///
+/// Rien n'est supprimé pendant le parcours : les positions des pages restent valables.
+struct Oldest<'a> {
+	storage: &'a dyn Storage,
+	images_only: bool,
+	offset: usize,
+	page: std::vec::IntoIter<ClipboardItem>,
+	exhausted: bool,
+}
+
+impl<'a> Oldest<'a> {
+	fn new(storage: &'a dyn Storage, images_only: bool) -> Self {
+		Self { storage, images_only, offset: 0, page: Vec::new().into_iter(), exhausted: false }
+	}
+
+	/// Élément suivant, en lisant la page suivante si besoin
+	fn next(&mut self) -> ClipboardResult<Option<ClipboardItem>> {
+		if let Some(item) = self.page.next() {
+			return Ok(Some(item));
+		}
+		if self.exhausted {
+			return Ok(None);
+		}
+		let page = self.storage.oldest_unpinned(self.offset, PRUNE_PAGE_SIZE, self.images_only)?;
+		self.offset += page.len();
+		self.exhausted = page.len() < PRUNE_PAGE_SIZE;
+		self.page = page.into_iter();
+		Ok(self.page.next())
+	}
+}
+
+/// Supprime les éléments non épinglés qui dépassent les limites de conservation
+///
+/// Les plus anciens partent en premier. Seuls les éléments à supprimer sont
+/// lus, dans les index du stockage : le coût ne dépend pas de la taille de
+/// l'historique. Le stockage n'est pas synchronisé sur le disque : c'est à
+/// l'appelant d'appeler `flush`.
+pub fn apply_retention(storage: &dyn Storage, policy: &RetentionPolicy) -> ClipboardResult<PruneReport> {
+	let mut report = PruneReport::default();
+	let mut removed: HashSet<Uuid> = HashSet::new();
+	// Volume des images supprimées, à déduire du volume enregistré
+	let mut removed_image_bytes: u64 = 0;
+
+	// 1. Date d'expiration propre à l'élément
+	let now = Utc::now();
+	for item in storage.lapsed_items(now)? {
+		if removed.insert(item.id) {
+			report.lapsed.push((item.selection, item.hash()));
+			report.expired += 1;
+			report.freed_bytes += item_size(&item);
+			removed_image_bytes += image_size(&item);
+		}
+	}
+
+	// 2. Âge maximal
+	if let Some(max_age) = policy.max_age {
+		let cutoff = now - max_age;
+		let mut oldest = Oldest::new(storage, false);
+		while let Some(item) = oldest.next()? {
+			if item.timestamp >= cutoff {
+				break;
+			}
+			if removed.insert(item.id) {
+				report.expired += 1;
+				report.freed_bytes += item_size(&item);
+				removed_image_bytes += image_size(&item);
+			}
+		}
+	}
+
+	// 3. Taille de l'historique
+	if let Some(max_items) = policy.max_items {
+		let mut remaining = storage.count_items()?.saturating_sub(removed.len());
+		let mut oldest = Oldest::new(storage, false);
+		while remaining > max_items {
+			let Some(item) = oldest.next()? else {
+				break;
+			};
+			if !removed.insert(item.id) {
+				continue;
+			}
+			report.over_count += 1;
+			report.freed_bytes += item_size(&item);
+			removed_image_bytes += image_size(&item);
+			remaining -= 1;
+		}
+	}
+
+	// 4. Volume des images
+	if let Some(max_image_bytes) = policy.max_image_bytes {
+		let mut image_bytes = storage.image_bytes()?.saturating_sub(removed_image_bytes);
+		let mut oldest = Oldest::new(storage, true);
+		while image_bytes > max_image_bytes {
+			let Some(item) = oldest.next()? else {
+				break;
+			};
+			if !removed.insert(item.id) {
+				continue;
+			}
+			report.over_image_size += 1;
+			report.freed_bytes += item_size(&item);
+			image_bytes = image_bytes.saturating_sub(image_size(&item));
+		}
+	}
+
+	for id in &removed {
+		storage.remove_item(*id)?;
+	}
+	report.removed = removed.into_iter().collect();
+
+	if report.total() > 0 {
+		info!("Nettoyage de l'historique: {}", report);
+	} else {
+		debug!("Nettoyage de l'historique: rien à supprimer");
+	}
+
+	Ok(report)
+}
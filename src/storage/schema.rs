@@ -0,0 +1,101 @@
+use crate::clipboard::ClipboardItem;
+use crate::error::{ClipboardError, ClipboardResult};
+use crate::storage::codec;
+use serde_json::{json, Map, Value};
+
+/// Version actuelle du format des éléments
+///
+/// Chaque base enregistre la version de ses éléments. À l'initialisation du
+/// stockage, ceux écrits par une version plus ancienne sont mis à niveau une
+/// migration après l'autre, sur leur JSON brut, avant d'être désérialisés.
+///
+/// Depuis la version 2, les éléments sont encodés en MessagePack et leurs
+/// données volumineuses rangées dans des blobs : passer de la version 1 à la
+/// version 2 consiste à réenregistrer chaque élément.
+///
+/// La version 3 ne touche pas aux éléments : seul l'index des empreintes de
+/// Sled change (voir `SLED_HASH_IDS_VERSION`).
+pub const SCHEMA_VERSION: u32 = 3;
+
+/// Dernière version dont les éléments sont enregistrés en JSON
+const LAST_JSON_VERSION: u32 = 1;
+
+/// Dernière version ayant changé l'encodage des éléments
+///
+/// Les éléments d'une base plus ancienne sont réenregistrés à la migration.
+pub const LAST_RECORD_VERSION: u32 = 2;
+
+/// Première version où l'index Sled des empreintes a une entrée par élément
+///
+/// L'index `hash_index` des versions précédentes ne désignait que l'élément le
+/// plus récent de chaque empreinte : la migration le remplace par `hash_ids`.
+pub const SLED_HASH_IDS_VERSION: u32 = 3;
+
+/// Clé sous laquelle les moteurs de stockage enregistrent la version
+pub const SCHEMA_VERSION_KEY: &str = "schema_version";
+
+/// Migration d'un élément de la version `n` à la version `n + 1`
+///
+/// Une migration doit pouvoir être rejouée sans effet sur un élément déjà
+/// migré : une mise à niveau interrompue est reprise depuis le début.
+type Migration = fn(&mut Map<String, Value>) -> ClipboardResult<()>;
+
+/// Migrations successives des éléments JSON, la n-ième passant de la version `n` à `n + 1`
+const JSON_MIGRATIONS: [Migration; LAST_JSON_VERSION as usize] = [v0_to_v1];
+
+/// Vérifie qu'une base peut être lue par cette version de l'application
+pub fn check_version(version: u32) -> ClipboardResult<()> {
+	if version > SCHEMA_VERSION {
+		return Err(ClipboardError::Storage(format!(
+			"Format de l'historique (version {}) plus récent que celui pris en charge (version {})",
+			version, SCHEMA_VERSION
+		)));
+	}
+	Ok(())
+}
+
+/// Met à niveau un élément enregistré dans la version `from`
+///
+/// Un élément JSON est retourné complet, un élément plus récent allégé de ses blobs.
+pub fn upgrade_record(value: &[u8], from: u32) -> ClipboardResult<ClipboardItem> {
+	check_version(from)?;
+	if from > LAST_JSON_VERSION {
+		return codec::decode_record(value);
+	}
+
+	let mut value: Value = serde_json::from_slice(value)?;
+	let record = value
+		.as_object_mut()
+		.ok_or_else(|| ClipboardError::Storage("Élément enregistré invalide: objet JSON attendu".to_string()))?;
+
+	for migration in &JSON_MIGRATIONS[from as usize..] {
+		migration(record)?;
+	}
+
+	let mut item: ClipboardItem = serde_json::from_value(value)?;
+	if item.content_hash.is_empty() {
+		item.content_hash = item.content.content_hash();
+	}
+	Ok(item)
+}
+
+/// Version 0 → 1 : formats MIME, sélection d'origine et déduplication
+///
+/// Les éléments de la version 0 ne contiennent que `id`, `content`,
+/// `timestamp` et `pinned`. L'empreinte est calculée après désérialisation.
+fn v0_to_v1(record: &mut Map<String, Value>) -> ClipboardResult<()> {
+	for field in ["id", "content", "timestamp"] {
+		if !record.contains_key(field) {
+			return Err(ClipboardError::Storage(format!("Élément enregistré sans champ `{}`", field)));
+		}
+	}
+
+	record.entry("pinned").or_insert(json!(false));
+	record.entry("formats").or_insert(json!([]));
+	record.entry("preferred_mime").or_insert(Value::Null);
+	record.entry("selection").or_insert(json!("Clipboard"));
+	record.entry("content_hash").or_insert(json!(""));
+	record.entry("copy_count").or_insert(json!(1));
+	record.entry("last_used").or_insert(Value::Null);
+	Ok(())
+}
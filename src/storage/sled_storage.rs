@@ -1,30 +1,136 @@
 use crate::clipboard::ClipboardItem;
 use crate::error::{ClipboardError, ClipboardResult};
-use crate::storage::Storage;
-use log::{debug, error, info};
-use serde_json;
-use sled::{Config, Db};
-use std::path::Path;
+use crate::storage::codec;
+use crate::storage::retention::image_size;
+use crate::storage::schema::{self, SCHEMA_VERSION, SCHEMA_VERSION_KEY};
+use crate::storage::{ItemPage, Storage, StorageChange, StorageEventReceiver};
+use chrono::{DateTime, Utc};
+use log::{debug, error, info, warn};
+use sled::{Config, Db, Tree};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::Duration;
+use tokio::sync::broadcast;
 use uuid::Uuid;
 
+/// Métadonnée marquant la construction de l'index des expirations
+const EXPIRY_INDEX_KEY: &str = "expiry_index";
+
+/// Métadonnée marquant la construction de l'index des images
+const IMAGE_INDEX_KEY: &str = "image_index";
+
+/// Tentatives d'ouverture tant que le verrou de la base est tenu
+///
+/// Sled relâche son verrou en arrière-plan à la fermeture de la base : une
+/// réouverture immédiate peut encore le trouver tenu.
+const LOCK_ATTEMPTS: u32 = 20;
+
+/// Attente entre deux tentatives d'ouverture
+const LOCK_RETRY_DELAY: Duration = Duration::from_millis(25);
+
+/// Dossier où la base est réécrite par `compact`, avant de remplacer l'ancienne
+const STAGING_DIR: &str = "sled-compact";
+
+/// Dossier où l'ancienne base est écartée pendant son remplacement
+const RETIRED_DIR: &str = "sled-retired";
+
 /// Implémentation du stockage utilisant Sled comme backend
+///
+/// La base ouverte peut être remplacée par une copie réécrite : voir `compact`.
 pub struct SledStorage {
+	data_dir: PathBuf,
+	/// Diffusion des modifications, conservée d'une base ouverte à la suivante
+	events: broadcast::Sender<StorageChange>,
+	/// Base ouverte, absente seulement si sa réouverture a échoué
+	base: RwLock<Option<SledBase>>,
+}
+
+/// Base Sled ouverte et ses arbres
+struct SledBase {
 	db: Db,
+	/// Index empreinte de contenu + ID → clé chronologique, une entrée par élément
+	/// pour que les doublons conservés restent tous indexés
+	hash_index: Tree,
+	/// Index horodatage + ID, dans l'ordre chronologique
+	time_index: Tree,
+	/// Index expiration + ID des éléments non épinglés qui expirent
+	expiry_index: Tree,
+	/// Index horodatage + ID des images → volume décompressé, dans l'ordre chronologique
+	image_index: Tree,
+	/// Métadonnées de la base (version du format des éléments)
+	meta: Tree,
+	/// Données volumineuses, adressées par leur empreinte
+	blobs: Tree,
+	/// Références clé de blob + ID d'élément, pour savoir quand supprimer un blob
+	blob_refs: Tree,
+	/// Diffusion des modifications (Sled verrouille la base : un seul processus y écrit)
+	events: broadcast::Sender<StorageChange>,
 }
 
-impl SledStorage {
-	/// Crée une nouvelle instance de stockage Sled
-	pub fn new<P: AsRef<Path>>(data_dir: P) -> ClipboardResult<Self> {
+impl SledBase {
+	/// Ouvre la base de `data_dir`, dont les modifications seront diffusées par `events`
+	fn open(data_dir: &Path, events: broadcast::Sender<StorageChange>) -> ClipboardResult<Self> {
 		let config = Config::new()
 			.path(data_dir)
 			.cache_capacity(64 * 1024 * 1024) // 64MB de cache
 			.flush_every_ms(Some(1000)); // Écriture sur disque toutes les secondes
 
-		let db = config
-			.open()
-			.map_err(|e| ClipboardError::Storage(format!("Erreur ouverture Sled: {}", e)))?;
+		let mut attempts = 1;
+		let db = loop {
+			match config.open() {
+				Ok(db) => break db,
+				// Sled ne distingue pas son verrou des autres erreurs d'entrée/sortie
+				Err(sled::Error::Io(ref io)) if Self::held_elsewhere(data_dir) => {
+					if attempts < LOCK_ATTEMPTS {
+						attempts += 1;
+						std::thread::sleep(LOCK_RETRY_DELAY);
+						continue;
+					}
+					return Err(ClipboardError::StorageLocked(format!("Base Sled déjà ouverte: {}", io)));
+				}
+				Err(e) => return Err(ClipboardError::Storage(format!("Erreur ouverture Sled: {}", e))),
+			}
+		};
+
+		let hash_index = db
+			.open_tree("hash_ids")
+			.map_err(|e| ClipboardError::Storage(format!("Erreur ouverture index: {}", e)))?;
+
+		let time_index = db
+			.open_tree("time_index")
+			.map_err(|e| ClipboardError::Storage(format!("Erreur ouverture index: {}", e)))?;
+
+		let expiry_index = db
+			.open_tree("expiry_index")
+			.map_err(|e| ClipboardError::Storage(format!("Erreur ouverture index: {}", e)))?;
+
+		let image_index = db
+			.open_tree("image_index")
+			.map_err(|e| ClipboardError::Storage(format!("Erreur ouverture index: {}", e)))?;
 
-		Ok(Self { db })
+		let meta = db
+			.open_tree("meta")
+			.map_err(|e| ClipboardError::Storage(format!("Erreur ouverture métadonnées: {}", e)))?;
+
+		let blobs = db
+			.open_tree("blobs")
+			.map_err(|e| ClipboardError::Storage(format!("Erreur ouverture blobs: {}", e)))?;
+
+		let blob_refs = db
+			.open_tree("blob_refs")
+			.map_err(|e| ClipboardError::Storage(format!("Erreur ouverture références: {}", e)))?;
+
+		Ok(Self {
+			db,
+			hash_index,
+			time_index,
+			expiry_index,
+			image_index,
+			meta,
+			blobs,
+			blob_refs,
+			events,
+		})
 	}
 
 	/// Convertit un ID UUID en clé pour Sled
@@ -32,52 +138,502 @@ impl SledStorage {
 		id.as_bytes().to_vec()
 	}
 
-	/// Convertit un élément en valeur pour Sled
-	fn item_to_value(item: &ClipboardItem) -> ClipboardResult<Vec<u8>> {
-		serde_json::to_vec(item).map_err(|e| e.into())
+	/// Convertit une valeur Sled en élément allégé
+	fn value_to_item(value: &[u8]) -> ClipboardResult<ClipboardItem> {
+		codec::decode_record(value)
 	}
 
-	/// Convertit une valeur Sled en élément
-	fn value_to_item(value: &[u8]) -> ClipboardResult<ClipboardItem> {
-		serde_json::from_slice(value).map_err(|e| e.into())
+	/// Clé d'index ordonnée : valeur ordonnable suivie de l'ID
+	fn ordered_key(value: i64, id: Uuid) -> Vec<u8> {
+		// Inverser le bit de signe pour que l'ordre des octets suive celui des valeurs
+		let mut key = (value as u64 ^ (1 << 63)).to_be_bytes().to_vec();
+		key.extend_from_slice(id.as_bytes());
+		key
 	}
-}
 
-impl Storage for SledStorage {
-	fn init(&self) -> ClipboardResult<()> {
-		debug!("Initialisation du stockage Sled");
+	/// Valeur d'une clé d'index ordonnée
+	fn key_value(key: &[u8]) -> Option<i64> {
+		let value = <[u8; 8]>::try_from(key.get(..8)?).ok()?;
+		Some((u64::from_be_bytes(value) ^ (1 << 63)) as i64)
+	}
+
+	/// Clé de l'index chronologique : horodatage en microsecondes suivi de l'ID
+	fn time_key(item: &ClipboardItem) -> Vec<u8> {
+		Self::ordered_key(item.timestamp.timestamp_micros(), item.id)
+	}
+
+	/// Clé de l'index des expirations, pour un élément non épinglé qui expire
+	///
+	/// L'expiration est gardée à la nanoseconde : le démon ne se réveille pas
+	/// avant qu'elle soit atteinte.
+	fn expiry_key(item: &ClipboardItem) -> Option<Vec<u8>> {
+		item.expires_at
+			.filter(|_| !item.pinned)
+			.map(|expiry| Self::ordered_key(expiry.timestamp_nanos_opt().unwrap_or(i64::MAX), item.id))
+	}
+
+	/// Ajoute ou retire l'entrée d'un élément dans l'index des expirations
+	fn index_expiry(&self, previous: Option<&ClipboardItem>, item: Option<&ClipboardItem>) -> ClipboardResult<()> {
+		let old_key = previous.and_then(Self::expiry_key);
+		let new_key = item.and_then(Self::expiry_key);
+		if old_key == new_key {
+			return Ok(());
+		}
+		if let Some(key) = old_key {
+			self.expiry_index
+				.remove(key)
+				.map_err(|e| ClipboardError::Storage(format!("Erreur mise à jour index: {}", e)))?;
+		}
+		if let Some(key) = new_key {
+			self.expiry_index
+				.insert(key, &[])
+				.map_err(|e| ClipboardError::Storage(format!("Erreur mise à jour index: {}", e)))?;
+		}
 		Ok(())
 	}
 
-	fn get_all_items(&self) -> ClipboardResult<Vec<ClipboardItem>> {
-		let mut items = Vec::new();
+	/// Reconstruit l'index des expirations à partir des éléments enregistrés
+	fn rebuild_expiry_index(&self) -> ClipboardResult<()> {
+		self.expiry_index
+			.clear()
+			.map_err(|e| ClipboardError::Storage(format!("Erreur réinitialisation index: {}", e)))?;
+		for result in self.db.iter() {
+			let (_, value) = result
+				.map_err(|e| ClipboardError::Storage(format!("Erreur lecture base de données: {}", e)))?;
+			if let Ok(item) = Self::value_to_item(&value) {
+				self.index_expiry(None, Some(&item))?;
+			}
+		}
+		self.meta
+			.insert(EXPIRY_INDEX_KEY, &[])
+			.map_err(|e| ClipboardError::Storage(format!("Erreur écriture métadonnées: {}", e)))?;
+
+		info!("Index des expirations reconstruit ({} entrées)", self.expiry_index.len());
+		Ok(())
+	}
 
+	/// Ajoute ou retire l'entrée d'un élément dans l'index des images
+	fn index_image(&self, previous: Option<&ClipboardItem>, item: Option<&ClipboardItem>) -> ClipboardResult<()> {
+		if let Some(previous) = previous {
+			self.image_index
+				.remove(Self::time_key(previous))
+				.map_err(|e| ClipboardError::Storage(format!("Erreur mise à jour index: {}", e)))?;
+		}
+		if let Some(item) = item {
+			let size = image_size(item);
+			if size > 0 {
+				self.image_index
+					.insert(Self::time_key(item), &size.to_be_bytes())
+					.map_err(|e| ClipboardError::Storage(format!("Erreur mise à jour index: {}", e)))?;
+			}
+		}
+		Ok(())
+	}
+
+	/// Reconstruit l'index des images à partir des éléments enregistrés
+	fn rebuild_image_index(&self) -> ClipboardResult<()> {
+		self.image_index
+			.clear()
+			.map_err(|e| ClipboardError::Storage(format!("Erreur réinitialisation index: {}", e)))?;
 		for result in self.db.iter() {
-			match result {
-				Ok((_, value)) => {
-					match Self::value_to_item(&value) {
-						Ok(item) => items.push(item),
-						Err(e) => error!("Erreur désérialisation élément: {}", e),
-					}
+			let (_, value) = result
+				.map_err(|e| ClipboardError::Storage(format!("Erreur lecture base de données: {}", e)))?;
+			if let Ok(item) = Self::value_to_item(&value) {
+				self.index_image(None, Some(&item))?;
+			}
+		}
+		self.meta
+			.insert(IMAGE_INDEX_KEY, &[])
+			.map_err(|e| ClipboardError::Storage(format!("Erreur écriture métadonnées: {}", e)))?;
+
+		info!("Index des images reconstruit ({} entrées)", self.image_index.len());
+		Ok(())
+	}
+
+	/// Reconstruit l'index chronologique à partir des éléments enregistrés
+	fn rebuild_time_index(&self) -> ClipboardResult<()> {
+		for result in self.db.iter() {
+			let (_, value) = result
+				.map_err(|e| ClipboardError::Storage(format!("Erreur lecture base de données: {}", e)))?;
+			match Self::value_to_item(&value) {
+				Ok(item) => {
+					self.time_index
+						.insert(Self::time_key(&item), &[])
+						.map_err(|e| ClipboardError::Storage(format!("Erreur mise à jour index: {}", e)))?;
 				}
-				Err(e) => {
-					error!("Erreur lecture base de données: {}", e);
+				Err(e) => error!("Erreur désérialisation élément: {}", e),
+			}
+		}
+
+		info!("Index chronologique reconstruit ({} entrées)", self.time_index.len());
+		Ok(())
+	}
+
+	/// Indique si le fichier verrouillé par Sled est tenu par un autre processus
+	///
+	/// Sled verrouille le fichier `db` de la base ; le verrou est éprouvé
+	/// directement puis relâché aussitôt.
+	fn held_elsewhere(data_dir: &Path) -> bool {
+		let Ok(file) = std::fs::OpenOptions::new().read(true).write(true).open(data_dir.join("db")) else {
+			return false;
+		};
+		matches!(file.try_lock(), Err(std::fs::TryLockError::WouldBlock))
+	}
+
+	/// Parcourt les éléments du plus récent au plus ancien, selon l'index chronologique
+	///
+	/// Les `skip` premières entrées de l'index sont sautées sans lire ni décoder
+	/// leur élément.
+	fn iter_recent(&self, skip: usize) -> impl Iterator<Item = ClipboardItem> + '_ {
+		self.time_index.iter().rev().skip(skip).filter_map(move |result| {
+			let (key, _) = result
+				.map_err(|e| error!("Erreur lecture index: {}", e))
+				.ok()?;
+			// Une entrée sans élément (arrêt brutal entre deux écritures) est ignorée
+			let value = self.db.get(&key[8..]).ok().flatten()?;
+			Self::value_to_item(&value)
+				.map_err(|e| error!("Erreur désérialisation élément: {}", e))
+				.ok()
+		})
+	}
+
+	/// Clé d'une référence d'un élément vers un blob
+	fn ref_key(blob: &str, id: Uuid) -> Vec<u8> {
+		let mut key = Self::ref_prefix(blob);
+		key.extend_from_slice(id.as_bytes());
+		key
+	}
+
+	/// Préfixe commun aux références vers un blob
+	fn ref_prefix(blob: &str) -> Vec<u8> {
+		let mut prefix = blob.as_bytes().to_vec();
+		prefix.push(b'/');
+		prefix
+	}
+
+	/// Supprime un élément, ses entrées d'index et les blobs qu'il est seul à référencer
+	///
+	/// Retourne `false` si l'élément n'existait pas.
+	fn delete_item(&self, id: Uuid) -> ClipboardResult<bool> {
+		let key = Self::id_to_key(id);
+
+		let removed = self.db
+			.remove(&key)
+			.map_err(|e| ClipboardError::Storage(format!("Erreur suppression élément: {}", e)))?;
+		let Some(value) = removed else {
+			return Ok(false);
+		};
+
+		// Retirer l'entrée d'index si elle désignait cet élément, puis ses blobs
+		if let Ok(item) = Self::value_to_item(&value) {
+			self.hash_index
+				.remove(Self::hash_key(&item.hash(), id))
+				.map_err(|e| ClipboardError::Storage(format!("Erreur mise à jour index: {}", e)))?;
+			self.time_index
+				.remove(Self::time_key(&item))
+				.map_err(|e| ClipboardError::Storage(format!("Erreur mise à jour index: {}", e)))?;
+			self.index_expiry(Some(&item), None)?;
+			self.index_image(Some(&item), None)?;
+			for blob in codec::blob_keys(&item) {
+				self.release_blob(&blob, id)?;
+			}
+		}
+
+		debug!("Élément supprimé: {}", id);
+		Ok(true)
+	}
+
+	/// Diffuse une modification aux abonnés
+	fn notify(&self, upserted: Vec<ClipboardItem>, removed: Vec<Uuid>) {
+		if self.events.receiver_count() == 0 {
+			return;
+		}
+		let total = self.time_index.len();
+		let _ = self.events.send(StorageChange { upserted, removed, total, reload: false });
+	}
+
+	/// Enregistre un élément et ses blobs, puis libère les blobs qu'il ne référence plus
+	///
+	/// Retourne l'élément allégé tel qu'enregistré.
+	fn store_item(&self, item: &ClipboardItem) -> ClipboardResult<ClipboardItem> {
+		let encoded = codec::encode_item(item)?;
+
+		for (blob, data) in encoded.blobs {
+			let exists = self.blobs
+				.contains_key(&blob)
+				.map_err(|e| ClipboardError::Storage(format!("Erreur lecture blob: {}", e)))?;
+			if !exists {
+				self.blobs
+					.insert(&blob, data)
+					.map_err(|e| ClipboardError::Storage(format!("Erreur écriture blob: {}", e)))?;
+			}
+		}
+		for blob in &encoded.references {
+			self.blob_refs
+				.insert(Self::ref_key(blob, item.id), &[])
+				.map_err(|e| ClipboardError::Storage(format!("Erreur écriture référence: {}", e)))?;
+		}
+
+		let previous = self.db
+			.insert(Self::id_to_key(item.id), encoded.record)
+			.map_err(|e| ClipboardError::Storage(format!("Erreur écriture élément: {}", e)))?;
+
+		self.time_index
+			.insert(Self::time_key(item), &[])
+			.map_err(|e| ClipboardError::Storage(format!("Erreur mise à jour index: {}", e)))?;
+
+		// Une version précédente illisible (ancien format) n'est ni indexée ni ne référence de blob
+		let previous = previous.and_then(|value| Self::value_to_item(&value).ok());
+		self.index_expiry(previous.as_ref(), Some(item))?;
+		self.index_image(previous.as_ref(), Some(item))?;
+		self.index_hash(item)?;
+		if let Some(previous) = previous {
+			if previous.hash() != item.hash() {
+				self.hash_index
+					.remove(Self::hash_key(&previous.hash(), item.id))
+					.map_err(|e| ClipboardError::Storage(format!("Erreur mise à jour index: {}", e)))?;
+			}
+			if previous.timestamp != item.timestamp {
+				self.time_index
+					.remove(Self::time_key(&previous))
+					.map_err(|e| ClipboardError::Storage(format!("Erreur mise à jour index: {}", e)))?;
+			}
+			for blob in codec::blob_keys(&previous) {
+				if !encoded.references.contains(&blob) {
+					self.release_blob(&blob, item.id)?;
 				}
 			}
 		}
 
-		// Trier par horodatage (le plus récent en premier)
-		items.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+		Ok(encoded.light)
+	}
+
+	/// Retire la référence d'un élément vers un blob, et le blob s'il n'est plus référencé
+	fn release_blob(&self, blob: &str, id: Uuid) -> ClipboardResult<()> {
+		self.blob_refs
+			.remove(Self::ref_key(blob, id))
+			.map_err(|e| ClipboardError::Storage(format!("Erreur suppression référence: {}", e)))?;
+
+		if self.blob_refs.scan_prefix(Self::ref_prefix(blob)).next().is_none() {
+			self.blobs
+				.remove(blob)
+				.map_err(|e| ClipboardError::Storage(format!("Erreur suppression blob: {}", e)))?;
+			debug!("Blob supprimé: {}", blob);
+		}
+
+		Ok(())
+	}
+
+	/// Préfixe commun aux entrées d'index d'une empreinte
+	fn hash_prefix(hash: &str) -> Vec<u8> {
+		let mut prefix = hash.as_bytes().to_vec();
+		prefix.push(b'/');
+		prefix
+	}
+
+	/// Clé de l'index des empreintes
+	fn hash_key(hash: &str, id: Uuid) -> Vec<u8> {
+		let mut key = Self::hash_prefix(hash);
+		key.extend_from_slice(id.as_bytes());
+		key
+	}
+
+	/// Indexe l'empreinte d'un élément, avec sa clé chronologique
+	fn index_hash(&self, item: &ClipboardItem) -> ClipboardResult<()> {
+		self.hash_index
+			.insert(Self::hash_key(&item.hash(), item.id), Self::time_key(item))
+			.map_err(|e| ClipboardError::Storage(format!("Erreur mise à jour index: {}", e)))?;
+		Ok(())
+	}
+
+	/// Version du format des éléments enregistrée dans la base
+	fn schema_version(&self) -> ClipboardResult<Option<u32>> {
+		let value = self.meta
+			.get(SCHEMA_VERSION_KEY)
+			.map_err(|e| ClipboardError::Storage(format!("Erreur lecture métadonnées: {}", e)))?;
+
+		value
+			.map(|value| {
+				<[u8; 4]>::try_from(value.as_ref())
+					.map(u32::from_be_bytes)
+					.map_err(|_| ClipboardError::Storage("Version du format invalide".to_string()))
+			})
+			.transpose()
+	}
+
+	/// Enregistre la version du format des éléments
+	fn set_schema_version(&self, version: u32) -> ClipboardResult<()> {
+		self.meta
+			.insert(SCHEMA_VERSION_KEY, &version.to_be_bytes())
+			.map_err(|e| ClipboardError::Storage(format!("Erreur écriture métadonnées: {}", e)))?;
+		Ok(())
+	}
+
+	/// Charge les blobs d'un élément allégé
+	fn hydrate(&self, item: &mut ClipboardItem) -> ClipboardResult<()> {
+		codec::hydrate(item, |blob| {
+			self.blobs
+				.get(blob)
+				.map(|data| data.map(|data| data.to_vec()))
+				.map_err(|e| ClipboardError::Storage(format!("Erreur lecture blob: {}", e)))
+		})
+	}
+
+	/// Lit tous les éléments complets, voir `SledStorage::export_items`
+	fn export_items(&self) -> ClipboardResult<Vec<ClipboardItem>> {
+		let from = match self.schema_version()? {
+			Some(version) => version,
+			None if self.db.is_empty() => return Ok(Vec::new()),
+			None => 0,
+		};
 
+		let mut items = Vec::new();
+		for result in self.db.iter() {
+			let (key, value) = result
+				.map_err(|e| ClipboardError::Storage(format!("Erreur lecture base de données: {}", e)))?;
+			match schema::upgrade_record(&value, from) {
+				Ok(mut item) => {
+					self.hydrate(&mut item)?;
+					items.push(item);
+				}
+				Err(e) => warn!("Élément {:?} illisible: {}", Uuid::from_slice(&key).ok(), e),
+			}
+		}
 		Ok(items)
 	}
 
+	/// Met à niveau tous les éléments écrits dans la version `from`
+	///
+	/// Un élément impossible à migrer est conservé tel quel et signalé.
+	fn migrate(&self, from: u32) -> ClipboardResult<()> {
+		info!("Migration de l'historique du format {} vers le format {}", from, SCHEMA_VERSION);
+
+		if from < schema::LAST_RECORD_VERSION {
+			let mut migrated = 0;
+			for result in self.db.iter() {
+				let (key, value) = result
+					.map_err(|e| ClipboardError::Storage(format!("Erreur lecture base de données: {}", e)))?;
+
+				match schema::upgrade_record(&value, from) {
+					Ok(item) => {
+						self.store_item(&item)?;
+						migrated += 1;
+					}
+					Err(e) => warn!("Élément {:?} non migré: {}", Uuid::from_slice(&key).ok(), e),
+				}
+			}
+			info!("{} éléments migrés", migrated);
+		}
+
+		if from < schema::SLED_HASH_IDS_VERSION {
+			// L'ancien index ne désignait que l'élément le plus récent de chaque empreinte
+			self.db
+				.drop_tree("hash_index")
+				.map_err(|e| ClipboardError::Storage(format!("Erreur suppression ancien index: {}", e)))?;
+		}
+
+		// Les empreintes ont pu être complétées : repartir d'un index vide, reconstruit à l'initialisation
+		self.hash_index
+			.clear()
+			.map_err(|e| ClipboardError::Storage(format!("Erreur réinitialisation index: {}", e)))?;
+		self.set_schema_version(SCHEMA_VERSION)?;
+
+		Ok(())
+	}
+
+	/// Reconstruit l'index des empreintes, en complétant les éléments enregistrés sans elle
+	fn rebuild_hash_index(&self) -> ClipboardResult<()> {
+		for mut item in self.get_all_items()? {
+			if item.content_hash.is_empty() {
+				item.content_hash = item.content.content_hash();
+				self.store_item(&item)?;
+			} else {
+				self.index_hash(&item)?;
+			}
+		}
+
+		info!("Index des empreintes reconstruit ({} entrées)", self.hash_index.len());
+		Ok(())
+	}
+}
+
+/// Opérations de `Storage`, appelées par `SledStorage` sur la base ouverte
+impl SledBase {
+	fn init(&self) -> ClipboardResult<()> {
+		debug!("Initialisation du stockage Sled");
+
+		match self.schema_version()? {
+			Some(version) => {
+				schema::check_version(version)?;
+				if version < SCHEMA_VERSION {
+					self.migrate(version)?;
+				}
+			}
+			// Base vide : rien à migrer
+			None if self.db.is_empty() => self.set_schema_version(SCHEMA_VERSION)?,
+			// Base antérieure au suivi des versions
+			None => self.migrate(0)?,
+		}
+
+		if self.time_index.is_empty() && !self.db.is_empty() {
+			self.rebuild_time_index()?;
+		}
+		if self.hash_index.is_empty() && !self.db.is_empty() {
+			self.rebuild_hash_index()?;
+		}
+		// Un index vide est légitime : sa construction est marquée dans les métadonnées
+		let expiry_indexed = self.meta
+			.contains_key(EXPIRY_INDEX_KEY)
+			.map_err(|e| ClipboardError::Storage(format!("Erreur lecture métadonnées: {}", e)))?;
+		if !expiry_indexed {
+			self.rebuild_expiry_index()?;
+		}
+		let images_indexed = self.meta
+			.contains_key(IMAGE_INDEX_KEY)
+			.map_err(|e| ClipboardError::Storage(format!("Erreur lecture métadonnées: {}", e)))?;
+		if !images_indexed {
+			self.rebuild_image_index()?;
+		}
+
+		Ok(())
+	}
+
+	fn get_all_items(&self) -> ClipboardResult<Vec<ClipboardItem>> {
+		Ok(self.iter_recent(0).collect())
+	}
+
+	fn get_items(&self, offset: usize, limit: usize) -> ClipboardResult<ItemPage> {
+		let items = self.iter_recent(offset).take(limit).collect();
+		let total = self.count_items()?;
+
+		Ok(ItemPage { items, offset, total })
+	}
+
+	fn search(&self, query: &str, offset: usize, limit: usize) -> ClipboardResult<ItemPage> {
+		// Sled n'indexe pas le texte : les éléments allégés sont parcourus sans charger leurs blobs
+		let mut items = Vec::new();
+		let mut total = 0;
+		for item in self.iter_recent(0).filter(|item| item.matches_search(query)) {
+			if total >= offset && items.len() < limit {
+				items.push(item);
+			}
+			total += 1;
+		}
+
+		Ok(ItemPage { items, offset, total })
+	}
+
+	fn count_items(&self) -> ClipboardResult<usize> {
+		Ok(self.time_index.len())
+	}
+
 	fn get_item(&self, id: Uuid) -> ClipboardResult<Option<ClipboardItem>> {
 		let key = Self::id_to_key(id);
 
 		match self.db.get(key) {
 			Ok(Some(value)) => {
-				let item = Self::value_to_item(&value)?;
+				let mut item = Self::value_to_item(&value)?;
+				self.hydrate(&mut item)?;
 				Ok(Some(item))
 			}
 			Ok(None) => Ok(None),
@@ -85,56 +641,143 @@ impl Storage for SledStorage {
 		}
 	}
 
-	fn add_item(&self, item: ClipboardItem) -> ClipboardResult<()> {
-		let key = Self::id_to_key(item.id);
-		let value = Self::item_to_value(&item)?;
+	fn find_by_hash(&self, hash: &str) -> ClipboardResult<Option<ClipboardItem>> {
+		let prefix = Self::hash_prefix(hash);
+		let mut entries = self.hash_index
+			.scan_prefix(&prefix)
+			.map(|result| result.map_err(|e| ClipboardError::Storage(format!("Erreur lecture index: {}", e))))
+			.collect::<ClipboardResult<Vec<_>>>()?;
 
-		self.db
-			.insert(key, value)
-			.map_err(|e| ClipboardError::Storage(format!("Erreur ajout élément: {}", e)))?;
+		// Le plus récent des éléments ayant cette empreinte
+		entries.sort_by(|(_, time), (_, other)| other.cmp(time));
+		for (key, _) in entries {
+			match self.db.get(&key[prefix.len()..]) {
+				Ok(Some(value)) => return Self::value_to_item(&value).map(Some),
+				Ok(None) => {
+					// Entrée orpheline : l'élément a disparu sans mise à jour de l'index
+					let _ = self.hash_index.remove(&key);
+				}
+				Err(e) => return Err(ClipboardError::Storage(format!("Erreur lecture élément: {}", e))),
+			}
+		}
+		Ok(None)
+	}
+
+	fn add_item(&self, item: ClipboardItem) -> ClipboardResult<ClipboardItem> {
+		let stored = self.store_item(&item)?;
 
 		debug!("Élément ajouté: {}", item.id);
-		Ok(())
+		self.notify(vec![stored.clone()], Vec::new());
+		Ok(stored)
 	}
 
-	fn update_item(&self, item: ClipboardItem) -> ClipboardResult<()> {
-		let key = Self::id_to_key(item.id);
-		let value = Self::item_to_value(&item)?;
-
-		self.db
-			.insert(key, value)
-			.map_err(|e| ClipboardError::Storage(format!("Erreur mise à jour élément: {}", e)))?;
+	fn update_item(&self, item: ClipboardItem) -> ClipboardResult<ClipboardItem> {
+		let stored = self.store_item(&item)?;
 
 		debug!("Élément mis à jour: {}", item.id);
-		Ok(())
+		self.notify(vec![stored.clone()], Vec::new());
+		Ok(stored)
 	}
 
 	fn remove_item(&self, id: Uuid) -> ClipboardResult<()> {
-		let key = Self::id_to_key(id);
-
-		self.db
-			.remove(key)
-			.map_err(|e| ClipboardError::Storage(format!("Erreur suppression élément: {}", e)))?;
-
-		debug!("Élément supprimé: {}", id);
+		if self.delete_item(id)? {
+			self.notify(Vec::new(), vec![id]);
+		}
 		Ok(())
 	}
 
-	fn clear_non_pinned(&self) -> ClipboardResult<()> {
-		// Récupérer tous les éléments
-		let items = self.get_all_items()?;
-		let mut removed = 0;
+	fn clear_non_pinned(&self) -> ClipboardResult<Vec<Uuid>> {
+		let mut removed = Vec::new();
 
 		// Supprimer tous les éléments non épinglés
-		for item in items {
-			if !item.pinned {
-				self.remove_item(item.id)?;
-				removed += 1;
+		for item in self.get_all_items()? {
+			if !item.pinned && self.delete_item(item.id)? {
+				removed.push(item.id);
 			}
 		}
 
-		info!("{} éléments non épinglés supprimés", removed);
-		Ok(())
+		info!("{} éléments non épinglés supprimés", removed.len());
+		if !removed.is_empty() {
+			self.notify(Vec::new(), removed.clone());
+		}
+		Ok(removed)
+	}
+
+	fn next_expiry(&self) -> ClipboardResult<Option<DateTime<Utc>>> {
+		for result in self.expiry_index.iter() {
+			let (key, _) = result
+				.map_err(|e| ClipboardError::Storage(format!("Erreur lecture index: {}", e)))?;
+			let present = self.db
+				.contains_key(&key[8..])
+				.map_err(|e| ClipboardError::Storage(format!("Erreur lecture élément: {}", e)))?;
+			if present {
+				return Ok(Self::key_value(&key).map(DateTime::from_timestamp_nanos));
+			}
+			// Entrée orpheline (arrêt brutal entre deux écritures)
+			let _ = self.expiry_index.remove(&key);
+		}
+		Ok(None)
+	}
+
+	fn lapsed_items(&self, now: DateTime<Utc>) -> ClipboardResult<Vec<ClipboardItem>> {
+		let now = now.timestamp_nanos_opt().unwrap_or(i64::MAX);
+		let mut items = Vec::new();
+		for result in self.expiry_index.iter() {
+			let (key, _) = result
+				.map_err(|e| ClipboardError::Storage(format!("Erreur lecture index: {}", e)))?;
+			if Self::key_value(&key).is_none_or(|expiry| expiry > now) {
+				break;
+			}
+			match self.db.get(&key[8..]) {
+				Ok(Some(value)) => match Self::value_to_item(&value) {
+					Ok(item) => items.push(item),
+					Err(e) => error!("Erreur désérialisation élément: {}", e),
+				},
+				// Entrée orpheline (arrêt brutal entre deux écritures)
+				Ok(None) => {
+					let _ = self.expiry_index.remove(&key);
+				}
+				Err(e) => return Err(ClipboardError::Storage(format!("Erreur lecture élément: {}", e))),
+			}
+		}
+		Ok(items)
+	}
+
+	fn oldest_unpinned(&self, offset: usize, limit: usize, images_only: bool) -> ClipboardResult<Vec<ClipboardItem>> {
+		let index = if images_only { &self.image_index } else { &self.time_index };
+		let mut items = Vec::new();
+		let mut skipped = 0;
+		for result in index.iter() {
+			if items.len() >= limit {
+				break;
+			}
+			let (key, _) = result
+				.map_err(|e| ClipboardError::Storage(format!("Erreur lecture index: {}", e)))?;
+			let value = match self.db.get(&key[8..]) {
+				Ok(Some(value)) => value,
+				// Entrée orpheline : l'élément a disparu sans mise à jour de l'index
+				Ok(None) => continue,
+				Err(e) => return Err(ClipboardError::Storage(format!("Erreur lecture élément: {}", e))),
+			};
+			match Self::value_to_item(&value) {
+				Ok(item) if item.pinned => {}
+				Ok(_) if skipped < offset => skipped += 1,
+				Ok(item) => items.push(item),
+				Err(e) => error!("Erreur désérialisation élément: {}", e),
+			}
+		}
+		Ok(items)
+	}
+
+	fn image_bytes(&self) -> ClipboardResult<u64> {
+		let mut total: u64 = 0;
+		for result in self.image_index.iter() {
+			let (_, value) = result
+				.map_err(|e| ClipboardError::Storage(format!("Erreur lecture index: {}", e)))?;
+			let size = <[u8; 8]>::try_from(value.as_ref()).map(u64::from_be_bytes).unwrap_or(0);
+			total = total.saturating_add(size);
+		}
+		Ok(total)
 	}
 
 	fn flush(&self) -> ClipboardResult<()> {
@@ -145,4 +788,227 @@ impl Storage for SledStorage {
 		debug!("Données synchronisées sur le disque");
 		Ok(())
 	}
+
+	/// Copie tous les arbres de la base dans une nouvelle base, ouverte dans `target`
+	///
+	/// Seules les valeurs actuelles sont écrites : rien des versions supprimées ou remplacées.
+	fn copy_to(&self, target: &Path) -> ClipboardResult<()> {
+		let copy = Config::new()
+			.path(target)
+			.create_new(true)
+			.open()
+			.map_err(|e| ClipboardError::Storage(format!("Erreur création copie Sled: {}", e)))?;
+		for name in self.db.tree_names() {
+			let source = self.db
+				.open_tree(&name)
+				.map_err(|e| ClipboardError::Storage(format!("Erreur ouverture index: {}", e)))?;
+			let destination = copy
+				.open_tree(&name)
+				.map_err(|e| ClipboardError::Storage(format!("Erreur création copie Sled: {}", e)))?;
+			for result in source.iter() {
+				let (key, value) = result
+					.map_err(|e| ClipboardError::Storage(format!("Erreur lecture base de données: {}", e)))?;
+				destination
+					.insert(key, value)
+					.map_err(|e| ClipboardError::Storage(format!("Erreur écriture copie Sled: {}", e)))?;
+			}
+		}
+		copy.flush()
+			.map_err(|e| ClipboardError::Storage(format!("Erreur écriture copie Sled: {}", e)))?;
+		Ok(())
+	}
+}
+
+impl SledStorage {
+	/// Crée une nouvelle instance de stockage Sled
+	///
+	/// Un remplacement de la base interrompu par `compact` est d'abord achevé ou annulé.
+	pub fn new<P: AsRef<Path>>(data_dir: P) -> ClipboardResult<Self> {
+		let data_dir = data_dir.as_ref().to_path_buf();
+		Self::recover(&data_dir)?;
+		let events = StorageChange::channel();
+		let base = SledBase::open(&data_dir, events.clone())?;
+
+		Ok(Self { data_dir, events, base: RwLock::new(Some(base)) })
+	}
+
+	/// Lit tous les éléments complets sans initialiser la base
+	///
+	/// Rien n'est écrit : les éléments d'un format antérieur sont mis à niveau
+	/// en mémoire seulement. Un élément illisible est signalé et ignoré.
+	pub fn export_items(&self) -> ClipboardResult<Vec<ClipboardItem>> {
+		self.with_base(SledBase::export_items)
+	}
+
+	/// Exécute `operation` sur la base ouverte
+	fn with_base<T>(&self, operation: impl FnOnce(&SledBase) -> ClipboardResult<T>) -> ClipboardResult<T> {
+		let base = self.base
+			.read()
+			.map_err(|_| ClipboardError::Storage("Verrou de la base Sled empoisonné".to_string()))?;
+		operation(base.as_ref().ok_or_else(closed)?)
+	}
+
+	/// Fichiers et dossiers de la base Sled rangés dans `dir`, `conf` en dernier
+	///
+	/// Le déplacement de `conf` termine ainsi celui d'une base entière.
+	fn sled_entries(dir: &Path) -> ClipboardResult<Vec<PathBuf>> {
+		let mut entries = Vec::new();
+		for entry in std::fs::read_dir(dir)? {
+			let path = entry?.path();
+			let name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+			if matches!(name, "conf" | "db" | "blobs") || name.starts_with("snap.") {
+				entries.push(path);
+			}
+		}
+		entries.sort_by_key(|path| path.ends_with("conf"));
+		Ok(entries)
+	}
+
+	/// Déplace les fichiers de la base Sled de `from` vers `to`
+	fn move_entries(from: &Path, to: &Path) -> ClipboardResult<()> {
+		for entry in Self::sled_entries(from)? {
+			if let Some(name) = entry.file_name() {
+				std::fs::rename(&entry, to.join(name))?;
+			}
+		}
+		Ok(())
+	}
+
+	/// Remplace la base de `data_dir` par la copie complète rangée dans `STAGING_DIR`
+	///
+	/// L'ancienne base est d'abord écartée dans `RETIRED_DIR` : la présence de son
+	/// `conf` indique qu'elle l'a été entièrement.
+	fn swap(data_dir: &Path) -> ClipboardResult<()> {
+		let staging = data_dir.join(STAGING_DIR);
+		let retired = data_dir.join(RETIRED_DIR);
+		std::fs::create_dir_all(&retired)?;
+		if !retired.join("conf").exists() {
+			Self::move_entries(data_dir, &retired)?;
+		}
+		Self::move_entries(&staging, data_dir)?;
+		std::fs::remove_dir_all(&staging)?;
+		std::fs::remove_dir_all(&retired)?;
+		Ok(())
+	}
+
+	/// Achève ou annule un remplacement de la base interrompu
+	fn recover(data_dir: &Path) -> ClipboardResult<()> {
+		let staging = data_dir.join(STAGING_DIR);
+		let retired = data_dir.join(RETIRED_DIR);
+		match (staging.exists(), retired.exists()) {
+			// La copie n'était pas terminée : la base n'a pas été touchée
+			(true, false) => std::fs::remove_dir_all(&staging)?,
+			// La copie était complète : terminer le remplacement
+			(true, true) if retired.join("conf").exists() => Self::swap(data_dir)?,
+			// L'ancienne base était en train d'être écartée : la remettre en place
+			(true, true) => {
+				Self::move_entries(&retired, data_dir)?;
+				std::fs::remove_dir_all(&retired)?;
+				std::fs::remove_dir_all(&staging)?;
+			}
+			(false, true) => std::fs::remove_dir_all(&retired)?,
+			(false, false) => return Ok(()),
+		}
+		warn!("Remplacement interrompu de la base Sled repris");
+		Ok(())
+	}
+}
+
+/// Erreur d'une base fermée après l'échec de sa réouverture
+fn closed() -> ClipboardError {
+	ClipboardError::Storage("Base Sled fermée".to_string())
+}
+
+impl Storage for SledStorage {
+	fn init(&self) -> ClipboardResult<()> {
+		self.with_base(SledBase::init)
+	}
+
+	fn get_all_items(&self) -> ClipboardResult<Vec<ClipboardItem>> {
+		self.with_base(SledBase::get_all_items)
+	}
+
+	fn get_items(&self, offset: usize, limit: usize) -> ClipboardResult<ItemPage> {
+		self.with_base(|base| base.get_items(offset, limit))
+	}
+
+	fn search(&self, query: &str, offset: usize, limit: usize) -> ClipboardResult<ItemPage> {
+		self.with_base(|base| base.search(query, offset, limit))
+	}
+
+	fn count_items(&self) -> ClipboardResult<usize> {
+		self.with_base(SledBase::count_items)
+	}
+
+	fn get_item(&self, id: Uuid) -> ClipboardResult<Option<ClipboardItem>> {
+		self.with_base(|base| base.get_item(id))
+	}
+
+	fn find_by_hash(&self, hash: &str) -> ClipboardResult<Option<ClipboardItem>> {
+		self.with_base(|base| base.find_by_hash(hash))
+	}
+
+	fn add_item(&self, item: ClipboardItem) -> ClipboardResult<ClipboardItem> {
+		self.with_base(|base| base.add_item(item))
+	}
+
+	fn update_item(&self, item: ClipboardItem) -> ClipboardResult<ClipboardItem> {
+		self.with_base(|base| base.update_item(item))
+	}
+
+	fn remove_item(&self, id: Uuid) -> ClipboardResult<()> {
+		self.with_base(|base| base.remove_item(id))
+	}
+
+	fn clear_non_pinned(&self) -> ClipboardResult<Vec<Uuid>> {
+		self.with_base(SledBase::clear_non_pinned)
+	}
+
+	fn next_expiry(&self) -> ClipboardResult<Option<DateTime<Utc>>> {
+		self.with_base(SledBase::next_expiry)
+	}
+
+	fn lapsed_items(&self, now: DateTime<Utc>) -> ClipboardResult<Vec<ClipboardItem>> {
+		self.with_base(|base| base.lapsed_items(now))
+	}
+
+	fn oldest_unpinned(&self, offset: usize, limit: usize, images_only: bool) -> ClipboardResult<Vec<ClipboardItem>> {
+		self.with_base(|base| base.oldest_unpinned(offset, limit, images_only))
+	}
+
+	fn image_bytes(&self) -> ClipboardResult<u64> {
+		self.with_base(SledBase::image_bytes)
+	}
+
+	fn subscribe(&self) -> StorageEventReceiver {
+		self.events.subscribe()
+	}
+
+	fn flush(&self) -> ClipboardResult<()> {
+		self.with_base(SledBase::flush)
+	}
+
+	fn compact(&self) -> ClipboardResult<()> {
+		let mut base = self.base
+			.write()
+			.map_err(|_| ClipboardError::Storage("Verrou de la base Sled empoisonné".to_string()))?;
+		let current = base.take().ok_or_else(closed)?;
+
+		let staging = self.data_dir.join(STAGING_DIR);
+		let copied = current.flush().and_then(|_| current.copy_to(&staging));
+		// La base doit être fermée pour être remplacée ; la copie relâche aussi son verrou
+		drop(current);
+		let swapped = match copied {
+			Ok(()) => Self::swap(&self.data_dir),
+			Err(e) => {
+				let _ = std::fs::remove_dir_all(&staging);
+				Err(e)
+			}
+		};
+
+		*base = Some(SledBase::open(&self.data_dir, self.events.clone())?);
+		swapped?;
+		info!("Base Sled réécrite sans ses anciennes versions");
+		Ok(())
+	}
 }
\ No newline at end of file
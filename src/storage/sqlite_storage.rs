@@ -0,0 +1,664 @@
+use crate::clipboard::ClipboardItem;
+use crate::error::{ClipboardError, ClipboardResult};
+use crate::storage::codec;
+use crate::storage::retention::image_size;
+use crate::storage::schema::{self, SCHEMA_VERSION, SCHEMA_VERSION_KEY};
+use crate::storage::{ItemPage, Storage, StorageChange, StorageEventReceiver};
+use chrono::{DateTime, Utc};
+use log::{debug, error, info, warn};
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{params, Connection, ErrorCode, OptionalExtension, Transaction};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+/// Nom du fichier de base de données dans le dossier de données
+pub const SQLITE_FILE_NAME: &str = "history.sqlite3";
+
+/// Schéma de la base : les éléments encodés, accompagnés des colonnes indexées,
+/// et les blobs qu'ils référencent
+///
+/// L'horodatage est en microsecondes, l'expiration en nanosecondes : le démon
+/// ne se réveille pas avant qu'elle soit atteinte. `image_bytes` est le volume
+/// décompressé d'une image, nul pour les autres contenus. `search_text` est
+/// le texte recherché, en minuscules, absent pour une image.
+const SCHEMA: &str = "
+	CREATE TABLE IF NOT EXISTS items (
+		id BLOB PRIMARY KEY NOT NULL,
+		timestamp INTEGER NOT NULL,
+		pinned INTEGER NOT NULL DEFAULT 0,
+		content_hash TEXT NOT NULL,
+		expires_at INTEGER,
+		image_bytes INTEGER NOT NULL DEFAULT 0,
+		search_text TEXT,
+		data BLOB NOT NULL
+	);
+	CREATE INDEX IF NOT EXISTS items_timestamp ON items (timestamp DESC);
+	CREATE INDEX IF NOT EXISTS items_pinned ON items (pinned, timestamp);
+	CREATE INDEX IF NOT EXISTS items_content_hash ON items (content_hash, timestamp);
+	CREATE TABLE IF NOT EXISTS meta (
+		key TEXT PRIMARY KEY NOT NULL,
+		value TEXT NOT NULL
+	);
+	CREATE TABLE IF NOT EXISTS blobs (
+		key TEXT PRIMARY KEY NOT NULL,
+		data BLOB NOT NULL
+	);
+	CREATE TABLE IF NOT EXISTS item_blobs (
+		item_id BLOB NOT NULL,
+		blob_key TEXT NOT NULL,
+		PRIMARY KEY (item_id, blob_key)
+	);
+	CREATE INDEX IF NOT EXISTS item_blobs_key ON item_blobs (blob_key);
+";
+
+/// Index des expirations, créé une fois la colonne ajoutée aux bases qui en étaient dépourvues
+const EXPIRY_INDEX: &str = "CREATE INDEX IF NOT EXISTS items_expiry ON items (pinned, expires_at)";
+
+/// Index des seules images, par ancienneté, qui couvre aussi le calcul de leur volume
+const IMAGE_INDEX: &str =
+	"CREATE INDEX IF NOT EXISTS items_images ON items (pinned, timestamp, image_bytes) WHERE image_bytes > 0";
+
+/// Intervalle de détection des modifications faites par d'autres processus
+const EXTERNAL_CHANGES_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Implémentation du stockage utilisant SQLite comme backend
+pub struct SqliteStorage {
+	conn: Arc<Mutex<Connection>>,
+	/// Diffusion des modifications
+	events: broadcast::Sender<StorageChange>,
+	/// La détection des modifications d'autres processus est lancée
+	watching: AtomicBool,
+}
+
+impl SqliteStorage {
+	/// Ouvre (ou crée) la base SQLite du dossier de données
+	pub fn new<P: AsRef<Path>>(data_dir: P) -> ClipboardResult<Self> {
+		Self::open(data_dir.as_ref().join(SQLITE_FILE_NAME))
+	}
+
+	/// Ouvre (ou crée) une base SQLite à l'emplacement indiqué
+	pub fn open<P: AsRef<Path>>(path: P) -> ClipboardResult<Self> {
+		let conn = Connection::open(path).map_err(|e| Self::open_error("Erreur ouverture SQLite", e))?;
+
+		// Journal WAL : lectures concurrentes et écritures moins coûteuses.
+		// Le contenu supprimé est effacé du fichier plutôt que laissé dans les pages libres.
+		conn.pragma_update(None, "journal_mode", "WAL")
+			.and_then(|_| conn.pragma_update(None, "synchronous", "NORMAL"))
+			.and_then(|_| conn.pragma_update(None, "secure_delete", "ON"))
+			.map_err(|e| Self::open_error("Erreur configuration SQLite", e))?;
+
+		Ok(Self::with_connection(conn))
+	}
+
+	/// Crée une base SQLite en mémoire, perdue à l'abandon du stockage
+	///
+	/// Sert d'historique de secours quand la base du dossier de données ne peut être ouverte.
+	pub fn in_memory() -> ClipboardResult<Self> {
+		let conn = Connection::open_in_memory()
+			.map_err(|e| ClipboardError::Storage(format!("Erreur création base en mémoire: {}", e)))?;
+		Ok(Self::with_connection(conn))
+	}
+
+	/// Stockage sur une connexion ouverte
+	fn with_connection(conn: Connection) -> Self {
+		Self {
+			conn: Arc::new(Mutex::new(conn)),
+			events: StorageChange::channel(),
+			watching: AtomicBool::new(false),
+		}
+	}
+
+	/// Erreur d'ouverture, qui distingue une base verrouillée par un autre processus
+	fn open_error(context: &str, e: rusqlite::Error) -> ClipboardError {
+		match e.sqlite_error_code() {
+			Some(ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked) => {
+				ClipboardError::StorageLocked(format!("{}: {}", context, e))
+			}
+			_ => ClipboardError::Storage(format!("{}: {}", context, e)),
+		}
+	}
+
+	/// Accès exclusif à la connexion
+	fn conn(&self) -> ClipboardResult<MutexGuard<'_, Connection>> {
+		self.conn
+			.lock()
+			.map_err(|_| ClipboardError::Storage("Connexion SQLite empoisonnée".to_string()))
+	}
+
+	/// Convertit une valeur SQLite en élément allégé
+	fn value_to_item(value: &[u8]) -> ClipboardResult<ClipboardItem> {
+		codec::decode_record(value)
+	}
+
+	/// Diffuse une modification aux abonnés
+	fn notify(&self, conn: &Connection, upserted: Vec<ClipboardItem>, removed: Vec<Uuid>) {
+		if self.events.receiver_count() == 0 {
+			return;
+		}
+		match Self::count(conn) {
+			Ok(total) => {
+				let _ = self.events.send(StorageChange { upserted, removed, total, reload: false });
+			}
+			Err(e) => error!("Modification non diffusée: {}", e),
+		}
+	}
+
+	/// Surveille dans un thread les écritures d'autres processus dans la base
+	///
+	/// SQLite incrémente `data_version` à chaque écriture validée par une autre
+	/// connexion ; le thread s'arrête avec le stockage.
+	fn watch_external_changes(&self) {
+		fn data_version(conn: &Mutex<Connection>) -> Option<i64> {
+			let conn = conn.lock().ok()?;
+			conn.query_row("PRAGMA data_version", [], |row| row.get(0)).ok()
+		}
+
+		let conn: Weak<Mutex<Connection>> = Arc::downgrade(&self.conn);
+		let events = self.events.clone();
+		let mut last_version = data_version(&self.conn);
+
+		let spawned = std::thread::Builder::new()
+			.name("sqlite-changes".to_string())
+			.spawn(move || {
+				while let Some(conn) = conn.upgrade() {
+					let version = data_version(&conn);
+					if version != last_version {
+						last_version = version;
+						let total = conn
+							.lock()
+							.ok()
+							.and_then(|conn| Self::count(&conn).ok())
+							.unwrap_or_default();
+						debug!("Base modifiée par un autre processus");
+						let _ = events.send(StorageChange { total, reload: true, ..StorageChange::default() });
+					}
+					drop(conn);
+					std::thread::sleep(EXTERNAL_CHANGES_INTERVAL);
+				}
+			});
+
+		if let Err(e) = spawned {
+			warn!("Détection des modifications externes indisponible: {}", e);
+		}
+	}
+
+	/// Insère ou remplace un élément, ainsi que ses blobs et ses références
+	///
+	/// Retourne l'élément allégé tel qu'enregistré. Les blobs qui ne sont plus
+	/// référencés restent en place jusqu'au prochain `collect_blobs`. Les
+	/// écritures sont multiples : à appeler dans une transaction.
+	fn upsert(conn: &Connection, item: &ClipboardItem) -> ClipboardResult<ClipboardItem> {
+		let encoded = codec::encode_item(item)?;
+		let id = item.id.as_bytes().as_slice();
+
+		for (blob, data) in &encoded.blobs {
+			conn.execute("INSERT OR IGNORE INTO blobs (key, data) VALUES (?1, ?2)", params![blob, data])
+				.map_err(|e| ClipboardError::Storage(format!("Erreur écriture blob: {}", e)))?;
+		}
+		conn.execute("DELETE FROM item_blobs WHERE item_id = ?1", [id])
+			.map_err(|e| ClipboardError::Storage(format!("Erreur écriture référence: {}", e)))?;
+		for blob in &encoded.references {
+			conn.execute("INSERT INTO item_blobs (item_id, blob_key) VALUES (?1, ?2)", params![id, blob])
+				.map_err(|e| ClipboardError::Storage(format!("Erreur écriture référence: {}", e)))?;
+		}
+
+		conn.execute(
+			"INSERT OR REPLACE INTO items (id, timestamp, pinned, content_hash, expires_at, image_bytes, search_text, data)
+			 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+			params![
+				item.id.as_bytes().as_slice(),
+				item.timestamp.timestamp_micros(),
+				item.pinned,
+				item.hash(),
+				expiry_nanos(item),
+				image_bytes(item),
+				item.search_text(),
+				encoded.record,
+			],
+		)
+		.map_err(|e| ClipboardError::Storage(format!("Erreur écriture élément: {}", e)))?;
+		Ok(encoded.light)
+	}
+
+	/// Exécute `write` dans une transaction, validée seulement s'il réussit
+	fn in_transaction<T>(
+		conn: &mut Connection,
+		write: impl FnOnce(&Transaction) -> ClipboardResult<T>,
+	) -> ClipboardResult<T> {
+		let tx = conn
+			.transaction()
+			.map_err(|e| ClipboardError::Storage(format!("Erreur ouverture transaction: {}", e)))?;
+		let result = write(&tx)?;
+		tx.commit()
+			.map_err(|e| ClipboardError::Storage(format!("Erreur validation transaction: {}", e)))?;
+		Ok(result)
+	}
+
+	/// Identifiant et enregistrement de tous les éléments
+	fn records(conn: &Connection) -> ClipboardResult<Vec<(Vec<u8>, Vec<u8>)>> {
+		let mut statement = conn
+			.prepare("SELECT id, data FROM items")
+			.map_err(|e| ClipboardError::Storage(format!("Erreur préparation requête: {}", e)))?;
+		let rows = statement
+			.query_map([], |row| Ok((row.get::<_, Vec<u8>>(0)?, row.get::<_, Vec<u8>>(1)?)))
+			.map_err(|e| ClipboardError::Storage(format!("Erreur lecture base de données: {}", e)))?;
+		rows.collect::<Result<Vec<_>, _>>()
+			.map_err(|e| ClipboardError::Storage(format!("Erreur lecture base de données: {}", e)))
+	}
+
+	/// Éléments (allégés) retournés par une requête sur la seule colonne `data`
+	///
+	/// Un élément illisible est signalé et ignoré.
+	fn query_items(conn: &Connection, sql: &str, params: impl rusqlite::Params) -> ClipboardResult<Vec<ClipboardItem>> {
+		let mut statement = conn
+			.prepare(sql)
+			.map_err(|e| ClipboardError::Storage(format!("Erreur préparation requête: {}", e)))?;
+		let rows = statement
+			.query_map(params, |row| row.get::<_, Vec<u8>>(0))
+			.map_err(|e| ClipboardError::Storage(format!("Erreur lecture base de données: {}", e)))?;
+
+		let mut items = Vec::new();
+		for row in rows {
+			match row {
+				Ok(value) => match Self::value_to_item(&value) {
+					Ok(item) => items.push(item),
+					Err(e) => error!("Erreur désérialisation élément: {}", e),
+				},
+				Err(e) => error!("Erreur lecture base de données: {}", e),
+			}
+		}
+		Ok(items)
+	}
+
+	/// Nombre d'éléments enregistrés
+	fn count(conn: &Connection) -> ClipboardResult<usize> {
+		conn.query_row("SELECT COUNT(*) FROM items", [], |row| row.get::<_, i64>(0))
+			.map(|count| count as usize)
+			.map_err(|e| ClipboardError::Storage(format!("Erreur lecture base de données: {}", e)))
+	}
+
+	/// Supprime les références des éléments disparus et les blobs qui ne sont plus référencés
+	fn collect_blobs(conn: &Connection) -> ClipboardResult<()> {
+		conn.execute_batch(
+			"DELETE FROM item_blobs WHERE item_id NOT IN (SELECT id FROM items);
+			 DELETE FROM blobs WHERE key NOT IN (SELECT blob_key FROM item_blobs);",
+		)
+		.map_err(|e| ClipboardError::Storage(format!("Erreur suppression blobs: {}", e)))
+	}
+
+	/// Lit une valeur de la table des métadonnées
+	pub fn meta(&self, key: &str) -> ClipboardResult<Option<String>> {
+		self.conn()?
+			.query_row("SELECT value FROM meta WHERE key = ?1", [key], |row| row.get(0))
+			.optional()
+			.map_err(|e| ClipboardError::Storage(format!("Erreur lecture métadonnée: {}", e)))
+	}
+
+	/// Enregistre une valeur dans la table des métadonnées
+	fn set_meta(conn: &Connection, key: &str, value: &str) -> ClipboardResult<()> {
+		conn.execute(
+			"INSERT OR REPLACE INTO meta (key, value) VALUES (?1, ?2)",
+			params![key, value],
+		)
+		.map_err(|e| ClipboardError::Storage(format!("Erreur écriture métadonnée: {}", e)))?;
+		Ok(())
+	}
+
+	/// Version du format des éléments enregistrée dans la base
+	fn schema_version(&self) -> ClipboardResult<Option<u32>> {
+		self.meta(SCHEMA_VERSION_KEY)?
+			.map(|value| {
+				value
+					.parse()
+					.map_err(|_| ClipboardError::Storage(format!("Version du format invalide: {}", value)))
+			})
+			.transpose()
+	}
+
+	/// Ajoute une colonne aux bases créées sans elle et la remplit avec la valeur
+	/// que `compute` tire de chaque élément
+	///
+	/// `name` désigne ce que la colonne indexe, pour le journal. Les éléments
+	/// d'un format antérieur sont laissés à la migration, qui les réécrit.
+	fn add_column(
+		&self,
+		column: &str,
+		definition: &str,
+		name: &str,
+		compute: impl Fn(&ClipboardItem) -> Option<SqlValue>,
+	) -> ClipboardResult<()> {
+		let mut conn = self.conn()?;
+		if conn.prepare(&format!("SELECT {} FROM items LIMIT 0", column)).is_ok() {
+			return Ok(());
+		}
+		info!("Ajout de l'index {} à l'historique", name);
+
+		let tx = conn
+			.transaction()
+			.map_err(|e| ClipboardError::Storage(format!("Erreur ouverture transaction: {}", e)))?;
+		tx.execute(&format!("ALTER TABLE items ADD COLUMN {} {}", column, definition), [])
+			.map_err(|e| ClipboardError::Storage(format!("Erreur mise à niveau schéma: {}", e)))?;
+
+		let update = format!("UPDATE items SET {} = ?1 WHERE id = ?2", column);
+		for (id, data) in Self::records(&tx)? {
+			let Ok(item) = Self::value_to_item(&data) else {
+				continue;
+			};
+			let Some(value) = compute(&item) else {
+				continue;
+			};
+			tx.execute(&update, params![value, id])
+				.map_err(|e| ClipboardError::Storage(format!("Erreur écriture élément: {}", e)))?;
+		}
+
+		tx.commit()
+			.map_err(|e| ClipboardError::Storage(format!("Erreur validation transaction: {}", e)))
+	}
+
+	/// Met à niveau, en une seule transaction, une base écrite dans la version `from`
+	///
+	/// Un élément impossible à migrer est conservé tel quel et signalé.
+	fn migrate(&self, from: u32) -> ClipboardResult<()> {
+		info!("Migration de l'historique du format {} vers le format {}", from, SCHEMA_VERSION);
+
+		let mut conn = self.conn()?;
+		let tx = conn
+			.transaction()
+			.map_err(|e| ClipboardError::Storage(format!("Erreur ouverture transaction: {}", e)))?;
+
+		// Les éléments déjà encodés dans la version actuelle ne sont pas réenregistrés
+		let records = if from >= schema::LAST_RECORD_VERSION {
+			Vec::new()
+		} else {
+			Self::records(&tx)?
+		};
+
+		let mut migrated = 0;
+		for (id, data) in records {
+			match schema::upgrade_record(&data, from) {
+				Ok(item) => {
+					Self::upsert(&tx, &item)?;
+					migrated += 1;
+				}
+				Err(e) => warn!("Élément {:?} non migré: {}", Uuid::from_slice(&id).ok(), e),
+			}
+		}
+
+		Self::collect_blobs(&tx)?;
+		Self::set_meta(&tx, SCHEMA_VERSION_KEY, &SCHEMA_VERSION.to_string())?;
+		tx.commit()
+			.map_err(|e| ClipboardError::Storage(format!("Erreur validation transaction: {}", e)))?;
+
+		info!("{} éléments migrés", migrated);
+		Ok(())
+	}
+
+	/// Importe des éléments en une seule transaction, puis enregistre la métadonnée `marker`
+	///
+	/// Soit tout est importé et marqué, soit rien ne l'est.
+	pub fn import_items(&self, items: &[ClipboardItem], marker: &str) -> ClipboardResult<usize> {
+		let mut conn = self.conn()?;
+		let tx = conn
+			.transaction()
+			.map_err(|e| ClipboardError::Storage(format!("Erreur ouverture transaction: {}", e)))?;
+
+		for item in items {
+			Self::upsert(&tx, item)?;
+		}
+		Self::set_meta(&tx, marker, &chrono::Utc::now().to_rfc3339())?;
+
+		tx.commit()
+			.map_err(|e| ClipboardError::Storage(format!("Erreur validation transaction: {}", e)))?;
+
+		Ok(items.len())
+	}
+}
+
+/// Expiration d'un élément, en nanosecondes
+fn expiry_nanos(item: &ClipboardItem) -> Option<i64> {
+	item.expires_at.map(|expiry| expiry.timestamp_nanos_opt().unwrap_or(i64::MAX))
+}
+
+/// Volume décompressé d'une image, nul pour les autres contenus
+fn image_bytes(item: &ClipboardItem) -> i64 {
+	image_size(item).min(i64::MAX as u64) as i64
+}
+
+impl Storage for SqliteStorage {
+	fn init(&self) -> ClipboardResult<()> {
+		debug!("Initialisation du stockage SQLite");
+
+		self.conn()?
+			.execute_batch(SCHEMA)
+			.map_err(|e| Self::open_error("Erreur création schéma", e))?;
+		self.add_column("expires_at", "INTEGER", "des expirations", |item| expiry_nanos(item).map(SqlValue::Integer))?;
+		self.add_column("image_bytes", "INTEGER NOT NULL DEFAULT 0", "des images", |item| {
+			Some(image_bytes(item)).filter(|bytes| *bytes > 0).map(SqlValue::Integer)
+		})?;
+		self.add_column("search_text", "TEXT", "de recherche", |item| item.search_text().map(SqlValue::Text))?;
+		self.conn()?
+			.execute_batch(&format!("{}; {};", EXPIRY_INDEX, IMAGE_INDEX))
+			.map_err(|e| ClipboardError::Storage(format!("Erreur création index: {}", e)))?;
+
+		match self.schema_version()? {
+			Some(version) => {
+				schema::check_version(version)?;
+				if version < SCHEMA_VERSION {
+					self.migrate(version)?;
+				}
+			}
+			None => {
+				if self.count_items()? == 0 {
+					// Base vide : rien à migrer
+					Self::set_meta(&*self.conn()?, SCHEMA_VERSION_KEY, &SCHEMA_VERSION.to_string())?;
+				} else {
+					// Base antérieure au suivi des versions
+					self.migrate(0)?;
+				}
+			}
+		}
+
+		Ok(())
+	}
+
+	fn get_all_items(&self) -> ClipboardResult<Vec<ClipboardItem>> {
+		Ok(self.get_items(0, i64::MAX as usize)?.items)
+	}
+
+	fn get_items(&self, offset: usize, limit: usize) -> ClipboardResult<ItemPage> {
+		let conn = self.conn()?;
+		// L'index sur l'horodatage évite tout tri
+		let items = Self::query_items(
+			&conn,
+			"SELECT data FROM items ORDER BY timestamp DESC LIMIT ?1 OFFSET ?2",
+			params![limit.min(i64::MAX as usize) as i64, offset as i64],
+		)?;
+
+		let total = Self::count(&conn)?;
+		Ok(ItemPage { items, offset, total })
+	}
+
+	fn search(&self, query: &str, offset: usize, limit: usize) -> ClipboardResult<ItemPage> {
+		if query.is_empty() {
+			return self.get_items(offset, limit);
+		}
+
+		// `instr` compare exactement les textes mis en minuscules, sans caractère spécial
+		let query = query.to_lowercase();
+		let conn = self.conn()?;
+		let items = Self::query_items(
+			&conn,
+			"SELECT data FROM items WHERE instr(search_text, ?1) > 0 ORDER BY timestamp DESC LIMIT ?2 OFFSET ?3",
+			params![query, limit.min(i64::MAX as usize) as i64, offset as i64],
+		)?;
+		let total: i64 = conn
+			.query_row("SELECT COUNT(*) FROM items WHERE instr(search_text, ?1) > 0", [&query], |row| row.get(0))
+			.map_err(|e| ClipboardError::Storage(format!("Erreur lecture base de données: {}", e)))?;
+
+		Ok(ItemPage { items, offset, total: total as usize })
+	}
+
+	fn count_items(&self) -> ClipboardResult<usize> {
+		Self::count(&*self.conn()?)
+	}
+
+	fn get_item(&self, id: Uuid) -> ClipboardResult<Option<ClipboardItem>> {
+		let conn = self.conn()?;
+		let value: Option<Vec<u8>> = conn
+			.query_row("SELECT data FROM items WHERE id = ?1", [id.as_bytes().as_slice()], |row| row.get(0))
+			.optional()
+			.map_err(|e| ClipboardError::Storage(format!("Erreur lecture élément: {}", e)))?;
+
+		let Some(value) = value else {
+			return Ok(None);
+		};
+		let mut item = Self::value_to_item(&value)?;
+		codec::hydrate(&mut item, |blob| {
+			conn.query_row("SELECT data FROM blobs WHERE key = ?1", [blob], |row| row.get(0))
+				.optional()
+				.map_err(|e| ClipboardError::Storage(format!("Erreur lecture blob: {}", e)))
+		})?;
+		Ok(Some(item))
+	}
+
+	fn find_by_hash(&self, hash: &str) -> ClipboardResult<Option<ClipboardItem>> {
+		let value: Option<Vec<u8>> = self.conn()?
+			.query_row(
+				"SELECT data FROM items WHERE content_hash = ?1 ORDER BY timestamp DESC LIMIT 1",
+				[hash],
+				|row| row.get(0),
+			)
+			.optional()
+			.map_err(|e| ClipboardError::Storage(format!("Erreur lecture index: {}", e)))?;
+
+		value.map(|value| Self::value_to_item(&value)).transpose()
+	}
+
+	fn add_item(&self, item: ClipboardItem) -> ClipboardResult<ClipboardItem> {
+		let mut conn = self.conn()?;
+		let stored = Self::in_transaction(&mut conn, |tx| Self::upsert(tx, &item))?;
+
+		debug!("Élément ajouté: {}", item.id);
+		self.notify(&conn, vec![stored.clone()], Vec::new());
+		Ok(stored)
+	}
+
+	fn update_item(&self, item: ClipboardItem) -> ClipboardResult<ClipboardItem> {
+		let mut conn = self.conn()?;
+		let stored = Self::in_transaction(&mut conn, |tx| {
+			let stored = Self::upsert(tx, &item)?;
+			Self::collect_blobs(tx)?;
+			Ok(stored)
+		})?;
+
+		debug!("Élément mis à jour: {}", item.id);
+		self.notify(&conn, vec![stored.clone()], Vec::new());
+		Ok(stored)
+	}
+
+	fn remove_item(&self, id: Uuid) -> ClipboardResult<()> {
+		let conn = self.conn()?;
+		let removed = conn
+			.execute("DELETE FROM items WHERE id = ?1", [id.as_bytes().as_slice()])
+			.map_err(|e| ClipboardError::Storage(format!("Erreur suppression élément: {}", e)))?;
+		Self::collect_blobs(&conn)?;
+
+		debug!("Élément supprimé: {}", id);
+		if removed > 0 {
+			self.notify(&conn, Vec::new(), vec![id]);
+		}
+		Ok(())
+	}
+
+	fn clear_non_pinned(&self) -> ClipboardResult<Vec<Uuid>> {
+		let conn = self.conn()?;
+		let removed = {
+			let mut statement = conn
+				.prepare("DELETE FROM items WHERE pinned = 0 RETURNING id")
+				.map_err(|e| ClipboardError::Storage(format!("Erreur préparation requête: {}", e)))?;
+			let rows = statement
+				.query_map([], |row| row.get::<_, Vec<u8>>(0))
+				.map_err(|e| ClipboardError::Storage(format!("Erreur suppression éléments: {}", e)))?;
+			rows.filter_map(|row| row.ok().and_then(|id| Uuid::from_slice(&id).ok()))
+				.collect::<Vec<_>>()
+		};
+		Self::collect_blobs(&conn)?;
+
+		info!("{} éléments non épinglés supprimés", removed.len());
+		if !removed.is_empty() {
+			self.notify(&conn, Vec::new(), removed.clone());
+		}
+		Ok(removed)
+	}
+
+	fn next_expiry(&self) -> ClipboardResult<Option<DateTime<Utc>>> {
+		let nanos: Option<i64> = self.conn()?
+			.query_row(
+				"SELECT MIN(expires_at) FROM items WHERE pinned = 0 AND expires_at IS NOT NULL",
+				[],
+				|row| row.get(0),
+			)
+			.map_err(|e| ClipboardError::Storage(format!("Erreur lecture base de données: {}", e)))?;
+		Ok(nanos.map(DateTime::from_timestamp_nanos))
+	}
+
+	fn lapsed_items(&self, now: DateTime<Utc>) -> ClipboardResult<Vec<ClipboardItem>> {
+		Self::query_items(
+			&*self.conn()?,
+			"SELECT data FROM items WHERE pinned = 0 AND expires_at <= ?1 ORDER BY expires_at",
+			[now.timestamp_nanos_opt().unwrap_or(i64::MAX)],
+		)
+	}
+
+	fn oldest_unpinned(&self, offset: usize, limit: usize, images_only: bool) -> ClipboardResult<Vec<ClipboardItem>> {
+		// Les index sur l'épinglage et sur les images évitent tout tri
+		let sql = if images_only {
+			"SELECT data FROM items WHERE pinned = 0 AND image_bytes > 0 ORDER BY timestamp LIMIT ?1 OFFSET ?2"
+		} else {
+			"SELECT data FROM items WHERE pinned = 0 ORDER BY timestamp LIMIT ?1 OFFSET ?2"
+		};
+		Self::query_items(&*self.conn()?, sql, params![limit.min(i64::MAX as usize) as i64, offset as i64])
+	}
+
+	fn image_bytes(&self) -> ClipboardResult<u64> {
+		self.conn()?
+			.query_row("SELECT COALESCE(SUM(image_bytes), 0) FROM items WHERE image_bytes > 0", [], |row| {
+				row.get::<_, i64>(0)
+			})
+			.map(|bytes| bytes as u64)
+			.map_err(|e| ClipboardError::Storage(format!("Erreur lecture base de données: {}", e)))
+	}
+
+	fn subscribe(&self) -> StorageEventReceiver {
+		if !self.watching.swap(true, Ordering::SeqCst) {
+			self.watch_external_changes();
+		}
+		self.events.subscribe()
+	}
+
+	fn flush(&self) -> ClipboardResult<()> {
+		// Chaque écriture est déjà validée : reporter le journal WAL dans la base
+		self.conn()?
+			.query_row("PRAGMA wal_checkpoint(PASSIVE)", [], |_| Ok(()))
+			.map_err(|e| ClipboardError::Storage(format!("Erreur flush stockage: {}", e)))?;
+
+		debug!("Données synchronisées sur le disque");
+		Ok(())
+	}
+
+	fn compact(&self) -> ClipboardResult<()> {
+		// VACUUM recopie la base sans ses pages libérées, puis le journal WAL est reporté et vidé
+		let conn = self.conn()?;
+		conn.execute_batch("VACUUM")
+			.map_err(|e| ClipboardError::Storage(format!("Erreur réécriture base de données: {}", e)))?;
+		conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))
+			.map_err(|e| ClipboardError::Storage(format!("Erreur réécriture base de données: {}", e)))?;
+
+		info!("Base SQLite réécrite sans ses anciennes versions");
+		Ok(())
+	}
+}
@@ -0,0 +1,482 @@
+use crate::config::KeySource;
+use crate::error::{ClipboardError, ClipboardResult};
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, KeyInit, OsRng, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use hmac::{Hmac, Mac};
+use log::info;
+use secret_service::blocking::SecretService;
+use secret_service::EncryptionType;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use zeroize::Zeroizing;
+
+/// Fichier du dossier de données décrivant la protection de la clé de l'historique
+pub const KEY_HEADER_FILE: &str = "encryption.json";
+
+/// Variable d'environnement fournissant la phrase secrète au démarrage
+pub const PASSPHRASE_ENV: &str = "CLIPBOARD_MANAGER_PASSPHRASE";
+
+/// Taille des clés (en octets)
+const KEY_LEN: usize = 32;
+
+/// Taille des nonces XChaCha20-Poly1305 (en octets)
+const NONCE_LEN: usize = 24;
+
+/// Données associées au chiffrement de la clé de l'historique
+const WRAPPED_KEY_AAD: &[u8] = b"clipboard-manager history key";
+
+/// Libellé de la clé dans le trousseau du bureau
+const SECRET_LABEL: &str = "Clé de l'historique du gestionnaire de presse-papiers";
+
+type Key = Zeroizing<[u8; KEY_LEN]>;
+
+/// Paramètres de la dérivation Argon2id d'une phrase secrète
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct KdfParams {
+	/// Mémoire utilisée (en Kio)
+	memory_kib: u32,
+	/// Nombre de passes
+	iterations: u32,
+	/// Nombre de voies parallèles
+	parallelism: u32,
+}
+
+impl Default for KdfParams {
+	fn default() -> Self {
+		Self {
+			memory_kib: Params::DEFAULT_M_COST,
+			iterations: Params::DEFAULT_T_COST,
+			parallelism: Params::DEFAULT_P_COST,
+		}
+	}
+}
+
+/// En-tête enregistré dans `KEY_HEADER_FILE`
+///
+/// La clé de l'historique est tirée au hasard ; seule la clé qui la protège
+/// dépend de la source choisie. Un changement de clé en tire une nouvelle et
+/// garde la précédente le temps que les éléments soient rechiffrés.
+#[derive(Debug, Serialize, Deserialize)]
+struct KeyHeader {
+	/// Origine de la clé qui protège la clé de l'historique
+	source: KeySource,
+	/// Sel et paramètres de la dérivation, pour une phrase secrète
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	salt: Vec<u8>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	kdf: Option<KdfParams>,
+	/// Clé de l'historique chiffrée : nonce suivi du chiffré
+	wrapped_key: Vec<u8>,
+	/// Clé précédente, chiffrée de même, tant que des éléments en dépendent
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	retired_key: Vec<u8>,
+}
+
+/// Clés dérivées de la clé de l'historique
+struct Keys {
+	/// Clé de l'historique, conservée pour la garder lors d'un changement de clé
+	data: Key,
+	/// Clé de chiffrement des éléments
+	items: Key,
+	/// Clé des empreintes de contenu
+	hashes: Key,
+	/// Clé de chiffrement des éléments pas encore rechiffrés après un changement de clé
+	retired_items: Option<Key>,
+}
+
+impl Keys {
+	fn derive(data: Key) -> ClipboardResult<Self> {
+		let items = hmac(data.as_slice(), b"items")?;
+		let hashes = hmac(data.as_slice(), b"content-hash")?;
+		Ok(Self { data, items, hashes, retired_items: None })
+	}
+
+	/// Garde la clé des éléments de l'ancienne clé de l'historique `retired`
+	fn retiring(mut self, retired: &Key) -> ClipboardResult<Self> {
+		self.retired_items = Some(hmac(retired.as_slice(), b"items")?);
+		Ok(self)
+	}
+}
+
+/// Coffre partagé entre le stockage chiffré et l'interface qui le déverrouille
+pub type VaultHandle = Arc<Vault>;
+
+/// Coffre détenant la clé de chiffrement de l'historique
+///
+/// Le coffre est créé verrouillé ; `unlock` retrouve la clé auprès de la
+/// source configurée, ou la génère lors de la première utilisation.
+pub struct Vault {
+	data_dir: PathBuf,
+	source: KeySource,
+	keyfile: PathBuf,
+	keys: RwLock<Option<Keys>>,
+}
+
+impl Vault {
+	/// Crée un coffre verrouillé pour l'historique de `data_dir`
+	pub fn new<P: AsRef<Path>>(data_dir: P, source: KeySource, keyfile: PathBuf) -> Self {
+		Self {
+			data_dir: data_dir.as_ref().to_path_buf(),
+			source,
+			keyfile,
+			keys: RwLock::new(None),
+		}
+	}
+
+	/// Source de la clé
+	pub fn source(&self) -> KeySource {
+		self.source
+	}
+
+	/// Indique si la clé de l'historique existe déjà
+	///
+	/// Sinon, le premier déverrouillage la crée : une phrase secrète doit alors être choisie.
+	pub fn is_initialized(&self) -> bool {
+		self.header_path().exists()
+	}
+
+	/// Indique si la clé n'est pas disponible
+	pub fn is_locked(&self) -> bool {
+		self.keys.read().map(|keys| keys.is_none()).unwrap_or(true)
+	}
+
+	/// Retrouve la clé de l'historique, ou la crée lors de la première utilisation
+	///
+	/// La phrase secrète n'est utilisée que si la source en demande une.
+	pub fn unlock(&self, passphrase: Option<&str>) -> ClipboardResult<()> {
+		let keys = match self.read_header()? {
+			Some(header) => {
+				if header.source != self.source {
+					return Err(ClipboardError::Storage(format!(
+						"L'historique est protégé par {}, pas par {} : changez sa clé avec `rekey`",
+						header.source, self.source
+					)));
+				}
+				let wrapping = self.wrapping_key(&header, passphrase, false)?;
+				let keys = Keys::derive(unwrap_key(&wrapping, &header.wrapped_key, self.source)?)?;
+				if header.retired_key.is_empty() {
+					keys
+				} else {
+					keys.retiring(&unwrap_key(&wrapping, &header.retired_key, self.source)?)?
+				}
+			}
+			None => {
+				let data = random_key();
+				self.write_header(&self.protect(&data, None, self.source, passphrase)?)?;
+				info!("Clé de l'historique créée ({})", self.source);
+				Keys::derive(data)?
+			}
+		};
+
+		*self.write_keys()? = Some(keys);
+		Ok(())
+	}
+
+	/// Oublie la clé de l'historique
+	pub fn lock(&self) {
+		if let Ok(mut keys) = self.keys.write() {
+			*keys = None;
+		}
+	}
+
+	/// Remplace la clé de l'historique par une nouvelle, protégée par `source`
+	///
+	/// Le coffre doit être déverrouillé. L'en-tête change d'un seul coup et garde
+	/// l'ancienne clé : les éléments restent lisibles jusqu'à ce que le stockage
+	/// chiffré les ait rechiffrés et appelé `retire`.
+	pub fn rekey(&mut self, source: KeySource, passphrase: Option<&str>) -> ClipboardResult<()> {
+		if source == KeySource::None {
+			return Err(ClipboardError::Storage("Une source de clé est nécessaire".to_string()));
+		}
+		if self.is_rotating() {
+			return Err(ClipboardError::Storage(
+				"Le changement de clé précédent n'est pas terminé : ouvrez l'historique pour le terminer".to_string(),
+			));
+		}
+
+		let data = random_key();
+		let (header, keys) = {
+			let keys = self.read_keys()?;
+			let keys = keys.as_ref().ok_or_else(locked)?;
+			let header = self.protect(&data, Some(&keys.data), source, passphrase)?;
+			(header, Keys::derive(data)?.retiring(&keys.data)?)
+		};
+		self.write_header(&header)?;
+		*self.write_keys()? = Some(keys);
+		self.source = source;
+		info!("Nouvelle clé de l'historique, protégée par {}", source);
+		Ok(())
+	}
+
+	/// Indique si des éléments peuvent encore dépendre de la clé remplacée par `rekey`
+	pub fn is_rotating(&self) -> bool {
+		self.keys
+			.read()
+			.map(|keys| keys.as_ref().is_some_and(|keys| keys.retired_items.is_some()))
+			.unwrap_or(false)
+	}
+
+	/// Oublie la clé remplacée par `rekey`, une fois tous les éléments rechiffrés
+	pub fn retire(&self) -> ClipboardResult<()> {
+		if let Some(mut header) = self.read_header()? {
+			header.retired_key.clear();
+			self.write_header(&header)?;
+		}
+		if let Some(keys) = self.write_keys()?.as_mut() {
+			keys.retired_items = None;
+		}
+		info!("Ancienne clé de l'historique oubliée");
+		Ok(())
+	}
+
+	/// Chiffre des données ; `aad` lie le chiffré à son contexte
+	pub fn seal(&self, data: &[u8], aad: &[u8]) -> ClipboardResult<Vec<u8>> {
+		let keys = self.read_keys()?;
+		let keys = keys.as_ref().ok_or_else(locked)?;
+		seal(&keys.items, data, aad)
+	}
+
+	/// Déchiffre des données produites par `seal` avec le même contexte
+	///
+	/// Pendant un changement de clé, l'ancienne clé est essayée à son tour.
+	pub fn open(&self, sealed: &[u8], aad: &[u8]) -> ClipboardResult<Vec<u8>> {
+		let keys = self.read_keys()?;
+		let keys = keys.as_ref().ok_or_else(locked)?;
+		match (open(&keys.items, sealed, aad), &keys.retired_items) {
+			(Err(_), Some(retired)) => open(retired, sealed, aad),
+			(result, _) => result,
+		}
+	}
+
+	/// Empreinte de contenu enregistrée sur le disque
+	///
+	/// Une empreinte en clair permettrait de vérifier qu'un contenu donné a été copié.
+	pub fn keyed_hash(&self, hash: &str) -> ClipboardResult<String> {
+		let keys = self.read_keys()?;
+		let keys = keys.as_ref().ok_or_else(locked)?;
+		Ok(hmac(keys.hashes.as_slice(), hash.as_bytes())?
+			.iter()
+			.map(|byte| format!("{:02x}", byte))
+			.collect())
+	}
+
+	fn read_keys(&self) -> ClipboardResult<std::sync::RwLockReadGuard<'_, Option<Keys>>> {
+		self.keys
+			.read()
+			.map_err(|e| ClipboardError::Storage(format!("Erreur verrou clé: {}", e)))
+	}
+
+	fn write_keys(&self) -> ClipboardResult<std::sync::RwLockWriteGuard<'_, Option<Keys>>> {
+		self.keys
+			.write()
+			.map_err(|e| ClipboardError::Storage(format!("Erreur verrou clé: {}", e)))
+	}
+
+	fn header_path(&self) -> PathBuf {
+		self.data_dir.join(KEY_HEADER_FILE)
+	}
+
+	fn read_header(&self) -> ClipboardResult<Option<KeyHeader>> {
+		let path = self.header_path();
+		if !path.exists() {
+			return Ok(None);
+		}
+		let content = fs::read(&path)
+			.map_err(|e| ClipboardError::Storage(format!("Erreur lecture {}: {}", path.display(), e)))?;
+		let header = serde_json::from_slice(&content)
+			.map_err(|e| ClipboardError::Storage(format!("En-tête de chiffrement invalide: {}", e)))?;
+		Ok(Some(header))
+	}
+
+	/// Remplace l'en-tête d'un seul coup : une interruption laisse l'ancien intact
+	fn write_header(&self, header: &KeyHeader) -> ClipboardResult<()> {
+		let path = self.header_path();
+		let temporary = path.with_extension("json.tmp");
+		let content = serde_json::to_vec_pretty(header)?;
+		fs::write(&temporary, content)
+			.and_then(|_| fs::rename(&temporary, &path))
+			.map_err(|e| ClipboardError::Storage(format!("Erreur écriture {}: {}", path.display(), e)))
+	}
+
+	/// Chiffre la clé de l'historique, et celle qu'elle remplace, avec une clé obtenue de `source`
+	fn protect(
+		&self,
+		data: &Key,
+		retired: Option<&Key>,
+		source: KeySource,
+		passphrase: Option<&str>,
+	) -> ClipboardResult<KeyHeader> {
+		let mut header =
+			KeyHeader { source, salt: Vec::new(), kdf: None, wrapped_key: Vec::new(), retired_key: Vec::new() };
+		if source == KeySource::Passphrase {
+			header.salt = vec![0u8; 16];
+			OsRng.fill_bytes(&mut header.salt);
+			header.kdf = Some(KdfParams::default());
+		}
+		let wrapping = self.wrapping_key(&header, passphrase, true)?;
+		header.wrapped_key = seal(&wrapping, data.as_ref(), WRAPPED_KEY_AAD)?;
+		if let Some(retired) = retired {
+			header.retired_key = seal(&wrapping, retired.as_ref(), WRAPPED_KEY_AAD)?;
+		}
+		Ok(header)
+	}
+
+	/// Clé protégeant la clé de l'historique ; `create` autorise à en générer une
+	fn wrapping_key(&self, header: &KeyHeader, passphrase: Option<&str>, create: bool) -> ClipboardResult<Key> {
+		match header.source {
+			KeySource::None => Err(ClipboardError::Storage("Aucune source de clé configurée".to_string())),
+			KeySource::Passphrase => {
+				let passphrase = passphrase
+					.filter(|passphrase| !passphrase.is_empty())
+					.ok_or_else(|| ClipboardError::Storage("Phrase secrète requise".to_string()))?;
+				derive_passphrase(passphrase, &header.salt, header.kdf.unwrap_or_default())
+			}
+			KeySource::Keyfile => read_keyfile(&self.keyfile, create),
+			KeySource::SecretService => secret_service_key(&self.data_dir, create),
+		}
+	}
+}
+
+/// Nouvelle clé de l'historique, tirée au hasard
+fn random_key() -> Key {
+	let mut key = Zeroizing::new([0u8; KEY_LEN]);
+	OsRng.fill_bytes(key.as_mut());
+	key
+}
+
+/// Déchiffre une clé de l'en-tête avec la clé obtenue de `source`
+fn unwrap_key(wrapping: &Key, wrapped: &[u8], source: KeySource) -> ClipboardResult<Key> {
+	let data = open(wrapping, wrapped, WRAPPED_KEY_AAD)
+		.map_err(|_| ClipboardError::Storage(format!("Clé de l'historique incorrecte ({})", source)))?;
+	let data: [u8; KEY_LEN] = data
+		.as_slice()
+		.try_into()
+		.map_err(|_| ClipboardError::Storage("Clé de l'historique invalide".to_string()))?;
+	Ok(Zeroizing::new(data))
+}
+
+fn locked() -> ClipboardError {
+	ClipboardError::Storage("Historique verrouillé".to_string())
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> ClipboardResult<Key> {
+	let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(key)
+		.map_err(|e| ClipboardError::Storage(format!("Erreur dérivation clé: {}", e)))?;
+	mac.update(data);
+	Ok(Zeroizing::new(mac.finalize().into_bytes().into()))
+}
+
+/// Chiffre avec XChaCha20-Poly1305 ; le nonce aléatoire précède le chiffré
+fn seal(key: &Key, data: &[u8], aad: &[u8]) -> ClipboardResult<Vec<u8>> {
+	let cipher = XChaCha20Poly1305::new(chacha20poly1305::Key::from_slice(key.as_slice()));
+	let mut nonce = [0u8; NONCE_LEN];
+	OsRng.fill_bytes(&mut nonce);
+	let ciphertext = cipher
+		.encrypt(XNonce::from_slice(&nonce), Payload { msg: data, aad })
+		.map_err(|e| ClipboardError::Storage(format!("Erreur chiffrement: {}", e)))?;
+
+	let mut sealed = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+	sealed.extend_from_slice(&nonce);
+	sealed.extend_from_slice(&ciphertext);
+	Ok(sealed)
+}
+
+/// Déchiffre et authentifie des données produites par `seal`
+fn open(key: &Key, sealed: &[u8], aad: &[u8]) -> ClipboardResult<Vec<u8>> {
+	if sealed.len() < NONCE_LEN {
+		return Err(ClipboardError::Storage("Données chiffrées tronquées".to_string()));
+	}
+	let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+	XChaCha20Poly1305::new(chacha20poly1305::Key::from_slice(key.as_slice()))
+		.decrypt(XNonce::from_slice(nonce), Payload { msg: ciphertext, aad })
+		.map_err(|_| ClipboardError::Storage("Données chiffrées altérées ou clé incorrecte".to_string()))
+}
+
+/// Dérive une clé d'une phrase secrète avec Argon2id
+fn derive_passphrase(passphrase: &str, salt: &[u8], kdf: KdfParams) -> ClipboardResult<Key> {
+	let params = Params::new(kdf.memory_kib, kdf.iterations, kdf.parallelism, Some(KEY_LEN))
+		.map_err(|e| ClipboardError::Storage(format!("Paramètres de dérivation invalides: {}", e)))?;
+	let mut key = Zeroizing::new([0u8; KEY_LEN]);
+	Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+		.hash_password_into(passphrase.as_bytes(), salt, key.as_mut())
+		.map_err(|e| ClipboardError::Storage(format!("Erreur dérivation phrase secrète: {}", e)))?;
+	Ok(key)
+}
+
+/// Lit la clé d'un fichier, en le créant (lisible du seul utilisateur) si besoin
+fn read_keyfile(path: &Path, create: bool) -> ClipboardResult<Key> {
+	if !path.exists() {
+		if !create {
+			return Err(ClipboardError::Storage(format!("Fichier de clé {} introuvable", path.display())));
+		}
+		let mut key = Zeroizing::new([0u8; KEY_LEN]);
+		OsRng.fill_bytes(key.as_mut());
+		if let Some(parent) = path.parent() {
+			fs::create_dir_all(parent)
+				.map_err(|e| ClipboardError::Storage(format!("Erreur création dossier de la clé: {}", e)))?;
+		}
+
+		let mut options = fs::OpenOptions::new();
+		options.write(true).create_new(true);
+		#[cfg(unix)]
+		std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+		options
+			.open(path)
+			.and_then(|mut file| file.write_all(key.as_ref()).and_then(|_| file.sync_all()))
+			.map_err(|e| ClipboardError::Storage(format!("Erreur création fichier de clé {}: {}", path.display(), e)))?;
+		info!("Fichier de clé créé: {}", path.display());
+		return Ok(key);
+	}
+
+	let content = Zeroizing::new(
+		fs::read(path)
+			.map_err(|e| ClipboardError::Storage(format!("Erreur lecture fichier de clé {}: {}", path.display(), e)))?,
+	);
+	let key: [u8; KEY_LEN] = content
+		.as_slice()
+		.try_into()
+		.map_err(|_| ClipboardError::Storage(format!("Fichier de clé {} invalide", path.display())))?;
+	Ok(Zeroizing::new(key))
+}
+
+/// Lit la clé dans le trousseau du bureau, en la créant si besoin
+///
+/// La clé est rangée dans la collection par défaut, qui est déverrouillée au
+/// besoin (le trousseau peut alors demander son mot de passe).
+fn secret_service_key(data_dir: &Path, create: bool) -> ClipboardResult<Key> {
+	let error = |e: secret_service::Error| ClipboardError::Storage(format!("Erreur trousseau du bureau: {}", e));
+
+	let service = SecretService::connect(EncryptionType::Dh).map_err(error)?;
+	let collection = service.get_default_collection().map_err(error)?;
+	collection.ensure_unlocked().map_err(error)?;
+
+	let data_dir = data_dir.to_string_lossy();
+	let attributes = HashMap::from([("application", "clipboard-manager"), ("data_dir", data_dir.as_ref())]);
+
+	if let Some(item) = collection.search_items(attributes.clone()).map_err(error)?.first() {
+		let secret = Zeroizing::new(item.get_secret().map_err(error)?);
+		let key: [u8; KEY_LEN] = secret
+			.as_slice()
+			.try_into()
+			.map_err(|_| ClipboardError::Storage("Clé du trousseau invalide".to_string()))?;
+		return Ok(Zeroizing::new(key));
+	}
+
+	if !create {
+		return Err(ClipboardError::Storage("Clé de l'historique absente du trousseau du bureau".to_string()));
+	}
+	let mut key = Zeroizing::new([0u8; KEY_LEN]);
+	OsRng.fill_bytes(key.as_mut());
+	collection
+		.create_item(SECRET_LABEL, attributes, key.as_ref(), false, "application/octet-stream")
+		.map_err(error)?;
+	info!("Clé de l'historique ajoutée au trousseau du bureau");
+	Ok(key)
+}
@@ -1,13 +1,20 @@
-use crate::clipboard::{ClipboardContent, ClipboardItem};
+use crate::clipboard::mime::mime_label;
+use crate::clipboard::{ClipboardContent, ClipboardItem, FileEntry, RecordingState, Selection};
 use crate::config::Theme;
-use crate::ui::Message;
-use crate::ui::style::{toolbar_style, search_bar_style, pinned_item_style, clipboard_item_style, round_button_style};
-use chrono::{DateTime, Utc};
-use iced::widget::{button, column, container, horizontal_rule, image, row, text, text_input, Space, svg};
+use crate::ui::{ItemExpiry, Message, SelectionFilter, StartupFailure};
+use crate::utils::format_size;
+use crate::ui::style::{toolbar_style, search_bar_style, pinned_item_style, clipboard_item_style, round_button_style, paused_banner_style};
+use chrono::{DateTime, Local, Utc};
+use iced::widget::{button, column, container, horizontal_rule, image, pick_list, row, text, text_input, Space, svg};
 use iced::{alignment, Length, Element};
 
+/// Délai de la pause proposée dans la barre d'outils (en minutes)
+const TIMED_PAUSE_MINUTES: i64 = 10;
+
 /// Crée la barre d'outils
-pub fn create_toolbar(current_theme: Theme, _iced_theme: &iced::Theme) -> Element<'static, Message> {
+///
+/// Les boutons de pause n'apparaissent que si l'application enregistre elle-même les copies (`recording`).
+pub fn create_toolbar(current_theme: Theme, recording: Option<RecordingState>, _iced_theme: &iced::Theme) -> Element<'static, Message> {
 	let title = text("Gestionnaire de presse-papiers")
 		.size(18)
 		.width(Length::Fill);
@@ -46,12 +53,29 @@ pub fn create_toolbar(current_theme: Theme, _iced_theme: &iced::Theme) -> Elemen
 		.style(|theme, _status| round_button_style(theme))
 		.width(Length::Shrink);
 
-	let toolbar = row![
-		title,
-		theme_button,
-		Space::with_width(Length::Fixed(10.0)),
-		clear_button
-	]
+	// Suspension de l'enregistrement, indéfiniment ou pour quelques minutes
+	let paused = recording.is_some_and(|recording| recording.is_paused());
+	let pause_button = recording.map(|_| {
+		button(text(if paused { "Reprendre" } else { "Pause" }).size(14))
+			.on_press(Message::TogglePause)
+			.style(|theme, _status| round_button_style(theme))
+			.padding(5)
+	});
+	let timed_pause_button = (recording.is_some() && !paused).then(|| {
+		button(text(format!("{} min", TIMED_PAUSE_MINUTES)).size(14))
+			.on_press(Message::PauseFor(chrono::Duration::minutes(TIMED_PAUSE_MINUTES)))
+			.style(|theme, _status| round_button_style(theme))
+			.padding(5)
+	});
+
+	let toolbar = row![title]
+	.push_maybe(pause_button)
+	.push_maybe(timed_pause_button)
+	.extend([
+		theme_button.into(),
+		Space::with_width(Length::Fixed(10.0)).into(),
+		clear_button.into(),
+	])
 	.padding(10)
 	.spacing(10)
 	.width(Length::Fill)
@@ -63,14 +87,149 @@ pub fn create_toolbar(current_theme: Theme, _iced_theme: &iced::Theme) -> Elemen
 		.into()
 }
 
-/// Crée la barre de recherche
-pub fn create_search_bar(search_query: &str, _iced_theme: &iced::Theme) -> Element<'static, Message> {
+/// Crée le bandeau affiché tant que l'enregistrement est suspendu
+pub fn create_paused_banner(recording: Option<RecordingState>) -> Option<Element<'static, Message>> {
+	let Some(recording @ RecordingState::Paused { until }) = recording else {
+		return None;
+	};
+	if !recording.is_paused() {
+		return None;
+	}
+
+	let label = match until {
+		Some(until) => format!(
+			"Enregistrement en pause jusqu'à {} : les copies ne sont pas conservées",
+			until.with_timezone(&Local).format("%H:%M")
+		),
+		None => "Enregistrement en pause : les copies ne sont pas conservées".to_string(),
+	};
+
+	Some(
+		container(text(label).size(14))
+			.style(paused_banner_style)
+			.padding(8)
+			.width(Length::Fill)
+			.into(),
+	)
+}
+
+/// Crée le bandeau signalant un historique gardé en mémoire, faute d'avoir pu ouvrir la base
+pub fn create_fallback_banner(reason: &str) -> Element<'static, Message> {
+	let label = text(format!(
+		"Historique gardé en mémoire jusqu'à la fermeture : {}",
+		reason
+	))
+	.size(14)
+	.width(Length::Fill);
+
+	container(
+		row![label, button(text("Réessayer").size(14)).on_press(Message::RetryStartup).padding(6)]
+			.spacing(10)
+			.align_y(alignment::Vertical::Center),
+	)
+	.style(paused_banner_style)
+	.padding(8)
+	.width(Length::Fill)
+	.into()
+}
+
+/// Crée l'explication des échecs du démarrage, suivie d'un bouton pour réessayer
+pub fn create_startup_failures(failures: &[StartupFailure]) -> Element<'static, Message> {
+	let mut content = column![text("Le gestionnaire de presse-papiers n'a pas pu démarrer").size(18)].spacing(16);
+
+	for failure in failures {
+		let mut entry = column![
+			text(format!("{} : {}", failure.step, failure.summary)).size(16),
+			text(failure.details.clone()).size(13),
+		]
+		.spacing(6);
+		if let Some(hint) = failure.hint {
+			entry = entry.push(text(hint).size(14));
+		}
+		content = content.push(entry);
+	}
+
+	content = content.push(button(text("Réessayer")).on_press(Message::RetryStartup).padding(10));
+
+	container(content)
+		.padding(20)
+		.style(search_bar_style)
+		.width(Length::Fill)
+		.into()
+}
+
+/// Crée le formulaire de déverrouillage de l'historique chiffré
+///
+/// Lors de la première utilisation, la phrase secrète est choisie et doit être confirmée.
+pub fn create_unlock_form(
+	passphrase: &str,
+	confirmation: &str,
+	first_use: bool,
+	error: Option<&str>,
+) -> Element<'static, Message> {
+	let title = if first_use {
+		"Choisissez la phrase secrète qui protégera l'historique"
+	} else {
+		"L'historique est chiffré : saisissez la phrase secrète"
+	};
+
+	let passphrase_input = text_input("Phrase secrète", passphrase)
+		.secure(true)
+		.on_input(Message::PassphraseChanged)
+		.on_submit(Message::Unlock)
+		.padding(10)
+		.width(Length::Fill);
+
+	let mut form = column![text(title).size(16), passphrase_input].spacing(10);
+
+	if first_use {
+		form = form.push(
+			text_input("Confirmation", confirmation)
+				.secure(true)
+				.on_input(Message::PassphraseConfirmationChanged)
+				.on_submit(Message::Unlock)
+				.padding(10)
+				.width(Length::Fill),
+		);
+	}
+
+	if let Some(error) = error {
+		form = form.push(text(error.to_string()).size(14));
+	}
+
+	let unlock_button = button(text(if first_use { "Chiffrer l'historique" } else { "Déverrouiller" }))
+		.on_press(Message::Unlock)
+		.padding(10);
+	form = form.push(unlock_button);
+
+	container(form)
+		.padding(20)
+		.style(search_bar_style)
+		.width(Length::Fill)
+		.into()
+}
+
+/// Crée la barre de recherche, avec le filtre par sélection s'il est fourni
+pub fn create_search_bar(
+	search_query: &str,
+	selection_filter: Option<SelectionFilter>,
+	_iced_theme: &iced::Theme,
+) -> Element<'static, Message> {
 	let search_input = text_input("Rechercher...", search_query)
 		.on_input(Message::SearchChanged)
 		.padding(10)
 		.width(Length::Fill);
 
-	container(search_input)
+	let search_row = match selection_filter {
+		Some(filter) => row![
+			search_input,
+			pick_list(SelectionFilter::ALL, Some(filter), Message::SelectionFilterChanged).padding(10)
+		]
+		.spacing(5),
+		None => row![search_input],
+	};
+
+	container(search_row)
 		.padding(5)
 		.style(search_bar_style)
 		.width(Length::Fill)
@@ -78,7 +237,12 @@ pub fn create_search_bar(search_query: &str, _iced_theme: &iced::Theme) -> Eleme
 }
 
 /// Crée un aperçu d'élément du presse-papiers
-pub fn create_clipboard_item_view(item: &ClipboardItem, selected: bool, _iced_theme: &iced::Theme) -> Element<'static, Message> {
+pub fn create_clipboard_item_view(
+	item: &ClipboardItem,
+	selected: bool,
+	track_primary: bool,
+	_iced_theme: &iced::Theme,
+) -> Element<'static, Message> {
 	let item_id = item.id;
 	let pinned = item.pinned;
 
@@ -93,8 +257,13 @@ pub fn create_clipboard_item_view(item: &ClipboardItem, selected: bool, _iced_th
 			
 			text::<iced::Theme, iced::Renderer>(preview).size(14).into()
 		}
-		ClipboardContent::Image(data, _metadata) => {
-			let handle = image::Handle::from_bytes(data.clone());
+		ClipboardContent::Image(data, metadata) => {
+			// Aperçu enregistré, ou pixels bruts pour un élément pas encore enregistré
+			let handle = if !metadata.thumbnail.is_empty() {
+				image::Handle::from_bytes(metadata.thumbnail.clone())
+			} else {
+				image::Handle::from_rgba(metadata.width as u32, metadata.height as u32, data.clone())
+			};
 			let img = image(handle)
 				.width(Length::Fixed(100.0))
 				.height(Length::Fixed(100.0))
@@ -105,10 +274,25 @@ pub fn create_clipboard_item_view(item: &ClipboardItem, selected: bool, _iced_th
 				.align_x(alignment::Horizontal::Center)
 				.into()
 		}
+		ClipboardContent::Files(entries) => create_file_list_view(entries),
 	};
 
-	// Métadonnées (horodatage)
-	let timestamp = format_timestamp(&item.timestamp);
+	// Métadonnées (horodatage et format d'origine pour les contenus riches)
+	let mut timestamp = format_timestamp(&item.timestamp);
+	if item.has_rich_formats() {
+		if let Some(mime_type) = &item.preferred_mime {
+			timestamp = format!("{} · {}", timestamp, mime_label(mime_type));
+		}
+	}
+	if item.copy_count > 1 {
+		timestamp = format!("{} · ×{}", timestamp, item.copy_count);
+	}
+	if track_primary && item.selection == Selection::Primary {
+		timestamp = format!("{} · {}", timestamp, Selection::Primary);
+	}
+	if let Some(expires_at) = item.expires_at.filter(|_| !pinned) {
+		timestamp = format!("{} · {}", timestamp, format_expiry(&expires_at));
+	}
 	let metadata = text::<iced::Theme, iced::Renderer>(timestamp).size(12).color(iced::Color::from_rgb(0.5, 0.5, 0.5));
 
 	// Boutons d'action
@@ -140,12 +324,33 @@ pub fn create_clipboard_item_view(item: &ClipboardItem, selected: bool, _iced_th
 		.style(|theme, _status| round_button_style(theme))
 		.padding(5);
 	
-	let buttons = row![
+	// Les éléments épinglés n'expirent jamais
+	let expiry_list = (!pinned).then(|| {
+		pick_list(ItemExpiry::ALL, None::<ItemExpiry>, move |expiry| Message::SetExpiry(item_id, expiry))
+			.placeholder("Expirer")
+			.text_size(14)
+			.padding(5)
+	});
+	
+	let mut buttons = row![
 		pin_button,
 		select_button,
 		remove_button
 	]
+	.push_maybe(expiry_list)
 	.spacing(10);
+
+	// Restauration explicite vers l'une ou l'autre sélection
+	if track_primary {
+		for (label, selection) in [("Copier", Selection::Clipboard), ("Sélection", Selection::Primary)] {
+			buttons = buttons.push(
+				button(text(label).size(14))
+					.on_press(Message::RestoreItem(item_id, selection))
+					.style(|theme, _status| round_button_style(theme))
+					.padding(5),
+			);
+		}
+	}
 	
 	// Disposition de l'élément
 	let content = column![
@@ -176,21 +381,88 @@ pub fn create_clipboard_item_view(item: &ClipboardItem, selected: bool, _iced_th
 	}
 }
 
+/// Nombre maximal de fichiers listés dans l'aperçu
+const MAX_FILES_PREVIEW: usize = 5;
+
+/// Crée l'aperçu d'une liste de fichiers copiés
+fn create_file_list_view(entries: &[FileEntry]) -> Element<'static, Message> {
+	let mut rows: Vec<Element<'static, Message>> = entries
+		.iter()
+		.take(MAX_FILES_PREVIEW)
+		.map(|entry| {
+			let icon = if entry.is_dir {
+				svg::Handle::from_path("assets/icons/folder.svg")
+			} else {
+				svg::Handle::from_path("assets/icons/file.svg")
+			};
+
+			let details: Element<'static, Message> = if !entry.exists() {
+				text("introuvable")
+					.size(12)
+					.color(iced::Color::from_rgb(0.8, 0.3, 0.2))
+					.into()
+			} else if let Some(size) = entry.size {
+				text(format_size(size))
+					.size(12)
+					.color(iced::Color::from_rgb(0.5, 0.5, 0.5))
+					.into()
+			} else {
+				Space::with_width(Length::Shrink).into()
+			};
+
+			row![
+				svg(icon).width(Length::Fixed(16.0)).height(Length::Fixed(16.0)),
+				text(entry.name()).size(14).width(Length::Fill),
+				details
+			]
+			.spacing(8)
+			.align_y(alignment::Vertical::Center)
+			.into()
+		})
+		.collect();
+
+	if entries.len() > MAX_FILES_PREVIEW {
+		rows.push(
+			text(format!("et {} autres", entries.len() - MAX_FILES_PREVIEW))
+				.size(12)
+				.color(iced::Color::from_rgb(0.5, 0.5, 0.5))
+				.into(),
+		);
+	}
+
+	column(rows).spacing(4).into()
+}
+
 /// Formate un horodatage pour l'affichage
 fn format_timestamp(timestamp: &DateTime<Utc>) -> String {
 	let now = Utc::now();
 	let diff = now.signed_duration_since(*timestamp);
 	
 	if diff.num_minutes() < 1 {
-		return "À l'instant".to_string();
+		"À l'instant".to_string()
 	} else if diff.num_minutes() < 60 {
-		return format!("Il y a {} minutes", diff.num_minutes());
+		format!("Il y a {} minutes", diff.num_minutes())
 	} else if diff.num_hours() < 24 {
-		return format!("Il y a {} heures", diff.num_hours());
+		format!("Il y a {} heures", diff.num_hours())
 	} else if diff.num_days() < 7 {
-		return format!("Il y a {} jours", diff.num_days());
+		format!("Il y a {} jours", diff.num_days())
+	} else {
+		timestamp.format("%d/%m/%Y %H:%M").to_string()
+	}
+}
+
+/// Formate le temps restant avant l'expiration d'un élément
+fn format_expiry(expires_at: &DateTime<Utc>) -> String {
+	let remaining = expires_at.signed_duration_since(Utc::now());
+
+	if remaining.num_minutes() < 1 {
+		"expire dans moins d'une minute".to_string()
+	} else if remaining.num_minutes() < 60 {
+		format!("expire dans {} min", remaining.num_minutes())
+	} else if remaining.num_hours() < 24 {
+		format!("expire dans {} h", remaining.num_hours())
 	} else {
-		return timestamp.format("%d/%m/%Y %H:%M").to_string();
+		format!("expire le {}", expires_at.format("%d/%m/%Y %H:%M"))
 	}
 }
 
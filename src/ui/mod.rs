@@ -2,31 +2,155 @@ mod components;
 mod style;
 mod subscription;
 
-use crate::clipboard::ClipboardItem;
-use crate::config::Theme;
-use components::{create_clipboard_item_view, create_search_bar, create_toolbar};
+use crate::clipboard::{
+	CaptureRulesHandle, ClipboardItem, ClipboardManager, PauseHandle, PollingHandle, RecordingState, SecretFilterHandle, Selection,
+};
+use crate::error::ClipboardError;
+use crate::instance::WindowInstance;
+use crate::ipc::DbusServer;
+use crate::storage::{ItemPage, Storage, StorageChange};
+use crate::config::{Config, Theme, WatcherBackend};
+use components::{
+	create_clipboard_item_view, create_fallback_banner, create_paused_banner, create_search_bar, create_startup_failures,
+	create_toolbar, create_unlock_form,
+};
+use chrono::{DateTime, Duration, Utc};
 use iced::{Element, Subscription, keyboard};
 use iced::widget::{column, container, scrollable, text};
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
 use style::container_style;
+use tokio::sync::Mutex;
 use uuid::Uuid;
 
 /// État interne de l'interface
 #[derive(Debug, Default, Clone)]
 pub struct State {
 	pub selected_index: usize,
+	pub selection_filter: SelectionFilter,
+}
+
+/// Filtre de l'historique selon la sélection d'origine
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SelectionFilter {
+	#[default]
+	All,
+	Only(Selection),
+}
+
+impl SelectionFilter {
+	/// Filtres proposés dans l'interface
+	pub const ALL: [SelectionFilter; 3] = [
+		SelectionFilter::All,
+		SelectionFilter::Only(Selection::Clipboard),
+		SelectionFilter::Only(Selection::Primary),
+	];
+
+	/// Vérifie si un élément passe le filtre
+	pub fn matches(&self, item: &ClipboardItem) -> bool {
+		match self {
+			SelectionFilter::All => true,
+			SelectionFilter::Only(selection) => item.selection == *selection,
+		}
+	}
+}
+
+impl fmt::Display for SelectionFilter {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			SelectionFilter::All => write!(f, "Tout"),
+			SelectionFilter::Only(selection) => write!(f, "{}", selection),
+		}
+	}
+}
+
+/// Étape du démarrage qui a échoué, telle qu'expliquée à l'utilisateur
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StartupFailure {
+	/// Étape concernée, par exemple « Ouverture de l'historique »
+	pub step: &'static str,
+	pub summary: &'static str,
+	/// Message d'erreur complet
+	pub details: String,
+	pub hint: Option<&'static str>,
+}
+
+impl StartupFailure {
+	/// Décrit l'échec d'une étape du démarrage
+	pub fn new(step: &'static str, error: &ClipboardError) -> Self {
+		Self { step, summary: error.summary(), details: error.to_string(), hint: error.hint() }
+	}
+}
+
+/// Délai d'expiration proposé pour un élément
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemExpiry {
+	Never,
+	Minute,
+	Hour,
+	Day,
+}
+
+impl ItemExpiry {
+	/// Délais proposés dans l'interface
+	pub const ALL: [ItemExpiry; 4] = [ItemExpiry::Minute, ItemExpiry::Hour, ItemExpiry::Day, ItemExpiry::Never];
+
+	/// Date d'expiration d'un élément auquel le délai est appliqué maintenant
+	pub fn expires_at(&self) -> Option<DateTime<Utc>> {
+		let delay = match self {
+			ItemExpiry::Never => return None,
+			ItemExpiry::Minute => Duration::minutes(1),
+			ItemExpiry::Hour => Duration::hours(1),
+			ItemExpiry::Day => Duration::days(1),
+		};
+		Some(Utc::now() + delay)
+	}
+}
+
+impl fmt::Display for ItemExpiry {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			ItemExpiry::Never => write!(f, "Ne pas expirer"),
+			ItemExpiry::Minute => write!(f, "Dans 1 min"),
+			ItemExpiry::Hour => write!(f, "Dans 1 h"),
+			ItemExpiry::Day => write!(f, "Dans 1 jour"),
+		}
+	}
 }
 
 /// Messages UI
 #[derive(Debug, Clone)]
 pub enum Message {
-	ItemsLoaded(Vec<ClipboardItem>),
+	ItemsLoaded(String, ItemPage),
+	MoreItemsLoaded(String, ItemPage),
+	StorageChanged(StorageChange),
+	LoadMoreItems,
+	ListScrolled(f32),
 	NewClipboardItem(ClipboardItem),
 	UseItem(Uuid),
 	PinItem(Uuid),
 	RemoveItem(Uuid),
+	RestoreItem(Uuid, Selection),
+	SetExpiry(Uuid, ItemExpiry),
+	TogglePause,
+	PauseFor(Duration),
+	PauseElapsed(DateTime<Utc>),
+	RecordingChanged(RecordingState),
+	DbusReady(DbusServer),
+	ToggleWindow,
+	RetryStartup,
 	ClearItems,
 	SetTheme(Theme),
 	SearchChanged(String),
+	SelectionFilterChanged(SelectionFilter),
+	ConfigChanged(Box<Config>),
+	PassphraseChanged(String),
+	PassphraseConfirmationChanged(String),
+	Unlock,
+	Unlocked(Result<(), String>),
+	ApplyRetention,
+	NextExpiry(Option<DateTime<Utc>>),
 	ReloadItems,
 	NavigateUp,
 	NavigateDown,
@@ -35,8 +159,56 @@ pub enum Message {
 }
 
 /// Abonnement aux événements du presse-papiers
-pub fn clipboard_subscription() -> Subscription<Message> {
-	subscription::clipboard_subscription()
+pub fn clipboard_subscription(
+	backend: WatcherBackend,
+	track_primary: bool,
+	polling: PollingHandle,
+	secrets: SecretFilterHandle,
+	capture: CaptureRulesHandle,
+	recording: PauseHandle,
+) -> Subscription<Message> {
+	subscription::clipboard_subscription(backend, track_primary, polling, secrets, capture, recording)
+}
+
+/// Abonnement servant le socket de contrôle, qui relaie les pauses demandées par les clients
+pub fn ipc_subscription(
+	storage: Arc<Mutex<Box<dyn Storage>>>,
+	clipboard: Arc<Mutex<ClipboardManager>>,
+	recording: PauseHandle,
+) -> Subscription<Message> {
+	subscription::ipc_subscription(storage, clipboard, recording)
+}
+
+/// Abonnement publiant le service D-Bus, qui relaie les demandes reçues sur le bus
+pub fn dbus_subscription(
+	storage: Arc<Mutex<Box<dyn Storage>>>,
+	clipboard: Arc<Mutex<ClipboardManager>>,
+	recording: PauseHandle,
+) -> Subscription<Message> {
+	subscription::dbus_subscription(storage, clipboard, recording)
+}
+
+/// Abonnement aux relances de l'application, qui affichent ou masquent la fenêtre
+pub fn window_subscription(instance: Arc<WindowInstance>) -> Subscription<Message> {
+	subscription::window_subscription(instance)
+}
+
+/// Abonnement aux modifications du fichier de configuration
+pub fn config_subscription(config_path: PathBuf) -> Subscription<Message> {
+	subscription::config_subscription(config_path)
+}
+
+/// Abonnement aux modifications du stockage, y compris celles d'autres processus
+///
+/// `generation` change quand l'historique est rouvert, ce qui relance l'abonnement.
+pub fn storage_subscription(storage: Arc<Mutex<Box<dyn Storage>>>, generation: u64) -> Subscription<Message> {
+	subscription::storage_subscription(storage, generation)
+}
+
+/// Abonnement au nettoyage périodique de l'historique
+pub fn retention_subscription(interval_minutes: u64) -> Subscription<Message> {
+	let interval = std::time::Duration::from_secs(interval_minutes.max(1) * 60);
+	iced::time::every(interval).map(|_| Message::ApplyRetention)
 }
 
 /// Abonnement aux événements clavier
@@ -51,19 +223,57 @@ pub fn keyboard_subscription() -> Subscription<Message> {
 	})
 }
 
+/// Vue affichée tant que l'historique chiffré est verrouillé
+pub fn unlock_view<'a>(
+	passphrase: &str,
+	confirmation: &str,
+	first_use: bool,
+	error: Option<&str>,
+) -> Element<'a, Message> {
+	let content = column![create_unlock_form(passphrase, confirmation, first_use, error)]
+		.padding(10)
+		.width(iced::Length::Fill)
+		.height(iced::Length::Fill);
+
+	container(content)
+		.style(container_style)
+		.width(iced::Length::Fill)
+		.height(iced::Length::Fill)
+		.into()
+}
+
+/// Vue affichée quand le démarrage a échoué, avec un bouton pour réessayer
+pub fn startup_error_view<'a>(failures: &[StartupFailure]) -> Element<'a, Message> {
+	let content = column![create_startup_failures(failures)]
+		.padding(10)
+		.width(iced::Length::Fill)
+		.height(iced::Length::Fill);
+
+	container(content)
+		.style(container_style)
+		.width(iced::Length::Fill)
+		.height(iced::Length::Fill)
+		.into()
+}
+
 /// Vue principale
+///
+/// `storage_fallback` explique pourquoi l'historique est gardé en mémoire, le cas échéant.
 pub fn view<'a>(
 	state: State,
 	items: Vec<ClipboardItem>,
 	search_query: String,
 	theme: Theme,
-	_iced_theme: iced::Theme,
+	track_primary: bool,
+	recording: Option<RecordingState>,
+	storage_fallback: Option<&str>,
 ) -> Element<'a, Message> {
 	// Barre d'outils en haut
-	let toolbar = create_toolbar(theme, &iced::Theme::Light);
+	let toolbar = create_toolbar(theme, recording, &iced::Theme::Light);
 	
 	// Barre de recherche
-	let search_bar = create_search_bar(&search_query, &iced::Theme::Light);
+	let selection_filter = track_primary.then_some(state.selection_filter);
+	let search_bar = create_search_bar(&search_query, selection_filter, &iced::Theme::Light);
 	
 	// Liste des éléments
 	let items_list = if items.is_empty() {
@@ -76,22 +286,23 @@ pub fn view<'a>(
 	} else {
 		let elements: Vec<Element<'a, Message>> = items.iter()
 			.enumerate()
-			.map(|(index, item)| create_clipboard_item_view(item, index == state.selected_index, &iced::Theme::Light))
+			.map(|(index, item)| create_clipboard_item_view(item, index == state.selected_index, track_primary, &iced::Theme::Light))
 			.collect();
 		column(elements).spacing(8).padding(8)
 	};
 	
 	// Conteneur scrollable pour la liste
 	let scrollable_items = scrollable(items_list)
+		.on_scroll(|viewport| Message::ListScrolled(viewport.relative_offset().y))
 		.width(iced::Length::Fill)
 		.height(iced::Length::Fill);
 	
 	// Mise en page principale
-	let content = column![
-		toolbar,
-		search_bar,
-		scrollable_items
-	]
+	let content = column![toolbar]
+	.push_maybe(storage_fallback.map(create_fallback_banner))
+	.push_maybe(create_paused_banner(recording))
+	.push(search_bar)
+	.push(scrollable_items)
 	.spacing(10)
 	.padding(10)
 	.width(iced::Length::Fill)
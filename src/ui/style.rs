@@ -179,6 +179,38 @@ impl From<PinnedItemStyle> for container::Style {
 	}
 }
 
+/// Style du bandeau signalant que l'enregistrement est suspendu
+pub struct PausedBannerStyle {
+	pub dark_mode: bool,
+}
+
+impl From<PausedBannerStyle> for container::Style {
+	fn from(style: PausedBannerStyle) -> Self {
+		container::Style {
+			background: Some(Background::Color(if style.dark_mode {
+				Color::from_rgb(0.45, 0.15, 0.15)
+			} else {
+				Color::from_rgb(1.0, 0.88, 0.85)
+			})),
+			text_color: Some(if style.dark_mode {
+				Color::from_rgb(1.0, 0.85, 0.85)
+			} else {
+				Color::from_rgb(0.55, 0.1, 0.1)
+			}),
+			border: Border {
+				radius: 8.0.into(),
+				width: 1.0,
+				color: if style.dark_mode {
+					Color::from_rgb(0.8, 0.3, 0.3)
+				} else {
+					Color::from_rgb(0.9, 0.5, 0.45)
+				},
+			},
+			shadow: Default::default(),
+		}
+	}
+}
+
 /// Style de bouton premium avec coins arrondis
 pub struct RoundButtonStyle {
 	pub dark_mode: bool,
@@ -213,49 +245,36 @@ impl From<RoundButtonStyle> for button::Style {
 
 // Fonctions de style mises à jour
 pub fn container_style(theme: &iced::Theme) -> container::Style {
-	let is_dark = match theme {
-		iced::Theme::Dark => true,
-		_ => false,
-	};
+	let is_dark = matches!(theme, iced::Theme::Dark);
 	ContainerStyle { dark_mode: is_dark }.into()
 }
 
 pub fn clipboard_item_style(selected: bool, theme: &iced::Theme) -> container::Style {
-	let is_dark = match theme {
-		iced::Theme::Dark => true,
-		_ => false,
-	};
+	let is_dark = matches!(theme, iced::Theme::Dark);
 	ClipboardItemStyle { selected, dark_mode: is_dark }.into()
 }
 
 pub fn toolbar_style(theme: &iced::Theme) -> container::Style {
-	let is_dark = match theme {
-		iced::Theme::Dark => true,
-		_ => false,
-	};
+	let is_dark = matches!(theme, iced::Theme::Dark);
 	ToolbarStyle { dark_mode: is_dark }.into()
 }
 
 pub fn search_bar_style(theme: &iced::Theme) -> container::Style {
-	let is_dark = match theme {
-		iced::Theme::Dark => true,
-		_ => false,
-	};
+	let is_dark = matches!(theme, iced::Theme::Dark);
 	SearchBarStyle { dark_mode: is_dark }.into()
 }
 
 pub fn pinned_item_style(theme: &iced::Theme) -> container::Style {
-	let is_dark = match theme {
-		iced::Theme::Dark => true,
-		_ => false,
-	};
+	let is_dark = matches!(theme, iced::Theme::Dark);
 	PinnedItemStyle { dark_mode: is_dark }.into()
 }
 
+pub fn paused_banner_style(theme: &iced::Theme) -> container::Style {
+	let is_dark = matches!(theme, iced::Theme::Dark);
+	PausedBannerStyle { dark_mode: is_dark }.into()
+}
+
 pub fn round_button_style(theme: &iced::Theme) -> button::Style {
-	let is_dark = match theme {
-		iced::Theme::Dark => true,
-		_ => false,
-	};
+	let is_dark = matches!(theme, iced::Theme::Dark);
 	RoundButtonStyle { dark_mode: is_dark }.into()
 }
\ No newline at end of file
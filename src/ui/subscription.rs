@@ -1,46 +1,78 @@
-use crate::clipboard::ClipboardWatcher;
+use crate::clipboard::{
+	CaptureRulesHandle, ClipboardManager, ClipboardWatcher, PauseHandle, PollingHandle, RecordingState, SecretFilterHandle,
+};
+use crate::config::{Config, WatcherBackend};
+use crate::instance::WindowInstance;
+use crate::ipc::{socket_path, DbusInterface, DbusServer, IpcContext, IpcServer};
+use crate::storage::{Storage, StorageChange, StorageEventReceiver};
 use crate::ui::Message;
 use iced::Subscription;
 use iced::futures::stream::{self};
-use log::{debug, info};
+use log::{debug, error, info};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::{broadcast, mpsc, Mutex};
 
 /// Crée un abonnement pour surveiller les changements du presse-papiers
-pub fn clipboard_subscription() -> Subscription<Message> {
-	// Version avec stream::unfold qui est compatible avec la version actuelle d'Iced
-	Subscription::run(|| {
+///
+/// Les réglages de scrutation, le filtre des secrets, les règles de capture et
+/// la pause de l'enregistrement sont partagés via `polling`, `secrets`,
+/// `capture` et `recording` et pris en compte à chaud ; changer de mécanisme ou de sélections suivies relance la surveillance.
+pub fn clipboard_subscription(
+	backend: WatcherBackend,
+	track_primary: bool,
+	polling: PollingHandle,
+	secrets: SecretFilterHandle,
+	capture: CaptureRulesHandle,
+	recording: PauseHandle,
+) -> Subscription<Message> {
+	Subscription::run_with_id((backend, track_primary), {
 		// Créer une fonction qui retourne un Stream
 		let stream = stream::unfold(
 			ClipboardWatcherState::Starting,
-			move |state| async move {
-				match state {
-					ClipboardWatcherState::Starting => {
-						// Créer et initialiser la surveillance
-						let mut watcher = ClipboardWatcher::new();
-						let receiver = watcher.take_receiver().expect("Impossible d'obtenir le récepteur");
+			move |state| {
+				let polling = polling.clone();
+				let secrets = secrets.clone();
+				let capture = capture.clone();
+				let recording = recording.clone();
+				async move {
+					match state {
+						ClipboardWatcherState::Starting => {
+							// Créer et initialiser la surveillance
+							let mut watcher = ClipboardWatcher::with_polling(backend, track_primary, polling)
+								.with_secret_filter(secrets)
+								.with_capture_rules(capture)
+								.with_recording(recording);
+							let receiver = watcher.take_receiver().expect("Impossible d'obtenir le récepteur");
 						
-						info!("Démarrage de la surveillance du presse-papiers");
-						watcher.start().await.expect("Erreur démarrage surveillance presse-papiers");
+							info!("Démarrage de la surveillance du presse-papiers");
+							if let Err(e) = watcher.start().await {
+								error!("Erreur démarrage surveillance presse-papiers: {}", e);
+								return None;
+							}
 						
-						// Passer à l'état Watching avec le receiver
-						Some((
-							Message::None, 
-							ClipboardWatcherState::Watching(watcher, receiver)
-						))
-					},
-					ClipboardWatcherState::Watching(watcher, mut receiver) => {
-						// Attendre le prochain événement du presse-papiers
-						if let Some(item) = receiver.recv().await {
-							debug!("Nouvel élément dans le presse-papiers détecté");
-							
-							// Émettre un événement et continuer d'écouter
+							// Passer à l'état Watching avec le receiver
 							Some((
-								Message::NewClipboardItem(item),
+								Message::None, 
 								ClipboardWatcherState::Watching(watcher, receiver)
 							))
-						} else {
-							// Le canal a été fermé, terminer la surveillance
-							info!("Surveillance du presse-papiers terminée");
-							None
+						},
+						ClipboardWatcherState::Watching(watcher, mut receiver) => {
+							// Attendre le prochain événement du presse-papiers
+							if let Some(item) = receiver.recv().await {
+								debug!("Nouvel élément dans le presse-papiers détecté");
+							
+								// Émettre un événement et continuer d'écouter
+								Some((
+									Message::NewClipboardItem(item),
+									ClipboardWatcherState::Watching(watcher, receiver)
+								))
+							} else {
+								// Le canal a été fermé, terminer la surveillance
+								info!("Surveillance du presse-papiers terminée");
+								None
+							}
 						}
 					}
 				}
@@ -56,4 +88,248 @@ pub fn clipboard_subscription() -> Subscription<Message> {
 enum ClipboardWatcherState {
 	Starting,
 	Watching(ClipboardWatcher, tokio::sync::mpsc::Receiver<crate::clipboard::ClipboardItem>),
-}
\ No newline at end of file
+}
+
+/// Crée un abonnement servant le socket de contrôle
+///
+/// Le serveur tourne dans sa propre tâche ; l'abonnement relaie à l'application
+/// les pauses et reprises demandées par les clients.
+pub fn ipc_subscription(
+	storage: Arc<Mutex<Box<dyn Storage>>>,
+	clipboard: Arc<Mutex<ClipboardManager>>,
+	recording: PauseHandle,
+) -> Subscription<Message> {
+	Subscription::run_with_id("ipc-server", {
+		stream::unfold(
+			IpcServerState::Starting(storage, clipboard, recording),
+			|state| async move {
+				match state {
+					IpcServerState::Starting(storage, clipboard, recording) => {
+						let (sender, receiver) = mpsc::unbounded_channel();
+						let context = IpcContext::new(storage, recording)
+							.with_clipboard(clipboard)
+							.with_recording_changes(sender);
+						match socket_path().and_then(|path| IpcServer::bind(&path, context)) {
+							Ok(server) => {
+								let server = tokio::spawn(server.serve());
+								Some((Message::None, IpcServerState::Serving(server, receiver)))
+							}
+							Err(e) => {
+								error!("Contrôle par socket indisponible: {}", e);
+								None
+							}
+						}
+					}
+					IpcServerState::Serving(server, mut receiver) => {
+						let recording = receiver.recv().await?;
+						Some((Message::RecordingChanged(recording), IpcServerState::Serving(server, receiver)))
+					}
+				}
+			}
+		)
+	})
+}
+
+/// État du serveur du socket de contrôle
+enum IpcServerState {
+	Starting(Arc<Mutex<Box<dyn Storage>>>, Arc<Mutex<ClipboardManager>>, PauseHandle),
+	Serving(tokio::task::JoinHandle<()>, mpsc::UnboundedReceiver<RecordingState>),
+}
+
+/// Crée un abonnement publiant le service D-Bus sur le bus de session
+///
+/// Le service est transmis à l'application, qui l'utilise pour signaler les
+/// copies enregistrées ; l'abonnement relaie les demandes d'affichage de la
+/// fenêtre ainsi que les pauses et reprises demandées sur le bus.
+pub fn dbus_subscription(
+	storage: Arc<Mutex<Box<dyn Storage>>>,
+	clipboard: Arc<Mutex<ClipboardManager>>,
+	recording: PauseHandle,
+) -> Subscription<Message> {
+	Subscription::run_with_id("dbus-service", {
+		stream::unfold(
+			DbusServiceState::Starting(storage, clipboard, recording),
+			|state| async move {
+				match state {
+					DbusServiceState::Starting(storage, clipboard, recording) => {
+						let (window_sender, window_receiver) = mpsc::unbounded_channel();
+						let (recording_sender, recording_receiver) = mpsc::unbounded_channel();
+						let context = IpcContext::new(storage, recording)
+							.with_clipboard(clipboard)
+							.with_recording_changes(recording_sender);
+						let interface = DbusInterface::new(context).with_window_toggles(window_sender);
+						match DbusServer::start(interface).await {
+							Ok(server) => Some((
+								Message::DbusReady(server.clone()),
+								DbusServiceState::Serving(server, window_receiver, recording_receiver),
+							)),
+							Err(e) => {
+								error!("Service D-Bus indisponible: {}", e);
+								None
+							}
+						}
+					}
+					DbusServiceState::Serving(server, mut windows, mut recordings) => {
+						let message = tokio::select! {
+							Some(()) = windows.recv() => Message::ToggleWindow,
+							Some(recording) = recordings.recv() => Message::RecordingChanged(recording),
+							else => return None,
+						};
+						Some((message, DbusServiceState::Serving(server, windows, recordings)))
+					}
+				}
+			}
+		)
+	})
+}
+
+/// État du service D-Bus
+enum DbusServiceState {
+	Starting(Arc<Mutex<Box<dyn Storage>>>, Arc<Mutex<ClipboardManager>>, PauseHandle),
+	Serving(DbusServer, mpsc::UnboundedReceiver<()>, mpsc::UnboundedReceiver<RecordingState>),
+}
+
+/// Crée un abonnement aux relances de l'application
+///
+/// Chaque connexion au socket de l'instance, ouverte par une relance, bascule la fenêtre.
+pub fn window_subscription(instance: Arc<WindowInstance>) -> Subscription<Message> {
+	Subscription::run_with_id("window-instance", {
+		stream::unfold(
+			WindowInstanceState::Starting(instance),
+			|state| async move {
+				let listener = match state {
+					WindowInstanceState::Starting(instance) => match instance.listener() {
+						Ok(listener) => listener,
+						Err(e) => {
+							error!("Relances de l'application non écoutées: {}", e);
+							return None;
+						}
+					},
+					WindowInstanceState::Listening(listener) => listener,
+				};
+
+				loop {
+					match listener.accept().await {
+						Ok(_) => {
+							debug!("Application relancée, bascule de la fenêtre");
+							return Some((Message::ToggleWindow, WindowInstanceState::Listening(listener)));
+						}
+						Err(e) => {
+							error!("Erreur connexion de l'instance: {}", e);
+							tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+						}
+					}
+				}
+			}
+		)
+	})
+}
+
+/// État de l'écoute des relances de l'application
+enum WindowInstanceState {
+	Starting(Arc<WindowInstance>),
+	Listening(tokio::net::UnixListener),
+}
+
+/// Crée un abonnement signalant les modifications du fichier de configuration
+pub fn config_subscription(config_path: PathBuf) -> Subscription<Message> {
+	Subscription::run_with_id(config_path.clone(), {
+		stream::unfold(
+			ConfigWatcherState::Starting(config_path),
+			|state| async move {
+				match state {
+					ConfigWatcherState::Starting(config_path) => {
+						let (sender, receiver) = mpsc::unbounded_channel();
+						let watcher = notify::recommended_watcher(move |result: notify::Result<Event>| {
+							if let Ok(event) = result {
+								let _ = sender.send(event);
+							}
+						});
+
+						// Surveiller le dossier : les éditeurs remplacent souvent le fichier
+						let watch_dir = config_path.parent().map(PathBuf::from).unwrap_or_default();
+						let watcher = watcher.and_then(|mut watcher| {
+							watcher.watch(&watch_dir, RecursiveMode::NonRecursive)?;
+							Ok(watcher)
+						});
+
+						match watcher {
+							Ok(watcher) => {
+								info!("Surveillance de la configuration {}", config_path.display());
+								Some((Message::None, ConfigWatcherState::Watching(config_path, watcher, receiver)))
+							}
+							Err(e) => {
+								error!("Erreur surveillance configuration: {}", e);
+								None
+							}
+						}
+					}
+					ConfigWatcherState::Watching(config_path, watcher, mut receiver) => {
+						while let Some(event) = receiver.recv().await {
+							let relevant = matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_))
+								&& event.paths.iter().any(|path| path.file_name() == config_path.file_name());
+							if !relevant {
+								continue;
+							}
+
+							match Config::load(&config_path) {
+								Ok(config) => {
+									debug!("Configuration rechargée");
+									return Some((
+										Message::ConfigChanged(Box::new(config)),
+										ConfigWatcherState::Watching(config_path, watcher, receiver),
+									));
+								}
+								// Fichier en cours d'écriture : attendre l'événement suivant
+								Err(e) => debug!("Configuration illisible pour l'instant: {}", e),
+							}
+						}
+						None
+					}
+				}
+			}
+		)
+	})
+}
+
+/// État de la surveillance du fichier de configuration
+enum ConfigWatcherState {
+	Starting(PathBuf),
+	Watching(PathBuf, RecommendedWatcher, mpsc::UnboundedReceiver<Event>),
+}
+
+/// Crée un abonnement relayant les modifications du stockage
+///
+/// Un abonné trop en retard a manqué des modifications : l'historique est alors relu.
+pub fn storage_subscription(storage: Arc<Mutex<Box<dyn Storage>>>, generation: u64) -> Subscription<Message> {
+	Subscription::run_with_id(("storage-changes", generation), {
+		stream::unfold(
+			StorageWatcherState::Starting(storage),
+			|state| async move {
+				let mut receiver = match state {
+					StorageWatcherState::Starting(storage) => storage.lock().await.subscribe(),
+					StorageWatcherState::Watching(receiver) => receiver,
+				};
+
+				match receiver.recv().await {
+					Ok(change) => Some((Message::StorageChanged(change), StorageWatcherState::Watching(receiver))),
+					Err(broadcast::error::RecvError::Lagged(skipped)) => {
+						debug!("{} modifications du stockage manquées, rechargement", skipped);
+						let change = StorageChange { reload: true, ..StorageChange::default() };
+						Some((Message::StorageChanged(change), StorageWatcherState::Watching(receiver)))
+					}
+					Err(broadcast::error::RecvError::Closed) => {
+						info!("Surveillance du stockage terminée");
+						None
+					}
+				}
+			}
+		)
+	})
+}
+
+/// État de la surveillance du stockage
+enum StorageWatcherState {
+	Starting(Arc<Mutex<Box<dyn Storage>>>),
+	Watching(StorageEventReceiver),
+}
@@ -1,5 +1,5 @@
 use crate::error::{ClipboardError, ClipboardResult};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::fs;
 use image::{DynamicImage, GenericImageView};
 
@@ -8,11 +8,55 @@ pub fn ensure_dir_exists<P: AsRef<Path>>(path: P) -> ClipboardResult<()> {
 	let path = path.as_ref();
 	if !path.exists() {
 		fs::create_dir_all(path)
-			.map_err(|e| ClipboardError::Io(e))?;
+			.map_err(ClipboardError::Io)?;
 	}
 	Ok(())
 }
 
+/// Dossier d'exécution propre à l'utilisateur : `$XDG_RUNTIME_DIR`
+///
+/// Sans dossier de session, `clipboard-manager-<uid>` est créé dans le dossier
+/// temporaire avec les droits 0700. Un dossier existant qui n'appartient pas à
+/// l'utilisateur ou reste accessible aux autres est refusé : un autre compte
+/// pourrait y intercepter le socket de contrôle.
+pub fn runtime_dir() -> ClipboardResult<PathBuf> {
+	use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+
+	if let Some(dir) = dirs::runtime_dir() {
+		return Ok(dir);
+	}
+
+	// SAFETY: getuid n'a aucune précondition et ne peut échouer
+	let uid = unsafe { libc::getuid() };
+	let dir = std::env::temp_dir().join(format!("clipboard-manager-{}", uid));
+	match fs::DirBuilder::new().mode(0o700).create(&dir) {
+		Ok(()) => {}
+		Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+		Err(e) => return Err(ClipboardError::Io(e)),
+	}
+
+	let metadata = fs::symlink_metadata(&dir)?;
+	if !metadata.is_dir() || metadata.uid() != uid || metadata.mode() & 0o077 != 0 {
+		return Err(ClipboardError::Unexpected(format!(
+			"Dossier d'exécution {} non privé (définir XDG_RUNTIME_DIR)",
+			dir.display()
+		)));
+	}
+	Ok(dir)
+}
+
+/// Exécute `f` avec un masque de création réservant les fichiers à l'utilisateur
+///
+/// Un socket créé par `bind` naît alors en 0600, sans instant où un autre
+/// compte pourrait s'y connecter avant `set_permissions`.
+pub fn with_private_umask<T>(f: impl FnOnce() -> T) -> T {
+	// SAFETY: umask ne fait que remplacer le masque du processus
+	let previous = unsafe { libc::umask(0o177) };
+	let result = f();
+	unsafe { libc::umask(previous) };
+	result
+}
+
 /// Redimensionne une image pour l'affichage dans l'interface
 pub fn resize_image(img: &DynamicImage, max_width: u32, max_height: u32) -> DynamicImage {
 	let (width, height) = img.dimensions();
@@ -58,6 +102,24 @@ pub fn sanitize_text(text: &str, max_length: usize) -> String {
 	format!("{}...", &trimmed[..max_length - 3])
 }
 
+/// Formate une taille en octets pour l'affichage
+pub fn format_size(bytes: u64) -> String {
+	const UNITS: [&str; 4] = ["Ko", "Mo", "Go", "To"];
+
+	if bytes < 1024 {
+		return format!("{} o", bytes);
+	}
+
+	let mut size = bytes as f64 / 1024.0;
+	let mut unit = 0;
+	while size >= 1024.0 && unit < UNITS.len() - 1 {
+		size /= 1024.0;
+		unit += 1;
+	}
+
+	format!("{:.1} {}", size, UNITS[unit])
+}
+
 /// Vérifie que les ressources sont disponibles et crée les dossiers si nécessaire
 pub fn ensure_resources_available() -> ClipboardResult<()> {
 	// Vérifier le dossier des icônes
@@ -72,6 +134,8 @@ pub fn ensure_resources_available() -> ClipboardResult<()> {
 		"pin.svg",
 		"pinned.svg",
 		"use.svg",
+		"file.svg",
+		"folder.svg",
 	];
 	
 	for icon in &icon_files {
@@ -0,0 +1,144 @@
+use clipboard_manager::clipboard::{ClipboardContent, ClipboardItem, ImageMetadata, MimeData};
+use clipboard_manager::storage::codec::INLINE_FORMAT_MAX;
+use clipboard_manager::storage::{SledStorage, SqliteStorage, Storage};
+use std::path::Path;
+
+/// Image 64×64 aux pixels variés
+fn image_item() -> ClipboardItem {
+	let pixels = (0..64 * 64).flat_map(|i| [(i % 256) as u8, (i / 64) as u8, 128, 255]).collect();
+	ClipboardItem::new(ClipboardContent::Image(pixels, ImageMetadata::new(64, 64)))
+}
+
+/// Texte accompagné d'une représentation HTML trop volumineuse pour rester en ligne
+fn rich_text_item() -> ClipboardItem {
+	let html = format!("<p>{}</p>", "x".repeat(INLINE_FORMAT_MAX)).into_bytes();
+	ClipboardItem::new(ClipboardContent::Text("x".repeat(INLINE_FORMAT_MAX)))
+		.with_formats(vec![MimeData::new("text/html", html), MimeData::new("text/plain", b"x".to_vec())])
+}
+
+fn sqlite_blob_count(dir: &Path) -> i64 {
+	let conn = rusqlite::Connection::open(dir.join("history.sqlite3")).unwrap();
+	conn.query_row("SELECT COUNT(*) FROM blobs", [], |row| row.get(0)).unwrap()
+}
+
+/// Vérifie que les données volumineuses ne sont chargées qu'à la demande
+fn assert_round_trip(storage: &dyn Storage) {
+	let image = image_item();
+	let rich = rich_text_item();
+	storage.add_item(image.clone()).unwrap();
+	storage.add_item(rich.clone()).unwrap();
+
+	for listed in storage.get_all_items().unwrap() {
+		match &listed.content {
+			ClipboardContent::Image(data, metadata) => {
+				assert!(data.is_empty());
+				assert!(metadata.blob.is_some());
+				assert!(!metadata.thumbnail.is_empty());
+			}
+			ClipboardContent::Text(_) => {
+				assert!(listed.formats[0].data.is_empty());
+				assert!(listed.formats[0].blob.is_some());
+				// Les petites représentations restent en ligne
+				assert_eq!(listed.formats[1].data, b"x");
+			}
+			other => panic!("contenu inattendu: {:?}", other),
+		}
+	}
+
+	let loaded = storage.get_item(image.id).unwrap().unwrap();
+	match (&loaded.content, &image.content) {
+		(ClipboardContent::Image(loaded, _), ClipboardContent::Image(original, _)) => assert_eq!(loaded, original),
+		other => panic!("contenu inattendu: {:?}", other),
+	}
+	let loaded = storage.get_item(rich.id).unwrap().unwrap();
+	assert_eq!(loaded.formats[0].data, rich.formats[0].data);
+}
+
+#[test]
+fn sled_loads_blobs_on_demand() {
+	let dir = tempfile::tempdir().unwrap();
+	let storage = SledStorage::new(dir.path()).unwrap();
+	storage.init().unwrap();
+
+	assert_round_trip(&storage);
+}
+
+#[test]
+fn sqlite_loads_blobs_on_demand() {
+	let dir = tempfile::tempdir().unwrap();
+	let storage = SqliteStorage::new(dir.path()).unwrap();
+	storage.init().unwrap();
+
+	assert_round_trip(&storage);
+}
+
+#[test]
+fn sqlite_shares_and_collects_blobs() {
+	let dir = tempfile::tempdir().unwrap();
+	let storage = SqliteStorage::new(dir.path()).unwrap();
+	storage.init().unwrap();
+
+	// Deux copies de la même image partagent un seul blob
+	let first = image_item();
+	let mut second = image_item();
+	second.content = first.content.clone();
+	storage.add_item(first.clone()).unwrap();
+	storage.add_item(second.clone()).unwrap();
+	assert_eq!(sqlite_blob_count(dir.path()), 1);
+
+	storage.remove_item(first.id).unwrap();
+	assert_eq!(sqlite_blob_count(dir.path()), 1);
+	assert!(storage.get_item(second.id).unwrap().is_some());
+
+	storage.remove_item(second.id).unwrap();
+	assert_eq!(sqlite_blob_count(dir.path()), 0);
+}
+
+#[test]
+fn a_failed_sqlite_write_leaves_no_blob_behind() {
+	let dir = tempfile::tempdir().unwrap();
+	let storage = SqliteStorage::new(dir.path()).unwrap();
+	storage.init().unwrap();
+
+	// L'écriture de l'élément échoue après celle de ses blobs
+	let conn = rusqlite::Connection::open(dir.path().join("history.sqlite3")).unwrap();
+	conn.execute_batch("CREATE TRIGGER refuse BEFORE INSERT ON items BEGIN SELECT RAISE(ABORT, 'refusé'); END;")
+		.unwrap();
+
+	assert!(storage.add_item(image_item()).is_err());
+	assert_eq!(sqlite_blob_count(dir.path()), 0);
+	let references: i64 = conn.query_row("SELECT COUNT(*) FROM item_blobs", [], |row| row.get(0)).unwrap();
+	assert_eq!(references, 0);
+}
+
+#[test]
+fn sled_shares_and_collects_blobs() {
+	let dir = tempfile::tempdir().unwrap();
+	let first = image_item();
+	let mut second = image_item();
+	second.content = first.content.clone();
+
+	{
+		let storage = SledStorage::new(dir.path()).unwrap();
+		storage.init().unwrap();
+		storage.add_item(first.clone()).unwrap();
+		storage.add_item(second.clone()).unwrap();
+
+		// Épingler réécrit l'élément allégé sans perdre son image
+		let mut light = storage.find_by_hash(&first.hash()).unwrap().unwrap();
+		light.pinned = true;
+		storage.update_item(light).unwrap();
+		assert!(matches!(
+			storage.get_item(first.id).unwrap().unwrap().content,
+			ClipboardContent::Image(data, _) if !data.is_empty()
+		));
+
+		storage.remove_item(first.id).unwrap();
+		storage.remove_item(second.id).unwrap();
+		storage.flush().unwrap();
+	}
+
+	let db = sled::open(dir.path()).unwrap();
+	assert!(db.open_tree("blobs").unwrap().is_empty());
+	assert!(db.open_tree("blob_refs").unwrap().is_empty());
+}
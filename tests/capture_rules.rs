@@ -0,0 +1,137 @@
+use chrono::Duration;
+use clipboard_manager::clipboard::source::{
+	parse_hyprland_window, parse_sway_tree, parse_xprop_active_window, parse_xprop_class,
+};
+use clipboard_manager::clipboard::{CaptureRules, ClipboardContent, ClipboardItem, ImageMetadata, MimeData, SourceApplication};
+use clipboard_manager::config::{CaptureAction, CaptureRule, Config};
+
+fn app(app_id: &str) -> SourceApplication {
+	SourceApplication { app_id: Some(app_id.to_string()), window_class: None }
+}
+
+fn rule(app: &str, action: CaptureAction) -> CaptureRule {
+	CaptureRule { app: app.to_string(), action }
+}
+
+fn rich_text() -> ClipboardItem {
+	ClipboardItem::new(ClipboardContent::Text("relevé de compte".to_string())).with_formats(vec![
+		MimeData::new("text/html", b"<b>relev\xc3\xa9 de compte</b>".to_vec()),
+		MimeData::new("text/plain;charset=utf-8", "relevé de compte".as_bytes().to_vec()),
+	])
+}
+
+#[test]
+fn first_matching_rule_wins() {
+	let config = Config {
+		capture_rules: vec![
+			rule("org.keepassxc.KeePassXC", CaptureAction::Ignore),
+			rule("firefox-banque", CaptureAction::Ignore),
+			rule("firefox-banque", CaptureAction::Store),
+			rule("code", CaptureAction::Expire { minutes: 5 }),
+		],
+		..Config::default()
+	};
+	let rules = CaptureRules::from(&config);
+
+	assert!(rules.needs_source());
+	assert_eq!(rules.action_for(Some(&app("org.keepassxc.KeePassXC"))), CaptureAction::Ignore);
+	assert_eq!(rules.action_for(Some(&app("Firefox-Banque"))), CaptureAction::Ignore);
+	let xwayland = SourceApplication { app_id: None, window_class: Some("Code".to_string()) };
+	assert_eq!(rules.action_for(Some(&xwayland)), CaptureAction::Expire { minutes: 5 });
+	assert_eq!(rules.action_for(Some(&app("org.gnome.TextEditor"))), CaptureAction::Store);
+	assert_eq!(rules.action_for(None), CaptureAction::Store);
+}
+
+#[test]
+fn allow_list_ignores_unknown_sources() {
+	let config = Config {
+		capture_rules: vec![rule("org.gnome.TextEditor", CaptureAction::Store)],
+		default_capture: CaptureAction::Ignore,
+		..Config::default()
+	};
+	let rules = CaptureRules::from(&config);
+	assert_eq!(rules.action_for(Some(&app("org.gnome.TextEditor"))), CaptureAction::Store);
+	assert_eq!(rules.action_for(Some(&app("firefox"))), CaptureAction::Ignore);
+	assert_eq!(rules.action_for(None), CaptureAction::Ignore);
+	assert!(!CaptureRules::default().needs_source());
+}
+
+#[test]
+fn actions_shape_the_stored_item() {
+	assert!(CaptureRules::apply(CaptureAction::Ignore, rich_text()).is_none());
+
+	let text_only = CaptureRules::apply(CaptureAction::TextOnly, rich_text()).unwrap();
+	assert_eq!(text_only.formats.len(), 1);
+	assert_eq!(text_only.preferred_mime.as_deref(), Some("text/plain;charset=utf-8"));
+	let image = ClipboardItem::new(ClipboardContent::Image(vec![0; 4], ImageMetadata::new(1, 1)));
+	assert!(CaptureRules::apply(CaptureAction::TextOnly, image).is_none());
+
+	let item = rich_text();
+	let expiring = CaptureRules::apply(CaptureAction::Expire { minutes: 10 }, item.clone()).unwrap();
+	assert_eq!(expiring.expires_at, Some(item.timestamp + Duration::minutes(10)));
+
+	// Une expiration plus proche est conservée
+	let mut secret = rich_text();
+	secret.expires_at = Some(secret.timestamp + Duration::minutes(1));
+	let kept = CaptureRules::apply(CaptureAction::Expire { minutes: 10 }, secret.clone()).unwrap();
+	assert_eq!(kept.expires_at, secret.expires_at);
+}
+
+#[test]
+fn rules_round_trip_through_config_file() {
+	let json = r#"{
+		"capture_rules": [
+			{ "app": "org.keepassxc.KeePassXC", "action": "Ignore" },
+			{ "app": "code", "action": { "Expire": { "minutes": 30 } } }
+		],
+		"default_capture": "TextOnly"
+	}"#;
+	let config: Config = serde_json::from_str(json).unwrap();
+	assert_eq!(config.capture_rules[1], rule("code", CaptureAction::Expire { minutes: 30 }));
+	assert_eq!(config.default_capture, CaptureAction::TextOnly);
+}
+
+#[test]
+fn hyprland_window_is_parsed() {
+	let native = r#"{"address": "0x5", "class": "org.keepassxc.KeePassXC", "title": "Coffre", "xwayland": false}"#;
+	assert_eq!(parse_hyprland_window(native), Some(app("org.keepassxc.KeePassXC")));
+
+	let xwayland = r#"{"class": "Code", "xwayland": true}"#;
+	assert_eq!(
+		parse_hyprland_window(xwayland),
+		Some(SourceApplication { app_id: None, window_class: Some("Code".to_string()) })
+	);
+	assert_eq!(parse_hyprland_window("{}"), None);
+}
+
+#[test]
+fn sway_focused_node_is_found() {
+	let tree = r#"{
+		"focused": false,
+		"nodes": [{
+			"focused": false,
+			"nodes": [
+				{ "focused": false, "app_id": "foot", "nodes": [] },
+				{ "focused": false, "nodes": [], "floating_nodes": [
+					{ "focused": true, "app_id": null, "window_properties": { "class": "Firefox", "instance": "Navigator" } }
+				] }
+			]
+		}]
+	}"#;
+	assert_eq!(
+		parse_sway_tree(tree),
+		Some(SourceApplication { app_id: None, window_class: Some("Firefox".to_string()) })
+	);
+	assert_eq!(parse_sway_tree(r#"{"focused": false, "nodes": []}"#), None);
+}
+
+#[test]
+fn xprop_output_is_parsed() {
+	assert_eq!(
+		parse_xprop_active_window("_NET_ACTIVE_WINDOW(WINDOW): window id # 0x3a00007\n"),
+		Some("0x3a00007".to_string())
+	);
+	assert_eq!(parse_xprop_active_window("_NET_ACTIVE_WINDOW(WINDOW): window id # 0x0\n"), None);
+	assert_eq!(parse_xprop_class("WM_CLASS(STRING) = \"navigator\", \"firefox\"\n"), Some("firefox".to_string()));
+	assert_eq!(parse_xprop_class("WM_CLASS:  not found.\n"), None);
+}
@@ -0,0 +1,122 @@
+mod common;
+
+use clipboard_manager::cli::{export_items, import_items, Command, HistoryStats, ItemRef};
+use clipboard_manager::clipboard::{ClipboardContent, ClipboardItem, ImageMetadata, Selection};
+use clipboard_manager::config::Config;
+use clipboard_manager::storage::{SqliteStorage, Storage};
+use common::text_item;
+use uuid::Uuid;
+
+fn args(line: &str) -> Vec<String> {
+	line.split_whitespace().map(str::to_string).collect()
+}
+
+fn sqlite_storage(dir: &tempfile::TempDir) -> SqliteStorage {
+	let storage = SqliteStorage::new(dir.path()).unwrap();
+	storage.init().unwrap();
+	storage
+}
+
+#[test]
+fn subcommands_are_parsed() {
+	assert_eq!(Command::parse(&args("")).unwrap(), None);
+	assert_eq!(Command::parse(&args("--paused")).unwrap(), None);
+	assert_eq!(Command::parse(&args("daemon --paused")).unwrap(), None);
+
+	assert_eq!(
+		Command::parse(&args("search facture --limit 5 --json")).unwrap(),
+		Some(Command::Search { query: "facture".to_string(), limit: 5, json: true })
+	);
+	assert_eq!(
+		Command::parse(&args("copy 3 --primary")).unwrap(),
+		Some(Command::Copy { item: ItemRef::Index(3), selection: Selection::Primary })
+	);
+
+	let id = Uuid::new_v4();
+	assert_eq!(
+		Command::parse(&args(&format!("unpin {}", id))).unwrap(),
+		Some(Command::Pin { item: ItemRef::Id(id), pinned: false })
+	);
+
+	assert!(Command::parse(&args("get")).is_err());
+	assert!(Command::parse(&args("rm 0")).is_err());
+	assert!(Command::parse(&args("list --limit beaucoup")).is_err());
+	assert!(Command::parse(&args("frobnicate")).is_err());
+}
+
+#[test]
+fn unknown_options_and_extra_arguments_are_rejected() {
+	let error = Command::parse(&args("list --jsno")).unwrap_err().to_string();
+	assert!(error.contains("Option inconnue: --jsno"), "{}", error);
+	assert!(error.contains("Utilisation : clipboard-manager"), "{}", error);
+
+	let error = Command::parse(&args("rm 3 4")).unwrap_err().to_string();
+	assert!(error.contains("Argument en trop pour rm: 4"), "{}", error);
+	assert!(Command::parse(&args("clear tout")).is_err());
+	assert!(Command::parse(&args("export a.json b.json")).is_err());
+
+	// Seul `--paused` accompagne le lancement de l'interface ou du démon
+	let error = Command::parse(&args("--pasued")).unwrap_err().to_string();
+	assert!(error.contains("Option inconnue: --pasued"), "{}", error);
+	assert!(error.contains("Utilisation : clipboard-manager"), "{}", error);
+	assert!(Command::parse(&args("--paused --verbose")).is_err());
+	assert!(Command::parse(&args("--paused list")).is_err());
+	assert!(Command::parse(&args("daemon --foreground")).is_err());
+	assert!(Command::parse(&args("daemon maintenant")).is_err());
+	assert_eq!(Command::parse(&args("--help")).unwrap(), Some(Command::Help));
+
+	assert_eq!(
+		Command::parse(&args("export historique.json")).unwrap(),
+		Some(Command::Export(Some("historique.json".into())))
+	);
+}
+
+#[test]
+fn export_and_import_round_trip() {
+	let source_dir = tempfile::tempdir().unwrap();
+	let source = sqlite_storage(&source_dir);
+	source.add_item(text_item("première copie")).unwrap();
+	let mut pinned = text_item("copie épinglée");
+	pinned.pinned = true;
+	source.add_item(pinned).unwrap();
+	let image = ClipboardItem::new(ClipboardContent::Image(vec![255; 4 * 4 * 4], ImageMetadata::new(4, 4)));
+	let image = source.add_item(image).unwrap();
+
+	let mut export = Vec::new();
+	assert_eq!(export_items(&source, &mut export).unwrap(), 3);
+
+	let target_dir = tempfile::tempdir().unwrap();
+	let target = sqlite_storage(&target_dir);
+	target.add_item(text_item("première copie")).unwrap();
+	assert_eq!(import_items(&target, export.as_slice()).unwrap(), (2, 1));
+	assert_eq!(import_items(&target, export.as_slice()).unwrap(), (0, 3));
+
+	assert_eq!(target.count_items().unwrap(), 3);
+	let imported = target.get_item(image.id).unwrap().unwrap();
+	match imported.content {
+		ClipboardContent::Image(pixels, metadata) => {
+			assert_eq!(pixels.len(), 4 * 4 * 4);
+			assert_eq!((metadata.width, metadata.height), (4, 4));
+		}
+		_ => panic!("image attendue"),
+	}
+}
+
+#[test]
+fn stats_summarize_the_history() {
+	let dir = tempfile::tempdir().unwrap();
+	let storage = sqlite_storage(&dir);
+	let mut pinned = text_item("épinglé");
+	pinned.pinned = true;
+	storage.add_item(pinned).unwrap();
+	let mut expiring = text_item("jeton");
+	expiring.expires_at = Some(chrono::Utc::now() + chrono::Duration::hours(1));
+	storage.add_item(expiring).unwrap();
+
+	let config = Config { data_dir: dir.path().to_path_buf(), ..Config::default() };
+	let stats = HistoryStats::collect(&storage, &config).unwrap();
+	assert_eq!((stats.items, stats.texts, stats.images), (2, 2, 0));
+	assert_eq!((stats.pinned, stats.expiring), (1, 1));
+	assert!(stats.oldest <= stats.newest);
+	assert!(stats.disk_usage > 0);
+}
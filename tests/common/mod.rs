@@ -0,0 +1,42 @@
+// Chaque fichier de test n'utilise qu'une partie de ces aides
+#![allow(dead_code)]
+
+use clipboard_manager::clipboard::{ClipboardContent, ClipboardItem};
+use clipboard_manager::storage::{RetentionPolicy, SqliteStorage, Storage};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Contenu texte
+pub fn text(text: &str) -> ClipboardContent {
+	ClipboardContent::Text(text.to_string())
+}
+
+/// Élément texte copié à l'instant
+pub fn text_item(text: &str) -> ClipboardItem {
+	ClipboardItem::new(self::text(text))
+}
+
+/// Politique de conservation sans aucune limite
+pub fn no_limits() -> RetentionPolicy {
+	RetentionPolicy { max_items: None, max_age: None, max_image_bytes: None }
+}
+
+/// Historique SQLite de test, partagé comme dans l'application
+pub fn shared_storage(dir: &tempfile::TempDir) -> Arc<Mutex<Box<dyn Storage>>> {
+	let storage = SqliteStorage::new(dir.path()).unwrap();
+	storage.init().unwrap();
+	Arc::new(Mutex::new(Box::new(storage)))
+}
+
+/// Textes de l'historique, du plus récent au plus ancien
+pub fn texts(storage: &dyn Storage) -> Vec<String> {
+	storage
+		.get_all_items()
+		.unwrap()
+		.into_iter()
+		.filter_map(|item| match item.content {
+			ClipboardContent::Text(text) => Some(text),
+			_ => None,
+		})
+		.collect()
+}
@@ -0,0 +1,124 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use clipboard_manager::clipboard::{ClipboardContent, ClipboardItem, MemoryClipboard, Selection};
+use clipboard_manager::config::{Config, DedupPolicy};
+use clipboard_manager::daemon::{Daemon, RecorderLock};
+use clipboard_manager::storage::{prune, record_copy, MemoryStorage, RetentionPolicy, SqliteStorage, Storage};
+use common::{no_limits, text_item};
+use std::sync::Arc;
+use tokio::sync::{oneshot, Mutex};
+
+#[test]
+fn only_one_process_records_the_history() {
+	let dir = tempfile::tempdir().unwrap();
+
+	let recorder = RecorderLock::try_acquire(dir.path()).unwrap();
+	assert!(recorder.is_some());
+	assert!(RecorderLock::try_acquire(dir.path()).unwrap().is_none());
+
+	drop(recorder);
+	assert!(RecorderLock::try_acquire(dir.path()).unwrap().is_some());
+}
+
+#[test]
+fn recorded_copies_are_visible_to_other_readers() {
+	let dir = tempfile::tempdir().unwrap();
+	let recorder = SqliteStorage::new(dir.path()).unwrap();
+	recorder.init().unwrap();
+	let reader = SqliteStorage::new(dir.path()).unwrap();
+
+	let retention = RetentionPolicy { max_items: Some(2), ..no_limits() };
+	for text in ["un", "deux", "trois"] {
+		record_copy(&recorder, text_item(text), DedupPolicy::MoveToTop, &retention).unwrap();
+	}
+
+	let (change, report) = record_copy(&recorder, text_item("quatre"), DedupPolicy::MoveToTop, &retention).unwrap();
+	assert_eq!(change.upserted.len(), 1);
+	assert_eq!(report.removed.len(), 1);
+	assert_eq!(change.total, 2);
+
+	let texts: Vec<_> = reader
+		.get_items(0, 10)
+		.unwrap()
+		.items
+		.into_iter()
+		.filter_map(|item| match item.content {
+			ClipboardContent::Text(text) => Some(text),
+			_ => None,
+		})
+		.collect();
+	assert_eq!(texts, ["quatre", "trois"]);
+}
+
+#[test]
+fn next_expiry_drives_pruning() {
+	let dir = tempfile::tempdir().unwrap();
+	let storage = SqliteStorage::new(dir.path()).unwrap();
+	storage.init().unwrap();
+	assert_eq!(storage.next_expiry().unwrap(), None);
+
+	let soon = Utc::now() + Duration::minutes(1);
+	let mut expiring = text_item("code à usage unique");
+	expiring.expires_at = Some(soon);
+	let mut pinned = text_item("épinglé");
+	pinned.pinned = true;
+	pinned.expires_at = Some(Utc::now() - Duration::minutes(1));
+	storage.add_item(expiring).unwrap();
+	storage.add_item(pinned).unwrap();
+	assert_eq!(storage.next_expiry().unwrap(), Some(soon));
+
+	let mut lapsed = text_item("jeton expiré");
+	lapsed.expires_at = Some(Utc::now() - Duration::seconds(1));
+	let lapsed = storage.add_item(lapsed).unwrap();
+
+	let (change, report) = prune(&storage, &no_limits()).unwrap();
+	assert_eq!(change.removed, [lapsed.id]);
+	assert_eq!(report.lapsed, [(lapsed.selection, lapsed.hash())]);
+	assert_eq!(change.total, 2);
+}
+
+/// Attend que l'historique vérifie `condition`, au plus cinq secondes
+async fn wait_for(history: &Arc<Mutex<Box<dyn Storage>>>, condition: impl Fn(&[ClipboardItem]) -> bool) {
+	let deadline = tokio::time::Instant::now() + std::time::Duration::from_secs(5);
+	loop {
+		let items = history.lock().await.get_all_items().unwrap();
+		if condition(&items) {
+			return;
+		}
+		assert!(tokio::time::Instant::now() < deadline, "historique inattendu: {:?}", items);
+		tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+	}
+}
+
+fn contains(items: &[ClipboardItem], text: &str) -> bool {
+	items.iter().any(|item| item.content == ClipboardContent::Text(text.to_string()))
+}
+
+#[tokio::test]
+async fn the_daemon_records_copies_and_prunes_expired_items() {
+	let clipboard = MemoryClipboard::new();
+	let storage = MemoryStorage::new();
+	let mut expiring = text_item("jeton");
+	expiring.expires_at = Some(Utc::now() + Duration::milliseconds(300));
+	storage.add_item(expiring).unwrap();
+	clipboard.copy(Selection::Clipboard, ClipboardContent::Text("jeton".to_string()));
+
+	let config = Config { check_interval_ms: 10, adaptive_polling: false, ..Config::default() };
+	let daemon = Daemon::with_history(config, Box::new(storage), clipboard.clone());
+	let history = daemon.history();
+	let (stop, stopped) = oneshot::channel::<()>();
+	let recording = tokio::spawn(daemon.record_until(async {
+		let _ = stopped.await;
+	}));
+
+	clipboard.copy(Selection::Clipboard, ClipboardContent::Text("copie".to_string()));
+	wait_for(&history, |items| contains(items, "copie")).await;
+
+	// L'expiration réveille le démon, qui retire l'élément de l'historique
+	wait_for(&history, |items| !contains(items, "jeton")).await;
+	assert_eq!(history.lock().await.next_expiry().unwrap(), None);
+
+	stop.send(()).unwrap();
+	recording.await.unwrap().unwrap();
+}
@@ -0,0 +1,85 @@
+mod common;
+
+use clipboard_manager::clipboard::{
+	ClipboardContent, ClipboardEventReceiver, ClipboardWatcher, MemoryClipboard, PollingSettings, Selection,
+	SelectionNotifier,
+};
+use clipboard_manager::config::WatcherBackend;
+use clipboard_manager::error::ClipboardError;
+use common::text;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Surveillant du presse-papiers en mémoire, dont la scrutation est rapide si elle prend le relais
+fn watcher(clipboard: &MemoryClipboard) -> ClipboardWatcher<MemoryClipboard> {
+	let polling = Arc::new(tokio::sync::Mutex::new(PollingSettings {
+		interval: Duration::from_millis(10),
+		max_interval: Duration::from_millis(10),
+		adaptive: false,
+	}));
+	let fake = clipboard.clone();
+	ClipboardWatcher::with_polling(WatcherBackend::Auto, false, polling).with_clipboard(move || Ok(fake.clone()))
+}
+
+async fn next_copy(receiver: &mut ClipboardEventReceiver) -> ClipboardContent {
+	tokio::time::timeout(Duration::from_secs(5), receiver.recv())
+		.await
+		.expect("aucune copie détectée")
+		.unwrap()
+		.content
+}
+
+#[tokio::test]
+async fn copies_are_read_when_the_compositor_notifies_them() {
+	let clipboard = MemoryClipboard::new();
+	let notifier: Arc<Mutex<Option<SelectionNotifier>>> = Arc::new(Mutex::new(None));
+	let registered = notifier.clone();
+	let mut watcher = watcher(&clipboard).with_selection_events(WatcherBackend::DataControl, move |sender, _| {
+		*registered.lock().unwrap() = Some(sender);
+		Ok(())
+	});
+	let mut copies = watcher.take_receiver().unwrap();
+	watcher.start().await.unwrap();
+	let notify = notifier.lock().unwrap().clone().expect("écoute non démarrée");
+
+	// Sans notification, le presse-papiers n'est pas relu
+	clipboard.copy(Selection::Clipboard, text("copie silencieuse"));
+	tokio::time::sleep(Duration::from_millis(100)).await;
+	assert!(copies.try_recv().is_err());
+
+	clipboard.copy(Selection::Clipboard, text("copie signalée"));
+	notify.send(Selection::Clipboard).unwrap();
+	assert_eq!(next_copy(&mut copies).await, text("copie signalée"));
+
+	// Une notification sans nouveau contenu n'enregistre rien
+	notify.send(Selection::Clipboard).unwrap();
+	tokio::time::sleep(Duration::from_millis(100)).await;
+	assert!(copies.try_recv().is_err());
+	watcher.stop().await;
+}
+
+#[tokio::test]
+async fn auto_falls_back_to_polling_without_data_control() {
+	let clipboard = MemoryClipboard::new();
+	let mut watcher = watcher(&clipboard).with_selection_events(WatcherBackend::Auto, |_, _| {
+		Err(ClipboardError::Clipboard("Data-control indisponible".to_string()))
+	});
+	let mut copies = watcher.take_receiver().unwrap();
+	watcher.start().await.unwrap();
+
+	clipboard.copy(Selection::Clipboard, text("copie scrutée"));
+	assert_eq!(next_copy(&mut copies).await, text("copie scrutée"));
+	watcher.stop().await;
+}
+
+#[tokio::test]
+async fn data_control_alone_fails_without_the_protocol() {
+	let clipboard = MemoryClipboard::new();
+	let watcher = watcher(&clipboard).with_selection_events(WatcherBackend::DataControl, |_, _| {
+		Err(ClipboardError::Clipboard("Data-control indisponible".to_string()))
+	});
+	assert!(watcher.start().await.is_err());
+
+	// L'échec laisse le surveillant prêt à être redémarré
+	assert!(watcher.start().await.is_err());
+}
@@ -0,0 +1,114 @@
+mod common;
+
+use clipboard_manager::clipboard::RecordingState;
+use clipboard_manager::ipc::dbus::{BusItem, BUS_NAME, INTERFACE_NAME, OBJECT_PATH};
+use clipboard_manager::ipc::{DbusInterface, DbusServer, IpcContext};
+use common::{shared_storage, text_item};
+use iced::futures::StreamExt;
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+use std::sync::Arc;
+use tokio::sync::{mpsc, Mutex};
+use zbus::Proxy;
+
+/// Bus de session privé, arrêté à la fin du test
+struct PrivateBus {
+	daemon: Child,
+	address: String,
+}
+
+impl PrivateBus {
+	/// Lance `dbus-daemon`, indispensable à ces tests
+	fn start() -> Self {
+		let mut daemon = Command::new("dbus-daemon")
+			.args(["--session", "--nofork", "--print-address"])
+			.stdout(Stdio::piped())
+			.stderr(Stdio::null())
+			.spawn()
+			.unwrap_or_else(|e| panic!("dbus-daemon requis par les tests D-Bus (paquet dbus): {}", e));
+		let mut address = String::new();
+		BufReader::new(daemon.stdout.take().unwrap()).read_line(&mut address).unwrap();
+		assert!(!address.trim().is_empty(), "dbus-daemon n'a pas donné d'adresse");
+		Self { daemon, address: address.trim().to_string() }
+	}
+
+	/// Proxy client sur le service publié
+	async fn proxy(&self) -> Proxy<'static> {
+		let connection = zbus::connection::Builder::address(self.address.as_str()).unwrap().build().await.unwrap();
+		Proxy::new(&connection, BUS_NAME, OBJECT_PATH, INTERFACE_NAME).await.unwrap()
+	}
+}
+
+impl Drop for PrivateBus {
+	fn drop(&mut self) {
+		let _ = self.daemon.kill();
+		let _ = self.daemon.wait();
+	}
+}
+
+#[tokio::test]
+async fn history_and_recording_are_exposed_on_the_bus() {
+	let bus = PrivateBus::start();
+	let dir = tempfile::tempdir().unwrap();
+	let storage = shared_storage(&dir);
+	let second = {
+		let storage = storage.lock().await;
+		storage.add_item(text_item("première copie")).unwrap();
+		storage.add_item(text_item("Seconde copie")).unwrap()
+	};
+
+	let recording = Arc::new(Mutex::new(RecordingState::Recording));
+	let (sender, mut changes) = mpsc::unbounded_channel();
+	let context = IpcContext::new(storage, recording.clone()).with_recording_changes(sender);
+	let _server = DbusServer::start_at(&bus.address, DbusInterface::new(context)).await.unwrap();
+	let proxy = bus.proxy().await;
+
+	let (items, total): (Vec<BusItem>, u32) = proxy.call("List", &(0u32, 10u32)).await.unwrap();
+	assert_eq!(total, 2);
+	assert_eq!(items[0].id, second.id.to_string());
+	assert_eq!((items[0].kind.as_str(), items[0].preview.as_str()), ("text", "Seconde copie"));
+
+	let (found, total): (Vec<BusItem>, u32) = proxy.call("Search", &("SECONDE", 10u32)).await.unwrap();
+	assert_eq!((found.len(), total), (1, 1));
+
+	let (paused, until): (bool, i64) = proxy.call("Pause", &(5u32,)).await.unwrap();
+	assert!(paused && until > 0);
+	assert!(recording.lock().await.is_paused());
+	assert!(changes.recv().await.unwrap().is_paused());
+
+	let (paused, until): (bool, i64) = proxy.call("Resume", &()).await.unwrap();
+	assert_eq!((paused, until), (false, 0));
+	assert_eq!(*recording.lock().await, RecordingState::Recording);
+
+	// Sans presse-papiers, la restauration échoue proprement
+	let error = proxy.call::<_, _, ()>("Restore", &(second.id.to_string(), false)).await.unwrap_err();
+	assert!(matches!(error, zbus::Error::MethodError(..)));
+	let error = proxy.call::<_, _, ()>("Restore", &("pas-un-identifiant", false)).await.unwrap_err();
+	assert!(matches!(error, zbus::Error::MethodError(..)));
+}
+
+#[tokio::test]
+async fn recorded_copies_and_window_toggles_are_signalled() {
+	let bus = PrivateBus::start();
+	let dir = tempfile::tempdir().unwrap();
+	let context = IpcContext::new(shared_storage(&dir), Arc::new(Mutex::new(RecordingState::Recording)));
+	let (sender, mut toggles) = mpsc::unbounded_channel();
+	let server = DbusServer::start_at(&bus.address, DbusInterface::new(context).with_window_toggles(sender))
+		.await
+		.unwrap();
+	let proxy = bus.proxy().await;
+
+	let mut signals = proxy.receive_signal("ItemAdded").await.unwrap();
+	let item = text_item("copie signalée");
+	server.item_added(&item).await.unwrap();
+	let signal = signals.next().await.unwrap();
+	let (added,): (BusItem,) = signal.body().deserialize().unwrap();
+	assert_eq!(added, BusItem::from(&item));
+
+	proxy.call::<_, _, ()>("ToggleWindow", &()).await.unwrap();
+	assert_eq!(toggles.recv().await, Some(()));
+
+	// Le nom du service ne peut être pris deux fois
+	let other = IpcContext::new(shared_storage(&dir), Arc::new(Mutex::new(RecordingState::Recording)));
+	assert!(DbusServer::start_at(&bus.address, DbusInterface::new(other)).await.is_err());
+}
@@ -0,0 +1,106 @@
+mod common;
+
+use chrono::Duration;
+use clipboard_manager::clipboard::{ClipboardContent, ClipboardItem, ImageMetadata};
+use clipboard_manager::config::DedupPolicy;
+use clipboard_manager::storage::{MemoryStorage, SledStorage, SqliteStorage, Storage};
+use common::{text_item, texts};
+
+/// Enregistre « un », « deux » puis de nouveau « un », une seconde d'intervalle
+fn record_twice(storage: &dyn Storage, policy: DedupPolicy) -> (ClipboardItem, ClipboardItem) {
+	let first = storage.record_item(text_item("un"), policy).unwrap();
+	let mut other = text_item("deux");
+	other.timestamp = first.timestamp + Duration::seconds(1);
+	storage.record_item(other, policy).unwrap();
+	let mut again = text_item("un");
+	again.timestamp = first.timestamp + Duration::seconds(2);
+	(first, storage.record_item(again, policy).unwrap())
+}
+
+fn assert_move_to_top(storage: &dyn Storage) {
+	let (first, again) = record_twice(storage, DedupPolicy::MoveToTop);
+	assert_eq!(again.id, first.id);
+	assert_eq!(again.copy_count, 2);
+	assert_eq!(texts(storage), ["un", "deux"]);
+}
+
+fn assert_update_in_place(storage: &dyn Storage) {
+	let (first, again) = record_twice(storage, DedupPolicy::UpdateInPlace);
+	assert_eq!((again.id, again.timestamp), (first.id, first.timestamp));
+	assert_eq!(again.copy_count, 2);
+	assert_eq!(texts(storage), ["deux", "un"]);
+}
+
+fn assert_keep_duplicates(storage: &dyn Storage) {
+	let (first, again) = record_twice(storage, DedupPolicy::KeepDuplicates);
+	assert_ne!(again.id, first.id);
+	assert_eq!(texts(storage), ["un", "deux", "un"]);
+	assert_eq!(storage.find_by_hash(&first.hash()).unwrap().map(|item| item.id), Some(again.id));
+
+	// Supprimer la copie la plus récente laisse la précédente retrouvable
+	storage.remove_item(again.id).unwrap();
+	assert_eq!(storage.find_by_hash(&first.hash()).unwrap().map(|item| item.id), Some(first.id));
+	storage.remove_item(first.id).unwrap();
+	assert_eq!(storage.find_by_hash(&first.hash()).unwrap(), None);
+}
+
+/// Applique une vérification à chaque moteur de stockage
+fn for_each_backend(check: fn(&dyn Storage)) {
+	let dir = tempfile::tempdir().unwrap();
+	let sqlite = SqliteStorage::new(dir.path()).unwrap();
+	sqlite.init().unwrap();
+	check(&sqlite);
+
+	let dir = tempfile::tempdir().unwrap();
+	let sled = SledStorage::new(dir.path()).unwrap();
+	sled.init().unwrap();
+	check(&sled);
+
+	check(&MemoryStorage::new());
+}
+
+#[test]
+fn move_to_top_raises_the_existing_item() {
+	for_each_backend(assert_move_to_top);
+}
+
+#[test]
+fn update_in_place_keeps_the_existing_position() {
+	for_each_backend(assert_update_in_place);
+}
+
+#[test]
+fn keep_duplicates_indexes_every_copy() {
+	for_each_backend(assert_keep_duplicates);
+}
+
+#[test]
+fn duplicates_stay_indexed_after_reopening_sled() {
+	let dir = tempfile::tempdir().unwrap();
+	let (first, again) = {
+		let sled = SledStorage::new(dir.path()).unwrap();
+		sled.init().unwrap();
+		record_twice(&sled, DedupPolicy::KeepDuplicates)
+	};
+
+	let sled = SledStorage::new(dir.path()).unwrap();
+	sled.init().unwrap();
+	sled.remove_item(again.id).unwrap();
+	assert_eq!(sled.find_by_hash(&first.hash()).unwrap().map(|item| item.id), Some(first.id));
+}
+
+#[test]
+fn the_quick_key_tells_images_apart_without_hashing_them() {
+	let image = |fill: u8| ClipboardContent::Image(vec![fill; 1920 * 1080 * 4], ImageMetadata::new(1920, 1080));
+	assert_eq!(image(0).quick_key(), image(0).quick_key());
+	assert_ne!(image(0).quick_key(), image(255).quick_key());
+
+	let ClipboardContent::Image(mut data, metadata) = image(0) else {
+		unreachable!()
+	};
+	data[0] = 1;
+	assert_ne!(ClipboardContent::Image(data, metadata).quick_key(), image(0).quick_key());
+
+	let text = |text: &str| ClipboardContent::Text(text.to_string());
+	assert_ne!(text("un").quick_key(), text("deux").quick_key());
+}
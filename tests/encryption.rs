@@ -0,0 +1,309 @@
+use clipboard_manager::clipboard::{ClipboardContent, ClipboardItem, ImageMetadata, MimeData};
+use clipboard_manager::config::{Config, DedupPolicy, KeySource};
+use clipboard_manager::storage::codec::INLINE_FORMAT_MAX;
+use clipboard_manager::storage::{
+	open_storage, EncryptedStorage, MemoryStorage, SledStorage, SqliteStorage, Storage, Vault, VaultHandle,
+};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+const SECRET_TEXT: &str = "mot de passe: correct horse battery staple";
+const SECRET_HTML_MARKER: &str = "jeton-d-acces-tres-confidentiel";
+
+/// Texte secret, accompagné d'une représentation HTML rangée dans un blob
+fn secret_item() -> ClipboardItem {
+	let html = format!("<p>{}{}</p>", SECRET_HTML_MARKER, " ".repeat(INLINE_FORMAT_MAX)).into_bytes();
+	ClipboardItem::new(ClipboardContent::Text(SECRET_TEXT.to_string()))
+		.with_formats(vec![MimeData::new("text/html", html)])
+}
+
+fn image_item() -> ClipboardItem {
+	let pixels = (0..32 * 32).flat_map(|i| [(i % 256) as u8, (i / 32) as u8, 64, 255]).collect();
+	ClipboardItem::new(ClipboardContent::Image(pixels, ImageMetadata::new(32, 32)))
+}
+
+fn keyfile_vault(data_dir: &Path, keyfile: PathBuf) -> VaultHandle {
+	let vault = Arc::new(Vault::new(data_dir, KeySource::Keyfile, keyfile));
+	vault.unlock(None).unwrap();
+	vault
+}
+
+fn encrypted(inner: Box<dyn Storage>, vault: VaultHandle) -> EncryptedStorage {
+	let storage = EncryptedStorage::new(inner, vault);
+	storage.init().unwrap();
+	storage
+}
+
+/// Indique si un fichier du dossier contient `needle`
+fn found_on_disk(dir: &Path, needle: &[u8]) -> bool {
+	std::fs::read_dir(dir).unwrap().any(|entry| {
+		let path = entry.unwrap().path();
+		if path.is_dir() {
+			return found_on_disk(&path, needle);
+		}
+		let content = std::fs::read(&path).unwrap();
+		content.windows(needle.len()).any(|window| window == needle)
+	})
+}
+
+/// Enregistre des éléments secrets, relit le disque puis rouvre l'historique
+fn assert_nothing_readable(open_inner: impl Fn(&Path) -> Box<dyn Storage>) {
+	let dir = tempfile::tempdir().unwrap();
+	let keys = tempfile::tempdir().unwrap();
+	let keyfile = keys.path().join("history.key");
+
+	let secret = secret_item();
+	let image = image_item();
+	{
+		let storage = encrypted(open_inner(dir.path()), keyfile_vault(dir.path(), keyfile.clone()));
+		storage.add_item(secret.clone()).unwrap();
+		storage.add_item(image.clone()).unwrap();
+		storage.flush().unwrap();
+	}
+
+	assert!(!found_on_disk(dir.path(), SECRET_TEXT.as_bytes()));
+	assert!(!found_on_disk(dir.path(), SECRET_HTML_MARKER.as_bytes()));
+	assert!(!found_on_disk(dir.path(), secret.content_hash.as_bytes()));
+	assert!(!found_on_disk(dir.path(), image.content_hash.as_bytes()));
+
+	let storage = encrypted(open_inner(dir.path()), keyfile_vault(dir.path(), keyfile));
+	let loaded = storage.get_item(secret.id).unwrap().unwrap();
+	assert_eq!(loaded.content.content_hash(), secret.content.content_hash());
+	assert_eq!(loaded.formats[0].data, secret.formats[0].data);
+	let loaded = storage.get_item(image.id).unwrap().unwrap();
+	assert_eq!(loaded.content.content_hash(), image.content.content_hash());
+
+	// La liste porte les éléments allégés, aperçu compris
+	let listed = storage.get_all_items().unwrap();
+	assert_eq!(listed.len(), 2);
+	assert!(listed.iter().any(|item| matches!(&item.content,
+		ClipboardContent::Image(data, metadata) if data.is_empty() && !metadata.thumbnail.is_empty())));
+}
+
+#[test]
+fn sqlite_writes_nothing_readable() {
+	assert_nothing_readable(|dir| {
+		let storage = SqliteStorage::new(dir).unwrap();
+		storage.init().unwrap();
+		Box::new(storage)
+	});
+}
+
+#[test]
+fn sled_writes_nothing_readable() {
+	assert_nothing_readable(|dir| {
+		let storage = SledStorage::new(dir).unwrap();
+		storage.init().unwrap();
+		Box::new(storage)
+	});
+}
+
+#[test]
+fn passphrase_unlocks_and_rekey_replaces_it() {
+	let dir = tempfile::tempdir().unwrap();
+	let keyfile = dir.path().join("history.key");
+	let item = secret_item();
+	{
+		let vault = Arc::new(Vault::new(dir.path(), KeySource::Passphrase, keyfile.clone()));
+		assert!(!vault.is_initialized());
+		vault.unlock(Some("première phrase")).unwrap();
+		let storage = encrypted(Box::new(SqliteStorage::new(dir.path()).unwrap()), vault);
+		storage.add_item(item.clone()).unwrap();
+	}
+
+	let vault = Vault::new(dir.path(), KeySource::Passphrase, keyfile.clone());
+	assert!(vault.is_initialized());
+	assert!(vault.unlock(Some("mauvaise phrase")).is_err());
+	assert!(vault.unlock(None).is_err());
+	assert!(vault.is_locked());
+
+	let mut vault = vault;
+	vault.unlock(Some("première phrase")).unwrap();
+	vault.rekey(KeySource::Keyfile, None).unwrap();
+	assert!(keyfile.exists());
+
+	// L'ancienne phrase ne sert plus : la clé provient désormais du fichier
+	let vault = Vault::new(dir.path(), KeySource::Passphrase, keyfile.clone());
+	assert!(vault.unlock(Some("première phrase")).is_err());
+
+	let storage = encrypted(
+		Box::new(SqliteStorage::new(dir.path()).unwrap()),
+		keyfile_vault(dir.path(), keyfile),
+	);
+	assert_eq!(storage.get_item(item.id).unwrap().unwrap().content.content_hash(), item.content.content_hash());
+}
+
+#[test]
+fn locked_history_is_unreadable() {
+	let dir = tempfile::tempdir().unwrap();
+	let keyfile = dir.path().join("history.key");
+	let vault = keyfile_vault(dir.path(), keyfile);
+	let storage = encrypted(Box::new(SqliteStorage::new(dir.path()).unwrap()), vault.clone());
+	let item = storage.add_item(secret_item()).unwrap();
+
+	vault.lock();
+	assert!(storage.get_all_items().is_err());
+	assert!(storage.get_item(item.id).is_err());
+	assert!(storage.add_item(secret_item()).is_err());
+	assert_eq!(storage.count_items().unwrap(), 1);
+}
+
+/// Chiffre un historique enregistré en clair, puis vérifie qu'il n'en reste rien de lisible
+fn assert_plaintext_is_sealed(open_inner: impl Fn(&Path) -> Box<dyn Storage>) {
+	let dir = tempfile::tempdir().unwrap();
+	let keys = tempfile::tempdir().unwrap();
+	let keyfile = keys.path().join("history.key");
+	let item = secret_item();
+	{
+		let plain = open_inner(dir.path());
+		plain.add_item(item.clone()).unwrap();
+		plain.flush().unwrap();
+	}
+	assert!(found_on_disk(dir.path(), SECRET_HTML_MARKER.as_bytes()));
+
+	let storage = encrypted(open_inner(dir.path()), keyfile_vault(dir.path(), keyfile));
+	assert_eq!(storage.get_item(item.id).unwrap().unwrap().content.content_hash(), item.content.content_hash());
+	drop(storage);
+
+	// Le moteur sous-jacent ne contient plus que l'enveloppe
+	let plain = open_inner(dir.path());
+	let envelope = plain.get_item(item.id).unwrap().unwrap();
+	assert!(matches!(&envelope.content, ClipboardContent::Text(text) if text.is_empty()));
+	assert_ne!(envelope.content_hash, item.content_hash);
+	assert!(envelope.formats.iter().all(|format| format.mime_type != "text/html"));
+	drop(plain);
+
+	// Les anciennes versions ne subsistent nulle part sur le disque
+	assert!(!found_on_disk(dir.path(), SECRET_TEXT.as_bytes()));
+	assert!(!found_on_disk(dir.path(), SECRET_HTML_MARKER.as_bytes()));
+}
+
+#[test]
+fn plaintext_sqlite_history_is_sealed_on_unlock() {
+	assert_plaintext_is_sealed(|dir| {
+		let storage = SqliteStorage::new(dir).unwrap();
+		storage.init().unwrap();
+		Box::new(storage)
+	});
+}
+
+#[test]
+fn plaintext_sled_history_is_sealed_on_unlock() {
+	assert_plaintext_is_sealed(|dir| {
+		let storage = SledStorage::new(dir).unwrap();
+		storage.init().unwrap();
+		Box::new(storage)
+	});
+}
+
+#[test]
+fn duplicates_are_detected_through_encryption() {
+	let dir = tempfile::tempdir().unwrap();
+	let keyfile = dir.path().join("history.key");
+	let storage = encrypted(
+		Box::new(SqliteStorage::new(dir.path()).unwrap()),
+		keyfile_vault(dir.path(), keyfile),
+	);
+
+	let first = storage.record_item(image_item(), DedupPolicy::MoveToTop).unwrap();
+	let second = storage.record_item(image_item(), DedupPolicy::MoveToTop).unwrap();
+	assert_eq!(first.id, second.id);
+	assert_eq!(second.copy_count, 2);
+	assert_eq!(storage.count_items().unwrap(), 1);
+
+	// L'élément allégé remonté garde ses pixels
+	let loaded = storage.get_item(first.id).unwrap().unwrap();
+	assert_eq!(loaded.content.content_hash(), image_item().content.content_hash());
+}
+
+#[test]
+fn image_volume_is_counted_through_encryption() {
+	let dir = tempfile::tempdir().unwrap();
+	let keyfile = dir.path().join("history.key");
+	let storage = encrypted(
+		Box::new(SledStorage::new(dir.path()).unwrap()),
+		keyfile_vault(dir.path(), keyfile),
+	);
+
+	let image = storage.add_item(image_item()).unwrap();
+	storage.add_item(secret_item()).unwrap();
+	assert_eq!(storage.image_bytes().unwrap(), 32 * 32 * 4);
+
+	// Les éléments les plus anciens sont rendus déchiffrés
+	let oldest = storage.oldest_unpinned(0, 10, true).unwrap();
+	assert_eq!(oldest.len(), 1);
+	assert_eq!(oldest[0].id, image.id);
+}
+
+#[test]
+fn rekey_rotates_the_history_key_and_reseals_every_item() {
+	let dir = tempfile::tempdir().unwrap();
+	let keyfile = dir.path().join("history.key");
+	let item = secret_item();
+	let old_envelope = {
+		let storage = encrypted(
+			Box::new(SqliteStorage::new(dir.path()).unwrap()),
+			keyfile_vault(dir.path(), keyfile.clone()),
+		);
+		storage.add_item(item.clone()).unwrap();
+		SqliteStorage::new(dir.path()).unwrap().get_item(item.id).unwrap().unwrap()
+	};
+
+	let mut vault = Vault::new(dir.path(), KeySource::Keyfile, keyfile.clone());
+	vault.unlock(None).unwrap();
+	vault.rekey(KeySource::Passphrase, Some("nouvelle phrase")).unwrap();
+	assert!(vault.is_rotating());
+	// Un second changement attend que le premier soit terminé
+	assert!(vault.rekey(KeySource::Keyfile, None).is_err());
+
+	// Interrompu avant le rechiffrement, le changement reprend à l'ouverture suivante
+	let vault = Arc::new(Vault::new(dir.path(), KeySource::Passphrase, keyfile));
+	vault.unlock(Some("nouvelle phrase")).unwrap();
+	assert!(vault.is_rotating());
+	let storage = encrypted(Box::new(SqliteStorage::new(dir.path()).unwrap()), vault.clone());
+	assert!(!vault.is_rotating());
+	let restored = storage.get_item(item.id).unwrap().unwrap();
+	assert_eq!(restored.content.content_hash(), item.content.content_hash());
+	assert_eq!(restored.formats[0].data, item.formats[0].data);
+	drop(storage);
+
+	// L'enveloppe scellée avec l'ancienne clé n'est plus lisible
+	let inner = MemoryStorage::new();
+	inner.add_item(old_envelope).unwrap();
+	let replayed = EncryptedStorage::new(Box::new(inner), vault);
+	assert!(replayed.get_item(item.id).is_err());
+
+	// La clé oubliée n'est plus dans l'en-tête : une nouvelle ouverture n'a plus rien à rechiffrer
+	let reopened = Vault::new(dir.path(), KeySource::Passphrase, PathBuf::new());
+	reopened.unlock(Some("nouvelle phrase")).unwrap();
+	assert!(!reopened.is_rotating());
+}
+
+#[test]
+fn default_keyfile_is_kept_out_of_the_history_directory() {
+	let config_home = tempfile::tempdir().unwrap();
+	// Seul test de ce fichier à dépendre du dossier de configuration par défaut
+	std::env::set_var("XDG_CONFIG_HOME", config_home.path());
+	let keyfile = config_home.path().join("clipboard-manager").join("history.key");
+
+	let fresh = tempfile::tempdir().unwrap();
+	let config = Config { data_dir: fresh.path().to_path_buf(), encryption: KeySource::Keyfile, ..Config::default() };
+	assert_eq!(config.keyfile(), keyfile);
+	let (storage, _) = open_storage(&config).unwrap();
+	storage.add_item(secret_item()).unwrap();
+	drop(storage);
+	assert!(keyfile.is_file() && !fresh.path().join("history.key").exists());
+	std::fs::remove_file(&keyfile).unwrap();
+
+	// Une clé rangée avec l'historique par une version précédente est déplacée
+	let legacy = tempfile::tempdir().unwrap();
+	let item = {
+		let vault = keyfile_vault(legacy.path(), legacy.path().join("history.key"));
+		let storage = encrypted(Box::new(SqliteStorage::new(legacy.path()).unwrap()), vault);
+		storage.add_item(secret_item()).unwrap()
+	};
+	let config = Config { data_dir: legacy.path().to_path_buf(), encryption: KeySource::Keyfile, ..Config::default() };
+	let (storage, _) = open_storage(&config).unwrap();
+	assert_eq!(storage.get_item(item.id).unwrap().unwrap().to_string(), SECRET_TEXT);
+	assert!(keyfile.is_file() && !legacy.path().join("history.key").exists());
+}
@@ -0,0 +1,273 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use clipboard_manager::app::ClipboardManagerApp;
+use clipboard_manager::clipboard::{
+	ClipboardBackend, ClipboardContent, ClipboardEventReceiver, ClipboardItem, ClipboardManager, ClipboardWatcher,
+	MemoryClipboard, PollingSettings, RecordingState, Selection,
+};
+use clipboard_manager::config::{Config, DedupPolicy, WatcherBackend};
+use clipboard_manager::ipc::{socket_path, IpcContext, IpcServer, Request};
+use clipboard_manager::storage::{prune, record_copy, MemoryStorage, Storage};
+use clipboard_manager::ui::{ItemExpiry, Message};
+use common::{no_limits, text, text_item, texts};
+use iced::futures::StreamExt;
+use iced::Task;
+use iced_runtime::Action;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Texte d'un contenu, `None` pour une image ou des fichiers
+fn text_of(content: &ClipboardContent) -> Option<&str> {
+	match content {
+		ClipboardContent::Text(text) => Some(text),
+		_ => None,
+	}
+}
+
+/// Texte actuel d'une sélection du presse-papiers en mémoire
+fn selection_text(clipboard: &MemoryClipboard, selection: Selection) -> Option<String> {
+	clipboard.content(selection).as_ref().and_then(text_of).map(str::to_string)
+}
+
+/// Surveillant scrutant rapidement le presse-papiers en mémoire
+async fn watch(clipboard: &MemoryClipboard, track_primary: bool) -> (ClipboardWatcher<MemoryClipboard>, ClipboardEventReceiver) {
+	let polling = Arc::new(Mutex::new(PollingSettings {
+		interval: std::time::Duration::from_millis(10),
+		max_interval: std::time::Duration::from_millis(10),
+		adaptive: false,
+	}));
+	let fake = clipboard.clone();
+	let mut watcher = ClipboardWatcher::with_polling(WatcherBackend::Auto, track_primary, polling)
+		.with_clipboard(move || Ok(fake.clone()));
+	let receiver = watcher.take_receiver().unwrap();
+	watcher.start().await.unwrap();
+	(watcher, receiver)
+}
+
+/// Prochaine copie détectée par le surveillant
+async fn next_copy(receiver: &mut ClipboardEventReceiver) -> ClipboardItem {
+	tokio::time::timeout(std::time::Duration::from_secs(5), receiver.recv())
+		.await
+		.expect("aucune copie détectée")
+		.unwrap()
+}
+
+/// Exécute une tâche de l'application et celles qui en découlent, comme le ferait iced
+async fn settle(app: &mut ClipboardManagerApp, task: Task<Message>) {
+	let mut pending = vec![task];
+	while let Some(task) = pending.pop() {
+		let Some(mut actions) = iced_runtime::task::into_stream(task) else {
+			continue;
+		};
+		while let Some(action) = actions.next().await {
+			if let Action::Output(message) = action {
+				pending.push(app.update(message));
+			}
+		}
+	}
+}
+
+#[tokio::test]
+async fn the_app_records_pins_and_restores_copies() {
+	let clipboard = MemoryClipboard::new();
+	let (mut app, startup) = ClipboardManagerApp::with_history(
+		Config::default(),
+		Box::new(MemoryStorage::new()),
+		ClipboardManager::with_backend(clipboard.clone()),
+	);
+	settle(&mut app, startup).await;
+	assert!(app.items().is_empty());
+
+	// Les copies détectées par le surveillant parviennent à l'application
+	let (watcher, mut copies) = watch(&clipboard, false).await;
+	for copied in ["numéro de commande", "lien de suivi"] {
+		clipboard.copy(Selection::Clipboard, text(copied));
+		let item = next_copy(&mut copies).await;
+		let task = app.update(Message::NewClipboardItem(item));
+		settle(&mut app, task).await;
+	}
+	watcher.stop().await;
+	let texts: Vec<_> = app.items().iter().map(|item| text_of(&item.content)).collect();
+	assert_eq!(texts, [Some("lien de suivi"), Some("numéro de commande")]);
+
+	// Épingler la première copie, puis la remettre dans le presse-papiers
+	let first = app.items()[1].id;
+	let task = app.update(Message::PinItem(first));
+	settle(&mut app, task).await;
+	assert!(app.items().iter().any(|item| item.id == first && item.pinned));
+
+	let task = app.update(Message::RestoreItem(first, Selection::Clipboard));
+	settle(&mut app, task).await;
+	assert_eq!(selection_text(&clipboard, Selection::Clipboard).as_deref(), Some("numéro de commande"));
+
+	// Vider l'historique épargne l'élément épinglé
+	let task = app.update(Message::ClearItems);
+	settle(&mut app, task).await;
+	let remaining: Vec<_> = app.items().iter().map(|item| item.id).collect();
+	assert_eq!(remaining, [first]);
+}
+
+#[tokio::test]
+async fn staggered_expiries_are_each_pruned_by_the_app() {
+	let storage = MemoryStorage::new();
+	for (copied, delay) in [("premier jeton", 200), ("second jeton", 400)] {
+		let mut item = ClipboardItem::new(text(copied));
+		item.expires_at = Some(Utc::now() + Duration::milliseconds(delay));
+		storage.add_item(item).unwrap();
+	}
+	storage.add_item(ClipboardItem::new(text("note durable"))).unwrap();
+
+	let (mut app, startup) = ClipboardManagerApp::with_history(
+		Config::default(),
+		Box::new(storage),
+		ClipboardManager::with_backend(MemoryClipboard::new()),
+	);
+	// Chaque nettoyage programme le suivant : le démarrage s'achève une fois les deux jetons expirés
+	settle(&mut app, startup).await;
+	let texts: Vec<_> = app.items().iter().map(|item| text_of(&item.content)).collect();
+	assert_eq!(texts, [Some("note durable")]);
+}
+
+#[tokio::test]
+async fn copies_are_recorded_pinned_and_restored() {
+	let clipboard = MemoryClipboard::new();
+	let (watcher, mut copies) = watch(&clipboard, false).await;
+	let storage: Arc<Mutex<Box<dyn Storage>>> = Arc::new(Mutex::new(Box::new(MemoryStorage::new())));
+
+	// Deux copies successives de l'utilisateur, enregistrées dans l'historique
+	for copied in ["mot de passe oublié", "adresse de livraison"] {
+		clipboard.copy(Selection::Clipboard, text(copied));
+		let item = next_copy(&mut copies).await;
+		assert_eq!(text_of(&item.content), Some(copied));
+		record_copy(storage.lock().await.as_ref(), item, DedupPolicy::MoveToTop, &no_limits()).unwrap();
+	}
+	watcher.stop().await;
+
+	let first = {
+		let storage = storage.lock().await;
+		let items = storage.get_all_items().unwrap();
+		assert_eq!(items.len(), 2);
+		assert_eq!(text_of(&items[0].content), Some("adresse de livraison"));
+		items[1].clone()
+	};
+
+	// Épingler puis restaurer la première copie, comme le ferait un client du socket
+	let manager = ClipboardManager::with_backend(Box::new(clipboard.clone()) as Box<dyn ClipboardBackend>);
+	let context = IpcContext::new(storage.clone(), Arc::new(Mutex::new(RecordingState::Recording)))
+		.with_clipboard(Arc::new(Mutex::new(manager)));
+	context.handle(Request::Pin { id: first.id, pinned: true }).await.unwrap().into_done().unwrap();
+	context
+		.handle(Request::Restore { id: first.id, selection: Selection::Clipboard })
+		.await
+		.unwrap()
+		.into_done()
+		.unwrap();
+	assert_eq!(selection_text(&clipboard, Selection::Clipboard).as_deref(), Some("mot de passe oublié"));
+
+	// L'élément épinglé survit au vidage de l'historique
+	let storage = storage.lock().await;
+	assert_eq!(storage.clear_non_pinned().unwrap().len(), 1);
+	let remaining = storage.get_all_items().unwrap();
+	assert_eq!(remaining.len(), 1);
+	assert!(remaining[0].pinned);
+}
+
+#[tokio::test]
+async fn the_primary_selection_is_watched_separately() {
+	let clipboard = MemoryClipboard::new();
+	let (watcher, mut copies) = watch(&clipboard, true).await;
+
+	clipboard.copy(Selection::Primary, text("texte surligné"));
+	let item = next_copy(&mut copies).await;
+	assert_eq!(item.selection, Selection::Primary);
+	assert_eq!(text_of(&item.content), Some("texte surligné"));
+	watcher.stop().await;
+
+	assert!(clipboard.content(Selection::Clipboard).is_none());
+}
+
+#[tokio::test]
+async fn lapsed_copies_are_cleared_from_the_clipboard() {
+	let clipboard = MemoryClipboard::new();
+	let mut manager = ClipboardManager::with_backend(clipboard.clone());
+	let storage = MemoryStorage::new();
+
+	let mut token = ClipboardItem::new(text("jeton à usage unique"));
+	token.expires_at = Some(Utc::now() - Duration::seconds(1));
+	manager.set_content(&token).unwrap();
+	storage.add_item(token).unwrap();
+
+	let (change, report) = prune(&storage, &no_limits()).unwrap();
+	assert_eq!((change.removed.len(), change.total), (1, 0));
+	manager.clear_lapsed(&report.lapsed);
+	assert!(clipboard.content(Selection::Clipboard).is_none());
+
+	// Un contenu copié depuis n'est pas touché
+	clipboard.copy(Selection::Clipboard, text("copie récente"));
+	manager.clear_lapsed(&report.lapsed);
+	assert_eq!(selection_text(&clipboard, Selection::Clipboard).as_deref(), Some("copie récente"));
+}
+
+#[test]
+fn the_memory_history_applies_the_dedup_policy() {
+	let storage = MemoryStorage::new();
+	let mut changes = storage.subscribe();
+
+	let first = record_copy(&storage, ClipboardItem::new(text("un")), DedupPolicy::MoveToTop, &no_limits()).unwrap().0;
+	record_copy(&storage, ClipboardItem::new(text("deux")), DedupPolicy::MoveToTop, &no_limits()).unwrap();
+	let again = record_copy(&storage, ClipboardItem::new(text("un")), DedupPolicy::MoveToTop, &no_limits()).unwrap().0;
+
+	// La nouvelle copie du même contenu remonte l'élément existant
+	assert_eq!(again.upserted[0].id, first.upserted[0].id);
+	let page = storage.get_items(0, 1).unwrap();
+	assert_eq!((text_of(&page.items[0].content), page.total), (Some("un"), 2));
+	assert!(page.has_more());
+	assert_eq!(changes.try_recv().unwrap().upserted[0].id, first.upserted[0].id);
+}
+
+#[tokio::test]
+async fn the_app_edits_the_history_through_the_running_daemon() {
+	// Seul test de ce fichier à dépendre du socket par défaut
+	let runtime = tempfile::tempdir().unwrap();
+	std::env::set_var("XDG_RUNTIME_DIR", runtime.path());
+
+	// Le démon et l'application voient ici deux copies distinctes d'un même historique
+	let daemon_storage: Box<dyn Storage> = Box::new(MemoryStorage::new());
+	let app_storage = MemoryStorage::new();
+	for item in ["ancienne", "gardée", "récente"].map(text_item) {
+		daemon_storage.add_item(item.clone()).unwrap();
+		app_storage.add_item(item).unwrap();
+	}
+	let daemon_storage = Arc::new(Mutex::new(daemon_storage));
+	let context = IpcContext::new(daemon_storage.clone(), Arc::new(Mutex::new(RecordingState::Recording)));
+	let server = tokio::spawn(IpcServer::bind(&socket_path().unwrap(), context).unwrap().serve());
+
+	let (mut app, startup) = ClipboardManagerApp::with_daemon(Config::default(), Box::new(app_storage));
+	settle(&mut app, startup).await;
+	let [recent, kept, old] = [0, 1, 2].map(|index| app.items()[index].id);
+
+	let pin = app.update(Message::PinItem(kept));
+	settle(&mut app, pin).await;
+	let expiry = app.update(Message::SetExpiry(recent, ItemExpiry::Hour));
+	settle(&mut app, expiry).await;
+	let removal = app.update(Message::RemoveItem(old));
+	settle(&mut app, removal).await;
+
+	{
+		let storage = daemon_storage.lock().await;
+		assert!(storage.get_item(kept).unwrap().unwrap().pinned);
+		assert!(storage.get_item(recent).unwrap().unwrap().expires_at.is_some());
+		assert!(storage.get_item(old).unwrap().is_none());
+	}
+	let clear = app.update(Message::ClearItems);
+	settle(&mut app, clear).await;
+	assert_eq!(texts(daemon_storage.lock().await.as_ref()), ["gardée"]);
+
+	// L'application n'a rien écrit elle-même : son historique est resté intact
+	let reload = app.update(Message::ReloadItems);
+	settle(&mut app, reload).await;
+	assert_eq!(app.items().len(), 3);
+	assert!(app.items().iter().all(|item| !item.pinned && item.expires_at.is_none()));
+	server.abort();
+}
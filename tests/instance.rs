@@ -0,0 +1,43 @@
+use clipboard_manager::instance::WindowInstance;
+use std::time::Duration;
+
+#[test]
+fn only_one_window_instance_runs() {
+	let dir = tempfile::tempdir().unwrap();
+
+	let instance = WindowInstance::acquire(dir.path()).unwrap();
+	assert!(instance.is_some());
+	assert!(WindowInstance::acquire(dir.path()).unwrap().is_none());
+
+	// Le socket de l'instance terminée est retiré puis recréé par la suivante
+	drop(instance);
+	assert!(WindowInstance::acquire(dir.path()).unwrap().is_some());
+}
+
+#[tokio::test]
+async fn relaunches_reach_the_running_instance() {
+	let dir = tempfile::tempdir().unwrap();
+	let instance = WindowInstance::acquire(dir.path()).unwrap().unwrap();
+	let listener = instance.listener().unwrap();
+
+	let path = dir.path().to_path_buf();
+	let relaunch = tokio::task::spawn_blocking(move || WindowInstance::toggle_running(&path));
+	tokio::time::timeout(Duration::from_secs(5), listener.accept()).await.unwrap().unwrap();
+	relaunch.await.unwrap().unwrap();
+
+	drop(listener);
+	drop(instance);
+	assert!(WindowInstance::toggle_running(dir.path()).is_err());
+}
+
+#[test]
+fn instance_files_are_private_to_the_user() {
+	use std::os::unix::fs::PermissionsExt;
+
+	let dir = tempfile::tempdir().unwrap();
+	let _instance = WindowInstance::acquire(dir.path()).unwrap().unwrap();
+	for name in ["clipboard-manager-window.lock", "clipboard-manager-window.sock"] {
+		let mode = std::fs::metadata(dir.path().join(name)).unwrap().permissions().mode();
+		assert_eq!(mode & 0o777, 0o600, "{}", name);
+	}
+}
@@ -0,0 +1,149 @@
+mod common;
+
+use clipboard_manager::clipboard::{RecordingState, Selection};
+use clipboard_manager::ipc::{IpcClient, IpcContext, IpcServer, ItemKind, Request, Response};
+use common::{shared_storage, text_item};
+use std::sync::Arc;
+use tokio::sync::{mpsc, Mutex};
+use uuid::Uuid;
+
+#[tokio::test]
+async fn clients_browse_and_edit_the_history() {
+	let dir = tempfile::tempdir().unwrap();
+	let storage = shared_storage(&dir);
+	let (first, second) = {
+		let storage = storage.lock().await;
+		let first = storage.add_item(text_item("première copie")).unwrap();
+		let second = storage.add_item(text_item("Seconde copie\nsur deux lignes")).unwrap();
+		(first, second)
+	};
+
+	let socket = dir.path().join("control.sock");
+	let context = IpcContext::new(storage.clone(), Arc::new(Mutex::new(RecordingState::Recording)));
+	let server = tokio::spawn(IpcServer::bind(&socket, context).unwrap().serve());
+	let mut client = IpcClient::connect(&socket).await.unwrap();
+
+	let (items, total) = client.list(0, 10).await.unwrap();
+	assert_eq!(total, 2);
+	assert_eq!(items[0].id, second.id);
+	assert_eq!(items[0].kind, ItemKind::Text);
+
+	let (found, total) = client.search("SECONDE", 10).await.unwrap();
+	assert_eq!(total, 1);
+	assert_eq!(found[0].id, second.id);
+
+	let details = client.get(second.id).await.unwrap();
+	assert_eq!(details.text.as_deref(), Some("Seconde copie\nsur deux lignes"));
+
+	client.pin(first.id, true).await.unwrap();
+	assert!(storage.lock().await.get_item(first.id).unwrap().unwrap().pinned);
+
+	let expires_at = chrono::Utc::now() + chrono::Duration::hours(1);
+	client.expire(second.id, Some(expires_at)).await.unwrap();
+	assert_eq!(storage.lock().await.get_item(second.id).unwrap().unwrap().expires_at, Some(expires_at));
+	client.expire(second.id, None).await.unwrap();
+	assert_eq!(storage.lock().await.get_item(second.id).unwrap().unwrap().expires_at, None);
+
+	assert_eq!(client.clear().await.unwrap(), 1);
+	client.delete(first.id).await.unwrap();
+	assert_eq!(storage.lock().await.count_items().unwrap(), 0);
+
+	server.abort();
+	let _ = server.await;
+	assert!(!socket.exists());
+}
+
+#[tokio::test]
+async fn errors_are_reported_without_closing_the_connection() {
+	let dir = tempfile::tempdir().unwrap();
+	let socket = dir.path().join("control.sock");
+	let context = IpcContext::new(shared_storage(&dir), Arc::new(Mutex::new(RecordingState::Recording)));
+	let server = tokio::spawn(IpcServer::bind(&socket, context).unwrap().serve());
+	let mut client = IpcClient::connect(&socket).await.unwrap();
+
+	let missing = client.send(&Request::Get { id: Uuid::new_v4() }).await.unwrap();
+	assert!(matches!(missing, Response::Error { .. }));
+
+	// Sans gestionnaire de presse-papiers, la restauration est refusée
+	let restore = Request::Restore { id: Uuid::new_v4(), selection: Selection::Clipboard };
+	assert!(client.call(&restore).await.is_err());
+
+	assert_eq!(client.list(0, 10).await.unwrap().1, 0);
+	server.abort();
+}
+
+#[tokio::test]
+async fn pause_requests_reach_the_recorder() {
+	let dir = tempfile::tempdir().unwrap();
+	let recording = Arc::new(Mutex::new(RecordingState::Recording));
+	let (sender, mut changes) = mpsc::unbounded_channel();
+	let context = IpcContext::new(shared_storage(&dir), recording.clone()).with_recording_changes(sender);
+
+	let paused = context.handle(Request::Pause { minutes: Some(10) }).await.unwrap();
+	assert!(matches!(paused, Response::Recording { paused: true, until: Some(_) }));
+	assert!(recording.lock().await.is_paused());
+	assert!(changes.recv().await.unwrap().is_paused());
+
+	let resumed = context.handle(Request::Resume).await.unwrap();
+	assert_eq!(resumed, Response::Recording { paused: false, until: None });
+	assert_eq!(*recording.lock().await, RecordingState::Recording);
+	assert_eq!(context.handle(Request::Status).await.unwrap(), resumed);
+}
+
+#[test]
+fn protocol_uses_tagged_json_lines() {
+	let request: Request = serde_json::from_str(r#"{"command":"list"}"#).unwrap();
+	assert_eq!(request, Request::List { offset: 0, limit: 20 });
+
+	let id = Uuid::new_v4();
+	let request: Request = serde_json::from_str(&format!(r#"{{"command":"pin","id":"{}"}}"#, id)).unwrap();
+	assert_eq!(request, Request::Pin { id, pinned: true });
+
+	let reply = serde_json::to_string(&Response::Cleared { removed: 3 }).unwrap();
+	assert_eq!(reply, r#"{"type":"cleared","removed":3}"#);
+}
+
+#[tokio::test]
+async fn history_is_exported_imported_and_counted_by_the_server() {
+	let dir = tempfile::tempdir().unwrap();
+	let socket = dir.path().join("control.sock");
+	let storage = shared_storage(&dir);
+	let mut pinned = text_item("copie épinglée");
+	pinned.pinned = true;
+	storage.lock().await.add_item(pinned).unwrap();
+	storage.lock().await.add_item(text_item("copie ordinaire")).unwrap();
+	let context = IpcContext::new(storage.clone(), Arc::new(Mutex::new(RecordingState::Recording)));
+	let server = tokio::spawn(IpcServer::bind(&socket, context).unwrap().serve());
+	let mut client = IpcClient::connect(&socket).await.unwrap();
+
+	let exported = client.call(&Request::Export).await.unwrap().into_exported().unwrap();
+	assert_eq!(exported.len(), 2);
+
+	let mut items = exported.clone();
+	items.push(text_item("copie importée"));
+	let imported = client.call(&Request::Import { items }).await.unwrap().into_imported().unwrap();
+	assert_eq!(imported, (1, 2));
+	assert_eq!(storage.lock().await.count_items().unwrap(), 3);
+
+	let stats = client.call(&Request::Stats).await.unwrap().into_stats().unwrap();
+	assert_eq!((stats.items, stats.texts, stats.pinned), (3, 3, 1));
+	assert!(stats.oldest <= stats.newest);
+	server.abort();
+}
+
+#[tokio::test]
+async fn the_socket_is_private_to_the_user() {
+	use std::os::unix::fs::PermissionsExt;
+
+	let dir = tempfile::tempdir().unwrap();
+	let socket = dir.path().join("control.sock");
+	let context = IpcContext::new(shared_storage(&dir), Arc::new(Mutex::new(RecordingState::Recording)));
+	let server = IpcServer::bind(&socket, context).unwrap();
+	assert_eq!(std::fs::metadata(&socket).unwrap().permissions().mode() & 0o777, 0o600);
+	drop(server);
+
+	// Le socket de session n'est jamais placé dans un dossier ouvert aux autres comptes
+	let path = clipboard_manager::ipc::socket_path().unwrap();
+	let parent = std::fs::metadata(path.parent().unwrap()).unwrap();
+	assert_eq!(parent.permissions().mode() & 0o077, 0);
+}
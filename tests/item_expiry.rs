@@ -0,0 +1,143 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use clipboard_manager::clipboard::Selection;
+use clipboard_manager::storage::{apply_retention, MemoryStorage, RetentionPolicy, SledStorage, SqliteStorage, Storage};
+use clipboard_manager::ui::ItemExpiry;
+use common::{no_limits, text_item};
+
+/// L'expiration choisie survit à l'enregistrement et à la mise à jour
+fn assert_expiry_round_trips(storage: &dyn Storage) {
+	let item = storage.add_item(text_item("code à usage unique 481516")).unwrap();
+	assert_eq!(storage.get_item(item.id).unwrap().unwrap().expires_at, None);
+
+	let mut updated = item.clone();
+	updated.expires_at = ItemExpiry::Hour.expires_at();
+	storage.update_item(updated.clone()).unwrap();
+	let loaded = storage.get_items(0, 10).unwrap().items.remove(0);
+	assert_eq!(loaded.expires_at, updated.expires_at);
+
+	updated.expires_at = ItemExpiry::Never.expires_at();
+	storage.update_item(updated).unwrap();
+	assert_eq!(storage.get_item(item.id).unwrap().unwrap().expires_at, None);
+}
+
+#[test]
+fn sqlite_keeps_item_expiry() {
+	let dir = tempfile::tempdir().unwrap();
+	let storage = SqliteStorage::new(dir.path()).unwrap();
+	storage.init().unwrap();
+	assert_expiry_round_trips(&storage);
+}
+
+#[test]
+fn sled_keeps_item_expiry() {
+	let dir = tempfile::tempdir().unwrap();
+	let storage = SledStorage::new(dir.path()).unwrap();
+	storage.init().unwrap();
+	assert_expiry_round_trips(&storage);
+}
+
+/// La prochaine expiration ne tient compte que des éléments non épinglés encore présents
+fn assert_next_expiry_follows_the_history(storage: &dyn Storage) {
+	assert_eq!(storage.next_expiry().unwrap(), None);
+
+	let soon = Utc::now() + Duration::minutes(1);
+	let later = Utc::now() + Duration::hours(1);
+	let mut first = text_item("premier jeton");
+	first.expires_at = Some(soon);
+	let mut second = text_item("second jeton");
+	second.expires_at = Some(later);
+	let first = storage.add_item(first).unwrap();
+	storage.add_item(second).unwrap();
+	assert_eq!(storage.next_expiry().unwrap(), Some(soon));
+
+	let mut pinned = first.clone();
+	pinned.pinned = true;
+	storage.update_item(pinned.clone()).unwrap();
+	assert_eq!(storage.next_expiry().unwrap(), Some(later));
+
+	pinned.pinned = false;
+	storage.update_item(pinned).unwrap();
+	assert_eq!(storage.next_expiry().unwrap(), Some(soon));
+
+	storage.remove_item(first.id).unwrap();
+	assert_eq!(storage.next_expiry().unwrap(), Some(later));
+	storage.clear_non_pinned().unwrap();
+	assert_eq!(storage.next_expiry().unwrap(), None);
+}
+
+#[test]
+fn next_expiry_is_indexed_by_every_backend() {
+	let dir = tempfile::tempdir().unwrap();
+	let sqlite = SqliteStorage::new(dir.path()).unwrap();
+	sqlite.init().unwrap();
+	assert_next_expiry_follows_the_history(&sqlite);
+
+	let dir = tempfile::tempdir().unwrap();
+	let sled = SledStorage::new(dir.path()).unwrap();
+	sled.init().unwrap();
+	assert_next_expiry_follows_the_history(&sled);
+
+	assert_next_expiry_follows_the_history(&MemoryStorage::new());
+}
+
+#[test]
+fn expiries_written_before_the_index_are_found() {
+	let dir = tempfile::tempdir().unwrap();
+	let soon = Utc::now() + Duration::minutes(1);
+	{
+		let storage = SqliteStorage::new(dir.path()).unwrap();
+		storage.init().unwrap();
+		let mut item = text_item("jeton");
+		item.expires_at = Some(soon);
+		storage.add_item(item).unwrap();
+	}
+
+	// Base créée avant la colonne des expirations
+	let conn = rusqlite::Connection::open(dir.path().join("history.sqlite3")).unwrap();
+	conn.execute_batch("DROP INDEX items_expiry; ALTER TABLE items DROP COLUMN expires_at;").unwrap();
+	drop(conn);
+
+	let storage = SqliteStorage::new(dir.path()).unwrap();
+	storage.init().unwrap();
+	assert_eq!(storage.next_expiry().unwrap(), Some(soon));
+}
+
+#[test]
+fn expiry_choices_are_relative_to_now() {
+	let before = Utc::now();
+	let minute = ItemExpiry::Minute.expires_at().unwrap();
+	let day = ItemExpiry::Day.expires_at().unwrap();
+	assert!(minute >= before + Duration::minutes(1) && minute <= Utc::now() + Duration::minutes(1));
+	assert!(day >= before + Duration::days(1));
+	assert_eq!(ItemExpiry::Never.expires_at(), None);
+}
+
+#[test]
+fn lapsed_items_are_reported_for_clipboard_clearing() {
+	let dir = tempfile::tempdir().unwrap();
+	let storage = SqliteStorage::new(dir.path()).unwrap();
+	storage.init().unwrap();
+
+	let mut lapsed = text_item("jeton temporaire").with_selection(Selection::Primary);
+	lapsed.expires_at = Some(Utc::now() - Duration::seconds(1));
+	let mut old = text_item("ancien élément");
+	old.timestamp = Utc::now() - Duration::days(30);
+	let pending = {
+		let mut item = text_item("code encore valide");
+		item.expires_at = ItemExpiry::Minute.expires_at();
+		item
+	};
+	for item in [&lapsed, &old, &pending] {
+		storage.add_item(item.clone()).unwrap();
+	}
+
+	let policy = RetentionPolicy { max_age: Some(Duration::days(7)), ..no_limits() };
+	let report = apply_retention(&storage, &policy).unwrap();
+	assert_eq!(report.expired, 2);
+	// Seul l'élément arrivé à sa propre date d'expiration est retiré du presse-papiers
+	assert_eq!(report.lapsed, vec![(Selection::Primary, lapsed.content_hash.clone())]);
+	assert_eq!(storage.count_items().unwrap(), 1);
+	assert!(storage.get_item(pending.id).unwrap().is_some());
+}
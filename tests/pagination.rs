@@ -0,0 +1,124 @@
+use chrono::{Duration, Utc};
+use clipboard_manager::clipboard::{ClipboardContent, ClipboardItem, FileEntry, ImageMetadata};
+use clipboard_manager::config::KeySource;
+use clipboard_manager::storage::{EncryptedStorage, MemoryStorage, SledStorage, SqliteStorage, Storage, Vault};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Ajoute `count` éléments espacés d'une minute, le dernier étant le plus récent
+fn seed(storage: &dyn Storage, count: usize) -> Vec<ClipboardItem> {
+	let start = Utc::now() - Duration::hours(1);
+	(0..count)
+		.map(|i| {
+			let mut item = ClipboardItem::new(ClipboardContent::Text(format!("élément {}", i)));
+			item.timestamp = start + Duration::minutes(i as i64);
+			storage.add_item(item.clone()).unwrap();
+			item
+		})
+		.collect()
+}
+
+fn texts(items: &[ClipboardItem]) -> Vec<String> {
+	items.iter().map(|item| item.to_string()).collect()
+}
+
+fn assert_pagination(storage: &dyn Storage) {
+	let seeded = seed(storage, 7);
+
+	let first = storage.get_items(0, 3).unwrap();
+	assert_eq!(first.total, 7);
+	assert_eq!(texts(&first.items), ["élément 6", "élément 5", "élément 4"]);
+	assert!(first.has_more());
+
+	let last = storage.get_items(6, 3).unwrap();
+	assert_eq!(texts(&last.items), ["élément 0"]);
+	assert!(!last.has_more());
+
+	// Un élément remonté en tête change de position dans l'index
+	let mut moved = seeded[1].clone();
+	moved.timestamp = Utc::now();
+	storage.update_item(moved).unwrap();
+	storage.remove_item(seeded[6].id).unwrap();
+
+	let page = storage.get_items(0, 2).unwrap();
+	assert_eq!(page.total, 6);
+	assert_eq!(texts(&page.items), ["élément 1", "élément 5"]);
+	assert_eq!(storage.get_all_items().unwrap().len(), 6);
+}
+
+#[test]
+fn sled_pages_follow_timestamps() {
+	let dir = tempfile::tempdir().unwrap();
+	let storage = SledStorage::new(dir.path()).unwrap();
+	storage.init().unwrap();
+
+	assert_pagination(&storage);
+}
+
+#[test]
+fn sqlite_pages_follow_timestamps() {
+	let dir = tempfile::tempdir().unwrap();
+	let storage = SqliteStorage::new(dir.path()).unwrap();
+	storage.init().unwrap();
+
+	assert_pagination(&storage);
+}
+
+/// Vérifie qu'une recherche est paginée parmi ses seuls résultats
+fn assert_search(storage: &dyn Storage) {
+	seed(storage, 7);
+	storage.add_item(ClipboardItem::new(ClipboardContent::Text("Autre chose".to_string()))).unwrap();
+	let files = vec![FileEntry::from_path(PathBuf::from("/tmp/Rapport Élément.pdf"))];
+	storage.add_item(ClipboardItem::new(ClipboardContent::Files(files))).unwrap();
+	let image = ClipboardContent::Image(vec![0; 4], ImageMetadata::new(1, 1));
+	storage.add_item(ClipboardItem::new(image)).unwrap();
+
+	// La casse est ignorée, accents compris ; le fichier, plus récent, est le premier résultat
+	let page = storage.search("ÉLÉMENT", 1, 3).unwrap();
+	assert_eq!(page.total, 8);
+	assert_eq!(texts(&page.items), ["élément 6", "élément 5", "élément 4"]);
+	assert!(page.has_more());
+
+	let last = storage.search("élément", 6, 3).unwrap();
+	assert_eq!(texts(&last.items), ["élément 1", "élément 0"]);
+	assert!(!last.has_more());
+
+	assert_eq!(storage.search("rapport", 0, 10).unwrap().total, 1);
+	assert_eq!(storage.search("introuvable", 0, 10).unwrap().total, 0);
+	// Une requête vide parcourt tout l'historique, images comprises
+	assert_eq!(storage.search("", 0, 3).unwrap().total, 10);
+}
+
+#[test]
+fn sled_searches_page_by_page() {
+	let dir = tempfile::tempdir().unwrap();
+	let storage = SledStorage::new(dir.path()).unwrap();
+	storage.init().unwrap();
+
+	assert_search(&storage);
+}
+
+#[test]
+fn sqlite_searches_page_by_page() {
+	let dir = tempfile::tempdir().unwrap();
+	let storage = SqliteStorage::new(dir.path()).unwrap();
+	storage.init().unwrap();
+
+	assert_search(&storage);
+}
+
+#[test]
+fn memory_searches_page_by_page() {
+	assert_search(&MemoryStorage::new());
+}
+
+#[test]
+fn encrypted_history_searches_page_by_page() {
+	let dir = tempfile::tempdir().unwrap();
+	let vault = Arc::new(Vault::new(dir.path(), KeySource::Keyfile, dir.path().join("history.key")));
+	vault.unlock(None).unwrap();
+	let storage = EncryptedStorage::new(Box::new(SqliteStorage::new(dir.path()).unwrap()), vault);
+	storage.init().unwrap();
+
+	assert_search(&storage);
+}
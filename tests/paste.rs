@@ -0,0 +1,97 @@
+use clipboard_manager::clipboard::paste::{self, dotool_script, keysym, Keystroke};
+use clipboard_manager::clipboard::virtual_keyboard::{keymap, layout, MAX_KEYSYMS};
+use clipboard_manager::clipboard::{ClipboardContent, ClipboardItem, ClipboardManager, MemoryClipboard, Selection};
+use clipboard_manager::config::{Config, PasteBackend};
+
+#[test]
+fn the_paste_backend_is_configurable() {
+	assert_eq!(Config::default().paste_backend, PasteBackend::Auto);
+
+	let config: Config = serde_json::from_str(r#"{ "paste_backend": "Wtype" }"#).unwrap();
+	assert_eq!(config.paste_backend, PasteBackend::Wtype);
+	let config: Config = serde_json::from_str(r#"{ "paste_backend": "CopyOnly" }"#).unwrap();
+	assert_eq!(paste::resolve(config.paste_backend), PasteBackend::CopyOnly);
+	assert!(paste::is_available(PasteBackend::CopyOnly));
+}
+
+#[tokio::test]
+async fn copy_only_leaves_the_item_in_the_clipboard() {
+	let clipboard = MemoryClipboard::new();
+	let mut manager = ClipboardManager::with_backend(clipboard.clone());
+	assert_eq!(manager.paste_backend(), PasteBackend::CopyOnly);
+
+	let item = ClipboardItem::new(ClipboardContent::Text("à coller".to_string()));
+	manager.paste_to_active_window(&item).await.unwrap();
+	match clipboard.content(Selection::Clipboard) {
+		Some(ClipboardContent::Text(text)) => assert_eq!(text, "à coller"),
+		other => panic!("texte attendu: {:?}", other),
+	}
+}
+
+#[tokio::test]
+async fn a_failed_injection_falls_back_to_copying() {
+	use std::os::unix::fs::PermissionsExt;
+
+	// Un xdotool qui échoue toujours, seul outil trouvé dans le PATH
+	let bin = tempfile::tempdir().unwrap();
+	let xdotool = bin.path().join("xdotool");
+	std::fs::write(&xdotool, "#!/bin/sh\necho 'pas de serveur X' >&2\nexit 1\n").unwrap();
+	std::fs::set_permissions(&xdotool, std::fs::Permissions::from_mode(0o755)).unwrap();
+	std::env::set_var("PATH", bin.path());
+
+	let clipboard = MemoryClipboard::new();
+	let mut manager = ClipboardManager::with_backend(clipboard.clone());
+	manager.set_paste_backend(PasteBackend::Xdotool);
+
+	let item = ClipboardItem::new(ClipboardContent::Text("à coller".to_string()));
+	let error = manager.paste_to_active_window(&item).await.unwrap_err();
+	assert!(error.to_string().contains("pas de serveur X"), "{}", error);
+	match clipboard.content(Selection::Clipboard) {
+		Some(ClipboardContent::Text(text)) => assert_eq!(text, "à coller"),
+		other => panic!("texte attendu: {:?}", other),
+	}
+}
+
+#[test]
+fn dotool_types_each_line_then_presses_enter() {
+	assert_eq!(
+		dotool_script(&Keystroke::Text("bonjour\nà tous".to_string())),
+		"type bonjour\nkey enter\ntype à tous\n"
+	);
+	assert_eq!(dotool_script(&Keystroke::Paste), "key ctrl+v\n");
+}
+
+#[test]
+fn characters_map_to_layout_independent_keysyms() {
+	assert_eq!(keysym('a'), Some(0x61));
+	assert_eq!(keysym('é'), Some(0xe9));
+	assert_eq!(keysym('€'), Some(0x0100_20ac));
+	assert_eq!(keysym('\n'), Some(0xff0d));
+	assert_eq!(keysym('\u{7}'), None);
+
+	let keymap = keymap(&[0x61, 0x0100_20ac]);
+	assert!(keymap.contains("maximum = 11;"));
+	assert!(keymap.contains("<K0> = 9;"));
+	assert!(keymap.contains("key <K0> { [ 0xffe3 ] };"));
+	assert!(keymap.contains("key <K2> { [ 0x10020ac ] };"));
+	assert!(keymap.contains("modifier_map Control { <K0> };"));
+}
+
+#[test]
+fn varied_text_is_typed_with_one_keymap_per_chunk() {
+	let text: String = (0..300).filter_map(|code| char::from_u32(0x100 + code)).collect();
+	let chunks = layout(&Keystroke::Text(format!("{}{}", text, text)));
+	assert_eq!(chunks.len(), 3);
+
+	// Chaque touche reste dans les codes XKB disponibles (au plus 255)
+	for (keysyms, keys) in &chunks {
+		assert!(keysyms.len() <= MAX_KEYSYMS);
+		assert!(keys.iter().all(|key| (2..=255 - 8).contains(key)));
+	}
+	assert_eq!(chunks[0].0.len(), MAX_KEYSYMS);
+	assert_eq!(chunks.iter().map(|(_, keys)| keys.len()).sum::<usize>(), 600);
+
+	// Un texte court tient dans un seul keymap, une touche par caractère distinct
+	let chunks = layout(&Keystroke::Text("abba".to_string()));
+	assert_eq!(chunks, [(vec![0x61, 0x62], vec![2, 3, 3, 2])]);
+}
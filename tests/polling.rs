@@ -0,0 +1,62 @@
+use clipboard_manager::clipboard::PollingSettings;
+use clipboard_manager::config::Config;
+use std::time::Duration;
+
+fn adaptive() -> PollingSettings {
+	PollingSettings {
+		interval: Duration::from_millis(500),
+		max_interval: Duration::from_millis(5000),
+		adaptive: true,
+	}
+}
+
+#[test]
+fn idle_polling_ramps_up_to_the_maximum_interval() {
+	let settings = adaptive();
+	assert_eq!(settings.next_delay(0, false), Duration::from_millis(500));
+	assert_eq!(settings.next_delay(19, false), Duration::from_millis(500));
+	assert_eq!(settings.next_delay(20, false), Duration::from_millis(1000));
+	assert_eq!(settings.next_delay(40, false), Duration::from_millis(2000));
+	assert_eq!(settings.next_delay(60, false), Duration::from_millis(4000));
+	assert_eq!(settings.next_delay(80, false), Duration::from_millis(5000));
+	assert_eq!(settings.next_delay(u32::MAX, false), Duration::from_millis(5000));
+}
+
+#[test]
+fn a_change_resets_the_delay_to_the_base_interval() {
+	let settings = adaptive();
+	let mut idle_checks = 100;
+	assert_eq!(settings.next_delay(idle_checks, false), Duration::from_millis(5000));
+
+	// Le surveillant remet le compteur à zéro dès qu'une copie est détectée
+	idle_checks = 0;
+	assert_eq!(settings.next_delay(idle_checks, false), Duration::from_millis(500));
+}
+
+#[test]
+fn a_locked_session_waits_the_maximum_interval() {
+	assert_eq!(adaptive().next_delay(0, true), Duration::from_millis(5000));
+
+	// Un maximum inférieur à la base n'accélère pas la scrutation
+	let settings = PollingSettings { max_interval: Duration::from_millis(100), ..adaptive() };
+	assert_eq!(settings.next_delay(0, true), Duration::from_millis(500));
+	assert_eq!(settings.next_delay(200, false), Duration::from_millis(500));
+}
+
+#[test]
+fn non_adaptive_polling_keeps_a_constant_delay() {
+	let settings = PollingSettings { adaptive: false, ..adaptive() };
+	for idle_checks in [0, 20, 1000, u32::MAX] {
+		assert_eq!(settings.next_delay(idle_checks, false), Duration::from_millis(500));
+		assert_eq!(settings.next_delay(idle_checks, true), Duration::from_millis(500));
+	}
+}
+
+#[test]
+fn settings_follow_the_config() {
+	let config = Config { check_interval_ms: 0, max_check_interval_ms: 2000, adaptive_polling: false, ..Config::default() };
+	let settings = PollingSettings::from(&config);
+	assert_eq!(settings.interval, Duration::from_millis(1));
+	assert_eq!(settings.max_interval, Duration::from_millis(2000));
+	assert!(!settings.adaptive);
+}
@@ -0,0 +1,70 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use clipboard_manager::clipboard::{
+	ClipboardWatcher, MemoryClipboard, PollingSettings, RecordingState, Selection,
+};
+use clipboard_manager::config::WatcherBackend;
+use common::text;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+#[test]
+fn recording_is_not_paused() {
+	assert!(!RecordingState::Recording.is_paused());
+	assert!(!RecordingState::default().is_paused());
+}
+
+#[test]
+fn pause_without_deadline_lasts_until_resumed() {
+	let paused = RecordingState::Paused { until: None };
+	assert!(paused.is_paused());
+	assert!(paused.is_paused_at(Utc::now() + Duration::days(365)));
+}
+
+#[test]
+fn timed_pause_ends_at_its_deadline() {
+	let now = Utc::now();
+	let paused = RecordingState::Paused { until: Some(now + Duration::minutes(10)) };
+	assert!(paused.is_paused_at(now));
+	assert!(paused.is_paused_at(now + Duration::minutes(9)));
+	assert!(!paused.is_paused_at(now + Duration::minutes(10)));
+
+	let elapsed = RecordingState::Paused { until: Some(now - Duration::seconds(1)) };
+	assert!(!elapsed.is_paused());
+}
+
+#[tokio::test]
+async fn copies_are_dropped_while_paused_and_recorded_after_resume() {
+	let clipboard = MemoryClipboard::new();
+	clipboard.copy(Selection::Clipboard, text("avant la pause"));
+	let recording = Arc::new(Mutex::new(RecordingState::Paused { until: None }));
+	let polling = Arc::new(Mutex::new(PollingSettings {
+		interval: std::time::Duration::from_millis(10),
+		max_interval: std::time::Duration::from_millis(10),
+		adaptive: false,
+	}));
+	let fake = clipboard.clone();
+	let mut watcher = ClipboardWatcher::with_polling(WatcherBackend::Polling, false, polling)
+		.with_recording(recording.clone())
+		.with_clipboard(move || Ok(fake.clone()));
+	let mut copies = watcher.take_receiver().unwrap();
+	watcher.start().await.unwrap();
+
+	clipboard.copy(Selection::Clipboard, text("mot de passe"));
+	tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+	assert!(copies.try_recv().is_err());
+
+	// Une copie faite juste après la reprise est enregistrée, pas celle faite pendant la pause
+	*recording.lock().await = RecordingState::Recording;
+	clipboard.copy(Selection::Clipboard, text("après la reprise"));
+	let item = tokio::time::timeout(std::time::Duration::from_secs(5), copies.recv())
+		.await
+		.expect("aucune copie détectée")
+		.unwrap();
+	assert_eq!(item.content, text("après la reprise"));
+
+	tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+	assert!(copies.try_recv().is_err());
+	watcher.stop().await;
+}
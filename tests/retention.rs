@@ -0,0 +1,121 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use clipboard_manager::clipboard::{ClipboardContent, ClipboardItem, ImageMetadata};
+use clipboard_manager::config::Config;
+use clipboard_manager::storage::{apply_retention, MemoryStorage, RetentionPolicy, SledStorage, SqliteStorage, Storage};
+use common::{no_limits, text_item, texts};
+use std::path::Path;
+
+/// Historique d'éléments copiés à une heure d'intervalle, le premier étant le plus récent
+fn history(items: &[(&str, bool)]) -> MemoryStorage {
+	let storage = MemoryStorage::new();
+	let now = Utc::now();
+	for (age, (text, pinned)) in items.iter().enumerate().rev() {
+		let mut item = text_item(text);
+		item.timestamp = now - Duration::hours(age as i64);
+		item.pinned = *pinned;
+		storage.add_item(item).unwrap();
+	}
+	storage
+}
+
+#[test]
+fn the_oldest_items_go_beyond_the_maximum_count() {
+	let storage = history(&[("quatre", false), ("trois", false), ("deux", false), ("un", false)]);
+
+	let report = apply_retention(&storage, &RetentionPolicy { max_items: Some(2), ..no_limits() }).unwrap();
+	assert_eq!(report.over_count, 2);
+	assert_eq!(report.removed.len(), 2);
+	assert_eq!(texts(&storage), ["quatre", "trois"]);
+}
+
+#[test]
+fn items_older_than_the_maximum_age_are_removed() {
+	let storage = history(&[("récent", false), ("hier", false)]);
+	let mut old = text_item("mois dernier");
+	old.timestamp = Utc::now() - Duration::days(30);
+	storage.add_item(old).unwrap();
+
+	let report = apply_retention(&storage, &RetentionPolicy { max_age: Some(Duration::days(7)), ..no_limits() }).unwrap();
+	assert_eq!(report.expired, 1);
+	assert_eq!(texts(&storage), ["récent", "hier"]);
+}
+
+#[test]
+fn pinned_items_are_never_removed_but_count_towards_the_size() {
+	let storage = history(&[("récent", false), ("épinglé", true), ("ancien", false)]);
+	let mut old_pinned = text_item("épinglé il y a longtemps");
+	old_pinned.timestamp = Utc::now() - Duration::days(30);
+	old_pinned.pinned = true;
+	storage.add_item(old_pinned).unwrap();
+
+	let policy = RetentionPolicy { max_items: Some(2), max_age: Some(Duration::days(7)), ..no_limits() };
+	let report = apply_retention(&storage, &policy).unwrap();
+	assert_eq!((report.expired, report.over_count), (0, 2));
+	assert_eq!(texts(&storage), ["épinglé", "épinglé il y a longtemps"]);
+}
+
+#[test]
+fn zero_limits_in_the_config_are_disabled() {
+	let config = Config { max_history_size: 0, retention_days: 0, max_image_storage_mb: 0, ..Config::default() };
+	assert_eq!(RetentionPolicy::from(&config), no_limits());
+
+	let config = Config { max_history_size: 10, retention_days: 3, max_image_storage_mb: 1, ..Config::default() };
+	let policy = RetentionPolicy::from(&config);
+	assert_eq!(policy.max_items, Some(10));
+	assert_eq!(policy.max_age, Some(Duration::days(3)));
+	assert_eq!(policy.max_image_bytes, Some(1024 * 1024));
+}
+
+/// Image unie de `side` pixels de côté
+fn image_item(side: usize) -> ClipboardItem {
+	let pixels = vec![128; side * side * 4];
+	ClipboardItem::new(ClipboardContent::Image(pixels, ImageMetadata::new(side, side)))
+}
+
+/// Applique les trois limites à un historique enregistré sur disque, lu par ses index
+fn prune_through_indexes(open: impl Fn(&Path) -> Box<dyn Storage>) {
+	let dir = tempfile::tempdir().unwrap();
+	let storage = open(dir.path());
+	let now = Utc::now();
+
+	// Du plus ancien au plus récent
+	let mut pinned = text_item("épinglé");
+	pinned.pinned = true;
+	let mut lapsed = text_item("expiré");
+	lapsed.expires_at = Some(now - Duration::minutes(1));
+	let items = [pinned, image_item(32), lapsed, text_item("ancien"), text_item("récent"), image_item(16)];
+	let count = items.len();
+	for (age, mut item) in items.into_iter().enumerate() {
+		item.timestamp = now - Duration::hours((count - age) as i64);
+		storage.add_item(item).unwrap();
+	}
+	assert_eq!(storage.image_bytes().unwrap(), (32 * 32 + 16 * 16) * 4);
+
+	let policy = RetentionPolicy { max_items: Some(4), max_age: None, max_image_bytes: Some(512) };
+	let report = apply_retention(&*storage, &policy).unwrap();
+	assert_eq!(report.lapsed.len(), 1);
+	assert_eq!((report.expired, report.over_count, report.over_image_size), (1, 1, 1));
+	assert_eq!(texts(&*storage), ["récent", "ancien", "épinglé"]);
+	assert_eq!(storage.count_items().unwrap(), 3);
+	assert_eq!(storage.image_bytes().unwrap(), 0);
+}
+
+#[test]
+fn sqlite_prunes_through_its_indexes() {
+	prune_through_indexes(|dir| {
+		let storage = SqliteStorage::new(dir).unwrap();
+		storage.init().unwrap();
+		Box::new(storage)
+	});
+}
+
+#[test]
+fn sled_prunes_through_its_indexes() {
+	prune_through_indexes(|dir| {
+		let storage = SledStorage::new(dir).unwrap();
+		storage.init().unwrap();
+		Box::new(storage)
+	});
+}
@@ -0,0 +1,260 @@
+use clipboard_manager::clipboard::{ClipboardContent, ClipboardItem, Selection};
+use clipboard_manager::storage::schema::{upgrade_record, SCHEMA_VERSION, SCHEMA_VERSION_KEY};
+use clipboard_manager::config::StorageBackend;
+use clipboard_manager::storage::{create_storage, SledStorage, SqliteStorage, Storage};
+use std::path::Path;
+use std::time::Duration;
+use uuid::Uuid;
+
+const V0_TEXT: &[u8] = include_bytes!("fixtures/records/v0_text.json");
+const V0_IMAGE: &[u8] = include_bytes!("fixtures/records/v0_image.json");
+const V0_INVALID: &[u8] = include_bytes!("fixtures/records/v0_invalid.json");
+const V1_FILES: &[u8] = include_bytes!("fixtures/records/v1_files.json");
+
+const V0_TEXT_ID: &str = "2f1c8e4a-5b7d-4c61-9a0e-3d2b1f6c8a90";
+const V0_IMAGE_ID: &str = "7a9d3c2e-1f4b-4e8a-b6c5-0d9e8f7a6b5c";
+
+/// Écrit des enregistrements bruts dans une base Sled, comme une ancienne version
+fn seed_sled(dir: &Path, records: &[(Uuid, &[u8])], version: Option<u32>) {
+	let db = sled::open(dir).unwrap();
+	for (id, record) in records {
+		db.insert(id.as_bytes(), *record).unwrap();
+	}
+	if let Some(version) = version {
+		db.open_tree("meta")
+			.unwrap()
+			.insert(SCHEMA_VERSION_KEY, &version.to_be_bytes())
+			.unwrap();
+	}
+	db.flush().unwrap();
+}
+
+/// Ouvre la base Sled brute, une fois relâché le verrou de la précédente ouverture
+fn open_sled(dir: &Path) -> sled::Db {
+	for _ in 0..40 {
+		if let Ok(db) = sled::open(dir) {
+			return db;
+		}
+		std::thread::sleep(Duration::from_millis(25));
+	}
+	sled::open(dir).unwrap()
+}
+
+/// Écrit des enregistrements bruts dans une base SQLite, comme une ancienne version
+fn seed_sqlite(dir: &Path, records: &[(Uuid, &[u8])], version: Option<u32>) {
+	let conn = rusqlite::Connection::open(dir.join("history.sqlite3")).unwrap();
+	conn.execute_batch(
+		"CREATE TABLE items (
+			id BLOB PRIMARY KEY NOT NULL,
+			timestamp INTEGER NOT NULL,
+			pinned INTEGER NOT NULL DEFAULT 0,
+			content_hash TEXT NOT NULL,
+			data BLOB NOT NULL
+		);
+		CREATE TABLE meta (key TEXT PRIMARY KEY NOT NULL, value TEXT NOT NULL);",
+	)
+	.unwrap();
+	for (id, record) in records {
+		conn.execute(
+			"INSERT INTO items (id, timestamp, pinned, content_hash, data) VALUES (?1, 0, 0, '', ?2)",
+			rusqlite::params![id.as_bytes().as_slice(), record],
+		)
+		.unwrap();
+	}
+	if let Some(version) = version {
+		conn.execute(
+			"INSERT INTO meta (key, value) VALUES (?1, ?2)",
+			rusqlite::params![SCHEMA_VERSION_KEY, version.to_string()],
+		)
+		.unwrap();
+	}
+}
+
+fn legacy_records() -> Vec<(Uuid, &'static [u8])> {
+	vec![
+		(Uuid::parse_str(V0_TEXT_ID).unwrap(), V0_TEXT),
+		(Uuid::parse_str(V0_IMAGE_ID).unwrap(), V0_IMAGE),
+		(Uuid::new_v4(), V0_INVALID),
+	]
+}
+
+/// Vérifie le contenu d'une base migrée depuis les enregistrements de `legacy_records`
+fn assert_legacy_history(storage: &dyn Storage) {
+	let items = storage.get_all_items().unwrap();
+	assert_eq!(items.len(), 2);
+
+	let text = storage.get_item(Uuid::parse_str(V0_TEXT_ID).unwrap()).unwrap().unwrap();
+	assert!(matches!(&text.content, ClipboardContent::Text(text) if text == "Bonjour le monde"));
+	assert!(text.pinned);
+	assert_eq!(text.content_hash, text.content.content_hash());
+
+	let found = storage.find_by_hash(&text.content_hash).unwrap().unwrap();
+	assert_eq!(found.id, text.id);
+
+	// Les pixels sont rangés à part : la liste n'en porte qu'un aperçu
+	let listed = items.iter().find(|item| item.id == Uuid::parse_str(V0_IMAGE_ID).unwrap()).unwrap();
+	match &listed.content {
+		ClipboardContent::Image(data, metadata) => {
+			assert!(data.is_empty());
+			assert!(!metadata.thumbnail.is_empty());
+		}
+		other => panic!("contenu inattendu: {:?}", other),
+	}
+	let image = storage.get_item(listed.id).unwrap().unwrap();
+	assert!(matches!(&image.content, ClipboardContent::Image(data, _) if data == &[255, 0, 0, 255, 0, 255, 0, 255]));
+}
+
+#[test]
+fn v0_text_record_gets_defaults() {
+	let item = upgrade_record(V0_TEXT, 0).unwrap();
+
+	assert_eq!(item.id, Uuid::parse_str(V0_TEXT_ID).unwrap());
+	assert!(item.pinned);
+	assert!(item.formats.is_empty());
+	assert_eq!(item.preferred_mime, None);
+	assert_eq!(item.selection, Selection::Clipboard);
+	assert_eq!(item.copy_count, 1);
+	assert_eq!(item.last_used, None);
+	assert_eq!(item.content_hash, item.content.content_hash());
+}
+
+#[test]
+fn v0_image_record_keeps_pixels() {
+	let item = upgrade_record(V0_IMAGE, 0).unwrap();
+
+	match item.content {
+		ClipboardContent::Image(data, metadata) => {
+			assert_eq!(data.len(), 8);
+			assert_eq!((metadata.width, metadata.height), (2, 1));
+		}
+		other => panic!("contenu inattendu: {:?}", other),
+	}
+}
+
+#[test]
+fn v1_record_is_left_as_is() {
+	let item = upgrade_record(V1_FILES, 1).unwrap();
+
+	assert!(matches!(&item.content, ClipboardContent::Files(files) if files.len() == 1));
+	assert_eq!(item.selection, Selection::Primary);
+	assert_eq!(item.copy_count, 3);
+	assert_eq!(item.formats.len(), 1);
+	assert!(item.last_used.is_some());
+	// L'empreinte manquante est calculée
+	assert_eq!(item.content_hash, item.content.content_hash());
+}
+
+#[test]
+fn migrations_can_be_replayed() {
+	let item = upgrade_record(V1_FILES, 0).unwrap();
+
+	assert_eq!(item.selection, Selection::Primary);
+	assert_eq!(item.copy_count, 3);
+}
+
+#[test]
+fn record_without_id_is_rejected() {
+	assert!(upgrade_record(V0_INVALID, 0).is_err());
+}
+
+#[test]
+fn newer_format_is_rejected() {
+	assert!(upgrade_record(V1_FILES, SCHEMA_VERSION + 1).is_err());
+}
+
+#[test]
+fn sled_migrates_legacy_history() {
+	let dir = tempfile::tempdir().unwrap();
+	seed_sled(dir.path(), &legacy_records(), None);
+
+	let storage = SledStorage::new(dir.path()).unwrap();
+	storage.init().unwrap();
+	assert_legacy_history(&storage);
+	drop(storage);
+
+	// La version est enregistrée : une seconde ouverture ne migre plus rien
+	let storage = SledStorage::new(dir.path()).unwrap();
+	storage.init().unwrap();
+	assert_legacy_history(&storage);
+}
+
+#[test]
+fn sled_refuses_newer_format() {
+	let dir = tempfile::tempdir().unwrap();
+	seed_sled(dir.path(), &[(Uuid::new_v4(), V1_FILES)], Some(SCHEMA_VERSION + 1));
+
+	let storage = SledStorage::new(dir.path()).unwrap();
+	assert!(storage.init().is_err());
+}
+
+#[test]
+fn sled_replaces_the_legacy_hash_index_once() {
+	let dir = tempfile::tempdir().unwrap();
+	let item = {
+		let storage = SledStorage::new(dir.path()).unwrap();
+		storage.init().unwrap();
+		storage.add_item(ClipboardItem::new(ClipboardContent::Text("Bonjour".to_string()))).unwrap()
+	};
+	// Base de la version 2 : seul l'ancien index, une entrée par empreinte, existe
+	{
+		let db = open_sled(dir.path());
+		db.drop_tree("hash_ids").unwrap();
+		db.open_tree("hash_index").unwrap().insert(item.hash().as_bytes(), item.id.as_bytes()).unwrap();
+		db.open_tree("meta").unwrap().insert(SCHEMA_VERSION_KEY, &2u32.to_be_bytes()).unwrap();
+		db.flush().unwrap();
+	}
+
+	let storage = SledStorage::new(dir.path()).unwrap();
+	storage.init().unwrap();
+	assert_eq!(storage.find_by_hash(&item.hash()).unwrap().map(|found| found.id), Some(item.id));
+	drop(storage);
+
+	let db = open_sled(dir.path());
+	assert!(!db.tree_names().iter().any(|name| name.as_ref() == b"hash_index"));
+	let version = db.open_tree("meta").unwrap().get(SCHEMA_VERSION_KEY).unwrap().unwrap();
+	assert_eq!(version.as_ref(), SCHEMA_VERSION.to_be_bytes());
+}
+
+#[test]
+fn sqlite_migrates_legacy_history() {
+	let dir = tempfile::tempdir().unwrap();
+	seed_sqlite(dir.path(), &legacy_records(), None);
+
+	let storage = SqliteStorage::new(dir.path()).unwrap();
+	storage.init().unwrap();
+	assert_legacy_history(&storage);
+	assert_eq!(storage.meta(SCHEMA_VERSION_KEY).unwrap(), Some(SCHEMA_VERSION.to_string()));
+}
+
+#[test]
+fn sqlite_refuses_newer_format() {
+	let dir = tempfile::tempdir().unwrap();
+	seed_sqlite(dir.path(), &[(Uuid::new_v4(), V1_FILES)], Some(SCHEMA_VERSION + 1));
+
+	let storage = SqliteStorage::new(dir.path()).unwrap();
+	assert!(storage.init().is_err());
+}
+
+#[test]
+fn empty_database_starts_at_current_format() {
+	let dir = tempfile::tempdir().unwrap();
+
+	let storage = SqliteStorage::new(dir.path()).unwrap();
+	storage.init().unwrap();
+	assert_eq!(storage.meta(SCHEMA_VERSION_KEY).unwrap(), Some(SCHEMA_VERSION.to_string()));
+}
+
+#[test]
+fn legacy_sled_history_is_imported_without_being_migrated() {
+	let dir = tempfile::tempdir().unwrap();
+	seed_sled(dir.path(), &legacy_records(), None);
+
+	let storage = create_storage(StorageBackend::Sqlite, dir.path()).unwrap();
+	assert_legacy_history(&*storage);
+	drop(storage);
+
+	// La base Sled reste lisible par la version qui l'a écrite
+	let db = open_sled(dir.path());
+	assert_eq!(db.get(Uuid::parse_str(V0_TEXT_ID).unwrap().as_bytes()).unwrap().unwrap().as_ref(), V0_TEXT);
+	assert!(db.open_tree("meta").unwrap().get(SCHEMA_VERSION_KEY).unwrap().is_none());
+}
@@ -0,0 +1,79 @@
+mod common;
+
+use clipboard_manager::storage::{SledStorage, Storage};
+use common::{text_item, texts};
+use std::path::Path;
+
+fn open(dir: &Path) -> SledStorage {
+	let storage = SledStorage::new(dir).unwrap();
+	storage.init().unwrap();
+	storage
+}
+
+/// Base Sled de `dir` ne contenant que `text`
+fn seed(dir: &Path, text: &str) {
+	std::fs::create_dir_all(dir).unwrap();
+	let storage = open(dir);
+	storage.add_item(text_item(text)).unwrap();
+	storage.flush().unwrap();
+}
+
+/// Déplace les fichiers Sled nommés de `from` vers `to`
+fn move_files(from: &Path, to: &Path, names: &[&str]) {
+	std::fs::create_dir_all(to).unwrap();
+	for name in names {
+		std::fs::rename(from.join(name), to.join(name)).unwrap();
+	}
+}
+
+#[test]
+fn compaction_keeps_the_history_and_its_subscribers() {
+	let dir = tempfile::tempdir().unwrap();
+	let storage = open(dir.path());
+	let kept = storage.add_item(text_item("gardé")).unwrap();
+	let removed = storage.add_item(text_item("supprimé")).unwrap();
+	storage.remove_item(removed.id).unwrap();
+	let mut changes = storage.subscribe();
+
+	storage.compact().unwrap();
+	assert_eq!(texts(&storage), ["gardé"]);
+	assert_eq!(storage.find_by_hash(&kept.hash()).unwrap().map(|item| item.id), Some(kept.id));
+	assert!(!dir.path().join("sled-compact").exists() && !dir.path().join("sled-retired").exists());
+
+	storage.add_item(text_item("après")).unwrap();
+	assert_eq!(changes.try_recv().unwrap().upserted[0].to_string(), "après");
+}
+
+#[test]
+fn an_interrupted_copy_leaves_the_base_untouched() {
+	let dir = tempfile::tempdir().unwrap();
+	seed(dir.path(), "actuel");
+	seed(&dir.path().join("sled-compact"), "copie partielle");
+
+	assert_eq!(texts(&open(dir.path())), ["actuel"]);
+	assert!(!dir.path().join("sled-compact").exists());
+}
+
+#[test]
+fn a_complete_copy_replaces_the_base_after_an_interruption() {
+	let dir = tempfile::tempdir().unwrap();
+	seed(dir.path(), "ancien");
+	seed(&dir.path().join("sled-compact"), "réécrit");
+	// L'ancienne base a été entièrement écartée, la copie à moitié mise en place
+	move_files(dir.path(), &dir.path().join("sled-retired"), &["db", "blobs", "conf"]);
+	move_files(&dir.path().join("sled-compact"), dir.path(), &["db"]);
+
+	assert_eq!(texts(&open(dir.path())), ["réécrit"]);
+	assert!(!dir.path().join("sled-retired").exists());
+}
+
+#[test]
+fn a_base_partly_set_aside_is_restored_after_an_interruption() {
+	let dir = tempfile::tempdir().unwrap();
+	seed(dir.path(), "ancien");
+	seed(&dir.path().join("sled-compact"), "réécrit");
+	move_files(dir.path(), &dir.path().join("sled-retired"), &["db"]);
+
+	assert_eq!(texts(&open(dir.path())), ["ancien"]);
+	assert!(!dir.path().join("sled-compact").exists() && !dir.path().join("sled-retired").exists());
+}
@@ -0,0 +1,62 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use clipboard_manager::config::StorageBackend;
+use clipboard_manager::storage::{create_storage, SledStorage, SqliteStorage, Storage};
+use common::text_item;
+
+#[test]
+fn items_round_trip_through_sqlite() {
+	let dir = tempfile::tempdir().unwrap();
+	let storage = SqliteStorage::new(dir.path()).unwrap();
+	storage.init().unwrap();
+
+	let first = storage.add_item(text_item("premier")).unwrap();
+	let mut second = text_item("second");
+	second.timestamp = first.timestamp + Duration::seconds(1);
+	let second = storage.add_item(second).unwrap();
+	assert_eq!(storage.count_items().unwrap(), 2);
+	assert_eq!(storage.get_item(first.id).unwrap().unwrap(), first);
+	assert_eq!(storage.find_by_hash(&second.hash()).unwrap().map(|item| item.id), Some(second.id));
+
+	let mut pinned = first.clone();
+	pinned.pinned = true;
+	storage.update_item(pinned.clone()).unwrap();
+	assert!(storage.get_item(first.id).unwrap().unwrap().pinned);
+
+	// Vider l'historique épargne l'élément épinglé
+	assert_eq!(storage.clear_non_pinned().unwrap(), [second.id]);
+	assert_eq!(storage.get_all_items().unwrap(), [pinned]);
+
+	storage.remove_item(first.id).unwrap();
+	assert_eq!(storage.get_item(first.id).unwrap(), None);
+	assert_eq!(storage.count_items().unwrap(), 0);
+}
+
+#[test]
+fn the_sled_history_is_imported_once() {
+	let dir = tempfile::tempdir().unwrap();
+	let mut old = text_item("copie ancienne");
+	old.timestamp = Utc::now() - Duration::days(3);
+	old.pinned = true;
+	let recent = text_item("copie récente");
+	{
+		let sled = SledStorage::new(dir.path()).unwrap();
+		sled.init().unwrap();
+		sled.add_item(old.clone()).unwrap();
+		sled.add_item(recent.clone()).unwrap();
+		sled.flush().unwrap();
+	}
+
+	let storage = create_storage(StorageBackend::Sqlite, dir.path()).unwrap();
+	let imported = storage.get_all_items().unwrap();
+	assert_eq!(imported.iter().map(|item| item.id).collect::<Vec<_>>(), [recent.id, old.id]);
+	let restored = storage.get_item(old.id).unwrap().unwrap();
+	assert_eq!((restored.timestamp, restored.pinned), (old.timestamp, true));
+
+	// Un élément supprimé depuis n'est pas réimporté au lancement suivant
+	storage.remove_item(recent.id).unwrap();
+	drop(storage);
+	let storage = create_storage(StorageBackend::Sqlite, dir.path()).unwrap();
+	assert_eq!(storage.get_all_items().unwrap().iter().map(|item| item.id).collect::<Vec<_>>(), [old.id]);
+}
@@ -0,0 +1,39 @@
+use clipboard_manager::clipboard::{ClipboardContent, ClipboardItem};
+use clipboard_manager::config::StorageBackend;
+use clipboard_manager::error::ClipboardError;
+use clipboard_manager::storage::{create_storage, SqliteStorage, Storage};
+
+#[test]
+fn a_locked_sled_database_is_reported_as_such() {
+	let dir = tempfile::tempdir().unwrap();
+	let _first = create_storage(StorageBackend::Sled, dir.path()).unwrap();
+
+	let error = create_storage(StorageBackend::Sled, dir.path()).err().unwrap();
+	assert!(matches!(error, ClipboardError::StorageLocked(_)), "{:?}", error);
+	assert_eq!(error.summary(), "Historique utilisé par un autre processus");
+	assert!(error.hint().is_some());
+}
+
+#[test]
+fn the_in_memory_history_works_like_the_database() {
+	let storage = SqliteStorage::in_memory().unwrap();
+	storage.init().unwrap();
+
+	let item = storage.add_item(ClipboardItem::new(ClipboardContent::Text("copie".to_string()))).unwrap();
+	assert_eq!(storage.count_items().unwrap(), 1);
+	assert!(storage.get_item(item.id).unwrap().is_some());
+
+	// Chaque base en mémoire est indépendante
+	let other = SqliteStorage::in_memory().unwrap();
+	other.init().unwrap();
+	assert_eq!(other.count_items().unwrap(), 0);
+}
+
+#[test]
+fn errors_are_explained_to_the_user() {
+	let error = ClipboardError::Clipboard("Unknown error while interacting with the clipboard".to_string());
+	assert_eq!(error.summary(), "Presse-papiers inaccessible");
+	assert!(error.hint().unwrap().contains("WAYLAND_DISPLAY"));
+
+	assert!(ClipboardError::Unexpected("?".to_string()).hint().is_none());
+}
@@ -0,0 +1,81 @@
+mod common;
+
+use clipboard_manager::storage::{SledStorage, SqliteStorage, Storage, StorageChange, StorageEventReceiver};
+use common::text_item;
+use std::time::{Duration, Instant};
+
+/// Attend la prochaine modification diffusée, au plus quelques secondes
+fn next_change(receiver: &mut StorageEventReceiver) -> StorageChange {
+	let deadline = Instant::now() + Duration::from_secs(5);
+	loop {
+		match receiver.try_recv() {
+			Ok(change) => return change,
+			Err(_) if Instant::now() < deadline => std::thread::sleep(Duration::from_millis(20)),
+			Err(e) => panic!("aucune modification reçue: {}", e),
+		}
+	}
+}
+
+fn assert_changes_are_broadcast(storage: &dyn Storage) {
+	let mut receiver = storage.subscribe();
+
+	let pinned = storage.add_item(text_item("épinglé")).unwrap();
+	let change = next_change(&mut receiver);
+	assert_eq!(change.upserted.len(), 1);
+	assert_eq!(change.upserted[0].id, pinned.id);
+	assert_eq!(change.total, 1);
+	assert!(!change.reload);
+
+	let mut update = pinned.clone();
+	update.pinned = true;
+	let stored = storage.update_item(update).unwrap();
+	assert!(stored.pinned);
+	assert!(next_change(&mut receiver).upserted[0].pinned);
+
+	let other = storage.add_item(text_item("autre")).unwrap();
+	next_change(&mut receiver);
+
+	let removed = storage.clear_non_pinned().unwrap();
+	assert_eq!(removed, vec![other.id]);
+	let change = next_change(&mut receiver);
+	assert_eq!(change.removed, vec![other.id]);
+	assert_eq!(change.total, 1);
+
+	storage.remove_item(pinned.id).unwrap();
+	let change = next_change(&mut receiver);
+	assert_eq!(change.removed, vec![pinned.id]);
+	assert_eq!(change.total, 0);
+}
+
+#[test]
+fn sled_broadcasts_changes() {
+	let dir = tempfile::tempdir().unwrap();
+	let storage = SledStorage::new(dir.path()).unwrap();
+	storage.init().unwrap();
+	assert_changes_are_broadcast(&storage);
+}
+
+#[test]
+fn sqlite_broadcasts_changes() {
+	let dir = tempfile::tempdir().unwrap();
+	let storage = SqliteStorage::new(dir.path()).unwrap();
+	storage.init().unwrap();
+	assert_changes_are_broadcast(&storage);
+}
+
+#[test]
+fn sqlite_reports_writes_from_another_connection() {
+	let dir = tempfile::tempdir().unwrap();
+	let storage = SqliteStorage::new(dir.path()).unwrap();
+	storage.init().unwrap();
+	let mut receiver = storage.subscribe();
+
+	// Une seconde connexion joue le rôle d'un autre processus
+	let other = SqliteStorage::new(dir.path()).unwrap();
+	other.init().unwrap();
+	other.add_item(text_item("ailleurs")).unwrap();
+
+	let change = next_change(&mut receiver);
+	assert!(change.reload);
+	assert_eq!(change.total, 1);
+}